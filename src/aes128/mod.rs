@@ -0,0 +1,270 @@
+use boojum::{
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{boolean::Boolean, tables::Xor8Table, u8::UInt8},
+};
+
+use crate::tables::{get_typed_table_id, TypedTableId};
+
+pub mod sbox;
+use self::sbox::*;
+
+pub const AES128_NUM_ROUNDS: usize = 10;
+
+const AES128_ROUND_CONSTANTS: [u8; AES128_NUM_ROUNDS] =
+    [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+/// State is stored by columns, so `state[c][r]` is the byte `4 * c + r` of the block
+pub type Aes128State<F> = [[UInt8<F>; 4]; 4];
+
+struct Aes128Tables {
+    xor: TypedTableId<Xor8Table>,
+    sbox: TypedTableId<Aes128SboxTable>,
+    mul_by_2: TypedTableId<Aes128MulBy2Table>,
+}
+
+impl Aes128Tables {
+    fn new<F: SmallField, CS: ConstraintSystem<F>>(cs: &CS) -> Self {
+        Self {
+            xor: get_typed_table_id::<Xor8Table, F, CS>(cs),
+            sbox: get_typed_table_id::<Aes128SboxTable, F, CS>(cs),
+            mul_by_2: get_typed_table_id::<Aes128MulBy2Table, F, CS>(cs),
+        }
+    }
+}
+
+fn xor<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: &Aes128Tables,
+    a: &UInt8<F>,
+    b: &UInt8<F>,
+) -> UInt8<F> {
+    let [result] = tables
+        .xor
+        .perform_lookup::<F, CS, 2, 1>(cs, &[a.get_variable(), b.get_variable()]);
+
+    unsafe { UInt8::from_variable_unchecked(result) }
+}
+
+fn substitute<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: &Aes128Tables,
+    byte: &UInt8<F>,
+) -> UInt8<F> {
+    let [substituted, _] = tables
+        .sbox
+        .perform_lookup::<F, CS, 1, 2>(cs, &[byte.get_variable()]);
+
+    unsafe { UInt8::from_variable_unchecked(substituted) }
+}
+
+fn sub_bytes_and_shift_rows<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: &Aes128Tables,
+    state: &Aes128State<F>,
+) -> Aes128State<F> {
+    let substituted = state.map(|column| column.map(|byte| substitute(cs, tables, &byte)));
+
+    // row `r` is rotated left by `r` positions
+    std::array::from_fn(|c| std::array::from_fn(|r| substituted[(c + r) % 4][r]))
+}
+
+fn mix_columns<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: &Aes128Tables,
+    state: &Aes128State<F>,
+) -> Aes128State<F> {
+    state.map(|column| {
+        let products = column.map(|byte| {
+            let [doubled, tripled] = tables
+                .mul_by_2
+                .perform_lookup::<F, CS, 1, 2>(cs, &[byte.get_variable()]);
+            unsafe {
+                (UInt8::from_variable_unchecked(doubled), UInt8::from_variable_unchecked(tripled))
+            }
+        });
+
+        // row `r` of the result is `2 * a_r + 3 * a_{r + 1} + a_{r + 2} + a_{r + 3}`
+        std::array::from_fn(|r| {
+            let tmp = xor(cs, tables, &products[r].0, &products[(r + 1) % 4].1);
+            let tmp = xor(cs, tables, &tmp, &column[(r + 2) % 4]);
+            xor(cs, tables, &tmp, &column[(r + 3) % 4])
+        })
+    })
+}
+
+fn add_round_key<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: &Aes128Tables,
+    state: &Aes128State<F>,
+    round_key: &Aes128State<F>,
+) -> Aes128State<F> {
+    std::array::from_fn(|c| {
+        std::array::from_fn(|r| xor(cs, tables, &state[c][r], &round_key[c][r]))
+    })
+}
+
+/// Regular AES-128 round: SubBytes, ShiftRows, MixColumns and AddRoundKey. `Xor8Table`,
+/// `Aes128SboxTable` and `Aes128MulBy2Table` must be present in the CS
+pub fn aes128_round<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    state: Aes128State<F>,
+    round_key: Aes128State<F>,
+) -> Aes128State<F> {
+    let tables = Aes128Tables::new(cs);
+
+    let state = sub_bytes_and_shift_rows(cs, &tables, &state);
+    let state = mix_columns(cs, &tables, &state);
+
+    add_round_key(cs, &tables, &state, &round_key)
+}
+
+/// Last AES-128 round, that skips MixColumns
+pub fn aes128_final_round<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    state: Aes128State<F>,
+    round_key: Aes128State<F>,
+) -> Aes128State<F> {
+    let tables = Aes128Tables::new(cs);
+
+    let state = sub_bytes_and_shift_rows(cs, &tables, &state);
+
+    add_round_key(cs, &tables, &state, &round_key)
+}
+
+/// Round keys for the initial AddRoundKey and all 10 rounds
+pub fn aes128_expand_key<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    key: Aes128State<F>,
+) -> [Aes128State<F>; AES128_NUM_ROUNDS + 1] {
+    let tables = Aes128Tables::new(cs);
+
+    let mut round_keys = [key; AES128_NUM_ROUNDS + 1];
+    for (round, round_constant) in AES128_ROUND_CONSTANTS.iter().enumerate() {
+        let previous = round_keys[round];
+
+        // RotWord and SubWord of the last word, and the round constant for its first byte
+        let last_word = previous[3];
+        let mut word: [UInt8<F>; 4] =
+            std::array::from_fn(|r| substitute(cs, &tables, &last_word[(r + 1) % 4]));
+        let round_constant = UInt8::allocated_constant(cs, *round_constant);
+        word[0] = xor(cs, &tables, &word[0], &round_constant);
+
+        let mut next = previous;
+        for c in 0..4 {
+            next[c] = std::array::from_fn(|r| xor(cs, &tables, &previous[c][r], &word[r]));
+            word = next[c];
+        }
+
+        round_keys[round + 1] = next;
+    }
+
+    round_keys
+}
+
+pub fn aes128_encrypt<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    key: [UInt8<F>; 16],
+    plaintext: [UInt8<F>; 16],
+) -> [UInt8<F>; 16] {
+    let into_state = |bytes: [UInt8<F>; 16]| -> Aes128State<F> {
+        std::array::from_fn(|c| std::array::from_fn(|r| bytes[4 * c + r]))
+    };
+
+    let tables = Aes128Tables::new(cs);
+    let round_keys = aes128_expand_key(cs, into_state(key));
+
+    let mut state = add_round_key(cs, &tables, &into_state(plaintext), &round_keys[0]);
+    for round_key in round_keys[1..AES128_NUM_ROUNDS].iter() {
+        state = aes128_round(cs, state, *round_key);
+    }
+    let state = aes128_final_round(cs, state, round_keys[AES128_NUM_ROUNDS]);
+
+    std::array::from_fn(|i| state[i / 4][i % 4])
+}
+
+/// Proves that `ciphertext` is the AES-128 encryption of `plaintext` under `key`
+pub fn aes128_encrypt_entry_point<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    key: [UInt8<F>; 16],
+    plaintext: [UInt8<F>; 16],
+    ciphertext: [UInt8<F>; 16],
+) {
+    let boolean_true = Boolean::allocated_constant(cs, true);
+    let encrypted = aes128_encrypt(cs, key, plaintext);
+
+    for (a, b) in encrypted.iter().zip(ciphertext.iter()) {
+        let is_equal = UInt8::equals(cs, a, b);
+        Boolean::enforce_equal(cs, &is_equal, &boolean_true);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use boojum::{
+        field::goldilocks::GoldilocksField,
+        gadgets::traits::{allocatable::CSAllocatable, witnessable::WitnessHookable},
+        worker::Worker,
+    };
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    fn allocate_block<CS: ConstraintSystem<F>>(cs: &mut CS, hex: &str) -> [UInt8<F>; 16] {
+        let bytes = hex::decode(hex).unwrap();
+        std::array::from_fn(|i| UInt8::allocate(cs, bytes[i]))
+    }
+
+    fn encrypt(key: &str, plaintext: &str, ciphertext: &str) -> (String, bool) {
+        let mut owned_cs = create_test_cs(1 << 20);
+        owned_cs.add_lookup_table::<Aes128SboxTable, 3>(create_aes128_sbox_table());
+        owned_cs.add_lookup_table::<Aes128MulBy2Table, 3>(create_aes128_mul_by_2_table());
+        let cs = &mut owned_cs;
+
+        let key = allocate_block(cs, key);
+        let plaintext = allocate_block(cs, plaintext);
+        let ciphertext = allocate_block(cs, ciphertext);
+
+        let encrypted = aes128_encrypt(cs, key, plaintext);
+        let encrypted = hex::encode(encrypted.witness_hook(&*cs)().unwrap());
+        aes128_encrypt_entry_point(cs, key, plaintext, ciphertext);
+
+        let worker = Worker::new();
+        owned_cs.pad_and_shrink();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+
+        (encrypted, owned_cs.check_if_satisfied(&worker))
+    }
+
+    #[test]
+    fn test_aes128_encrypt() {
+        // FIPS 197, appendix B and C.1
+        let (encrypted, is_satisfied) = encrypt(
+            "2b7e151628aed2a6abf7158809cf4f3c",
+            "3243f6a8885a308d313198a2e0370734",
+            "3925841d02dc09fbdc118597196a0b32",
+        );
+        assert_eq!(encrypted, "3925841d02dc09fbdc118597196a0b32");
+        assert!(is_satisfied);
+
+        let (encrypted, is_satisfied) = encrypt(
+            "000102030405060708090a0b0c0d0e0f",
+            "00112233445566778899aabbccddeeff",
+            "69c4e0d86a7b0430d8cdb78070b4c55a",
+        );
+        assert_eq!(encrypted, "69c4e0d86a7b0430d8cdb78070b4c55a");
+        assert!(is_satisfied);
+    }
+
+    #[test]
+    fn test_aes128_encrypt_wrong_ciphertext() {
+        let (_, is_satisfied) = encrypt(
+            "000102030405060708090a0b0c0d0e0f",
+            "00112233445566778899aabbccddeeff",
+            "69c4e0d86a7b0430d8cdb78070b4c55b",
+        );
+        assert!(is_satisfied == false);
+    }
+}
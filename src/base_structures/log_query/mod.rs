@@ -23,6 +23,7 @@ use boojum::{
 use cs_derive::*;
 
 use super::*;
+use crate::ethereum_types::U256;
 
 #[derive(Derivative, CSAllocatable, CSSelectable, WitnessHookable, CSVarLengthEncodable)]
 #[derivative(Clone, Copy, Debug, Hash)]
@@ -425,6 +426,62 @@ pub(crate) fn log_query_witness_from_values<F: SmallField>(
     }
 }
 
+/// Out of circuit counterpart of `CircuitEncodable::encode` for `LogQuery`, so queue states can
+/// be simulated without a constraint system
+pub fn log_query_witness_encoding<F: SmallField>(
+    witness: &<LogQuery<F> as CSAllocatable<F>>::Witness,
+) -> [F; LOG_QUERY_PACKED_WIDTH] {
+    debug_assert!(F::CAPACITY_BITS >= 56);
+
+    let u32_limbs_of_u256 =
+        |value: &U256| -> [u32; 8] { std::array::from_fn(|i| (*value >> (32 * i)).low_u32()) };
+    let address_bytes = witness.address.as_bytes();
+    let address_limbs: [u32; 5] = std::array::from_fn(|i| {
+        let end = 20 - 4 * i;
+        u32::from_be_bytes(address_bytes[(end - 4)..end].try_into().unwrap())
+    });
+
+    let key_bytes = u32_limbs_of_u256(&witness.key).map(|el| el.to_le_bytes());
+    let address_bytes = address_limbs.map(|el| el.to_le_bytes());
+    let read_value = u32_limbs_of_u256(&witness.read_value);
+    let written_value = u32_limbs_of_u256(&witness.written_value);
+
+    // same mixing of "key" and "address" bytes as in the circuit encoding
+    let flat_key_bytes: Vec<u8> = key_bytes.iter().flatten().copied().collect();
+    let flat_address_bytes: Vec<u8> = address_bytes.iter().flatten().copied().collect();
+    let mixed_bytes: Vec<u8> = flat_key_bytes
+        .iter()
+        .chain(flat_address_bytes[..16].iter())
+        .copied()
+        .collect();
+    debug_assert_eq!(mixed_bytes.len(), 16 * 3);
+
+    let pack = |base: u32, bytes: &[u8]| -> F {
+        let mut result = base as u64;
+        for (idx, byte) in bytes.iter().enumerate() {
+            result += (*byte as u64) << (32 + 8 * idx);
+        }
+
+        F::from_u64_unchecked(result)
+    };
+
+    let mut result = [F::ZERO; LOG_QUERY_PACKED_WIDTH];
+    for (idx, base) in read_value.iter().chain(written_value.iter()).enumerate() {
+        result[idx] = pack(*base, &mixed_bytes[(3 * idx)..(3 * idx + 3)]);
+    }
+    result[16] = pack(witness.timestamp, &flat_address_bytes[16..19]);
+    result[17] = F::from_u64_unchecked(
+        witness.tx_number_in_block as u64
+            + ((flat_address_bytes[19] as u64) << 32)
+            + ((witness.aux_byte as u64) << 40)
+            + ((witness.shard_id as u64) << 48),
+    );
+    result[18] = F::from_u64_unchecked(witness.rw_flag as u64 + 2 * (witness.is_service as u64));
+    result[ROLLBACK_PACKING_FLAG_VARIABLE_IDX] = F::from_u64_unchecked(witness.rollback as u64);
+
+    result
+}
+
 impl<F: SmallField> CSAllocatableExt<F> for LogQuery<F> {
     const INTERNAL_STRUCT_LEN: usize = FLATTENED_VARIABLE_LENGTH;
 
@@ -484,6 +541,9 @@ pub type LogQueryQueue<F, const AW: usize, const SW: usize, const CW: usize, R>
 pub const L2_TO_L1_MESSAGE_BYTE_LENGTH: usize = 88;
 
 impl<F: SmallField> ByteSerializable<F, L2_TO_L1_MESSAGE_BYTE_LENGTH> for LogQuery<F> {
+    // shard id, service flag, lowest 2 bytes of tx number, address, key and written value
+    const SERIALIZED_LEN: usize = 1 + 1 + 2 + 20 + 32 + 32;
+
     fn into_bytes<CS: ConstraintSystem<F>>(
         &self,
         cs: &mut CS,
@@ -518,8 +578,44 @@ impl<F: SmallField> ByteSerializable<F, L2_TO_L1_MESSAGE_BYTE_LENGTH> for LogQue
         result[offset..(offset + bytes_be.len())].copy_from_slice(&bytes_be);
         offset += bytes_be.len();
 
-        assert_eq!(offset, L2_TO_L1_MESSAGE_BYTE_LENGTH);
+        assert_eq!(offset, Self::SERIALIZED_LEN);
 
         result
     }
 }
+
+/// Out of circuit counterpart of `ByteSerializable::into_bytes` for `LogQuery`
+pub fn log_query_witness_into_bytes<F: SmallField>(
+    witness: &<LogQuery<F> as CSAllocatable<F>>::Witness,
+) -> [u8; L2_TO_L1_MESSAGE_BYTE_LENGTH] {
+    let mut result = [0u8; L2_TO_L1_MESSAGE_BYTE_LENGTH];
+    result[0] = witness.shard_id;
+    result[1] = witness.is_service as u8;
+    result[2..4].copy_from_slice(&witness.tx_number_in_block.to_be_bytes()[2..]);
+    result[4..24].copy_from_slice(witness.address.as_bytes());
+    witness.key.to_big_endian(&mut result[24..56]);
+    witness.written_value.to_big_endian(&mut result[56..88]);
+
+    result
+}
+
+/// Returns `false` if both queries are writes to the same storage slot, but `prev` doesn't
+/// strictly precede `curr` in time. Rollbacks have the same timestamp as the writes they revert,
+/// so callers should only flag forward writes
+pub fn verify_adjacent_storage_writes_consistent<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    prev: &LogQuery<F>,
+    curr: &LogQuery<F>,
+    prev_is_write: Boolean<F>,
+    curr_is_write: Boolean<F>,
+) -> Boolean<F> {
+    let same_address = UInt160::equals(cs, &prev.address, &curr.address);
+    let same_key = UInt256::equals(cs, &prev.key, &curr.key);
+    let must_be_ordered =
+        Boolean::multi_and(cs, &[prev_is_write, curr_is_write, same_address, same_key]);
+
+    let (_, prev_timestamp_is_less) = prev.timestamp.overflowing_sub(cs, curr.timestamp);
+    let no_ordering_required = must_be_ordered.negated(cs);
+
+    Boolean::multi_or(cs, &[no_ordering_required, prev_timestamp_is_less])
+}
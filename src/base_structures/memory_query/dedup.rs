@@ -0,0 +1,25 @@
+use boojum::{
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{boolean::Boolean, u32::UInt32},
+};
+
+use super::MemoryQuery;
+
+/// Checks that two adjacent queries in the sorted memory queue are not duplicates, so either they
+/// access different cells, or the timestamp strictly increases
+pub fn are_adjacent_memory_queries_consistent<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    prev: &MemoryQuery<F>,
+    curr: &MemoryQuery<F>,
+) -> Boolean<F> {
+    // prev - curr underflows if curr > prev
+    let (_, timestamp_is_increasing) = prev.timestamp.overflowing_sub(cs, curr.timestamp);
+
+    let same_page = UInt32::equals(cs, &prev.memory_page, &curr.memory_page);
+    let different_page = same_page.negated(cs);
+    let same_index = UInt32::equals(cs, &prev.index, &curr.index);
+    let different_index = same_index.negated(cs);
+
+    Boolean::multi_or(cs, &[timestamp_is_increasing, different_page, different_index])
+}
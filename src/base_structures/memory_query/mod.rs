@@ -1,4 +1,5 @@
 use boojum::{
+    algebraic_props::round_function::AlgebraicRoundFunction,
     config::*,
     cs::{
         traits::cs::{ConstraintSystem, DstBuffer},
@@ -13,6 +14,7 @@ use boojum::{
             allocatable::{CSAllocatable, CSAllocatableExt},
             castable::WitnessCastable,
             encodable::{CircuitEncodable, CircuitEncodableExt},
+            round_function::CircuitRoundFunction,
             selectable::Selectable,
             witnessable::WitnessHookable,
         },
@@ -25,6 +27,8 @@ use cs_derive::*;
 use super::*;
 use crate::ethereum_types::U256;
 
+pub mod dedup;
+
 pub const MEMORY_QUERY_PACKED_WIDTH: usize = 8;
 
 #[derive(Derivative, CSSelectable, CSAllocatable, WitnessHookable)]
@@ -258,5 +262,26 @@ pub type MemoryQueryQueue<F, const AW: usize, const SW: usize, const CW: usize,
     FullStateCircuitQueue<F, MemoryQuery<F>, AW, SW, CW, MEMORY_QUERY_PACKED_WIDTH, R>;
 pub type MemoryQueue<F, R> = MemoryQueryQueue<F, 8, 12, 4, R>;
 
+/// Pushes a fixed set of queries under the same condition, e.g. all the writes of a precompile
+/// call. NOTE: every query is already encoded into the full rate, so it's still one permutation
+/// per query and no rounds are saved, it only removes repetitive code at call sites
+pub fn batch_push_memory_queries<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+    const N: usize,
+>(
+    cs: &mut CS,
+    queue: &mut MemoryQueue<F, R>,
+    queries: [MemoryQuery<F>; N],
+    condition: Boolean<F>,
+) where
+    [(); <MemoryQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+{
+    for query in queries.into_iter() {
+        let _ = queue.push(cs, query, condition);
+    }
+}
+
 pub type MemoryQueryQueueWitness<F, const SW: usize> =
     FullStateCircuitQueueWitness<F, MemoryQuery<F>, SW, MEMORY_QUERY_PACKED_WIDTH>;
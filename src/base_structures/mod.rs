@@ -9,9 +9,14 @@ pub mod recursion_query;
 pub mod register;
 pub mod vm_state;
 
+pub mod page_id;
 pub mod precompile_input_outputs;
+pub mod precompile_kind;
 pub mod state_diff_record;
 
 pub trait ByteSerializable<F: SmallField, const N: usize> {
+    /// Length of the serialization as it follows from the layout of the type
+    const SERIALIZED_LEN: usize;
+
     fn into_bytes<CS: ConstraintSystem<F>>(&self, cs: &mut CS) -> [UInt8<F>; N];
 }
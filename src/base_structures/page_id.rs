@@ -0,0 +1,43 @@
+use std::marker::PhantomData;
+
+use boojum::{
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{boolean::Boolean, traits::selectable::Selectable, u32::UInt32},
+};
+use derivative::*;
+
+/// Marker for pages that a precompile reads its arguments from
+#[derive(Clone, Copy, Debug)]
+pub struct InputTag;
+
+/// Marker for pages that a precompile writes its results to
+#[derive(Clone, Copy, Debug)]
+pub struct OutputTag;
+
+/// Memory page tagged with its role in a precompile call, so input and output pages can not be
+/// swapped by accident. The page has to be unwrapped explicitly to form a memory query
+#[derive(Derivative)]
+#[derivative(Clone, Copy, Debug)]
+pub struct PageId<F: SmallField, Tag>(UInt32<F>, PhantomData<Tag>);
+
+impl<F: SmallField, Tag> PageId<F, Tag> {
+    pub fn new(page: UInt32<F>) -> Self {
+        Self(page, PhantomData)
+    }
+
+    pub fn page(&self) -> UInt32<F> {
+        self.0
+    }
+}
+
+impl<F: SmallField, Tag: Clone> Selectable<F> for PageId<F, Tag> {
+    fn conditionally_select<CS: ConstraintSystem<F>>(
+        cs: &mut CS,
+        flag: Boolean<F>,
+        a: &Self,
+        b: &Self,
+    ) -> Self {
+        Self::new(UInt32::conditionally_select(cs, flag, &a.0, &b.0))
+    }
+}
@@ -3,6 +3,7 @@ use boojum::{
     field::SmallField,
     gadgets::{
         boolean::Boolean,
+        num::Num,
         queue::QueueState,
         traits::{
             allocatable::{CSAllocatable, CSPlaceholder},
@@ -11,6 +12,7 @@ use boojum::{
             selectable::Selectable,
             witnessable::WitnessHookable,
         },
+        u32::UInt32,
     },
 };
 use cs_derive::*;
@@ -45,10 +47,26 @@ impl<F: SmallField> CSPlaceholder<F> for PrecompileFunctionInputData<F> {
 #[DerivePrettyComparison("true")]
 pub struct PrecompileFunctionOutputData<F: SmallField> {
     pub final_memory_state: QueueState<F, FULL_SPONGE_QUEUE_STATE_WIDTH>,
+    pub num_successful_calls: UInt32<F>,
+    pub gas_used: UInt32<F>,
 }
 
 impl<F: SmallField> CSPlaceholder<F> for PrecompileFunctionOutputData<F> {
     fn placeholder<CS: ConstraintSystem<F>>(cs: &mut CS) -> Self {
-        Self { final_memory_state: QueueState::<F, FULL_SPONGE_QUEUE_STATE_WIDTH>::placeholder(cs) }
+        Self {
+            final_memory_state: QueueState::<F, FULL_SPONGE_QUEUE_STATE_WIDTH>::placeholder(cs),
+            num_successful_calls: UInt32::zero(cs),
+            gas_used: UInt32::zero(cs),
+        }
+    }
+}
+
+impl<F: SmallField> PrecompileFunctionOutputData<F> {
+    /// Rolling Poseidon2 hash of every memory query pushed so far. The memory queue is a full
+    /// state sponge that absorbs the encoding of each query, including timestamp, page, index,
+    /// r/w flag and value, so its tail already is such a hash, and it's committed as a part of
+    /// the output. An external verifier can recompute it from the memory access trace
+    pub fn global_integrity_hash(&self) -> [Num<F>; FULL_SPONGE_QUEUE_STATE_WIDTH] {
+        self.final_memory_state.tail.tail
     }
 }
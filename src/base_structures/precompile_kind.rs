@@ -0,0 +1,61 @@
+use boojum::{cs::traits::cs::ConstraintSystem, field::SmallField, gadgets::u160::UInt160};
+
+use crate::ethereum_types::H160;
+
+/// Formal address of the Schnorr signature verification precompile. It is not assigned in
+/// `zkevm_opcode_defs` yet, so it is defined next to the other system precompiles here
+pub const SCHNORR_VERIFY_INNER_FUNCTION_PRECOMPILE_ADDRESS: u16 = 0x0101;
+
+/// Formal address of the Pedersen hash precompile, not assigned in `zkevm_opcode_defs` either
+pub const PEDERSEN_HASH_INNER_FUNCTION_PRECOMPILE_ADDRESS: u16 = 0x0102;
+
+/// Formal address of the BLS12-381 G1 point addition precompile
+pub const BLS12_381_G1_ADD_INNER_FUNCTION_PRECOMPILE_ADDRESS: u16 = 0x0103;
+
+/// Formal address of the nullifier derivation precompile
+pub const NULLIFIER_INNER_FUNCTION_PRECOMPILE_ADDRESS: u16 = 0x0104;
+
+/// Precompiles that are implemented as separate circuits and receive their requests from the
+/// log demuxer by formal address. Schnorr verification, Pedersen hash, BLS12-381 G1 addition and
+/// nullifier derivation are not wired into the scheduler yet, so the demuxer enforces that no
+/// requests are routed to them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PrecompileKind {
+    Ecrecover,
+    Keccak256,
+    Sha256,
+    Secp256r1Verify,
+    SchnorrVerify,
+    PedersenHash,
+    Bls12_381G1Add,
+    Nullifier,
+}
+
+impl PrecompileKind {
+    pub fn address(&self) -> H160 {
+        use zkevm_opcode_defs::system_params::*;
+
+        match self {
+            Self::Ecrecover => *ECRECOVER_INNER_FUNCTION_PRECOMPILE_FORMAL_ADDRESS,
+            Self::Keccak256 => *KECCAK256_ROUND_FUNCTION_PRECOMPILE_FORMAL_ADDRESS,
+            Self::Sha256 => *SHA256_ROUND_FUNCTION_PRECOMPILE_FORMAL_ADDRESS,
+            Self::Secp256r1Verify => *SECP256R1_VERIFY_INNER_FUNCTION_PRECOMPILE_FORMAL_ADDRESS,
+            Self::SchnorrVerify => {
+                H160::from_low_u64_be(SCHNORR_VERIFY_INNER_FUNCTION_PRECOMPILE_ADDRESS as u64)
+            }
+            Self::PedersenHash => {
+                H160::from_low_u64_be(PEDERSEN_HASH_INNER_FUNCTION_PRECOMPILE_ADDRESS as u64)
+            }
+            Self::Bls12_381G1Add => {
+                H160::from_low_u64_be(BLS12_381_G1_ADD_INNER_FUNCTION_PRECOMPILE_ADDRESS as u64)
+            }
+            Self::Nullifier => {
+                H160::from_low_u64_be(NULLIFIER_INNER_FUNCTION_PRECOMPILE_ADDRESS as u64)
+            }
+        }
+    }
+
+    pub fn to_address<F: SmallField, CS: ConstraintSystem<F>>(self, cs: &mut CS) -> UInt160<F> {
+        UInt160::allocated_constant(cs, self.address())
+    }
+}
@@ -10,10 +10,12 @@ use boojum::{
         num::Num,
         traits::{
             allocatable::{CSAllocatable, CSAllocatableExt, CSPlaceholder},
+            castable::WitnessCastable,
             encodable::{CircuitEncodable, CircuitEncodableExt, CircuitVarLengthEncodable},
             selectable::Selectable,
             witnessable::WitnessHookable,
         },
+        u32::UInt32,
     },
     serde_utils::BigArraySerde,
 };
@@ -27,8 +29,14 @@ use crate::fsm_input_output::circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH;
 pub struct RecursionQuery<F: SmallField> {
     pub circuit_type: Num<F>,
     pub input_commitment: [Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH],
+    pub circuit_version: UInt32<F>,
 }
 
+// version of the basic circuits that scheduler expects to be proven. It's checked by the leaf
+// layer against the version in the leaf parameters, so proofs of the same circuit type from
+// different versions can not be mixed
+pub const CURRENT_CIRCUIT_VERSION: u32 = 0;
+
 pub const RECURSION_QUERY_PACKED_WIDTH: usize = 8;
 
 impl<F: SmallField> CircuitEncodable<F, RECURSION_QUERY_PACKED_WIDTH> for RecursionQuery<F> {
@@ -38,12 +46,13 @@ impl<F: SmallField> CircuitEncodable<F, RECURSION_QUERY_PACKED_WIDTH> for Recurs
     ) -> [Variable; RECURSION_QUERY_PACKED_WIDTH] {
         let zero = cs.allocate_constant(F::ZERO);
         let [t0, t1, t2, t3] = self.input_commitment.map(|el| el.get_variable());
-        [self.circuit_type.get_variable(), t0, t1, t2, t3, zero, zero, zero]
+        let version = self.circuit_version.get_variable();
+        [self.circuit_type.get_variable(), t0, t1, t2, t3, version, zero, zero]
     }
 }
 
 impl<F: SmallField> CSAllocatableExt<F> for RecursionQuery<F> {
-    const INTERNAL_STRUCT_LEN: usize = 5;
+    const INTERNAL_STRUCT_LEN: usize = 6;
 
     fn witness_from_set_of_values(values: [F; Self::INTERNAL_STRUCT_LEN]) -> Self::Witness {
         let circuit_type = values[0];
@@ -53,7 +62,9 @@ impl<F: SmallField> CSAllocatableExt<F> for RecursionQuery<F> {
         let t2 = values[3];
         let t3 = values[4];
 
-        Self::Witness { circuit_type, input_commitment: [t0, t1, t2, t3] }
+        let circuit_version: u32 = WitnessCastable::cast_from_source(values[5]);
+
+        Self::Witness { circuit_type, input_commitment: [t0, t1, t2, t3], circuit_version }
     }
 
     fn flatten_as_variables(&self) -> [Variable; Self::INTERNAL_STRUCT_LEN]
@@ -66,6 +77,7 @@ impl<F: SmallField> CSAllocatableExt<F> for RecursionQuery<F> {
             self.input_commitment[1].get_variable(),
             self.input_commitment[2].get_variable(),
             self.input_commitment[3].get_variable(),
+            self.circuit_version.get_variable(),
         ]
     }
     fn set_internal_variables_values(witness: Self::Witness, dst: &mut DstBuffer<'_, '_, F>) {
@@ -73,6 +85,7 @@ impl<F: SmallField> CSAllocatableExt<F> for RecursionQuery<F> {
         for src in witness.input_commitment.into_iter() {
             Num::set_internal_variables_values(src, dst);
         }
+        UInt32::set_internal_variables_values(witness.circuit_version, dst);
     }
 }
 
@@ -85,6 +98,7 @@ impl<F: SmallField> CSPlaceholder<F> for RecursionQuery<F> {
         Self {
             circuit_type: zero_num,
             input_commitment: [zero_num; INPUT_OUTPUT_COMMITMENT_LENGTH],
+            circuit_version: UInt32::zero(cs),
         }
     }
 }
@@ -98,5 +112,78 @@ pub type RecursionQueryQueue<F, const AW: usize, const SW: usize, const CW: usiz
 
 pub type RecursionQueue<F, R> = RecursionQueryQueue<F, 8, 12, 4, R>;
 
+impl<F: SmallField> RecursionQuery<F> {
+    pub fn conditionally_enforce_version<CS: ConstraintSystem<F>>(
+        &self,
+        cs: &mut CS,
+        should_enforce: Boolean<F>,
+        expected_version: UInt32<F>,
+    ) {
+        Num::conditionally_enforce_equal(
+            cs,
+            should_enforce,
+            &self.circuit_version.into_num(),
+            &expected_version.into_num(),
+        );
+    }
+}
+
 pub type RecursionQueueWitness<F, const SW: usize> =
     FullStateCircuitQueueWitness<F, RecursionQuery<F>, SW, RECURSION_QUERY_PACKED_WIDTH>;
+
+#[cfg(test)]
+mod tests {
+    use boojum::{field::goldilocks::GoldilocksField, worker::Worker};
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    fn allocate_query<CS: ConstraintSystem<F>>(cs: &mut CS, version: u32) -> RecursionQuery<F> {
+        let witness = RecursionQueryWitness {
+            circuit_type: F::from_u64_unchecked(3),
+            input_commitment: [F::from_u64_unchecked(1); INPUT_OUTPUT_COMMITMENT_LENGTH],
+            circuit_version: version,
+        };
+
+        RecursionQuery::allocate(cs, witness)
+    }
+
+    #[test]
+    fn test_recursion_query_version_check() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let query = allocate_query(cs, CURRENT_CIRCUIT_VERSION);
+        let expected_version = UInt32::allocated_constant(cs, CURRENT_CIRCUIT_VERSION);
+        let boolean_true = Boolean::allocated_constant(cs, true);
+        query.conditionally_enforce_version(cs, boolean_true, expected_version);
+
+        // no check if it's not enforced
+        let query = allocate_query(cs, CURRENT_CIRCUIT_VERSION + 1);
+        let boolean_false = Boolean::allocated_constant(cs, false);
+        query.conditionally_enforce_version(cs, boolean_false, expected_version);
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+
+    #[test]
+    fn test_recursion_query_version_mismatch() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let query = allocate_query(cs, CURRENT_CIRCUIT_VERSION + 1);
+        let expected_version = UInt32::allocated_constant(cs, CURRENT_CIRCUIT_VERSION);
+        let boolean_true = Boolean::allocated_constant(cs, true);
+        query.conditionally_enforce_version(cs, boolean_true, expected_version);
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(!owned_cs.check_if_satisfied(&worker));
+    }
+}
@@ -8,12 +8,13 @@ use boojum::{
         boolean::Boolean,
         traits::{
             allocatable::{CSAllocatable, CSAllocatableExt},
+            castable::WitnessCastable,
             encodable::CircuitVarLengthEncodable,
             selectable::Selectable,
             witnessable::WitnessHookable,
         },
         u16::UInt16,
-        u256::UInt256,
+        u256::{recompose_u256_as_u32x8, UInt256},
         u32::UInt32,
     },
 };
@@ -76,6 +77,14 @@ impl<F: SmallField> VMRegister<F> {
         self.value.inner[1] = self.value.inner[1].mask_negated(cs, condition);
         self.value.inner[2] = self.value.inner[2].mask_negated(cs, condition);
     }
+
+    pub fn equals<CS: ConstraintSystem<F>>(cs: &mut CS, a: &Self, b: &Self) -> Boolean<F> {
+        let pointer_flags_differ = a.is_pointer.xor(cs, b.is_pointer);
+        let pointer_flags_are_equal = pointer_flags_differ.negated(cs);
+        let values_are_equal = UInt256::equals(cs, &a.value, &b.value);
+
+        Boolean::multi_and(cs, &[pointer_flags_are_equal, values_are_equal])
+    }
 }
 
 impl<F: SmallField> CSAllocatableExt<F> for VMRegister<F> {
@@ -98,11 +107,68 @@ impl<F: SmallField> CSAllocatableExt<F> for VMRegister<F> {
         ]
     }
 
-    fn set_internal_variables_values(_witness: Self::Witness, _dst: &mut DstBuffer<'_, '_, F>) {
-        todo!()
+    fn set_internal_variables_values(witness: Self::Witness, dst: &mut DstBuffer<'_, '_, F>) {
+        // NOTE: must be same sequence as in `flatten_as_variables`
+        Boolean::set_internal_variables_values(witness.is_pointer, dst);
+        UInt256::set_internal_variables_values(witness.value, dst);
     }
 
-    fn witness_from_set_of_values(_values: [F; Self::INTERNAL_STRUCT_LEN]) -> Self::Witness {
-        todo!()
+    fn witness_from_set_of_values(values: [F; Self::INTERNAL_STRUCT_LEN]) -> Self::Witness {
+        let is_pointer: bool = WitnessCastable::cast_from_source(values[0]);
+        let value: [u32; 8] = [
+            WitnessCastable::cast_from_source(values[1]),
+            WitnessCastable::cast_from_source(values[2]),
+            WitnessCastable::cast_from_source(values[3]),
+            WitnessCastable::cast_from_source(values[4]),
+            WitnessCastable::cast_from_source(values[5]),
+            WitnessCastable::cast_from_source(values[6]),
+            WitnessCastable::cast_from_source(values[7]),
+            WitnessCastable::cast_from_source(values[8]),
+        ];
+        let value = recompose_u256_as_u32x8(value);
+
+        Self::Witness { is_pointer, value }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use boojum::worker::Worker;
+
+    use super::*;
+    use crate::{ethereum_types::U256, test_utils::create_test_cs};
+
+    #[test]
+    fn test_register_equals() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let value = U256::from_dec_str("123456789012345678901234567890").unwrap();
+        let other_value = value + U256::one();
+
+        for a_is_pointer in [false, true] {
+            for b_is_pointer in [false, true] {
+                for values_are_equal in [false, true] {
+                    let a = VMRegister {
+                        is_pointer: Boolean::allocate(cs, a_is_pointer),
+                        value: UInt256::allocate(cs, value),
+                    };
+                    let b_value = if values_are_equal { value } else { other_value };
+                    let b = VMRegister {
+                        is_pointer: Boolean::allocate(cs, b_is_pointer),
+                        value: UInt256::allocate(cs, b_value),
+                    };
+
+                    let result = VMRegister::equals(cs, &a, &b);
+                    let expected = (a_is_pointer == b_is_pointer) && values_are_equal;
+                    assert_eq!(result.witness_hook(&*cs)().unwrap(), expected);
+                }
+            }
+        }
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
     }
 }
@@ -0,0 +1,150 @@
+use boojum::{
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{
+        boolean::Boolean, non_native_field::implementations::*, traits::selectable::Selectable,
+    },
+    pairing::{
+        bls12_381::Fq as Bls12_381Fq,
+        ff::{Field, PrimeField},
+    },
+};
+
+// 381-bit modulus fits into 24 16-bit limbs
+pub const NUM_WORDS_FQ: usize = 24;
+
+pub type Bls12_381BaseNNFieldParams = NonNativeFieldOverU16Params<Bls12_381Fq, NUM_WORDS_FQ>;
+pub type Bls12_381BaseNNField<F> = NonNativeFieldOverU16<F, Bls12_381Fq, NUM_WORDS_FQ>;
+
+// G1 is `y^2 = x^3 + 4` over the base field
+const CURVE_B: u64 = 4;
+
+pub fn bls12_381_base_field_params() -> Bls12_381BaseNNFieldParams {
+    NonNativeFieldOverU16Params::create()
+}
+
+fn select_point<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    flag: Boolean<F>,
+    a: &(Bls12_381BaseNNField<F>, Bls12_381BaseNNField<F>),
+    b: &(Bls12_381BaseNNField<F>, Bls12_381BaseNNField<F>),
+) -> (Bls12_381BaseNNField<F>, Bls12_381BaseNNField<F>) {
+    let x = Selectable::conditionally_select(cs, flag, &a.0, &b.0);
+    let y = Selectable::conditionally_select(cs, flag, &a.1, &b.1);
+
+    (x, y)
+}
+
+/// The point at infinity is encoded as `(0, 0)`, that does not belong to the curve
+pub fn is_point_at_infinity<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    point: &mut (Bls12_381BaseNNField<F>, Bls12_381BaseNNField<F>),
+) -> Boolean<F> {
+    let params = point.0.params.clone();
+    let mut zero_nn = Bls12_381BaseNNField::allocated_constant(cs, Bls12_381Fq::zero(), &params);
+
+    let x_is_zero = Bls12_381BaseNNField::equals(cs, &mut point.0, &mut zero_nn);
+    let y_is_zero = Bls12_381BaseNNField::equals(cs, &mut point.1, &mut zero_nn);
+
+    Boolean::multi_and(cs, &[x_is_zero, y_is_zero])
+}
+
+/// Checks `y^2 = x^3 + 4`, the point at infinity is accepted too
+pub fn is_on_curve<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    point: &mut (Bls12_381BaseNNField<F>, Bls12_381BaseNNField<F>),
+) -> Boolean<F> {
+    let params = point.0.params.clone();
+    let mut curve_b_nn = Bls12_381BaseNNField::allocated_constant(
+        cs,
+        Bls12_381Fq::from_str(&CURVE_B.to_string()).unwrap(),
+        &params,
+    );
+
+    let mut lhs = point.1.square(cs);
+    lhs.normalize(cs);
+
+    let mut rhs = point.0.square(cs);
+    let mut rhs = rhs.mul(cs, &mut point.0);
+    let mut rhs = rhs.add(cs, &mut curve_b_nn);
+    rhs.normalize(cs);
+
+    let on_curve = Bls12_381BaseNNField::equals(cs, &mut lhs, &mut rhs);
+    let is_infinity = is_point_at_infinity(cs, point);
+
+    Boolean::multi_or(cs, &[on_curve, is_infinity])
+}
+
+/// Adds two points of G1 in affine form, with `(0, 0)` for the point at infinity. Caller must
+/// ensure that both points are on the curve. The result is normalized
+pub fn bls12_381_g1_add<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    p: (Bls12_381BaseNNField<F>, Bls12_381BaseNNField<F>),
+    q: (Bls12_381BaseNNField<F>, Bls12_381BaseNNField<F>),
+) -> (Bls12_381BaseNNField<F>, Bls12_381BaseNNField<F>) {
+    let params = p.0.params.clone();
+    let mut p = p;
+    let mut q = q;
+
+    let zero_nn = Bls12_381BaseNNField::allocated_constant(cs, Bls12_381Fq::zero(), &params);
+    let one_nn = Bls12_381BaseNNField::allocated_constant(cs, Bls12_381Fq::one(), &params);
+
+    let p_is_infinity = is_point_at_infinity(cs, &mut p);
+    let q_is_infinity = is_point_at_infinity(cs, &mut q);
+
+    let x_equal = Bls12_381BaseNNField::equals(cs, &mut p.0, &mut q.0);
+    let y_equal = Bls12_381BaseNNField::equals(cs, &mut p.1, &mut q.1);
+    let y_not_equal = y_equal.negated(cs);
+    // for points on the curve equal `x` means that either `p = q` or `p = -q`
+    let is_opposite = Boolean::multi_and(cs, &[x_equal, y_not_equal]);
+
+    // tangent for doubling, `lambda = 3 * x1^2 / (2 * y1)`
+    let mut x1_squared = p.0.square(cs);
+    let mut two_x1_squared = x1_squared.add(cs, &mut x1_squared.clone());
+    let tangent_numerator = two_x1_squared.add(cs, &mut x1_squared);
+    let tangent_denominator = p.1.add(cs, &mut p.1.clone());
+
+    // chord otherwise, `lambda = (y2 - y1) / (x2 - x1)`
+    let chord_numerator = q.1.sub(cs, &mut p.1);
+    let chord_denominator = q.0.sub(cs, &mut p.0);
+
+    let mut denominator: Bls12_381BaseNNField<F> =
+        Selectable::conditionally_select(cs, x_equal, &tangent_denominator, &chord_denominator);
+
+    // G1 has no points of order 2, so the denominator is zero only if one of the points is at
+    // infinity. The sum is discarded in this case, and any non-zero dummy keeps the division
+    // well defined
+    denominator.normalize(cs);
+    let mut zero_to_compare = zero_nn.clone();
+    let denominator_is_zero =
+        Bls12_381BaseNNField::equals(cs, &mut denominator, &mut zero_to_compare);
+    let mut denominator =
+        Selectable::conditionally_select(cs, denominator_is_zero, &one_nn, &denominator);
+
+    let mut lambda = <Bls12_381BaseNNField<F> as Selectable<F>>::conditionally_select(
+        cs,
+        x_equal,
+        &tangent_numerator,
+        &chord_numerator,
+    )
+    .div_unchecked(cs, &mut denominator);
+
+    // x3 = lambda^2 - x1 - x2, y3 = lambda * (x1 - x3) - y1
+    let mut lambda_squared = lambda.square(cs);
+    let mut x3 = lambda_squared.sub(cs, &mut p.0);
+    let mut x3 = x3.sub(cs, &mut q.0);
+    let mut x1_minus_x3 = p.0.sub(cs, &mut x3);
+    let mut y3 = lambda.mul(cs, &mut x1_minus_x3);
+    let y3 = y3.sub(cs, &mut p.1);
+
+    let infinity = (zero_nn.clone(), zero_nn);
+    let sum = (x3, y3);
+    let result = select_point(cs, is_opposite, &infinity, &sum);
+    let result = select_point(cs, q_is_infinity, &p, &result);
+    let (mut x, mut y) = select_point(cs, p_is_infinity, &q, &result);
+
+    x.normalize(cs);
+    y.normalize(cs);
+
+    (x, y)
+}
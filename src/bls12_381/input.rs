@@ -0,0 +1,20 @@
+use std::collections::VecDeque;
+
+use boojum::gadgets::queue::*;
+
+use super::*;
+use crate::precompile_loop::input::*;
+
+pub type Bls12_381G1AddCircuitFSMInputOutput<F> = PrecompileFunctionFSMInputOutput<F>;
+pub type Bls12_381G1AddCircuitInputOutput<F> = PrecompileFunctionCircuitInputOutput<F>;
+pub type Bls12_381G1AddCircuitInputOutputWitness<F> =
+    PrecompileFunctionCircuitInputOutputWitness<F>;
+
+#[derive(Derivative, serde::Serialize, serde::Deserialize)]
+#[derivative(Clone, Debug, Default)]
+#[serde(bound = "")]
+pub struct Bls12_381G1AddCircuitInstanceWitness<F: SmallField> {
+    pub closed_form_input: Bls12_381G1AddCircuitInputOutputWitness<F>,
+    pub requests_queue_witness: CircuitQueueRawWitness<F, LogQuery<F>, 4, LOG_QUERY_PACKED_WIDTH>,
+    pub memory_reads_witness: VecDeque<[U256; MEMORY_QUERIES_PER_CALL]>,
+}
@@ -0,0 +1,366 @@
+use std::sync::Arc;
+
+use arrayvec::ArrayVec;
+use boojum::{
+    algebraic_props::round_function::AlgebraicRoundFunction,
+    crypto_bigint::{Zero, U1024},
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{
+        boolean::Boolean,
+        non_native_field::implementations::*,
+        num::Num,
+        traits::{
+            allocatable::CSAllocatableExt, round_function::CircuitRoundFunction,
+            selectable::Selectable, witnessable::WitnessHookable,
+        },
+        u16::UInt16,
+        u256::UInt256,
+        u32::UInt32,
+    },
+    pairing::{bls12_381::Fq as Bls12_381Fq, ff::Field},
+};
+
+use super::*;
+use crate::{
+    base_structures::{log_query::*, memory_query::*, precompile_kind::PrecompileKind},
+    ethereum_types::U256,
+    fsm_input_output::circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH,
+    gas_meter::BLS12_381_G1_ADD_GAS_COST_PER_CALL,
+    precompile_loop::{boolean_into_u256, precompile_loop_entry_point},
+};
+
+pub mod g1;
+pub mod input;
+
+pub use self::{g1::*, input::*};
+
+// every coordinate takes 64 bytes in big-endian form with the top 16 bytes being zero, so it spans
+// two memory words, the high one first
+pub const WORDS_PER_COORDINATE: usize = 2;
+// two points are read per call
+pub const MEMORY_QUERIES_PER_CALL: usize = 4 * WORDS_PER_COORDINATE;
+const EXCEPTION_FLAGS_ARR_LEN: usize = 6;
+
+/// Composes the field element from the high and low words of the coordinate. Returns whether the
+/// encoding is canonical, that is the top 128 bits of the high word are zero and the value is
+/// less than the modulus. The returned element is always reduced
+fn convert_words_to_field_element<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    high: &UInt256<F>,
+    low: &UInt256<F>,
+    params: &Arc<Bls12_381BaseNNFieldParams>,
+) -> (Bls12_381BaseNNField<F>, Boolean<F>) {
+    let zero_var = cs.allocate_constant(F::ZERO);
+    let mut limbs = [zero_var; NUM_WORDS_FQ];
+    for (dst, src) in limbs
+        .array_chunks_mut::<2>()
+        .zip(low.inner.iter().chain(high.inner[..4].iter()))
+    {
+        let [b0, b1, b2, b3] = src.to_le_bytes(cs);
+        let low_limb = UInt16::from_le_bytes(cs, [b0, b1]);
+        let high_limb = UInt16::from_le_bytes(cs, [b2, b3]);
+
+        *dst = [low_limb.get_variable(), high_limb.get_variable()];
+    }
+
+    let top_limbs_are_zero: ArrayVec<_, 4> =
+        high.inner[4..].iter().map(|el| el.is_zero(cs)).collect();
+    let top_limbs_are_zero = Boolean::multi_and(cs, &top_limbs_are_zero[..]);
+
+    let mut max_value = U1024::from_word(1u64);
+    max_value = max_value.shl_vartime(16 * NUM_WORDS_FQ);
+    max_value = max_value.saturating_sub(&U1024::from_word(1u64));
+
+    let (overflows, rem) = max_value.div_rem(&params.modulus_u1024);
+    assert!(overflows.lt(&U1024::from_word(1u64 << 32)));
+    let mut max_moduluses = overflows.as_words()[0] as u32;
+    if rem.is_zero().unwrap_u8() != 1 {
+        max_moduluses += 1;
+    }
+
+    let element = NonNativeFieldOverU16 {
+        limbs: limbs,
+        non_zero_limbs: NUM_WORDS_FQ,
+        tracker: OverflowTracker { max_moduluses },
+        form: RepresentationForm::Normalized,
+        params: params.clone(),
+        _marker: std::marker::PhantomData,
+    };
+
+    // reduction keeps the limbs intact only for values below the modulus
+    let mut reduced = element.clone();
+    reduced.normalize(cs);
+    let limbs_are_equal: ArrayVec<_, NUM_WORDS_FQ> = element
+        .limbs
+        .iter()
+        .zip(reduced.limbs.iter())
+        .map(|(a, b)| Num::equals(cs, &Num::from_variable(*a), &Num::from_variable(*b)))
+        .collect();
+    let is_in_range = Boolean::multi_and(cs, &limbs_are_equal[..]);
+
+    let is_canonical = Boolean::multi_and(cs, &[top_limbs_are_zero, is_in_range]);
+
+    (reduced, is_canonical)
+}
+
+// NOTE: caller must ensure that the field element is normalized
+fn convert_field_element_to_words<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    mut elem: Bls12_381BaseNNField<F>,
+) -> [UInt256<F>; WORDS_PER_COORDINATE] {
+    assert_eq!(elem.form, RepresentationForm::Normalized);
+    elem.tracker.assert_normalized();
+
+    let mut high = UInt256::zero(cs);
+    let mut low = UInt256::zero(cs);
+    let two_pow_16 = Num::allocated_constant(cs, F::from_u64_unchecked(2u32.pow(16) as u64));
+    for (dst, src) in low
+        .inner
+        .iter_mut()
+        .chain(high.inner[..4].iter_mut())
+        .zip(elem.limbs.array_chunks_mut::<2>())
+    {
+        let low_limb = Num::from_variable(src[0]);
+        let high_limb = Num::from_variable(src[1]);
+        *dst = unsafe {
+            UInt32::from_variable_unchecked(
+                Num::fma(cs, &high_limb, &two_pow_16, &F::ONE, &low_limb, &F::ONE).get_variable(),
+            )
+        };
+    }
+
+    [high, low]
+}
+
+/// Reads two points as `p_x, p_y, q_x, q_y` coordinates of two words each, and returns whether
+/// the encoding is valid and both points are on the curve, and the words of `p + q` that are
+/// zero on failure
+pub(crate) fn bls12_381_g1_add_function_inner<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    inputs: &[UInt256<F>; MEMORY_QUERIES_PER_CALL],
+    params: &Arc<Bls12_381BaseNNFieldParams>,
+) -> (Boolean<F>, [UInt256<F>; 2 * WORDS_PER_COORDINATE]) {
+    let mut exception_flags = ArrayVec::<_, EXCEPTION_FLAGS_ARR_LEN>::new();
+
+    let [p_x, p_y, q_x, q_y] = std::array::from_fn(|i| {
+        let high = &inputs[i * WORDS_PER_COORDINATE];
+        let low = &inputs[i * WORDS_PER_COORDINATE + 1];
+        let (coordinate, is_canonical) = convert_words_to_field_element(cs, high, low, params);
+        let is_not_canonical = is_canonical.negated(cs);
+        exception_flags.push(is_not_canonical);
+
+        coordinate
+    });
+
+    let mut p = (p_x, p_y);
+    let mut q = (q_x, q_y);
+    for point in [&mut p, &mut q] {
+        let on_curve = is_on_curve(cs, point);
+        let not_on_curve = on_curve.negated(cs);
+        exception_flags.push(not_on_curve);
+    }
+
+    let any_exception = Boolean::multi_or(cs, &exception_flags[..]);
+
+    // we mask both points to infinity to ensure that our arithmetic formulas work, and the sum is
+    // then the point at infinity as well
+    let zero_nn = Bls12_381BaseNNField::allocated_constant(cs, Bls12_381Fq::zero(), params);
+    let [p_x, p_y, q_x, q_y] = [p.0, p.1, q.0, q.1]
+        .map(|el| Selectable::conditionally_select(cs, any_exception, &zero_nn, &el));
+
+    let (x, y) = bls12_381_g1_add(cs, (p_x, p_y), (q_x, q_y));
+
+    let [x_high, x_low] = convert_field_element_to_words(cs, x);
+    let [y_high, y_low] = convert_field_element_to_words(cs, y);
+
+    if crate::config::CIRCUIT_VERSOBE {
+        dbg!([x_high, x_low, y_high, y_low].witness_hook(cs)());
+    }
+
+    let all_ok = any_exception.negated(cs);
+
+    (all_ok, [x_high, x_low, y_high, y_low])
+}
+
+pub fn bls12_381_g1_add_entry_point<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+>(
+    cs: &mut CS,
+    witness: Bls12_381G1AddCircuitInstanceWitness<F>,
+    round_function: &R,
+    limit: usize,
+) -> [Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH]
+where
+    [(); <LogQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <MemoryQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN + 1]:,
+{
+    let Bls12_381G1AddCircuitInstanceWitness {
+        closed_form_input,
+        requests_queue_witness,
+        memory_reads_witness,
+    } = witness;
+
+    let params = Arc::new(bls12_381_base_field_params());
+
+    precompile_loop_entry_point(
+        cs,
+        closed_form_input,
+        requests_queue_witness,
+        memory_reads_witness,
+        PrecompileKind::Bls12_381G1Add,
+        BLS12_381_G1_ADD_GAS_COST_PER_CALL,
+        round_function,
+        limit,
+        |cs, read_values| {
+            let (success, [x_high, x_low, y_high, y_low]) =
+                bls12_381_g1_add_function_inner(cs, &read_values, &params);
+
+            (success, [boolean_into_u256(cs, success), x_high, x_low, y_high, y_low])
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use boojum::{
+        field::goldilocks::GoldilocksField,
+        gadgets::traits::allocatable::CSAllocatable,
+        pairing::{
+            bls12_381::G1Affine,
+            ff::{PrimeField, PrimeFieldRepr},
+            CurveAffine, CurveProjective,
+        },
+        worker::Worker,
+    };
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    // generator of G1 as specified in EIP-2537
+    const GENERATOR_X: &str = "17f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb";
+    const GENERATOR_Y: &str = "08b3f481e3aaa0f1a09e30ed741d8ae4fcf5e095d5d00af600db18cb2c04b3edd03cc744a2888ae40caa232946c5e7e1";
+
+    fn words_from_hex(value: &str) -> [U256; WORDS_PER_COORDINATE] {
+        let (high, low) = value.split_at(value.len() - 64);
+
+        [U256::from_str_radix(high, 16).unwrap(), U256::from_str_radix(low, 16).unwrap()]
+    }
+
+    fn fq_into_words(el: Bls12_381Fq) -> [U256; WORDS_PER_COORDINATE] {
+        let repr = el.into_repr();
+        let limbs = repr.as_ref();
+
+        [U256([limbs[4], limbs[5], 0, 0]), U256([limbs[0], limbs[1], limbs[2], limbs[3]])]
+    }
+
+    fn point_into_words(point: G1Affine) -> [U256; 2 * WORDS_PER_COORDINATE] {
+        if point.is_zero() {
+            return [U256::zero(); 2 * WORDS_PER_COORDINATE];
+        }
+
+        let (x, y) = point.into_xy_unchecked();
+        let [x_high, x_low] = fq_into_words(x);
+        let [y_high, y_low] = fq_into_words(y);
+
+        [x_high, x_low, y_high, y_low]
+    }
+
+    fn native_add(p: G1Affine, q: G1Affine) -> G1Affine {
+        let mut sum = p.into_projective();
+        sum.add_assign_mixed(&q);
+
+        sum.into_affine()
+    }
+
+    fn add_in_circuit<CS: ConstraintSystem<F>>(
+        cs: &mut CS,
+        p: [U256; 2 * WORDS_PER_COORDINATE],
+        q: [U256; 2 * WORDS_PER_COORDINATE],
+        params: &Arc<Bls12_381BaseNNFieldParams>,
+    ) -> (bool, [U256; 2 * WORDS_PER_COORDINATE]) {
+        let mut inputs = [U256::zero(); MEMORY_QUERIES_PER_CALL];
+        inputs[..2 * WORDS_PER_COORDINATE].copy_from_slice(&p);
+        inputs[2 * WORDS_PER_COORDINATE..].copy_from_slice(&q);
+        let inputs = inputs.map(|el| UInt256::allocate(cs, el));
+
+        let (all_ok, result) = bls12_381_g1_add_function_inner(cs, &inputs, params);
+
+        (all_ok.witness_hook(&*cs)().unwrap(), result.witness_hook(&*cs)().unwrap())
+    }
+
+    #[test]
+    fn test_bls12_381_g1_add() {
+        let mut owned_cs = create_test_cs(1 << 22);
+        let cs = &mut owned_cs;
+
+        let params = Arc::new(bls12_381_base_field_params());
+
+        let generator = G1Affine::one();
+        let [x_high, x_low] = words_from_hex(GENERATOR_X);
+        let [y_high, y_low] = words_from_hex(GENERATOR_Y);
+        assert_eq!(point_into_words(generator), [x_high, x_low, y_high, y_low]);
+
+        let mut doubled = generator.into_projective();
+        doubled.double();
+        let doubled = doubled.into_affine();
+        let mut negated = generator;
+        negated.negate();
+        let infinity = G1Affine::zero();
+
+        let cases = [
+            (generator, doubled),
+            (doubled, generator),
+            (generator, generator),
+            (generator, negated),
+            (infinity, generator),
+            (generator, infinity),
+            (infinity, infinity),
+        ];
+        for (p, q) in cases.into_iter() {
+            let expected = point_into_words(native_add(p, q));
+            let (all_ok, result) =
+                add_in_circuit(cs, point_into_words(p), point_into_words(q), &params);
+            assert!(all_ok);
+            assert_eq!(result, expected);
+        }
+
+        // point that is not on the curve
+        let mut not_on_curve = point_into_words(generator);
+        not_on_curve[3] = not_on_curve[3] + U256::one();
+        let (all_ok, result) = add_in_circuit(cs, not_on_curve, point_into_words(doubled), &params);
+        assert!(all_ok == false);
+        assert_eq!(result, [U256::zero(); 2 * WORDS_PER_COORDINATE]);
+
+        // coordinate that is not less than the modulus
+        let modulus = Bls12_381Fq::char();
+        let modulus_limbs = modulus.as_ref();
+        let mut non_canonical = point_into_words(generator);
+        non_canonical[0] = U256([modulus_limbs[4], modulus_limbs[5], 0, 0]);
+        non_canonical[1] =
+            U256([modulus_limbs[0], modulus_limbs[1], modulus_limbs[2], modulus_limbs[3]]);
+        let (all_ok, result) =
+            add_in_circuit(cs, non_canonical, point_into_words(doubled), &params);
+        assert!(all_ok == false);
+        assert_eq!(result, [U256::zero(); 2 * WORDS_PER_COORDINATE]);
+
+        // top 16 bytes of the high word must be zero
+        let mut non_canonical = point_into_words(generator);
+        non_canonical[0] = non_canonical[0] + (U256::one() << 128);
+        let (all_ok, result) =
+            add_in_circuit(cs, non_canonical, point_into_words(doubled), &params);
+        assert!(all_ok == false);
+        assert_eq!(result, [U256::zero(); 2 * WORDS_PER_COORDINATE]);
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+}
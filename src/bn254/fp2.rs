@@ -0,0 +1,287 @@
+use std::sync::Arc;
+
+use boojum::{
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{boolean::Boolean, traits::selectable::Selectable},
+    pairing::{
+        bn256::Fq as Bn254Fq,
+        ff::{Field, PrimeField},
+    },
+};
+use derivative::*;
+
+use super::*;
+
+// non-residue of `Fp6 = Fp2[v]/(v^3 - xi)`, `xi = 9 + u`
+const XI_C0: u64 = 9;
+
+/// Element `c0 + c1 * u` of `Fp2 = Fp[u]/(u^2 + 1)`
+#[derive(Derivative)]
+#[derivative(Clone, Debug)]
+pub struct Fp2<F: SmallField>(pub Bn254NNField<F>, pub Bn254NNField<F>);
+
+impl<F: SmallField> Selectable<F> for Fp2<F> {
+    fn conditionally_select<CS: ConstraintSystem<F>>(
+        cs: &mut CS,
+        flag: Boolean<F>,
+        a: &Self,
+        b: &Self,
+    ) -> Self {
+        let c0 = Selectable::conditionally_select(cs, flag, &a.0, &b.0);
+        let c1 = Selectable::conditionally_select(cs, flag, &a.1, &b.1);
+
+        Self(c0, c1)
+    }
+}
+
+impl<F: SmallField> Fp2<F> {
+    pub fn allocated_constant<CS: ConstraintSystem<F>>(
+        cs: &mut CS,
+        c0: Bn254Fq,
+        c1: Bn254Fq,
+        params: &Arc<Bn254BaseNNFieldParams>,
+    ) -> Self {
+        let c0 = Bn254NNField::allocated_constant(cs, c0, params);
+        let c1 = Bn254NNField::allocated_constant(cs, c1, params);
+
+        Self(c0, c1)
+    }
+
+    pub fn zero<CS: ConstraintSystem<F>>(
+        cs: &mut CS,
+        params: &Arc<Bn254BaseNNFieldParams>,
+    ) -> Self {
+        Self::allocated_constant(cs, Bn254Fq::zero(), Bn254Fq::zero(), params)
+    }
+
+    pub fn one<CS: ConstraintSystem<F>>(cs: &mut CS, params: &Arc<Bn254BaseNNFieldParams>) -> Self {
+        Self::allocated_constant(cs, Bn254Fq::one(), Bn254Fq::zero(), params)
+    }
+
+    pub fn add<CS: ConstraintSystem<F>>(&mut self, cs: &mut CS, other: &mut Self) -> Self {
+        let c0 = self.0.add(cs, &mut other.0);
+        let c1 = self.1.add(cs, &mut other.1);
+
+        Self(c0, c1)
+    }
+
+    pub fn sub<CS: ConstraintSystem<F>>(&mut self, cs: &mut CS, other: &mut Self) -> Self {
+        let c0 = self.0.sub(cs, &mut other.0);
+        let c1 = self.1.sub(cs, &mut other.1);
+
+        Self(c0, c1)
+    }
+
+    pub fn negated<CS: ConstraintSystem<F>>(&mut self, cs: &mut CS) -> Self {
+        let c0 = self.0.negated(cs);
+        let c1 = self.1.negated(cs);
+
+        Self(c0, c1)
+    }
+
+    /// `(a + b * u) * (c + d * u) = (ac - bd) + (ad + bc) * u`
+    pub fn mul<CS: ConstraintSystem<F>>(&mut self, cs: &mut CS, other: &mut Self) -> Self {
+        let mut ac = self.0.mul(cs, &mut other.0);
+        let mut bd = self.1.mul(cs, &mut other.1);
+        let mut ad = self.0.mul(cs, &mut other.1);
+        let mut bc = self.1.mul(cs, &mut other.0);
+
+        let c0 = ac.sub(cs, &mut bd);
+        let c1 = ad.add(cs, &mut bc);
+
+        Self(c0, c1)
+    }
+
+    /// `(a + b * u)^2 = (a + b) * (a - b) + 2ab * u`
+    pub fn square<CS: ConstraintSystem<F>>(&mut self, cs: &mut CS) -> Self {
+        let mut a_plus_b = self.0.add(cs, &mut self.1.clone());
+        let mut a_minus_b = self.0.sub(cs, &mut self.1.clone());
+        let mut ab = self.0.mul(cs, &mut self.1);
+
+        let c0 = a_plus_b.mul(cs, &mut a_minus_b);
+        let c1 = ab.add(cs, &mut ab.clone());
+
+        Self(c0, c1)
+    }
+
+    pub fn conjugate<CS: ConstraintSystem<F>>(&mut self, cs: &mut CS) -> Self {
+        let c1 = self.1.negated(cs);
+
+        Self(self.0.clone(), c1)
+    }
+
+    /// `(a + b * u)^-1 = (a - b * u) / (a^2 + b^2)`. Caller must ensure that the element is not
+    /// zero, as the norm is inverted without checks
+    pub fn inverse<CS: ConstraintSystem<F>>(&mut self, cs: &mut CS) -> Self {
+        let mut a_squared = self.0.square(cs);
+        let mut b_squared = self.1.square(cs);
+        let mut norm = a_squared.add(cs, &mut b_squared);
+        let mut norm_inv = norm.inverse_unchecked(cs);
+
+        let c0 = self.0.mul(cs, &mut norm_inv);
+        let mut c1 = self.1.mul(cs, &mut norm_inv);
+        let c1 = c1.negated(cs);
+
+        Self(c0, c1)
+    }
+
+    /// Multiplies by `xi = 9 + u`, so `(a + b * u) * xi = (9a - b) + (a + 9b) * u`
+    pub fn mul_by_nonresidue<CS: ConstraintSystem<F>>(&mut self, cs: &mut CS) -> Self {
+        let params = self.0.params.clone();
+        let mut xi_c0 = Bn254NNField::allocated_constant(
+            cs,
+            Bn254Fq::from_str(&XI_C0.to_string()).unwrap(),
+            &params,
+        );
+
+        let mut nine_a = self.0.mul(cs, &mut xi_c0);
+        let mut nine_b = self.1.mul(cs, &mut xi_c0);
+
+        let c0 = nine_a.sub(cs, &mut self.1);
+        let c1 = nine_b.add(cs, &mut self.0);
+
+        Self(c0, c1)
+    }
+
+    /// Raises the element to `p^power`. As `p = 3 mod 4` we have `u^p = -u`, so odd powers are
+    /// conjugation and even powers are identity
+    pub fn frobenius_map<CS: ConstraintSystem<F>>(&mut self, cs: &mut CS, power: u32) -> Self {
+        if power % 2 == 1 { self.conjugate(cs) } else { self.clone() }
+    }
+
+    pub fn normalize<CS: ConstraintSystem<F>>(&mut self, cs: &mut CS) {
+        self.0.normalize(cs);
+        self.1.normalize(cs);
+    }
+
+    pub fn equals<CS: ConstraintSystem<F>>(cs: &mut CS, a: &mut Self, b: &mut Self) -> Boolean<F> {
+        let c0_equal = Bn254NNField::equals(cs, &mut a.0, &mut b.0);
+        let c1_equal = Bn254NNField::equals(cs, &mut a.1, &mut b.1);
+
+        Boolean::multi_and(cs, &[c0_equal, c1_equal])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use boojum::{
+        field::goldilocks::GoldilocksField,
+        gadgets::{
+            traits::{allocatable::CSAllocatable, witnessable::WitnessHookable},
+            u256::UInt256,
+        },
+        pairing::{bn256::Fq2 as Bn254Fq2, ff::PrimeFieldRepr},
+        worker::Worker,
+    };
+    use rand::{Rng, SeedableRng, XorShiftRng};
+
+    use super::*;
+    use crate::{
+        ecrecover::new_optimized::{
+            convert_field_element_to_uint256, convert_uint256_to_field_element,
+        },
+        ethereum_types::U256,
+        test_utils::create_test_cs,
+    };
+
+    type F = GoldilocksField;
+
+    fn repr_into_u256<T: PrimeFieldRepr>(repr: T) -> U256 {
+        let mut u256 = U256::zero();
+        u256.0.copy_from_slice(&repr.as_ref()[..4]);
+
+        u256
+    }
+
+    fn allocate_fp2<CS: ConstraintSystem<F>>(
+        cs: &mut CS,
+        value: Bn254Fq2,
+        params: &Arc<Bn254BaseNNFieldParams>,
+    ) -> Fp2<F> {
+        let [c0, c1] = [value.c0, value.c1].map(|el| {
+            let el = UInt256::allocate(cs, repr_into_u256(el.into_repr()));
+            convert_uint256_to_field_element(cs, &el, params)
+        });
+
+        Fp2(c0, c1)
+    }
+
+    fn fp2_witness<CS: ConstraintSystem<F>>(cs: &mut CS, mut value: Fp2<F>) -> [U256; 2] {
+        value.normalize(cs);
+        let c0 = convert_field_element_to_uint256(cs, value.0);
+        let c1 = convert_field_element_to_uint256(cs, value.1);
+
+        [c0.witness_hook(&*cs)().unwrap(), c1.witness_hook(&*cs)().unwrap()]
+    }
+
+    fn native_witness(value: Bn254Fq2) -> [U256; 2] {
+        [value.c0, value.c1].map(|el| repr_into_u256(el.into_repr()))
+    }
+
+    #[test]
+    fn test_fp2_inverse() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let params = Arc::new(bn254_base_field_params());
+        let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        let mut one = Fp2::one(cs, &params);
+        for _ in 0..4 {
+            let mut x = allocate_fp2(cs, rng.gen(), &params);
+            let mut x_inv = x.inverse(cs);
+            let mut product = x.mul(cs, &mut x_inv);
+            product.normalize(cs);
+
+            let is_one = Fp2::equals(cs, &mut product, &mut one);
+            assert!(is_one.witness_hook(&*cs)().unwrap() == true);
+        }
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+
+    #[test]
+    fn test_fp2_against_native() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let params = Arc::new(bn254_base_field_params());
+        let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        let a: Bn254Fq2 = rng.gen();
+        let b: Bn254Fq2 = rng.gen();
+        let mut a_var = allocate_fp2(cs, a, &params);
+        let mut b_var = allocate_fp2(cs, b, &params);
+
+        let mut expected = a;
+        expected.mul_assign(&b);
+        let result = a_var.mul(cs, &mut b_var);
+        assert_eq!(fp2_witness(cs, result), native_witness(expected));
+
+        let mut expected = a;
+        expected.square();
+        let result = a_var.square(cs);
+        assert_eq!(fp2_witness(cs, result), native_witness(expected));
+
+        let mut expected = a;
+        expected.mul_by_nonresidue();
+        let result = a_var.mul_by_nonresidue(cs);
+        assert_eq!(fp2_witness(cs, result), native_witness(expected));
+
+        for power in 0..4 {
+            let mut expected = a;
+            expected.frobenius_map(power as usize);
+            let result = a_var.frobenius_map(cs, power);
+            assert_eq!(fp2_witness(cs, result), native_witness(expected));
+        }
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+}
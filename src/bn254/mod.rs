@@ -0,0 +1,20 @@
+use boojum::{
+    gadgets::non_native_field::implementations::*,
+    pairing::bn256::{Fq as Bn254Fq, Fr as Bn254Fr},
+};
+
+pub mod fp2;
+
+pub type Bn254BaseNNFieldParams = NonNativeFieldOverU16Params<Bn254Fq, 17>;
+pub type Bn254ScalarNNFieldParams = NonNativeFieldOverU16Params<Bn254Fr, 17>;
+
+pub type Bn254NNField<F> = NonNativeFieldOverU16<F, Bn254Fq, 17>;
+pub type Bn254ScalarNNField<F> = NonNativeFieldOverU16<F, Bn254Fr, 17>;
+
+pub fn bn254_base_field_params() -> Bn254BaseNNFieldParams {
+    NonNativeFieldOverU16Params::create()
+}
+
+pub fn bn254_scalar_field_params() -> Bn254ScalarNNFieldParams {
+    NonNativeFieldOverU16Params::create()
+}
@@ -0,0 +1,85 @@
+use boojum::{
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{boolean::Boolean, traits::selectable::Selectable, u8::UInt8},
+};
+
+pub const PUSH1_OPCODE: u8 = 0x60;
+pub const MAX_PUSH_DATA_LEN: u8 = 32;
+
+/// Walks over the bytecode and checks that the data of the last PUSH1-PUSH32 is not truncated.
+/// `bytes_remaining` is the number of data bytes that follow, and only bytes where it's zero are
+/// interpreted as opcodes
+pub fn bytecode_validity_entry_point<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    bytecode: &[UInt8<F>],
+) -> Boolean<F> {
+    let zero_u8 = UInt8::zero(cs);
+    let one_u8 = UInt8::allocated_constant(cs, 1);
+    let push0_opcode = UInt8::allocated_constant(cs, PUSH1_OPCODE - 1);
+    let push1_opcode = UInt8::allocated_constant(cs, PUSH1_OPCODE);
+    let max_push_data_len = UInt8::allocated_constant(cs, MAX_PUSH_DATA_LEN);
+
+    let mut bytes_remaining = zero_u8;
+    for byte in bytecode.iter() {
+        let is_opcode = bytes_remaining.is_zero(cs);
+
+        // `push_n - 1` for PUSH-n, underflows or goes past 31 for anything else
+        let (push_n_minus_one, below_push1) = byte.overflowing_sub(cs, &push1_opcode);
+        let (_, fits_push32) = push_n_minus_one.overflowing_sub(cs, &max_push_data_len);
+        let at_least_push1 = below_push1.negated(cs);
+        let is_push = Boolean::multi_and(cs, &[is_opcode, at_least_push1, fits_push32]);
+
+        // meaningless unless it's PUSH-n, but must not overflow in any case
+        let (push_n, _) = byte.overflowing_sub(cs, &push0_opcode);
+        let (decremented, _) = bytes_remaining.overflowing_sub(cs, &one_u8);
+
+        let next = UInt8::conditionally_select(cs, is_opcode, &zero_u8, &decremented);
+        bytes_remaining = UInt8::conditionally_select(cs, is_push, &push_n, &next);
+    }
+
+    bytes_remaining.is_zero(cs)
+}
+
+#[cfg(test)]
+mod test {
+    use boojum::{
+        field::goldilocks::GoldilocksField,
+        gadgets::traits::{allocatable::CSAllocatable, witnessable::WitnessHookable},
+    };
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    fn is_valid_bytecode(bytecode: &str) -> bool {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let bytes = hex::decode(bytecode).unwrap();
+        let bytes: Vec<_> = bytes
+            .into_iter()
+            .map(|el| UInt8::<F>::allocate(cs, el))
+            .collect();
+
+        let is_valid = bytecode_validity_entry_point(cs, &bytes);
+
+        is_valid.witness_hook(&*cs)().unwrap()
+    }
+
+    #[test]
+    fn test_bytecode_validity() {
+        // PUSH1 0x80 PUSH1 0x40 MSTORE PUSH32 0x5b..5b PUSH2 0x0060 JUMP, where JUMPDEST and
+        // PUSH1 inside of the data must not be interpreted
+        let bytecode = format!("6080604052{}{}61006056", "7f", "5b60".repeat(16));
+        assert!(is_valid_bytecode(&bytecode));
+    }
+
+    #[test]
+    fn test_bytecode_validity_truncated_push32() {
+        // PUSH1 0x80 PUSH32 with only 31 bytes of data
+        let bytecode = format!("60807f{}", "ff".repeat(31));
+        assert!(is_valid_bytecode(&bytecode) == false);
+    }
+}
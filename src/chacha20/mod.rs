@@ -0,0 +1,212 @@
+use boojum::{
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{
+        tables::{ByteSplitTable, Xor8Table},
+        u32::UInt32,
+        u8::UInt8,
+    },
+};
+
+use crate::{
+    tables::{get_typed_table_id, TypedTableId},
+    utils::{rotate_bits_right, rotate_bytes_right, xor_into_bytes},
+};
+
+pub const CHACHA20_BLOCK_SIZE: usize = 64;
+pub const CHACHA20_NUM_DOUBLE_ROUNDS: usize = 10;
+
+// "expand 32-byte k"
+pub const CHACHA20_CONSTANTS: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+// column and diagonal quarter rounds, as `(a, b, c, d)` indexes into the state
+const CHACHA20_QUARTER_ROUND_INDEXES: [[usize; 4]; 8] = [
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+    [0, 5, 10, 15],
+    [1, 6, 11, 12],
+    [2, 7, 8, 13],
+    [3, 4, 9, 14],
+];
+
+struct ChaCha20Tables {
+    xor: TypedTableId<Xor8Table>,
+    split_at_1: TypedTableId<ByteSplitTable<1>>,
+    split_at_4: TypedTableId<ByteSplitTable<4>>,
+}
+
+impl ChaCha20Tables {
+    fn new<F: SmallField, CS: ConstraintSystem<F>>(cs: &CS) -> Self {
+        Self {
+            xor: get_typed_table_id::<Xor8Table, F, CS>(cs),
+            split_at_1: get_typed_table_id::<ByteSplitTable<1>, F, CS>(cs),
+            split_at_4: get_typed_table_id::<ByteSplitTable<4>, F, CS>(cs),
+        }
+    }
+}
+
+// rotations to the left by 16, 12, 8 and 7 are done as rotations to the right by 16, 20, 24
+// and 25
+fn quarter_round<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: &ChaCha20Tables,
+    state: &mut [UInt32<F>; 16],
+    [a, b, c, d]: [usize; 4],
+) {
+    (state[a], _) = state[a].overflowing_add(cs, state[b]);
+    let bytes = xor_into_bytes(cs, &tables.xor, &state[d], &state[a]);
+    state[d] = UInt32::from_le_bytes(cs, rotate_bytes_right(bytes, 2));
+
+    (state[c], _) = state[c].overflowing_add(cs, state[d]);
+    let bytes = xor_into_bytes(cs, &tables.xor, &state[b], &state[c]);
+    let bytes = rotate_bits_right(cs, &tables.split_at_4, rotate_bytes_right(bytes, 2));
+    state[b] = UInt32::from_le_bytes(cs, bytes);
+
+    (state[a], _) = state[a].overflowing_add(cs, state[b]);
+    let bytes = xor_into_bytes(cs, &tables.xor, &state[d], &state[a]);
+    state[d] = UInt32::from_le_bytes(cs, rotate_bytes_right(bytes, 3));
+
+    (state[c], _) = state[c].overflowing_add(cs, state[d]);
+    let bytes = xor_into_bytes(cs, &tables.xor, &state[b], &state[c]);
+    let bytes = rotate_bits_right(cs, &tables.split_at_1, rotate_bytes_right(bytes, 3));
+    state[b] = UInt32::from_le_bytes(cs, bytes);
+}
+
+/// ChaCha20 block function from RFC 8439. Words are split into bytes for xors and rotations, so
+/// `Xor8Table`, `ByteSplitTable<1>` and `ByteSplitTable<4>` must be present in the CS
+pub fn chacha20_block<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    key: [UInt32<F>; 8],
+    nonce: [UInt32<F>; 3],
+    counter: UInt32<F>,
+) -> [UInt32<F>; 16] {
+    let tables = ChaCha20Tables::new(cs);
+
+    let constants = CHACHA20_CONSTANTS.map(|el| UInt32::allocated_constant(cs, el));
+    let mut initial_state = [counter; 16];
+    initial_state[..4].copy_from_slice(&constants);
+    initial_state[4..12].copy_from_slice(&key);
+    initial_state[13..].copy_from_slice(&nonce);
+
+    let mut state = initial_state;
+    for _ in 0..CHACHA20_NUM_DOUBLE_ROUNDS {
+        for indexes in CHACHA20_QUARTER_ROUND_INDEXES.iter() {
+            quarter_round(cs, &tables, &mut state, *indexes);
+        }
+    }
+
+    std::array::from_fn(|i| state[i].overflowing_add(cs, initial_state[i]).0)
+}
+
+/// Serialized keystream of `num_blocks` consecutive blocks starting from `initial_counter`. The
+/// counter must not overflow
+pub fn chacha20_keystream_entry_point<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    key: [UInt32<F>; 8],
+    nonce: [UInt32<F>; 3],
+    initial_counter: UInt32<F>,
+    num_blocks: usize,
+) -> Vec<UInt8<F>> {
+    assert!(num_blocks <= u32::MAX as usize);
+
+    let mut keystream = Vec::with_capacity(num_blocks * CHACHA20_BLOCK_SIZE);
+    for block_idx in 0..num_blocks {
+        let offset = UInt32::allocated_constant(cs, block_idx as u32);
+        let counter = initial_counter.add_no_overflow(cs, offset);
+        let block = chacha20_block(cs, key, nonce, counter);
+        for word in block.iter() {
+            keystream.extend(word.to_le_bytes(cs));
+        }
+    }
+
+    keystream
+}
+
+#[cfg(test)]
+mod test {
+    use boojum::{
+        field::goldilocks::GoldilocksField,
+        gadgets::traits::{allocatable::CSAllocatable, witnessable::WitnessHookable},
+        worker::Worker,
+    };
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    // RFC 8439 test vectors use 00:01:02:...:1f as the key
+    fn allocate_key_and_nonce<CS: ConstraintSystem<F>>(
+        cs: &mut CS,
+        nonce: [u8; 12],
+    ) -> ([UInt32<F>; 8], [UInt32<F>; 3]) {
+        let key: [u8; 32] = std::array::from_fn(|i| i as u8);
+        let key = std::array::from_fn(|i| {
+            UInt32::allocate(cs, u32::from_le_bytes(key[4 * i..4 * i + 4].try_into().unwrap()))
+        });
+        let nonce = std::array::from_fn(|i| {
+            UInt32::allocate(cs, u32::from_le_bytes(nonce[4 * i..4 * i + 4].try_into().unwrap()))
+        });
+
+        (key, nonce)
+    }
+
+    #[test]
+    fn test_chacha20_block() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        // RFC 8439, section 2.3.2
+        let nonce = [0, 0, 0, 0x09, 0, 0, 0, 0x4a, 0, 0, 0, 0];
+        let (key, nonce) = allocate_key_and_nonce(cs, nonce);
+        let counter = UInt32::allocate(cs, 1);
+
+        let block = chacha20_block(cs, key, nonce, counter);
+        let block = block.witness_hook(&*cs)().unwrap();
+
+        let expected = [
+            0xe4e7f110, 0x15593bd1, 0x1fdd0f50, 0xc47120a3, 0xc7f4d1c7, 0x0368c033, 0x9aaa2204,
+            0x4e6cd4c3, 0x466482d2, 0x09aa9f07, 0x05d7c214, 0xa2028bd9, 0xd19c12b5, 0xb94e16de,
+            0xe883d0cb, 0x4e3c50a2,
+        ];
+        assert_eq!(block, expected);
+
+        let worker = Worker::new();
+        owned_cs.pad_and_shrink();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+
+    #[test]
+    fn test_chacha20_keystream() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        // RFC 8439, section 2.4.2
+        let nonce = [0, 0, 0, 0, 0, 0, 0, 0x4a, 0, 0, 0, 0];
+        let (key, nonce) = allocate_key_and_nonce(cs, nonce);
+        let counter = UInt32::allocate(cs, 1);
+
+        let keystream = chacha20_keystream_entry_point(cs, key, nonce, counter, 2);
+        let keystream: Vec<u8> = keystream
+            .iter()
+            .map(|el| el.witness_hook(&*cs)().unwrap())
+            .collect();
+
+        let expected = hex::decode(
+            "224f51f3401bd9e12fde276fb8631ded8c131f823d2c06e27e4fcaec9ef3cf788a3b0aa372600a92\
+             b57974cded2b9334794cba40c63e34cdea212c4cf07d41b769a6749f3f630f4122cafe28ec4dc47e\
+             26d4346d70b98c73f3e9c53ac40c5945398b6eda1a832c89c167eacd901d7e2bf363740373201aa1\
+             88fbbce83991c4ed",
+        )
+        .unwrap();
+        assert_eq!(keystream, expected);
+
+        let worker = Worker::new();
+        owned_cs.pad_and_shrink();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+}
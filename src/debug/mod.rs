@@ -0,0 +1,4 @@
+pub mod span;
+pub mod witness_dump;
+
+pub use self::{span::*, witness_dump::*};
@@ -0,0 +1,57 @@
+use boojum::{cs::traits::cs::ConstraintSystem, field::SmallField};
+
+/// Marks a region of synthesis and measures how many rows were used by it. Output is only
+/// printed with `verbose_circuits` feature, so markers can stay in production code
+pub struct CircuitSpan;
+
+impl CircuitSpan {
+    pub fn begin<F: SmallField>(
+        cs: &impl ConstraintSystem<F>,
+        label: &'static str,
+    ) -> (usize, &'static str) {
+        (cs.next_available_row(), label)
+    }
+
+    /// Returns the number of rows used since the corresponding `begin`
+    pub fn end<F: SmallField>(
+        cs: &impl ConstraintSystem<F>,
+        (start, label): (usize, &'static str),
+    ) -> usize {
+        let num_gates = cs.next_available_row() - start;
+        if crate::config::CIRCUIT_VERSOBE {
+            eprintln!("{}: {} gates", label, num_gates);
+        }
+
+        num_gates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use boojum::{field::goldilocks::GoldilocksField, gadgets::num::Num};
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_span_counts_rows() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let empty_span = CircuitSpan::begin(&*cs, "empty");
+        assert_eq!(CircuitSpan::end(&*cs, empty_span), 0);
+
+        let span = CircuitSpan::begin(&*cs, "multiplications");
+        let start = cs.next_available_row();
+        let mut acc = Num::allocate(cs, F::TWO);
+        // FMA gates are placed in general purpose columns, so enough of them must use new rows
+        for _ in 0..1000 {
+            acc = acc.mul(cs, &acc);
+        }
+        let num_gates = CircuitSpan::end(&*cs, span);
+        assert!(num_gates > 0);
+        assert_eq!(num_gates, cs.next_available_row() - start);
+    }
+}
@@ -0,0 +1,67 @@
+use std::io::Write;
+
+use boojum::{
+    cs::{traits::cs::ConstraintSystem, Variable},
+    field::SmallField,
+    gadgets::{num::Num, traits::witnessable::WitnessHookable},
+};
+
+/// Writes `(variable_id, value)` rows for every variable allocated so far. Variables without a
+/// resolved witness are written with an empty value
+pub fn dump_witness_to_csv<F: SmallField>(
+    cs: &impl ConstraintSystem<F>,
+    path: &std::path::Path,
+) -> std::io::Result<()> {
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+    writeln!(writer, "variable_id,value")?;
+
+    for idx in 0..cs.next_available_place_idx() {
+        let variable = Variable::from_variable_index(idx);
+        match Num::from_variable(variable).witness_hook(cs)() {
+            Some(value) => writeln!(writer, "{},{}", idx, value.as_u64_reduced())?,
+            None => writeln!(writer, "{},", idx)?,
+        }
+    }
+
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use boojum::{field::goldilocks::GoldilocksField, worker::Worker};
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_dump_witness_of_unsatisfied_cs() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        // enforce 0 == 1
+        let zero = Num::allocate(cs, F::ZERO);
+        let one = Num::allocate(cs, F::ONE);
+        Num::enforce_equal(cs, &zero, &one);
+
+        let path = std::env::temp_dir().join("zkevm_circuits_witness_dump_test.csv");
+        dump_witness_to_csv(&*cs, &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut lines = content.lines();
+        assert_eq!(lines.next(), Some("variable_id,value"));
+        let rows: Vec<_> = lines.collect();
+        assert_eq!(rows.len() as u64, cs.next_available_place_idx());
+        let zero_row = format!("{},0", zero.get_variable().as_variable_index());
+        let one_row = format!("{},1", one.get_variable().as_variable_index());
+        assert!(rows.contains(&zero_row.as_str()));
+        assert!(rows.contains(&one_row.as_str()));
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker) == false);
+    }
+}
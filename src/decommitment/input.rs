@@ -0,0 +1,100 @@
+use std::collections::VecDeque;
+
+use boojum::{
+    cs::{traits::cs::ConstraintSystem, Variable},
+    field::SmallField,
+    gadgets::{
+        boolean::Boolean,
+        queue::*,
+        traits::{
+            allocatable::*, auxiliary::PrettyComparison, encodable::CircuitVarLengthEncodable,
+            selectable::Selectable, witnessable::WitnessHookable,
+        },
+        u256::UInt256,
+        u32::UInt32,
+    },
+    serde_utils::BigArraySerde,
+};
+use cs_derive::*;
+use derivative::*;
+
+use crate::{
+    base_structures::vm_state::*,
+    ethereum_types::U256,
+    fsm_input_output::{ClosedFormInput, ClosedFormInputWitness},
+};
+
+#[derive(Derivative, CSAllocatable, CSSelectable, CSVarLengthEncodable, WitnessHookable)]
+#[derivative(Clone, Copy, Debug)]
+#[DerivePrettyComparison("true")]
+pub struct CodeDecommitVerifyInputData<F: SmallField> {
+    pub code_hash: UInt256<F>,
+    pub num_words: UInt32<F>,
+    pub page: UInt32<F>,
+    pub timestamp: UInt32<F>,
+    pub initial_memory_queue_state: QueueState<F, FULL_SPONGE_QUEUE_STATE_WIDTH>,
+}
+
+impl<F: SmallField> CSPlaceholder<F> for CodeDecommitVerifyInputData<F> {
+    fn placeholder<CS: ConstraintSystem<F>>(cs: &mut CS) -> Self {
+        Self {
+            code_hash: UInt256::zero(cs),
+            num_words: UInt32::zero(cs),
+            page: UInt32::zero(cs),
+            timestamp: UInt32::zero(cs),
+            initial_memory_queue_state: QueueState::<F, FULL_SPONGE_QUEUE_STATE_WIDTH>::placeholder(
+                cs,
+            ),
+        }
+    }
+}
+
+#[derive(Derivative, CSAllocatable, CSSelectable, CSVarLengthEncodable, WitnessHookable)]
+#[derivative(Clone, Copy, Debug)]
+#[DerivePrettyComparison("true")]
+pub struct CodeDecommitVerifyOutputData<F: SmallField> {
+    pub final_memory_state: QueueState<F, FULL_SPONGE_QUEUE_STATE_WIDTH>,
+}
+
+impl<F: SmallField> CSPlaceholder<F> for CodeDecommitVerifyOutputData<F> {
+    fn placeholder<CS: ConstraintSystem<F>>(cs: &mut CS) -> Self {
+        Self { final_memory_state: QueueState::<F, FULL_SPONGE_QUEUE_STATE_WIDTH>::placeholder(cs) }
+    }
+}
+
+/// The whole bytecode is verified by a single instance, so the state is only carried for
+/// uniformity with other circuits
+#[derive(Derivative, CSAllocatable, CSSelectable, CSVarLengthEncodable, WitnessHookable)]
+#[derivative(Clone, Copy, Debug)]
+#[DerivePrettyComparison("true")]
+pub struct CodeDecommitVerifyFSMState<F: SmallField> {
+    pub memory_queue_state: QueueState<F, FULL_SPONGE_QUEUE_STATE_WIDTH>,
+}
+
+impl<F: SmallField> CSPlaceholder<F> for CodeDecommitVerifyFSMState<F> {
+    fn placeholder<CS: ConstraintSystem<F>>(cs: &mut CS) -> Self {
+        Self { memory_queue_state: QueueState::<F, FULL_SPONGE_QUEUE_STATE_WIDTH>::placeholder(cs) }
+    }
+}
+
+pub type CodeDecommitVerifyInputOutput<F> = ClosedFormInput<
+    F,
+    CodeDecommitVerifyFSMState<F>,
+    CodeDecommitVerifyInputData<F>,
+    CodeDecommitVerifyOutputData<F>,
+>;
+
+pub type CodeDecommitVerifyInputOutputWitness<F> = ClosedFormInputWitness<
+    F,
+    CodeDecommitVerifyFSMState<F>,
+    CodeDecommitVerifyInputData<F>,
+    CodeDecommitVerifyOutputData<F>,
+>;
+
+#[derive(Derivative, serde::Serialize, serde::Deserialize)]
+#[derivative(Clone, Debug, Default)]
+#[serde(bound = "")]
+pub struct CodeDecommitVerifyCircuitInstanceWitness<F: SmallField> {
+    pub closed_form_input: CodeDecommitVerifyInputOutputWitness<F>,
+    pub bytecode_words_witness: VecDeque<U256>,
+}
@@ -0,0 +1,349 @@
+use std::sync::{Arc, RwLock};
+
+use boojum::{
+    algebraic_props::round_function::AlgebraicRoundFunction,
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{
+        boolean::Boolean,
+        keccak256,
+        num::Num,
+        traits::{
+            allocatable::{CSAllocatableExt, CSPlaceholder},
+            round_function::CircuitRoundFunction,
+            selectable::Selectable,
+        },
+        u256::UInt256,
+        u32::UInt32,
+        u8::UInt8,
+    },
+};
+
+use crate::{
+    base_structures::memory_query::{MemoryQuery, MemoryQueue},
+    fsm_input_output::circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH,
+    linear_hasher::keccak256_squeeze,
+    storage_application::{
+        keccak256_conditionally_absorb_and_run_permutation, ConditionalWitnessAllocator,
+    },
+};
+
+pub mod input;
+use self::input::*;
+
+/// Bytecode length is encoded in the versioned code hash as a `u16` number of words, see
+/// `zkevm_opcode_defs::ContractCodeSha256Format` and how `far_call` decodes it
+pub const MAX_BYTECODE_LEN_IN_WORDS: usize = u16::MAX as usize;
+
+/// Reads the first `num_words` words of `page` and enforces that `keccak256` of their big-endian
+/// bytes equals `code_hash`. Up to `limit` words are read, every one of them is pushed into the
+/// memory queue, and the final state of the queue is the output of the circuit
+pub fn code_decommit_verify_entry_point<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+>(
+    cs: &mut CS,
+    witness: CodeDecommitVerifyCircuitInstanceWitness<F>,
+    round_function: &R,
+    limit: usize,
+) -> [Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH]
+where
+    [(); <MemoryQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN + 1]:,
+{
+    use boojum::gadgets::keccak256::KECCAK_RATE_BYTES;
+
+    assert!(limit <= MAX_BYTECODE_LEN_IN_WORDS);
+
+    let CodeDecommitVerifyCircuitInstanceWitness { closed_form_input, bytecode_words_witness } =
+        witness;
+
+    let mut structured_input =
+        CodeDecommitVerifyInputOutput::alloc_ignoring_outputs(cs, closed_form_input.clone());
+    let start_flag = structured_input.start_flag;
+
+    let zero_u8 = UInt8::zero(cs);
+    let zero_u256 = UInt256::zero(cs);
+    let boolean_false = Boolean::allocated_constant(cs, false);
+    let boolean_true = Boolean::allocated_constant(cs, true);
+
+    // only 1 instance of the circuit here for now
+    Boolean::enforce_equal(cs, &start_flag, &boolean_true);
+
+    let CodeDecommitVerifyInputData {
+        code_hash,
+        num_words,
+        page,
+        timestamp,
+        initial_memory_queue_state,
+    } = structured_input.observable_input;
+
+    // it must be trivial
+    initial_memory_queue_state.enforce_trivial_head(cs);
+
+    let mut memory_queue = MemoryQueue::<F, R>::from_state(cs, initial_memory_queue_state);
+
+    // bytecode must fit into the instance
+    let limit_u32 = UInt32::allocated_constant(cs, limit as u32);
+    let (_, too_long) = limit_u32.overflowing_sub(cs, num_words);
+    Boolean::enforce_equal(cs, &too_long, &boolean_false);
+
+    // has_words[k] is set if bytecode is at least k words long
+    let mut has_words = Vec::with_capacity(limit + 2);
+    has_words.push(boolean_true);
+    for k in 1..=limit {
+        let k = UInt32::allocated_constant(cs, k as u32);
+        let (_, is_shorter) = num_words.overflowing_sub(cs, k);
+        has_words.push(is_shorter.negated(cs));
+    }
+    has_words.push(boolean_false);
+
+    let words_allocator = ConditionalWitnessAllocator::<F, UInt256<F>> {
+        witness_source: Arc::new(RwLock::new(bytecode_words_witness)),
+    };
+
+    let mut bytes = Vec::with_capacity(limit * 32);
+    for index in 0..limit {
+        let is_bytecode_word = has_words[index + 1];
+        let word: UInt256<F> = words_allocator.conditionally_allocate(cs, is_bytecode_word);
+        // words past the end are not read and hashed as zeroes
+        let word = UInt256::conditionally_select(cs, is_bytecode_word, &word, &zero_u256);
+
+        let read_query = MemoryQuery {
+            timestamp,
+            memory_page: page,
+            index: UInt32::allocated_constant(cs, index as u32),
+            rw_flag: boolean_false,
+            is_ptr: boolean_false,
+            value: word,
+        };
+
+        let _ = memory_queue.push(cs, read_query, is_bytecode_word);
+
+        bytes.extend(word.to_be_bytes(cs));
+    }
+
+    // padding starts at the first byte of the word after the last one
+    let mut is_padding_start = Vec::with_capacity(limit + 1);
+    for index in 0..=limit {
+        let is_past_end = has_words[index + 1].negated(cs);
+        is_padding_start.push(Boolean::multi_and(cs, &[has_words[index], is_past_end]));
+    }
+
+    // block is absorbed if it starts before or at the padding, so the first word it touches
+    // must exist
+    let num_blocks = limit * 32 / KECCAK_RATE_BYTES + 1;
+    let absorb_block: Vec<_> = (0..=num_blocks)
+        .map(|block| {
+            let first_word = (block * KECCAK_RATE_BYTES).div_ceil(32);
+            has_words.get(first_word).copied().unwrap_or(boolean_false)
+        })
+        .collect();
+
+    let keccak_accumulator_state =
+        [[[zero_u8; keccak256::BYTES_PER_WORD]; keccak256::LANE_WIDTH]; keccak256::LANE_WIDTH];
+    let mut keccak_accumulator_state =
+        keccak_accumulator_state.map(|el| el.map(|el| el.map(|el| el.get_variable())));
+
+    for block in 0..num_blocks {
+        let next_block_is_skipped = absorb_block[block + 1].negated(cs);
+        let is_last_block = Boolean::multi_and(cs, &[absorb_block[block], next_block_is_skipped]);
+
+        let mut buffer = [zero_u8.get_variable(); KECCAK_RATE_BYTES];
+        for (offset, dst) in buffer.iter_mut().enumerate() {
+            let position = block * KECCAK_RATE_BYTES + offset;
+            // at most one of the terms is non-zero, as bytecode is a whole number of words and
+            // the padding never starts at the last byte of a block
+            let mut terms = Vec::with_capacity(3);
+            if position < bytes.len() {
+                terms.push((bytes[position].get_variable(), F::ONE));
+            }
+            if position % 32 == 0 && position / 32 <= limit {
+                terms.push((is_padding_start[position / 32].get_variable(), F::ONE));
+            }
+            if offset == KECCAK_RATE_BYTES - 1 {
+                terms.push((is_last_block.get_variable(), F::from_u64_unchecked(0x80)));
+            }
+
+            *dst = match terms.as_slice() {
+                [] => zero_u8.get_variable(),
+                [(variable, coeff)] if *coeff == F::ONE => *variable,
+                _ => Num::linear_combination(cs, &terms).get_variable(),
+            };
+        }
+
+        keccak256_conditionally_absorb_and_run_permutation(
+            cs,
+            absorb_block[block],
+            &mut keccak_accumulator_state,
+            &buffer,
+        );
+    }
+
+    let mut digest_bytes = keccak256_squeeze(&keccak_accumulator_state);
+    digest_bytes.reverse();
+    let digest = UInt256::from_le_bytes(cs, digest_bytes);
+
+    for (a, b) in digest.inner.iter().zip(code_hash.inner.iter()) {
+        Num::enforce_equal(
+            cs,
+            &Num::from_variable(a.get_variable()),
+            &Num::from_variable(b.get_variable()),
+        );
+    }
+
+    structured_input.completion_flag = boolean_true;
+
+    let fsm_output = CodeDecommitVerifyFSMState::placeholder(cs);
+    structured_input.hidden_fsm_output = fsm_output;
+
+    let mut observable_output = CodeDecommitVerifyOutputData::placeholder(cs);
+    observable_output.final_memory_state = memory_queue.into_state();
+    structured_input.observable_output = observable_output;
+
+    // self-check
+    structured_input.hook_compare_witness(cs, &closed_form_input);
+
+    use boojum::cs::gates::PublicInputGate;
+
+    use crate::fsm_input_output::{
+        commit_variable_length_encodable_item, ClosedFormInputCompactForm,
+    };
+
+    let compact_form =
+        ClosedFormInputCompactForm::from_full_form(cs, &structured_input, round_function);
+    let input_commitment = commit_variable_length_encodable_item(cs, &compact_form, round_function);
+    for el in input_commitment.iter() {
+        let gate = PublicInputGate::new(el.get_variable());
+        gate.add_to_cs(cs);
+    }
+
+    input_commitment
+}
+
+#[cfg(test)]
+mod test {
+    use boojum::{
+        field::goldilocks::GoldilocksField,
+        gadgets::{
+            queue::QueueState,
+            traits::{allocatable::CSAllocatable, witnessable::WitnessHookable},
+        },
+        implementations::poseidon2::Poseidon2Goldilocks,
+        worker::Worker,
+    };
+    use zkevm_opcode_defs::sha3::*;
+
+    use super::*;
+    use crate::{
+        base_structures::vm_state::FULL_SPONGE_QUEUE_STATE_WIDTH, ethereum_types::U256,
+        test_utils::create_test_cs,
+    };
+
+    type F = GoldilocksField;
+    type R = Poseidon2Goldilocks;
+
+    const CODE_PAGE: u32 = 2368;
+    const TIMESTAMP: u32 = 40973;
+
+    // ERC-20 style function dispatcher for name, approve, totalSupply, transferFrom, decimals,
+    // balanceOf, symbol, transfer and allowance, padded to whole words
+    const ERC20_DISPATCHER_BYTECODE: [&str; 5] = [
+        "608060405234801561001057600080fd5b506004361061009e5760003560e01c",
+        "806306fdde03146100a3578063095ea7b3146100c357806318160ddd146100e3",
+        "57806323b872dd14610103578063313ce5671461012357806370a08231146101",
+        "4357806395d89b4114610163578063a9059cbb14610183578063dd62ed3e1461",
+        "01a3575b600080fd000000000000000000000000000000000000000000000000",
+    ];
+
+    fn bytecode_words() -> Vec<U256> {
+        ERC20_DISPATCHER_BYTECODE
+            .iter()
+            .map(|el| U256::from_str_radix(el, 16).unwrap())
+            .collect()
+    }
+
+    fn native_code_hash(words: &[U256]) -> U256 {
+        let mut hasher = Keccak256::new();
+        for word in words.iter() {
+            let mut bytes = [0u8; 32];
+            word.to_big_endian(&mut bytes);
+            hasher.update(&bytes);
+        }
+
+        U256::from_big_endian(hasher.finalize().as_slice())
+    }
+
+    fn synthesize_decommitment(code_hash: U256, limit: usize) -> bool {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+        let round_function = Poseidon2Goldilocks;
+
+        let words = bytecode_words();
+
+        // the same reads, to get the expected state of the memory queue
+        let boolean_false = Boolean::allocated_constant(cs, false);
+        let boolean_true = Boolean::allocated_constant(cs, true);
+        let mut memory_queue = MemoryQueue::<F, R>::empty(cs);
+        for (index, word) in words.iter().enumerate() {
+            let read_query = MemoryQuery {
+                timestamp: UInt32::allocated_constant(cs, TIMESTAMP),
+                memory_page: UInt32::allocated_constant(cs, CODE_PAGE),
+                index: UInt32::allocated_constant(cs, index as u32),
+                rw_flag: boolean_false,
+                is_ptr: boolean_false,
+                value: UInt256::allocate(cs, *word),
+            };
+            let _ = memory_queue.push(cs, read_query, boolean_true);
+        }
+        let final_memory_state = memory_queue.into_state().witness_hook(&*cs)().unwrap();
+
+        let closed_form_input = CodeDecommitVerifyInputOutputWitness {
+            start_flag: true,
+            completion_flag: true,
+            observable_input: CodeDecommitVerifyInputDataWitness {
+                code_hash,
+                num_words: words.len() as u32,
+                page: CODE_PAGE,
+                timestamp: TIMESTAMP,
+                initial_memory_queue_state:
+                    QueueState::<F, FULL_SPONGE_QUEUE_STATE_WIDTH>::placeholder_witness(),
+            },
+            observable_output: CodeDecommitVerifyOutputDataWitness { final_memory_state },
+            hidden_fsm_input: CodeDecommitVerifyFSMState::placeholder_witness(),
+            hidden_fsm_output: CodeDecommitVerifyFSMState::placeholder_witness(),
+        };
+        let witness = CodeDecommitVerifyCircuitInstanceWitness {
+            closed_form_input,
+            bytecode_words_witness: words.into_iter().collect(),
+        };
+
+        let _ = code_decommit_verify_entry_point(cs, witness, &round_function, limit);
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        owned_cs.check_if_satisfied(&worker)
+    }
+
+    #[test]
+    fn test_code_decommit_verify() {
+        let code_hash = native_code_hash(&bytecode_words());
+        assert!(synthesize_decommitment(code_hash, ERC20_DISPATCHER_BYTECODE.len()));
+    }
+
+    #[test]
+    fn test_code_decommit_verify_shorter_than_limit() {
+        // a 9 words long bytecode would take one more block, that must be skipped here
+        let code_hash = native_code_hash(&bytecode_words());
+        assert!(synthesize_decommitment(code_hash, 9));
+    }
+
+    #[test]
+    fn test_code_decommit_verify_wrong_hash() {
+        let code_hash = native_code_hash(&bytecode_words());
+        assert!(synthesize_decommitment(code_hash ^ U256::one(), 9) == false);
+    }
+}
@@ -99,6 +99,19 @@ impl<F: SmallField> LogDemuxerOutputData<F> {
                 DemuxOutput::TransientStorage,
                 &self.output_queue_states[DemuxOutput::TransientStorage as usize],
             ),
+            (
+                DemuxOutput::SchnorrVerify,
+                &self.output_queue_states[DemuxOutput::SchnorrVerify as usize],
+            ),
+            (
+                DemuxOutput::PedersenHash,
+                &self.output_queue_states[DemuxOutput::PedersenHash as usize],
+            ),
+            (
+                DemuxOutput::Bls12_381G1Add,
+                &self.output_queue_states[DemuxOutput::Bls12_381G1Add as usize],
+            ),
+            (DemuxOutput::Nullifier, &self.output_queue_states[DemuxOutput::Nullifier as usize]),
         ];
         assert_eq!(tuples.len(), NUM_DEMUX_OUTPUTS);
 
@@ -27,6 +27,7 @@ use zkevm_opcode_defs::system_params::*;
 use crate::{
     base_structures::{
         log_query::{LogQuery, LOG_QUERY_PACKED_WIDTH},
+        precompile_kind::PrecompileKind,
         vm_state::*,
     },
     demux_log_queue::input::*,
@@ -39,6 +40,43 @@ pub type StorageLogQueue<F, R> = CircuitQueue<F, LogQuery<F>, 8, 12, 4, 4, 20, R
 pub type StorageLogQueueWitness<F> =
     CircuitQueueWitness<F, LogQuery<F>, QUEUE_STATE_WIDTH, LOG_QUERY_PACKED_WIDTH>;
 
+// NOTE: queue is append-only, so elements pushed after the checkpoint can not be removed. Rewinding
+// does not shrink the queue, it only checks that nothing was pushed since the checkpoint. Frames
+// that did write have to re-push their queries with the `rollback` flag set instead
+
+/// Records the current tail of the storage log queue
+pub fn checkpoint_storage_log_queue<
+    F: SmallField,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+>(
+    queue: &StorageLogQueue<F, R>,
+) -> QueueTailState<F, QUEUE_STATE_WIDTH> {
+    QueueTailState { tail: queue.tail, length: queue.length }
+}
+
+/// Enforces that the tail and length of the storage log queue are equal to the ones recorded in
+/// the checkpoint, if `should_enforce` is set
+pub fn conditionally_enforce_rewound_to_checkpoint<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+>(
+    cs: &mut CS,
+    queue: &StorageLogQueue<F, R>,
+    checkpoint: &QueueTailState<F, QUEUE_STATE_WIDTH>,
+    should_enforce: Boolean<F>,
+) {
+    for (a, b) in queue.tail.iter().zip(checkpoint.tail.iter()) {
+        Num::conditionally_enforce_equal(cs, should_enforce, a, b);
+    }
+    Num::conditionally_enforce_equal(
+        cs,
+        should_enforce,
+        &queue.length.into_num(),
+        &checkpoint.length.into_num(),
+    );
+}
+
 #[repr(usize)]
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum DemuxOutput {
@@ -51,9 +89,13 @@ pub enum DemuxOutput {
     ECRecover,
     Secp256r1Verify,
     TransientStorage,
+    SchnorrVerify,
+    PedersenHash,
+    Bls12_381G1Add,
+    Nullifier,
 }
 
-pub const NUM_DEMUX_OUTPUTS: usize = DemuxOutput::TransientStorage as usize + 1;
+pub const NUM_DEMUX_OUTPUTS: usize = DemuxOutput::Nullifier as usize + 1;
 
 pub const ALL_DEMUX_OUTPUTS: [DemuxOutput; NUM_DEMUX_OUTPUTS] = [
     DemuxOutput::RollupStorage,
@@ -65,12 +107,22 @@ pub const ALL_DEMUX_OUTPUTS: [DemuxOutput; NUM_DEMUX_OUTPUTS] = [
     DemuxOutput::ECRecover,
     DemuxOutput::Secp256r1Verify,
     DemuxOutput::TransientStorage,
+    DemuxOutput::SchnorrVerify,
+    DemuxOutput::PedersenHash,
+    DemuxOutput::Bls12_381G1Add,
+    DemuxOutput::Nullifier,
 ];
 
 impl DemuxOutput {
+    /// Outputs that no circuit consumes yet. They are still routed, so the requests are not lost
+    /// silently, but the queues must stay empty
     pub fn is_implemented(&self) -> bool {
         match self {
-            Self::PorterStorage => false,
+            Self::PorterStorage
+            | Self::SchnorrVerify
+            | Self::PedersenHash
+            | Self::Bls12_381G1Add
+            | Self::Nullifier => false,
             _ => true,
         }
     }
@@ -85,16 +137,28 @@ impl DemuxOutput {
         }
     }
 
-    pub fn precompile_address(&self) -> Option<zkevm_opcode_defs::ethereum_types::H160> {
+    pub fn precompile(&self) -> Option<PrecompileKind> {
         match self {
-            Self::Keccak => Some(*zkevm_opcode_defs::system_params::KECCAK256_ROUND_FUNCTION_PRECOMPILE_FORMAL_ADDRESS),
-            Self::Sha256 => Some(*zkevm_opcode_defs::system_params::SHA256_ROUND_FUNCTION_PRECOMPILE_FORMAL_ADDRESS),
-            Self::ECRecover => Some(*zkevm_opcode_defs::system_params::ECRECOVER_INNER_FUNCTION_PRECOMPILE_FORMAL_ADDRESS),
-            Self::Secp256r1Verify => Some(*zkevm_opcode_defs::system_params::SECP256R1_VERIFY_INNER_FUNCTION_PRECOMPILE_FORMAL_ADDRESS),
-            _ => None,
+            Self::RollupStorage
+            | Self::PorterStorage
+            | Self::Events
+            | Self::L2ToL1Messages
+            | Self::TransientStorage => None,
+            Self::Keccak => Some(PrecompileKind::Keccak256),
+            Self::Sha256 => Some(PrecompileKind::Sha256),
+            Self::ECRecover => Some(PrecompileKind::Ecrecover),
+            Self::Secp256r1Verify => Some(PrecompileKind::Secp256r1Verify),
+            Self::SchnorrVerify => Some(PrecompileKind::SchnorrVerify),
+            Self::PedersenHash => Some(PrecompileKind::PedersenHash),
+            Self::Bls12_381G1Add => Some(PrecompileKind::Bls12_381G1Add),
+            Self::Nullifier => Some(PrecompileKind::Nullifier),
         }
     }
 
+    pub fn precompile_address(&self) -> Option<zkevm_opcode_defs::ethereum_types::H160> {
+        self.precompile().map(|el| el.address())
+    }
+
     pub fn shard_id(&self) -> Option<u8> {
         match self {
             Self::RollupStorage => Some(0u8),
@@ -162,11 +226,10 @@ where
 
     structured_input.hidden_fsm_output.output_queue_states = queue_states.map(|el| el.into_state());
 
-    let porter_storage_queue =
-        structured_input.hidden_fsm_output.output_queue_states[DemuxOutput::PorterStorage as usize];
-    let porter_storage_queue_is_empty = porter_storage_queue.tail.length.is_zero(cs);
-    let boolean_true = Boolean::allocated_constant(cs, true);
-    Boolean::enforce_equal(cs, &porter_storage_queue_is_empty, &boolean_true);
+    enforce_unimplemented_outputs_are_empty(
+        cs,
+        &structured_input.hidden_fsm_output.output_queue_states,
+    );
 
     // copy into observable output
     for (dst, src) in structured_input
@@ -198,6 +261,22 @@ where
     input_commitment
 }
 
+/// Porter storage and precompiles that are not wired into the scheduler have no circuit to
+/// process their requests
+pub fn enforce_unimplemented_outputs_are_empty<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    output_queue_states: &[QueueState<F, QUEUE_STATE_WIDTH>; NUM_DEMUX_OUTPUTS],
+) {
+    let boolean_true = Boolean::allocated_constant(cs, true);
+    for el in ALL_DEMUX_OUTPUTS.into_iter() {
+        if el.is_implemented() {
+            continue;
+        }
+        let queue_is_empty = output_queue_states[el as usize].tail.length.is_zero(cs);
+        Boolean::enforce_equal(cs, &queue_is_empty, &boolean_true);
+    }
+}
+
 pub fn demultiplex_storage_logs_inner<
     F: SmallField,
     CS: ConstraintSystem<F>,
@@ -497,6 +576,87 @@ mod tests {
         assert!(owned_cs.check_if_satisfied(&worker));
     }
 
+    #[test]
+    fn test_unwired_precompile_requests_are_not_dropped() {
+        use boojum::gadgets::traits::{allocatable::CSAllocatable, witnessable::WitnessHookable};
+
+        use crate::{base_structures::log_query::LogQueryWitness, test_utils::create_test_cs};
+
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let execute = Boolean::allocated_constant(cs, true);
+        let mut storage_log_queue = StorageLogQueue::<F, Poseidon2Goldilocks>::empty(cs);
+        let request = LogQueryWitness {
+            address: PrecompileKind::SchnorrVerify.address(),
+            key: U256::from(0x1234u64),
+            read_value: U256::zero(),
+            written_value: U256::zero(),
+            aux_byte: PRECOMPILE_AUX_BYTE,
+            rw_flag: false,
+            rollback: false,
+            is_service: false,
+            shard_id: 0,
+            tx_number_in_block: 1,
+            timestamp: 2048,
+        };
+        let request = LogQuery::allocate(cs, request);
+        storage_log_queue.push(cs, request, execute);
+
+        let mut output = std::array::from_fn(|_| StorageLogQueue::empty(cs));
+        demultiplex_storage_logs_inner(cs, &mut storage_log_queue, &mut output, 1);
+
+        let output_states = output.map(|el| el.into_state());
+        let routed = output_states[DemuxOutput::SchnorrVerify as usize]
+            .tail
+            .length;
+        assert_eq!(routed.witness_hook(&*cs)().unwrap(), 1);
+
+        enforce_unimplemented_outputs_are_empty(cs, &output_states);
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker) == false);
+    }
+
+    fn synthesize_rewind(push_after_checkpoint: bool, should_enforce: bool) -> bool {
+        use crate::test_utils::create_test_cs;
+
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let execute = Boolean::allocated_constant(cs, true);
+        let mut queue = StorageLogQueue::<F, Poseidon2Goldilocks>::empty(cs);
+        let mut queries = witness_input_unsorted(cs).into_iter();
+        queue.push(cs, queries.next().unwrap(), execute);
+
+        let checkpoint = checkpoint_storage_log_queue(&queue);
+        if push_after_checkpoint {
+            queue.push(cs, queries.next().unwrap(), execute);
+        }
+
+        let should_enforce = Boolean::allocated_constant(cs, should_enforce);
+        conditionally_enforce_rewound_to_checkpoint(cs, &queue, &checkpoint, should_enforce);
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        owned_cs.check_if_satisfied(&worker)
+    }
+
+    #[test]
+    fn test_rewind_to_checkpoint() {
+        assert!(synthesize_rewind(false, true));
+        // nothing is checked if the frame is not rolled back
+        assert!(synthesize_rewind(true, false));
+    }
+
+    #[test]
+    fn test_rewind_after_push_is_rejected() {
+        assert!(synthesize_rewind(true, true) == false);
+    }
+
     fn witness_input_unsorted<CS: ConstraintSystem<F>>(cs: &mut CS) -> Vec<LogQuery<F>> {
         let mut unsorted_querie = vec![];
         let bool_false = Boolean::allocated_constant(cs, false);
@@ -21,7 +21,6 @@ use boojum::{
             selectable::Selectable,
         },
         u16::UInt16,
-        u160::UInt160,
         u256::UInt256,
         u32::UInt32,
         u8::UInt8,
@@ -31,9 +30,15 @@ use zkevm_opcode_defs::system_params::PRECOMPILE_AUX_BYTE;
 
 use super::*;
 use crate::{
-    base_structures::precompile_input_outputs::PrecompileFunctionOutputData,
-    demux_log_queue::StorageLogQueue, ethereum_types::U256,
+    base_structures::{
+        page_id::{InputTag, OutputTag, PageId},
+        precompile_input_outputs::PrecompileFunctionOutputData,
+        precompile_kind::PrecompileKind,
+    },
+    demux_log_queue::StorageLogQueue,
+    ethereum_types::U256,
     fsm_input_output::circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH,
+    gas_meter::{GasMeter, ECRECOVER_GAS_COST_PER_CALL},
 };
 
 pub const MEMORY_QUERIES_PER_CALL: usize = 4;
@@ -41,9 +46,9 @@ pub const MEMORY_QUERIES_PER_CALL: usize = 4;
 #[derive(Derivative, CSSelectable)]
 #[derivative(Clone, Debug)]
 pub struct EcrecoverPrecompileCallParams<F: SmallField> {
-    pub input_page: UInt32<F>,
+    pub input_page: PageId<F, InputTag>,
     pub input_offset: UInt32<F>,
-    pub output_page: UInt32<F>,
+    pub output_page: PageId<F, OutputTag>,
     pub output_offset: UInt32<F>,
 }
 
@@ -60,8 +65,8 @@ impl<F: SmallField> EcrecoverPrecompileCallParams<F> {
     pub fn from_encoding<CS: ConstraintSystem<F>>(_cs: &mut CS, encoding: UInt256<F>) -> Self {
         let input_offset = encoding.inner[0];
         let output_offset = encoding.inner[2];
-        let input_page = encoding.inner[4];
-        let output_page = encoding.inner[5];
+        let input_page = PageId::new(encoding.inner[4]);
+        let output_page = PageId::new(encoding.inner[5]);
 
         let new = Self { input_page, input_offset, output_page, output_offset };
 
@@ -456,10 +461,7 @@ where
 
     let memory_reads_witness: VecDeque<_> = memory_reads_witness.into_iter().flatten().collect();
 
-    let precompile_address = UInt160::allocated_constant(
-        cs,
-        *zkevm_opcode_defs::system_params::ECRECOVER_INNER_FUNCTION_PRECOMPILE_FORMAL_ADDRESS,
-    );
+    let precompile_address = PrecompileKind::Ecrecover.to_address(cs);
     let aux_byte_for_precompile = UInt8::allocated_constant(cs, PRECOMPILE_AUX_BYTE);
 
     let scalar_params = Arc::new(secp256k1_scalar_field_params());
@@ -532,11 +534,40 @@ where
         witness_source: Arc::new(RwLock::new(memory_reads_witness)),
     };
 
+    let zero_u32 = UInt32::zero(cs);
+    let mut num_requests_processed = UInt32::conditionally_select(
+        cs,
+        start_flag,
+        &zero_u32,
+        &structured_input.hidden_fsm_input.num_requests_processed,
+    );
+    let mut num_successful_calls = UInt32::conditionally_select(
+        cs,
+        start_flag,
+        &zero_u32,
+        &structured_input.hidden_fsm_input.num_successful_calls,
+    );
+    let mut gas_meter = GasMeter::new(UInt32::conditionally_select(
+        cs,
+        start_flag,
+        &zero_u32,
+        &structured_input.hidden_fsm_input.gas_used,
+    ));
+    let gas_cost_per_call = UInt32::allocated_constant(cs, ECRECOVER_GAS_COST_PER_CALL);
+
     for _cycle in 0..limit {
         let is_empty = requests_queue.is_empty(cs);
         let should_process = is_empty.negated(cs);
         let (request, _) = requests_queue.pop_front(cs, should_process);
 
+        // failed calls are charged too
+        let gas_overflow = gas_meter.charge(cs, gas_cost_per_call, should_process);
+        Boolean::enforce_equal(cs, &gas_overflow, &boolean_false);
+
+        num_requests_processed = num_requests_processed.add_no_overflow(cs, unsafe {
+            UInt32::from_variable_unchecked(should_process.get_variable())
+        });
+
         let mut precompile_call_params =
             EcrecoverPrecompileCallParams::from_encoding(cs, request.key);
 
@@ -574,7 +605,7 @@ where
 
             let read_query = MemoryQuery {
                 timestamp: timestamp_to_use_for_read,
-                memory_page: precompile_call_params.input_page,
+                memory_page: precompile_call_params.input_page.page(),
                 index: precompile_call_params.input_offset,
                 rw_flag: boolean_false,
                 is_ptr: boolean_false,
@@ -604,13 +635,18 @@ where
             &scalar_params,
         );
 
+        let call_succeeded = Boolean::multi_and(cs, &[success, should_process]);
+        num_successful_calls = num_successful_calls.add_no_overflow(cs, unsafe {
+            UInt32::from_variable_unchecked(call_succeeded.get_variable())
+        });
+
         let success_as_u32 = unsafe { UInt32::from_variable_unchecked(success.get_variable()) };
         let mut success_as_u256 = zero_u256;
         success_as_u256.inner[0] = success_as_u32;
 
         let success_query = MemoryQuery {
             timestamp: timestamp_to_use_for_write,
-            memory_page: precompile_call_params.output_page,
+            memory_page: precompile_call_params.output_page.page(),
             index: precompile_call_params.output_offset,
             rw_flag: boolean_true,
             value: success_as_u256,
@@ -625,7 +661,7 @@ where
 
         let value_query = MemoryQuery {
             timestamp: timestamp_to_use_for_write,
-            memory_page: precompile_call_params.output_page,
+            memory_page: precompile_call_params.output_page.page(),
             index: precompile_call_params.output_offset,
             rw_flag: boolean_true,
             value: written_value,
@@ -651,9 +687,24 @@ where
         &final_memory_state,
         &structured_input.observable_output.final_memory_state,
     );
+    structured_input.observable_output.num_successful_calls = UInt32::conditionally_select(
+        cs,
+        structured_input.completion_flag,
+        &num_successful_calls,
+        &structured_input.observable_output.num_successful_calls,
+    );
+    structured_input.observable_output.gas_used = UInt32::conditionally_select(
+        cs,
+        structured_input.completion_flag,
+        &gas_meter.gas_used,
+        &structured_input.observable_output.gas_used,
+    );
 
     structured_input.hidden_fsm_output.log_queue_state = final_requets_state;
     structured_input.hidden_fsm_output.memory_queue_state = final_memory_state;
+    structured_input.hidden_fsm_output.num_requests_processed = num_requests_processed;
+    structured_input.hidden_fsm_output.num_successful_calls = num_successful_calls;
+    structured_input.hidden_fsm_output.gas_used = gas_meter.gas_used;
 
     // self-check
     structured_input.hook_compare_witness(cs, &closed_form_input);
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use boojum::{
+    field::goldilocks::GoldilocksField,
+    gadgets::{
+        traits::{allocatable::CSAllocatable, witnessable::WitnessHookable},
+        u256::UInt256,
+        u8::UInt8,
+    },
+    pairing::ff::PrimeField,
+    worker::Worker,
+};
+
+use super::{new_optimized::ecrecover_precompile_inner_routine, *};
+use crate::test_utils::create_test_cs;
+
+type F = GoldilocksField;
+
+const REFERENCE_VECTORS_PATH: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/test_vectors/ecrecover_reference.json");
+
+/// Single vector in the same format as go-ethereum precompile tests. Empty `expected` means that
+/// the precompile must return an error
+#[derive(Debug, serde::Deserialize)]
+struct EcrecoverReferenceVector {
+    hash: String,
+    v: u8,
+    r: String,
+    s: String,
+    expected: String,
+}
+
+fn decode_hex(value: &str) -> Vec<u8> {
+    hex::decode(value.trim_start_matches("0x")).unwrap()
+}
+
+fn load_reference_vectors() -> Vec<EcrecoverReferenceVector> {
+    let content = std::fs::read_to_string(REFERENCE_VECTORS_PATH).unwrap();
+
+    serde_json::from_str(&content).unwrap()
+}
+
+#[test]
+fn test_ecrecover_against_reference_vectors() {
+    let vectors = load_reference_vectors();
+    assert!(vectors.len() >= 20);
+
+    let scalar_params = Arc::new(secp256k1_scalar_field_params());
+    let base_params = Arc::new(secp256k1_base_field_params());
+
+    for vector in vectors.into_iter() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let expected = decode_hex(&vector.expected);
+
+        let rec_id = UInt8::allocate_checked(cs, vector.v);
+        let r = UInt256::allocate(cs, U256::from_big_endian(&decode_hex(&vector.r)));
+        let s = UInt256::allocate(cs, U256::from_big_endian(&decode_hex(&vector.s)));
+        let digest = UInt256::allocate(cs, U256::from_big_endian(&decode_hex(&vector.hash)));
+
+        let valid_x_in_external_field = Secp256BaseNNField::allocated_constant(
+            cs,
+            Secp256Fq::from_str("9").unwrap(),
+            &base_params,
+        );
+        let valid_t_in_external_field = Secp256BaseNNField::allocated_constant(
+            cs,
+            Secp256Fq::from_str("16").unwrap(),
+            &base_params,
+        );
+        let valid_y_in_external_field = Secp256BaseNNField::allocated_constant(
+            cs,
+            Secp256Fq::from_str("4").unwrap(),
+            &base_params,
+        );
+
+        let (no_error, digest) = ecrecover_precompile_inner_routine::<_, _, true>(
+            cs,
+            &rec_id,
+            &r,
+            &s,
+            &digest,
+            valid_x_in_external_field,
+            valid_y_in_external_field,
+            valid_t_in_external_field,
+            &base_params,
+            &scalar_params,
+        );
+
+        let no_error = no_error.witness_hook(&*cs)().unwrap();
+        if expected.is_empty() {
+            assert!(no_error == false, "vector {:?} must fail", vector);
+        } else {
+            assert!(no_error == true, "vector {:?} must succeed", vector);
+            let recovered_address = digest.to_be_bytes(cs);
+            let recovered_address = recovered_address.witness_hook(&*cs)().unwrap();
+            assert_eq!(&recovered_address[12..], &expected[..], "vector {:?}", vector);
+        }
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+}
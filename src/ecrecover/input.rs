@@ -9,6 +9,7 @@ use boojum::{
             auxiliary::PrettyComparison,
             encodable::CircuitVarLengthEncodable,
         },
+        u32::UInt32,
     },
 };
 
@@ -21,6 +22,9 @@ use crate::base_structures::{precompile_input_outputs::*, vm_state::*};
 pub struct EcrecoverCircuitFSMInputOutput<F: SmallField> {
     pub log_queue_state: QueueState<F, QUEUE_STATE_WIDTH>,
     pub memory_queue_state: QueueState<F, FULL_SPONGE_QUEUE_STATE_WIDTH>,
+    pub num_requests_processed: UInt32<F>,
+    pub num_successful_calls: UInt32<F>,
+    pub gas_used: UInt32<F>,
 }
 
 impl<F: SmallField> CSPlaceholder<F> for EcrecoverCircuitFSMInputOutput<F> {
@@ -28,6 +32,9 @@ impl<F: SmallField> CSPlaceholder<F> for EcrecoverCircuitFSMInputOutput<F> {
         Self {
             log_queue_state: QueueState::<F, QUEUE_STATE_WIDTH>::placeholder(cs),
             memory_queue_state: QueueState::<F, FULL_SPONGE_QUEUE_STATE_WIDTH>::placeholder(cs),
+            num_requests_processed: UInt32::zero(cs),
+            num_successful_calls: UInt32::zero(cs),
+            gas_used: UInt32::zero(cs),
         }
     }
 }
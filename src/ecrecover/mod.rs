@@ -30,6 +30,9 @@ pub mod naf_abs_div2_table;
 pub mod baseline;
 pub mod new_optimized;
 
+#[cfg(test)]
+mod cross_validation;
+
 // characteristics of the base field for secp curve
 use self::secp256k1::fq::Fq as Secp256Fq;
 // order of group of points for secp curve
@@ -39,20 +42,20 @@ use self::secp256k1::PointAffine as Secp256Affine;
 
 const BASE_FIELD_REPR_LIMBS: usize = 17;
 const SCALAR_FIELD_REPR_LIMBS: usize = 17;
-const BASE_FIELD_CANONICAL_REPR_LIMBS: usize = 16;
-const SCALAR_FIELD_CANONICAL_REPR_LIMBS: usize = 16;
+pub(crate) const BASE_FIELD_CANONICAL_REPR_LIMBS: usize = 16;
+pub(crate) const SCALAR_FIELD_CANONICAL_REPR_LIMBS: usize = 16;
 
-type Secp256BaseNNFieldParams = NonNativeFieldOverU16Params<Secp256Fq, 17>;
-type Secp256ScalarNNFieldParams = NonNativeFieldOverU16Params<Secp256Fr, 17>;
+pub(crate) type Secp256BaseNNFieldParams = NonNativeFieldOverU16Params<Secp256Fq, 17>;
+pub(crate) type Secp256ScalarNNFieldParams = NonNativeFieldOverU16Params<Secp256Fr, 17>;
 
 type Secp256BaseNNField<F> = NonNativeFieldOverU16<F, Secp256Fq, 17>;
 type Secp256ScalarNNField<F> = NonNativeFieldOverU16<F, Secp256Fr, 17>;
 
-fn secp256k1_base_field_params() -> Secp256BaseNNFieldParams {
+pub(crate) fn secp256k1_base_field_params() -> Secp256BaseNNFieldParams {
     NonNativeFieldOverU16Params::create()
 }
 
-fn secp256k1_scalar_field_params() -> Secp256ScalarNNFieldParams {
+pub(crate) fn secp256k1_scalar_field_params() -> Secp256ScalarNNFieldParams {
     NonNativeFieldOverU16Params::create()
 }
 
@@ -23,7 +23,6 @@ use boojum::{
             selectable::Selectable,
         },
         u16::UInt16,
-        u160::UInt160,
         u256::UInt256,
         u32::UInt32,
         u512::UInt512,
@@ -36,10 +35,19 @@ use zkevm_opcode_defs::system_params::PRECOMPILE_AUX_BYTE;
 pub use self::input::*;
 use super::*;
 use crate::{
-    base_structures::precompile_input_outputs::PrecompileFunctionOutputData,
+    base_structures::{
+        page_id::{InputTag, OutputTag, PageId},
+        precompile_input_outputs::PrecompileFunctionOutputData,
+        precompile_kind::PrecompileKind,
+    },
+    debug::CircuitSpan,
     demux_log_queue::StorageLogQueue,
-    ecrecover::secp256k1::fixed_base_mul_table::FixedBaseMulTable, ethereum_types::U256,
+    ecrecover::secp256k1::fixed_base_mul_table::get_fixed_base_mul_table_ids,
+    ethereum_types::U256,
     fsm_input_output::circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH,
+    gas_meter::{GasMeter, ECRECOVER_GAS_COST_PER_CALL},
+    tables::{get_typed_table_id, TableMarker, TypedTableId},
+    utils::OverflowTrackerExt,
 };
 
 pub const MEMORY_QUERIES_PER_CALL: usize = 4;
@@ -48,29 +56,65 @@ pub const ALLOW_ZERO_MESSAGE: bool = true;
 #[derive(Derivative, CSSelectable)]
 #[derivative(Clone, Debug)]
 pub struct EcrecoverPrecompileCallParams<F: SmallField> {
-    pub input_page: UInt32<F>,
+    pub input_page: PageId<F, InputTag>,
     pub input_offset: UInt32<F>,
-    pub output_page: UInt32<F>,
+    pub output_page: PageId<F, OutputTag>,
     pub output_offset: UInt32<F>,
 }
 
 impl<F: SmallField> EcrecoverPrecompileCallParams<F> {
-    pub fn from_encoding<CS: ConstraintSystem<F>>(_cs: &mut CS, encoding: UInt256<F>) -> Self {
+    /// The encoding is `PrecompileCallABI`, where words 1 and 3 are input and output lengths set by
+    /// the system contract, and words 6 and 7 are the interpreted data that ecrecover doesn't use.
+    /// Only the latter are reserved, so they must be zero
+    pub fn validate_reserved_fields<CS: ConstraintSystem<F>>(cs: &mut CS, encoding: &UInt256<F>) {
+        let zero_u32 = UInt32::zero(cs);
+        for reserved in encoding.inner[6..].iter() {
+            Num::enforce_equal(cs, &reserved.into_num(), &zero_u32.into_num());
+        }
+    }
+
+    pub fn from_encoding<CS: ConstraintSystem<F>>(cs: &mut CS, encoding: UInt256<F>) -> Self {
+        Self::validate_reserved_fields(cs, &encoding);
+
         let input_offset = encoding.inner[0];
         let output_offset = encoding.inner[2];
-        let input_page = encoding.inner[4];
-        let output_page = encoding.inner[5];
+        let input_page = PageId::new(encoding.inner[4]);
+        let output_page = PageId::new(encoding.inner[5]);
 
         let new = Self { input_page, input_offset, output_page, output_offset };
 
         new
     }
+
+    /// Returns whether the output range of a call fits both output words, the success flag and
+    /// the recovered address, that is `output_length >= 2`. A call with a shorter range is valid
+    /// VM execution, so it is not rejected, but reported as failed
+    pub fn output_length_fits<CS: ConstraintSystem<F>>(
+        cs: &mut CS,
+        output_length: UInt32<F>,
+    ) -> Boolean<F> {
+        let num_output_words = UInt32::allocated_constant(cs, NUM_OUTPUT_WORDS_PER_CALL as u32);
+        let (_, too_short) = output_length.overflowing_sub(cs, num_output_words);
+
+        too_short.negated(cs)
+    }
 }
 
 const NUM_WORDS: usize = 17;
 const SECP_B_COEF: u64 = 7;
-const EXCEPTION_FLAGS_ARR_LEN: usize = 9;
+// Exception flags raised by `ecrecover_precompile_inner_routine_with_exception_flags`, in order:
+// - `r + n` overflows 2^256 when `x_overflow` is requested
+// - recovered `x` is not less than `p`
+// - `r` is zero
+// - `s` is zero
+// - message hash is zero (constant `false` if it is allowed to be zero)
+// - `t = x^3 + 7` is zero
+// - `t` is not a quadratic residue, so `y` can not be recovered
+// - recovered public key is the point at infinity
+const EXCEPTION_FLAGS_ARR_LEN: usize = 8;
+const _: () = assert!(EXCEPTION_FLAGS_ARR_LEN == 8);
 const NUM_MEMORY_READS_PER_CYCLE: usize = 4;
+const NUM_OUTPUT_WORDS_PER_CALL: usize = 2;
 const X_POWERS_ARR_LEN: usize = 256;
 const VALID_Y_IN_EXTERNAL_FIELD: u64 = 4;
 const VALID_X_CUBED_IN_EXTERNAL_FIELD: u64 = 9;
@@ -95,13 +139,179 @@ const A1: &'static str = "0x3086d221a7d46bcde86c90e49284eb15";
 const B1: &'static str = "0xe4437ed6010e88286f547fa90abfe4c3";
 const A2: &'static str = "0x114ca50f7a8e2f3f657c1108d9d44cfd8";
 
+// Checks that decomposition constants above form a basis of the lattice of determinant `n`, so
+// `A1 * B2 - A2 * (-B1) = A1^2 + A2 * B1 = n` (B1 is stored without the sign), and that BETA is a
+// nontrivial cubic root of unity modulo `p`. Everything is evaluated at compile time
+const fn verify_glv_constants() -> bool {
+    const SECP_P: [u64; 4] =
+        [0xfffffffefffffc2f, 0xffffffffffffffff, 0xffffffffffffffff, 0xffffffffffffffff];
+    const SECP_N: [u64; 4] =
+        [0xbfd25e8cd0364141, 0xbaaedce6af48a03b, 0xfffffffffffffffe, 0xffffffffffffffff];
+
+    let a1 = glv_const::parse(A1);
+    let b1 = glv_const::parse(B1);
+    let a2 = glv_const::parse(A2);
+    let beta = glv_const::parse(BETA);
+
+    let (determinant, of) =
+        glv_const::add_wide(glv_const::mul_wide(a1, a1), glv_const::mul_wide(a2, b1));
+    if of || !glv_const::eq_wide(determinant, glv_const::widen(SECP_N)) {
+        return false;
+    }
+
+    let one = [1u64, 0, 0, 0];
+    if !glv_const::lt(beta, SECP_P) || glv_const::eq(beta, one) {
+        return false;
+    }
+    let beta_squared = glv_const::reduce(glv_const::mul_wide(beta, beta), SECP_P);
+    let beta_cubed = glv_const::reduce(glv_const::mul_wide(beta_squared, beta), SECP_P);
+
+    glv_const::eq(beta_cubed, one)
+}
+
+const _: () = assert!(verify_glv_constants(), "GLV constants are invalid");
+
+// Minimal little-endian bigint arithmetic that is usable in const context
+mod glv_const {
+    pub(super) const fn parse(s: &str) -> [u64; 4] {
+        let bytes = s.as_bytes();
+        let (radix, mut i) = if bytes.len() > 2 && bytes[0] == b'0' && bytes[1] == b'x' {
+            (16u64, 2)
+        } else {
+            (10u64, 0)
+        };
+
+        let mut result = [0u64; 4];
+        while i < bytes.len() {
+            let digit = match bytes[i] {
+                b'0'..=b'9' => (bytes[i] - b'0') as u64,
+                b'a'..=b'f' => (bytes[i] - b'a' + 10) as u64,
+                b'A'..=b'F' => (bytes[i] - b'A' + 10) as u64,
+                _ => panic!("invalid digit"),
+            };
+            assert!(digit < radix);
+
+            let mut carry = digit as u128;
+            let mut j = 0;
+            while j < 4 {
+                let tmp = (result[j] as u128) * (radix as u128) + carry;
+                result[j] = tmp as u64;
+                carry = tmp >> 64;
+                j += 1;
+            }
+            assert!(carry == 0, "constant does not fit into 256 bits");
+            i += 1;
+        }
+
+        result
+    }
+
+    pub(super) const fn widen(a: [u64; 4]) -> [u64; 8] {
+        [a[0], a[1], a[2], a[3], 0, 0, 0, 0]
+    }
+
+    pub(super) const fn mul_wide(a: [u64; 4], b: [u64; 4]) -> [u64; 8] {
+        let mut result = [0u64; 8];
+        let mut i = 0;
+        while i < 4 {
+            let mut carry = 0u128;
+            let mut j = 0;
+            while j < 4 {
+                let tmp = (a[i] as u128) * (b[j] as u128) + (result[i + j] as u128) + carry;
+                result[i + j] = tmp as u64;
+                carry = tmp >> 64;
+                j += 1;
+            }
+            result[i + 4] = carry as u64;
+            i += 1;
+        }
+
+        result
+    }
+
+    pub(super) const fn add_wide(a: [u64; 8], b: [u64; 8]) -> ([u64; 8], bool) {
+        let mut result = [0u64; 8];
+        let mut carry = 0u128;
+        let mut i = 0;
+        while i < 8 {
+            let tmp = (a[i] as u128) + (b[i] as u128) + carry;
+            result[i] = tmp as u64;
+            carry = tmp >> 64;
+            i += 1;
+        }
+
+        (result, carry != 0)
+    }
+
+    pub(super) const fn eq_wide(a: [u64; 8], b: [u64; 8]) -> bool {
+        let mut i = 0;
+        while i < 8 {
+            if a[i] != b[i] {
+                return false;
+            }
+            i += 1;
+        }
+
+        true
+    }
+
+    pub(super) const fn eq(a: [u64; 4], b: [u64; 4]) -> bool {
+        eq_wide(widen(a), widen(b))
+    }
+
+    pub(super) const fn lt(a: [u64; 4], b: [u64; 4]) -> bool {
+        let mut i = 4;
+        while i > 0 {
+            i -= 1;
+            if a[i] != b[i] {
+                return a[i] < b[i];
+            }
+        }
+
+        false
+    }
+
+    // `a mod modulus` by bitwise long division. Remainder is kept in 5 words, as shifting it by
+    // one bit can exceed 256 bits
+    pub(super) const fn reduce(a: [u64; 8], modulus: [u64; 4]) -> [u64; 4] {
+        let mut rem = [0u64; 5];
+        let mut bit = 512;
+        while bit > 0 {
+            bit -= 1;
+
+            let mut i = 4;
+            while i > 0 {
+                rem[i] = (rem[i] << 1) | (rem[i - 1] >> 63);
+                i -= 1;
+            }
+            rem[0] = (rem[0] << 1) | ((a[bit / 64] >> (bit % 64)) & 1);
+
+            let fits = rem[4] == 0 && lt([rem[0], rem[1], rem[2], rem[3]], modulus);
+            if !fits {
+                let mut borrow = 0u64;
+                let mut j = 0;
+                while j < 5 {
+                    let m = if j < 4 { modulus[j] } else { 0 };
+                    let (tmp, b0) = rem[j].overflowing_sub(m);
+                    let (tmp, b1) = tmp.overflowing_sub(borrow);
+                    rem[j] = tmp;
+                    borrow = (b0 || b1) as u64;
+                    j += 1;
+                }
+            }
+        }
+
+        [rem[0], rem[1], rem[2], rem[3]]
+    }
+}
+
 const WINDOW_WIDTH: usize = 4;
 const NUM_MULTIPLICATION_STEPS_FOR_WIDTH_4: usize = 33;
 const PRECOMPUTATION_TABLE_SIZE: usize = (1 << WINDOW_WIDTH) - 1;
 
 // assume that constructed field element is not zero
 // if this is not satisfied - set the result to be F::one
-fn convert_uint256_to_field_element_masked<
+pub(crate) fn convert_uint256_to_field_element_masked<
     F: SmallField,
     CS: ConstraintSystem<F>,
     P: boojum::pairing::ff::PrimeField,
@@ -154,7 +364,7 @@ where
     (selected, is_zero)
 }
 
-fn convert_uint256_to_field_element<
+pub(crate) fn convert_uint256_to_field_element<
     F: SmallField,
     CS: ConstraintSystem<F>,
     P: boojum::pairing::ff::PrimeField,
@@ -200,7 +410,7 @@ fn convert_uint256_to_field_element<
 }
 
 // NOTE: caller must ensure that the field element is normalized, otherwise this will fail.
-fn convert_field_element_to_uint256<
+pub(crate) fn convert_field_element_to_uint256<
     F: SmallField,
     CS: ConstraintSystem<F>,
     P: boojum::pairing::ff::PrimeField,
@@ -210,7 +420,7 @@ fn convert_field_element_to_uint256<
     mut elem: NonNativeFieldOverU16<F, P, N>,
 ) -> UInt256<F> {
     assert_eq!(elem.form, RepresentationForm::Normalized);
-    assert_eq!(elem.tracker.max_moduluses, 1);
+    elem.tracker.assert_normalized();
 
     let mut limbs = [UInt32::<F>::zero(cs); 8];
     let two_pow_16 = Num::allocated_constant(cs, F::from_u64_unchecked(2u32.pow(16) as u64));
@@ -227,7 +437,7 @@ fn convert_field_element_to_uint256<
     UInt256 { inner: limbs }
 }
 
-fn width_4_windowed_multiplication<F: SmallField, CS: ConstraintSystem<F>>(
+pub(crate) fn width_4_windowed_multiplication<F: SmallField, CS: ConstraintSystem<F>>(
     cs: &mut CS,
     mut point: SWProjectivePoint<F, Secp256Affine, Secp256BaseNNField<F>>,
     mut scalar: Secp256ScalarNNField<F>,
@@ -259,6 +469,7 @@ fn width_4_windowed_multiplication<F: SmallField, CS: ConstraintSystem<F>>(
     let boolean_false = Boolean::allocated_constant(cs, false);
 
     // Scalar decomposition
+    let span = CircuitSpan::begin(&*cs, "GLV decomposition");
     let (k1_was_negated, k1, k2_was_negated, k2) = {
         let k = convert_field_element_to_uint256(cs, scalar.clone());
 
@@ -320,6 +531,7 @@ fn width_4_windowed_multiplication<F: SmallField, CS: ConstraintSystem<F>>(
 
         (k1_out_of_range, k1, k2_out_of_range, k2)
     };
+    CircuitSpan::end(&*cs, span);
 
     // dbg!(k1.witness_hook(cs)());
     // dbg!(k2.witness_hook(cs)());
@@ -450,9 +662,7 @@ fn to_width_4_window_form<F: SmallField, CS: ConstraintSystem<F>>(
         Num::enforce_equal(cs, &word, &zero_num);
     }
 
-    let byte_split_id = cs
-        .get_table_id_for_marker::<ByteSplitTable<4>>()
-        .expect("table should exist");
+    let byte_split_id = get_typed_table_id::<ByteSplitTable<4>, F, CS>(cs);
     let mut result = Vec::with_capacity(32);
     // special case
     {
@@ -460,7 +670,7 @@ fn to_width_4_window_form<F: SmallField, CS: ConstraintSystem<F>>(
         let word = unsafe { UInt16::from_variable_unchecked(highest_word) };
         let [high, low] = word.to_be_bytes(cs);
         Num::enforce_equal(cs, &high.into_num(), &zero_num);
-        let [l, h] = cs.perform_lookup::<1, 2>(byte_split_id, &[low.get_variable()]);
+        let [l, h] = byte_split_id.perform_lookup::<F, CS, 1, 2>(cs, &[low.get_variable()]);
         Num::enforce_equal(cs, &Num::from_variable(h), &zero_num);
         let l = Num::from_variable(l);
         result.push(l);
@@ -470,7 +680,7 @@ fn to_width_4_window_form<F: SmallField, CS: ConstraintSystem<F>>(
         let word = unsafe { UInt16::from_variable_unchecked(*word) };
         let [high, low] = word.to_be_bytes(cs);
         for t in [high, low].into_iter() {
-            let [l, h] = cs.perform_lookup::<1, 2>(byte_split_id, &[t.get_variable()]);
+            let [l, h] = byte_split_id.perform_lookup::<F, CS, 1, 2>(cs, &[t.get_variable()]);
             let h = Num::from_variable(h);
             let l = Num::from_variable(l);
             result.push(h);
@@ -488,6 +698,7 @@ pub(crate) fn fixed_base_mul<
     NNS: boojum::pairing::ff::PrimeField,
     NNB: boojum::pairing::ff::PrimeField + boojum::pairing::ff::SqrtField,
     NNC: boojum::pairing::GenericCurveAffine<Base = NNB>,
+    T: TableMarker,
     const N: usize,
 >(
     cs: &mut CS,
@@ -495,7 +706,7 @@ pub(crate) fn fixed_base_mul<
     base_field_params: &Arc<NonNativeFieldOverU16Params<NNB, N>>,
     scalar_canonical_limbs: usize,
     base_canonical_limbs_canonical_limbs: usize,
-    fixed_base_table_ids: &[[u32; 8]],
+    fixed_base_table_ids: &[[TypedTableId<T>; 8]],
 ) -> SWProjectivePoint<F, NNC, NonNativeFieldOverU16<F, NNB, N>>
 where
     [(); N + 1]:,
@@ -528,7 +739,7 @@ where
             let (x, y): (Vec<Variable>, Vec<Variable>) = ids
                 .iter()
                 .flat_map(|id| {
-                    let [x_v, y_v] = cs.perform_lookup::<1, 2>(*id, &[byte.get_variable()]);
+                    let [x_v, y_v] = id.perform_lookup::<F, CS, 1, 2>(cs, &[byte.get_variable()]);
                     let x_v = unsafe { UInt32::from_variable_unchecked(x_v) };
                     let y_v = unsafe { UInt32::from_variable_unchecked(y_v) };
                     let x_v = x_v.to_le_bytes(cs);
@@ -576,7 +787,83 @@ where
     acc
 }
 
-fn ecrecover_precompile_inner_routine<
+pub(crate) fn negate_point<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    NNB: boojum::pairing::ff::PrimeField,
+    NNC: boojum::pairing::GenericCurveAffine<Base = NNB>,
+    const N: usize,
+>(
+    cs: &mut CS,
+    point: &SWProjectivePoint<F, NNC, NonNativeFieldOverU16<F, NNB, N>>,
+) -> SWProjectivePoint<F, NNC, NonNativeFieldOverU16<F, NNB, N>>
+where
+    [(); N + 1]:,
+{
+    // -(X : Y : Z) = (X : -Y : Z)
+    let mut y = point.y.clone();
+    let y_negated = y.negated(cs);
+
+    SWProjectivePoint {
+        x: point.x.clone(),
+        y: y_negated,
+        z: point.z.clone(),
+        _marker: std::marker::PhantomData,
+    }
+}
+
+// computes P - Q for affine Q = (x, y) as P + (x, -y)
+pub(crate) fn sub_mixed<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    NNB: boojum::pairing::ff::PrimeField,
+    NNC: boojum::pairing::GenericCurveAffine<Base = NNB>,
+    const N: usize,
+>(
+    cs: &mut CS,
+    point: &mut SWProjectivePoint<F, NNC, NonNativeFieldOverU16<F, NNB, N>>,
+    other_xy: &mut (NonNativeFieldOverU16<F, NNB, N>, NonNativeFieldOverU16<F, NNB, N>),
+) -> SWProjectivePoint<F, NNC, NonNativeFieldOverU16<F, NNB, N>>
+where
+    [(); N + 1]:,
+{
+    let y_negated = other_xy.1.negated(cs);
+    let mut negated_other_xy = (other_xy.0.clone(), y_negated);
+
+    point.add_mixed(cs, &mut negated_other_xy)
+}
+
+// checks that the affine point is on the curve y^2 = x^3 + b (so only for curves with a = 0)
+pub(crate) fn check_on_curve<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    P: boojum::pairing::ff::PrimeField,
+    const N: usize,
+>(
+    cs: &mut CS,
+    x: &mut NonNativeFieldOverU16<F, P, N>,
+    y: &mut NonNativeFieldOverU16<F, P, N>,
+    b: &mut NonNativeFieldOverU16<F, P, N>,
+) -> Boolean<F>
+where
+    [(); N + 1]:,
+{
+    let mut lhs = y.clone();
+    let mut lhs = lhs.mul(cs, y);
+    lhs.normalize(cs);
+
+    let mut rhs = x.clone();
+    let mut rhs = rhs.mul(cs, x);
+    let mut rhs = rhs.mul(cs, x);
+    let mut rhs = rhs.add(cs, b);
+    rhs.normalize(cs);
+
+    lhs.tracker.assert_normalized();
+    rhs.tracker.assert_normalized();
+    NonNativeFieldOverU16::equals(cs, &mut lhs, &mut rhs)
+}
+
+pub(crate) fn ecrecover_precompile_inner_routine<
     F: SmallField,
     CS: ConstraintSystem<F>,
     const MESSAGE_HASH_CAN_BE_ZERO: bool,
@@ -592,6 +879,41 @@ fn ecrecover_precompile_inner_routine<
     base_field_params: &Arc<Secp256BaseNNFieldParams>,
     scalar_field_params: &Arc<Secp256ScalarNNFieldParams>,
 ) -> (Boolean<F>, UInt256<F>) {
+    let (_exception_flags, all_ok, written_value) =
+        ecrecover_precompile_inner_routine_with_exception_flags::<F, CS, MESSAGE_HASH_CAN_BE_ZERO>(
+            cs,
+            recid,
+            r,
+            s,
+            message_hash,
+            valid_x_in_external_field,
+            valid_y_in_external_field,
+            valid_t_in_external_field,
+            base_field_params,
+            scalar_field_params,
+        );
+
+    (all_ok, written_value)
+}
+
+// same as `ecrecover_precompile_inner_routine`, but also returns the individual exception flags in
+// the order they are raised, so tests can check which of them fired
+fn ecrecover_precompile_inner_routine_with_exception_flags<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    const MESSAGE_HASH_CAN_BE_ZERO: bool,
+>(
+    cs: &mut CS,
+    recid: &UInt8<F>,
+    r: &UInt256<F>,
+    s: &UInt256<F>,
+    message_hash: &UInt256<F>,
+    valid_x_in_external_field: Secp256BaseNNField<F>,
+    valid_y_in_external_field: Secp256BaseNNField<F>,
+    valid_t_in_external_field: Secp256BaseNNField<F>,
+    base_field_params: &Arc<Secp256BaseNNFieldParams>,
+    scalar_field_params: &Arc<Secp256ScalarNNFieldParams>,
+) -> (ArrayVec<Boolean<F>, EXCEPTION_FLAGS_ARR_LEN>, Boolean<F>, UInt256<F>) {
     use boojum::pairing::ff::Field;
     let curve_b = Secp256Affine::b_coeff();
 
@@ -684,6 +1006,7 @@ fn ecrecover_precompile_inner_routine<
     // if t is zero then just mask
     let t = Selectable::conditionally_select(cs, t_is_zero, &valid_t_in_external_field, &t);
 
+    let span = CircuitSpan::begin(&*cs, "Legendre symbol");
     // array of powers of t of the form t^{2^i} starting from i = 0 to 255
     let mut t_powers = Vec::with_capacity(X_POWERS_ARR_LEN);
     t_powers.push(t);
@@ -700,6 +1023,7 @@ fn ecrecover_precompile_inner_routine<
         acc = acc.mul(cs, other);
     }
     let mut legendre_symbol = t_powers[255].div_unchecked(cs, &mut acc);
+    CircuitSpan::end(&*cs, span);
 
     // we can also reuse the same values to compute square root in case of p = 3 mod 4
     //           p = 2^256 - 2^32 - 2^9 - 2^8 - 2^7 - 2^6 - 2^4 - 1
@@ -770,6 +1094,7 @@ fn ecrecover_precompile_inner_routine<
         SWProjectivePoint::<F, Secp256Affine, Secp256BaseNNField<F>>::from_xy_unchecked(cs, x, y);
 
     // now we do multiplication
+    let span = CircuitSpan::begin(&*cs, "s * X multiplication");
     let mut s_times_x = width_4_windowed_multiplication(
         cs,
         recovered_point.clone(),
@@ -777,31 +1102,12 @@ fn ecrecover_precompile_inner_routine<
         &base_field_params,
         &scalar_field_params,
     );
+    CircuitSpan::end(&*cs, span);
 
-    let mut full_table_ids = vec![];
-    seq_macro::seq!(C in 0..32 {
-        let ids = [
-            cs.get_table_id_for_marker::<FixedBaseMulTable<0, C>>()
-                .expect("table must exist"),
-            cs.get_table_id_for_marker::<FixedBaseMulTable<1, C>>()
-                .expect("table must exist"),
-            cs.get_table_id_for_marker::<FixedBaseMulTable<2, C>>()
-                .expect("table must exist"),
-            cs.get_table_id_for_marker::<FixedBaseMulTable<3, C>>()
-                .expect("table must exist"),
-            cs.get_table_id_for_marker::<FixedBaseMulTable<4, C>>()
-                .expect("table must exist"),
-            cs.get_table_id_for_marker::<FixedBaseMulTable<5, C>>()
-                .expect("table must exist"),
-            cs.get_table_id_for_marker::<FixedBaseMulTable<6, C>>()
-                .expect("table must exist"),
-            cs.get_table_id_for_marker::<FixedBaseMulTable<7, C>>()
-                .expect("table must exist"),
-        ];
-        full_table_ids.push(ids);
-    });
+    let full_table_ids = get_fixed_base_mul_table_ids::<F, CS>(&*cs);
 
-    let mut hash_times_g = fixed_base_mul::<F, CS, Secp256Fr, Secp256Fq, Secp256Affine, 17>(
+    let span = CircuitSpan::begin(&*cs, "hash * G multiplication");
+    let mut hash_times_g = fixed_base_mul::<F, CS, Secp256Fr, Secp256Fq, Secp256Affine, _, 17>(
         cs,
         message_hash_by_r_inv_negated,
         &base_field_params,
@@ -809,6 +1115,7 @@ fn ecrecover_precompile_inner_routine<
         BASE_FIELD_CANONICAL_REPR_LIMBS,
         &full_table_ids,
     );
+    CircuitSpan::end(&*cs, span);
 
     let (mut q_acc, is_infinity) =
         hash_times_g.convert_to_affine_or_default(cs, Secp256Affine::one());
@@ -817,6 +1124,7 @@ fn ecrecover_precompile_inner_routine<
 
     let ((q_x, q_y), is_infinity) = q_acc.convert_to_affine_or_default(cs, Secp256Affine::one());
     exception_flags.push(is_infinity);
+    debug_assert_eq!(exception_flags.len(), EXCEPTION_FLAGS_ARR_LEN);
     let any_exception = Boolean::multi_or(cs, &exception_flags[..]);
 
     let zero_u8 = UInt8::zero(cs);
@@ -837,7 +1145,9 @@ fn ecrecover_precompile_inner_routine<
         *dst = limb.to_be_bytes(cs);
     }
 
+    let span = CircuitSpan::begin(&*cs, "keccak256");
     let mut digest_bytes = keccak256(cs, &bytes_to_hash);
+    CircuitSpan::end(&*cs, span);
     // digest is 32 bytes, but we need only 20 to recover address
     digest_bytes[0..12].copy_from_slice(&[zero_u8; 12]); // empty out top bytes
     digest_bytes.reverse();
@@ -846,7 +1156,32 @@ fn ecrecover_precompile_inner_routine<
     let written_value = written_value_unmasked.mask_negated(cs, any_exception);
     let all_ok = any_exception.negated(cs);
 
-    (all_ok, written_value)
+    (exception_flags, all_ok, written_value)
+}
+
+/// Allocates `(x, y, t)` that the inner routine uses in place of an unrecoverable point, where
+/// `t = x^3 + 7`
+pub(crate) fn allocate_masking_constants<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    base_field_params: &Arc<Secp256BaseNNFieldParams>,
+) -> (Secp256BaseNNField<F>, Secp256BaseNNField<F>, Secp256BaseNNField<F>) {
+    let valid_x_in_external_field = Secp256BaseNNField::allocated_constant(
+        cs,
+        Secp256Fq::from_str(&VALID_X_CUBED_IN_EXTERNAL_FIELD.to_string()).unwrap(),
+        base_field_params,
+    );
+    let valid_t_in_external_field = Secp256BaseNNField::allocated_constant(
+        cs,
+        Secp256Fq::from_str(&(VALID_X_CUBED_IN_EXTERNAL_FIELD + SECP_B_COEF).to_string()).unwrap(),
+        base_field_params,
+    );
+    let valid_y_in_external_field = Secp256BaseNNField::allocated_constant(
+        cs,
+        Secp256Fq::from_str(&VALID_Y_IN_EXTERNAL_FIELD.to_string()).unwrap(),
+        base_field_params,
+    );
+
+    (valid_x_in_external_field, valid_y_in_external_field, valid_t_in_external_field)
 }
 
 pub fn ecrecover_function_entry_point<
@@ -875,30 +1210,14 @@ where
 
     let memory_reads_witness: VecDeque<_> = memory_reads_witness.into_iter().flatten().collect();
 
-    let precompile_address = UInt160::allocated_constant(
-        cs,
-        *zkevm_opcode_defs::system_params::ECRECOVER_INNER_FUNCTION_PRECOMPILE_FORMAL_ADDRESS,
-    );
+    let precompile_address = PrecompileKind::Ecrecover.to_address(cs);
     let aux_byte_for_precompile = UInt8::allocated_constant(cs, PRECOMPILE_AUX_BYTE);
 
     let scalar_params = Arc::new(secp256k1_scalar_field_params());
     let base_params = Arc::new(secp256k1_base_field_params());
 
-    let valid_x_in_external_field = Secp256BaseNNField::allocated_constant(
-        cs,
-        Secp256Fq::from_str(&VALID_X_CUBED_IN_EXTERNAL_FIELD.to_string()).unwrap(),
-        &base_params,
-    );
-    let valid_t_in_external_field = Secp256BaseNNField::allocated_constant(
-        cs,
-        Secp256Fq::from_str(&(VALID_X_CUBED_IN_EXTERNAL_FIELD + SECP_B_COEF).to_string()).unwrap(),
-        &base_params,
-    );
-    let valid_y_in_external_field = Secp256BaseNNField::allocated_constant(
-        cs,
-        Secp256Fq::from_str(&VALID_Y_IN_EXTERNAL_FIELD.to_string()).unwrap(),
-        &base_params,
-    );
+    let (valid_x_in_external_field, valid_y_in_external_field, valid_t_in_external_field) =
+        allocate_masking_constants(cs, &base_params);
 
     let mut structured_input =
         EcrecoverCircuitInputOutput::alloc_ignoring_outputs(cs, closed_form_input.clone());
@@ -949,11 +1268,41 @@ where
         witness_source: Arc::new(RwLock::new(memory_reads_witness)),
     };
 
+    let zero_u32 = UInt32::zero(cs);
+    let mut num_requests_processed = UInt32::conditionally_select(
+        cs,
+        start_flag,
+        &zero_u32,
+        &structured_input.hidden_fsm_input.num_requests_processed,
+    );
+    let mut num_successful_calls = UInt32::conditionally_select(
+        cs,
+        start_flag,
+        &zero_u32,
+        &structured_input.hidden_fsm_input.num_successful_calls,
+    );
+    let mut gas_meter = GasMeter::new(UInt32::conditionally_select(
+        cs,
+        start_flag,
+        &zero_u32,
+        &structured_input.hidden_fsm_input.gas_used,
+    ));
+    let gas_cost_per_call = UInt32::allocated_constant(cs, ECRECOVER_GAS_COST_PER_CALL);
+
     for _cycle in 0..limit {
         let is_empty = requests_queue.is_empty(cs);
         let should_process = is_empty.negated(cs);
         let (request, _) = requests_queue.pop_front(cs, should_process);
 
+        // failed calls are charged too
+        let gas_overflow = gas_meter.charge(cs, gas_cost_per_call, should_process);
+        Boolean::enforce_equal(cs, &gas_overflow, &boolean_false);
+
+        num_requests_processed = num_requests_processed.add_no_overflow(cs, unsafe {
+            UInt32::from_variable_unchecked(should_process.get_variable())
+        });
+
+        let output_length = request.key.inner[3];
         let mut precompile_call_params =
             EcrecoverPrecompileCallParams::from_encoding(cs, request.key);
 
@@ -991,7 +1340,7 @@ where
 
             let read_query = MemoryQuery {
                 timestamp: timestamp_to_use_for_read,
-                memory_page: precompile_call_params.input_page,
+                memory_page: precompile_call_params.input_page.page(),
                 index: precompile_call_params.input_offset,
                 rw_flag: boolean_false,
                 is_ptr: boolean_false,
@@ -1030,6 +1379,16 @@ where
             &scalar_params,
         );
 
+        let output_length_fits =
+            EcrecoverPrecompileCallParams::output_length_fits(cs, output_length);
+        let success = success.and(cs, output_length_fits);
+        let written_value = written_value.mask(cs, output_length_fits);
+
+        let call_succeeded = Boolean::multi_and(cs, &[success, should_process]);
+        num_successful_calls = num_successful_calls.add_no_overflow(cs, unsafe {
+            UInt32::from_variable_unchecked(call_succeeded.get_variable())
+        });
+
         let success_as_u32 = unsafe { UInt32::from_variable_unchecked(success.get_variable()) };
         let mut success_as_u256 = zero_u256;
         success_as_u256.inner[0] = success_as_u32;
@@ -1043,7 +1402,7 @@ where
 
         let success_query = MemoryQuery {
             timestamp: timestamp_to_use_for_write,
-            memory_page: precompile_call_params.output_page,
+            memory_page: precompile_call_params.output_page.page(),
             index: precompile_call_params.output_offset,
             rw_flag: boolean_true,
             value: success_as_u256,
@@ -1054,18 +1413,21 @@ where
             .output_offset
             .add_no_overflow(cs, one_u32);
 
-        let _ = memory_queue.push(cs, success_query, should_process);
-
         let value_query = MemoryQuery {
             timestamp: timestamp_to_use_for_write,
-            memory_page: precompile_call_params.output_page,
+            memory_page: precompile_call_params.output_page.page(),
             index: precompile_call_params.output_offset,
             rw_flag: boolean_true,
             value: written_value,
             is_ptr: boolean_false,
         };
 
-        let _ = memory_queue.push(cs, value_query, should_process);
+        batch_push_memory_queries(
+            cs,
+            &mut memory_queue,
+            [success_query, value_query],
+            should_process,
+        );
     }
 
     requests_queue.enforce_consistency(cs);
@@ -1084,9 +1446,24 @@ where
         &final_memory_state,
         &structured_input.observable_output.final_memory_state,
     );
+    structured_input.observable_output.num_successful_calls = UInt32::conditionally_select(
+        cs,
+        structured_input.completion_flag,
+        &num_successful_calls,
+        &structured_input.observable_output.num_successful_calls,
+    );
+    structured_input.observable_output.gas_used = UInt32::conditionally_select(
+        cs,
+        structured_input.completion_flag,
+        &gas_meter.gas_used,
+        &structured_input.observable_output.gas_used,
+    );
 
     structured_input.hidden_fsm_output.log_queue_state = final_requets_state;
     structured_input.hidden_fsm_output.memory_queue_state = final_memory_state;
+    structured_input.hidden_fsm_output.num_requests_processed = num_requests_processed;
+    structured_input.hidden_fsm_output.num_successful_calls = num_successful_calls;
+    structured_input.hidden_fsm_output.gas_used = gas_meter.gas_used;
 
     // self-check
     structured_input.hook_compare_witness(cs, &closed_form_input);
@@ -1116,14 +1493,15 @@ mod test {
     use super::*;
 
     type F = GoldilocksField;
-    type P = GoldilocksField;
 
     use boojum::{
-        config::DevCSConfig,
+        gadgets::tables::byte_split::ByteSplitTable,
         pairing::{ff::PrimeFieldRepr, GenericCurveAffine, GenericCurveProjective},
     };
     use rand::{Rng, SeedableRng, XorShiftRng};
 
+    use crate::{test_utils::create_test_cs, tools::complexity_report::generate_complexity_report};
+
     pub fn deterministic_rng() -> XorShiftRng {
         XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654])
     }
@@ -1190,164 +1568,105 @@ mod test {
         u256
     }
 
-    use boojum::{
-        cs::{
-            cs_builder::*, cs_builder_reference::CsReferenceImplementationBuilder, gates::*,
-            implementations::reference_cs::CSReferenceImplementation,
-            traits::gate::GatePlacementStrategy, CSGeometry, *,
-        },
-        gadgets::tables::{byte_split::ByteSplitTable, *},
-    };
+    fn check_byte_split_table<CS: ConstraintSystem<F>, const SPLIT_AT: usize>(cs: &mut CS) {
+        let table_id = cs
+            .get_table_id_for_marker::<ByteSplitTable<SPLIT_AT>>()
+            .expect("table must exist");
+        for byte in 0..=u8::MAX {
+            let input = UInt8::allocate_checked(cs, byte);
+            let [low, high] = cs.perform_lookup::<1, 2>(table_id, &[input.get_variable()]);
+            let low = Num::from_variable(low).witness_hook(cs)().unwrap();
+            let high = Num::from_variable(high).witness_hook(cs)().unwrap();
+            assert_eq!(low.as_u64(), (byte as u64) & ((1u64 << SPLIT_AT) - 1));
+            assert_eq!(high.as_u64(), (byte as u64) >> SPLIT_AT);
+        }
+    }
 
-    use crate::ecrecover::secp256k1::fixed_base_mul_table::{
-        create_fixed_base_mul_table, FixedBaseMulTable,
-    };
+    #[test]
+    fn test_wide_byte_split_tables() {
+        let mut owned_cs = create_test_cs(1 << 21);
+        let cs = &mut owned_cs;
 
-    fn create_cs(
-        max_trace_len: usize,
-    ) -> CSReferenceImplementation<
-        F,
-        P,
-        DevCSConfig,
-        impl GateConfigurationHolder<F>,
-        impl StaticToolboxHolder,
-    > {
-        let geometry = CSGeometry {
-            num_columns_under_copy_permutation: 100,
-            num_witness_columns: 0,
-            num_constant_columns: 8,
-            max_allowed_constraint_degree: 4,
-        };
-        let max_variables = 1 << 26;
-
-        fn configure<
-            F: SmallField,
-            T: CsBuilderImpl<F, T>,
-            GC: GateConfigurationHolder<F>,
-            TB: StaticToolboxHolder,
-        >(
-            builder: CsBuilder<T, F, GC, TB>,
-        ) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
-            let builder = builder.allow_lookup(
-                LookupParameters::UseSpecializedColumnsWithTableIdAsConstant {
-                    width: 3,
-                    num_repetitions: 8,
-                    share_table_id: true,
-                },
-            );
-            let builder = U8x4FMAGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = ConstantsAllocatorGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = ReductionGate::<F, 4>::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            // let owned_cs = ReductionGate::<F, 4>::configure_for_cs(owned_cs,
-            // GatePlacementStrategy::UseSpecializedColumns { num_repetitions: 8, share_constants:
-            // true });
-            let builder = BooleanConstraintGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = UIntXAddGate::<32>::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = UIntXAddGate::<16>::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = UIntXAddGate::<8>::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = SelectionGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = ZeroCheckGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-                false,
-            );
-            let builder = DotProductGate::<4>::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            // let owned_cs = DotProductGate::<4>::configure_for_cs(owned_cs,
-            // GatePlacementStrategy::UseSpecializedColumns { num_repetitions: 1, share_constants:
-            // true });
-            let builder = NopGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
+        check_byte_split_table::<_, 5>(cs);
+        check_byte_split_table::<_, 6>(cs);
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+
+    #[test]
+    fn test_point_negation_and_subtraction() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+        let base_params = Arc::new(secp256k1_base_field_params());
+
+        let generator = Secp256Affine::one();
+        let (gx, gy) = generator.as_xy();
+        let x = Secp256BaseNNField::allocate_checked(cs, *gx, &base_params);
+        let y = Secp256BaseNNField::allocate_checked(cs, *gy, &base_params);
+
+        let mut point =
+            SWProjectivePoint::<F, Secp256Affine, Secp256BaseNNField<F>>::from_xy_unchecked(
+                cs,
+                x.clone(),
+                y.clone(),
             );
 
-            builder
-        }
+        let mut negated = negate_point(cs, &point);
+        let ((negated_x, negated_y), _) =
+            negated.convert_to_affine_or_default(cs, Secp256Affine::one());
+        let mut expected_y = *gy;
+        expected_y.negate();
+        assert_eq!(negated_x.witness_hook(cs)().unwrap().get(), *gx);
+        assert_eq!(negated_y.witness_hook(cs)().unwrap().get(), expected_y);
+
+        // 2G - G = G
+        let mut doubled = point.double(cs);
+        let mut result = sub_mixed(cs, &mut doubled, &mut (x, y));
+        let ((result_x, result_y), is_infinity) =
+            result.convert_to_affine_or_default(cs, Secp256Affine::one());
+        assert_eq!(result_x.witness_hook(cs)().unwrap().get(), *gx);
+        assert_eq!(result_y.witness_hook(cs)().unwrap().get(), *gy);
+        assert!(!is_infinity.witness_hook(cs)().unwrap());
 
-        let builder_impl =
-            CsReferenceImplementationBuilder::<F, P, DevCSConfig>::new(geometry, max_trace_len);
-        let builder = new_builder::<_, F>(builder_impl);
-
-        let builder = configure(builder);
-        let mut owned_cs = builder.build(max_variables);
-
-        // add tables
-        let table = create_xor8_table();
-        owned_cs.add_lookup_table::<Xor8Table, 3>(table);
-
-        let table = create_and8_table();
-        owned_cs.add_lookup_table::<And8Table, 3>(table);
-
-        // let table = create_naf_abs_div2_table();
-        // owned_cs.add_lookup_table::<NafAbsDiv2Table, 3>(table);
-
-        // let table = create_wnaf_decomp_table();
-        // owned_cs.add_lookup_table::<WnafDecompTable, 3>(table);
-
-        seq_macro::seq!(C in 0..32 {
-            let table = create_fixed_base_mul_table::<F, 0, C>();
-            owned_cs.add_lookup_table::<FixedBaseMulTable<0, C>, 3>(table);
-            let table = create_fixed_base_mul_table::<F, 1, C>();
-            owned_cs.add_lookup_table::<FixedBaseMulTable<1, C>, 3>(table);
-            let table = create_fixed_base_mul_table::<F, 2, C>();
-            owned_cs.add_lookup_table::<FixedBaseMulTable<2, C>, 3>(table);
-            let table = create_fixed_base_mul_table::<F, 3, C>();
-            owned_cs.add_lookup_table::<FixedBaseMulTable<3, C>, 3>(table);
-            let table = create_fixed_base_mul_table::<F, 4, C>();
-            owned_cs.add_lookup_table::<FixedBaseMulTable<4, C>, 3>(table);
-            let table = create_fixed_base_mul_table::<F, 5, C>();
-            owned_cs.add_lookup_table::<FixedBaseMulTable<5, C>, 3>(table);
-            let table = create_fixed_base_mul_table::<F, 6, C>();
-            owned_cs.add_lookup_table::<FixedBaseMulTable<6, C>, 3>(table);
-            let table = create_fixed_base_mul_table::<F, 7, C>();
-            owned_cs.add_lookup_table::<FixedBaseMulTable<7, C>, 3>(table);
-        });
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
 
-        let table = create_byte_split_table::<F, 1>();
-        owned_cs.add_lookup_table::<ByteSplitTable<1>, 3>(table);
-        let table = create_byte_split_table::<F, 2>();
-        owned_cs.add_lookup_table::<ByteSplitTable<2>, 3>(table);
-        let table = create_byte_split_table::<F, 3>();
-        owned_cs.add_lookup_table::<ByteSplitTable<3>, 3>(table);
-        let table = create_byte_split_table::<F, 4>();
-        owned_cs.add_lookup_table::<ByteSplitTable<4>, 3>(table);
+    #[test]
+    fn test_check_on_curve() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+        let base_params = Arc::new(secp256k1_base_field_params());
 
-        owned_cs
+        let (gx, gy) = Secp256Affine::one().into_xy_unchecked();
+        let mut off_curve_y = gy;
+        off_curve_y.add_assign(&Secp256Fq::one());
+
+        let mut x = Secp256BaseNNField::allocate_checked(cs, gx, &base_params);
+        let mut y = Secp256BaseNNField::allocate_checked(cs, gy, &base_params);
+        let mut wrong_y = Secp256BaseNNField::allocate_checked(cs, off_curve_y, &base_params);
+        let mut b =
+            Secp256BaseNNField::allocated_constant(cs, Secp256Affine::b_coeff(), &base_params);
+
+        let is_on_curve = check_on_curve(cs, &mut x, &mut y, &mut b);
+        assert!(is_on_curve.witness_hook(cs)().unwrap());
+        let is_on_curve = check_on_curve(cs, &mut x, &mut wrong_y, &mut b);
+        assert!(!is_on_curve.witness_hook(cs)().unwrap());
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
     }
 
     #[test]
     fn test_fixed_base_mul() {
-        let mut owned_cs = create_cs(1 << 21);
+        let mut owned_cs = create_test_cs(1 << 21);
         let cs = &mut owned_cs;
         let scalar_params = Arc::new(secp256k1_scalar_field_params());
         let base_params = Arc::new(secp256k1_base_field_params());
@@ -1355,32 +1674,11 @@ mod test {
         let mut seed = Secp256Fr::multiplicative_generator();
         seed = seed.pow([1234]);
 
-        let mut full_table_ids = vec![];
-        seq_macro::seq!(C in 0..32 {
-            let ids = [
-                cs.get_table_id_for_marker::<FixedBaseMulTable<0, C>>()
-                    .expect("table must exist"),
-                cs.get_table_id_for_marker::<FixedBaseMulTable<1, C>>()
-                    .expect("table must exist"),
-                cs.get_table_id_for_marker::<FixedBaseMulTable<2, C>>()
-                    .expect("table must exist"),
-                cs.get_table_id_for_marker::<FixedBaseMulTable<3, C>>()
-                    .expect("table must exist"),
-                cs.get_table_id_for_marker::<FixedBaseMulTable<4, C>>()
-                    .expect("table must exist"),
-                cs.get_table_id_for_marker::<FixedBaseMulTable<5, C>>()
-                    .expect("table must exist"),
-                cs.get_table_id_for_marker::<FixedBaseMulTable<6, C>>()
-                    .expect("table must exist"),
-                cs.get_table_id_for_marker::<FixedBaseMulTable<7, C>>()
-                    .expect("table must exist"),
-            ];
-            full_table_ids.push(ids);
-        });
+        let full_table_ids = get_fixed_base_mul_table_ids::<GoldilocksField, _>(&*cs);
 
         for _i in 0..16 {
             let scalar = Secp256ScalarNNField::allocate_checked(cs, seed, &scalar_params);
-            let mut result = fixed_base_mul::<GoldilocksField, _, _, _, _, 17>(
+            let mut result = fixed_base_mul::<GoldilocksField, _, _, _, _, _, 17>(
                 cs,
                 scalar,
                 &base_params,
@@ -1403,7 +1701,7 @@ mod test {
 
     #[test]
     fn test_variable_base_mul() {
-        let mut owned_cs = create_cs(1 << 21);
+        let mut owned_cs = create_test_cs(1 << 21);
         let cs = &mut owned_cs;
         let scalar_params = Arc::new(secp256k1_scalar_field_params());
         let base_params = Arc::new(secp256k1_base_field_params());
@@ -1445,7 +1743,7 @@ mod test {
 
     #[test]
     fn test_signature_for_address_verification() {
-        let mut owned_cs = create_cs(1 << 20);
+        let mut owned_cs = create_test_cs(1 << 20);
         let cs = &mut owned_cs;
 
         let sk = crate::ff::from_hex::<Secp256Fr>(
@@ -1487,6 +1785,7 @@ mod test {
             &base_params,
         );
 
+        let span = CircuitSpan::begin(&*cs, "ecrecover_precompile_inner_routine");
         for _ in 0..5 {
             let (no_error, digest) = ecrecover_precompile_inner_routine::<_, _, true>(
                 cs,
@@ -1507,8 +1806,16 @@ mod test {
             assert_eq!(&recovered_address[12..], &eth_address[..]);
         }
 
+        let routine_gates = CircuitSpan::end(&*cs, span);
+
         dbg!(cs.next_available_row());
 
+        let mut report = generate_complexity_report("ecrecover", &*cs);
+        report.record_gates("ecrecover_precompile_inner_routine", routine_gates);
+        if crate::config::CIRCUIT_VERSOBE {
+            println!("{}", report.as_json());
+        }
+
         cs.pad_and_shrink();
 
         let mut cs = owned_cs.into_assembly::<std::alloc::Global>();
@@ -1519,7 +1826,7 @@ mod test {
 
     #[test]
     fn test_signature_from_reference_vector() {
-        let mut owned_cs = create_cs(1 << 20);
+        let mut owned_cs = create_test_cs(1 << 20);
         let cs = &mut owned_cs;
 
         let digest =
@@ -1595,7 +1902,7 @@ mod test {
 
     #[test]
     fn test_signature_from_reference_vector_2() {
-        let mut owned_cs = create_cs(1 << 20);
+        let mut owned_cs = create_test_cs(1 << 20);
         let cs = &mut owned_cs;
 
         let digest =
@@ -1671,7 +1978,7 @@ mod test {
 
     #[test]
     fn test_ecrecover_zero_elements() {
-        let mut owned_cs = create_cs(1 << 21);
+        let mut owned_cs = create_test_cs(1 << 21);
         let cs = &mut owned_cs;
 
         let sk = crate::ff::from_hex::<Secp256Fr>(
@@ -1779,7 +2086,7 @@ mod test {
     // https://ethresear.ch/t/you-can-kinda-abuse-ecrecover-to-do-ecmul-in-secp256k1-today/2384
     #[test]
     fn test_ecrecover_scalar_mul_trick() {
-        let mut owned_cs = create_cs(1 << 20);
+        let mut owned_cs = create_test_cs(1 << 20);
         let cs = &mut owned_cs;
 
         // NOTE: This is essentially reducing a base field to a scalar field element. Due to the
@@ -1857,4 +2164,429 @@ mod test {
         let worker = Worker::new();
         assert!(cs.check_if_satisfied(&worker));
     }
+
+    fn address_for_pk(pk: Secp256Affine) -> Vec<u8> {
+        use zkevm_opcode_defs::sha3::*;
+
+        let (x, y) = pk.into_xy_unchecked();
+        let mut encoding = Vec::with_capacity(64);
+        x.into_repr().write_be(&mut encoding).unwrap();
+        y.into_repr().write_be(&mut encoding).unwrap();
+        let digest = Keccak256::digest(&encoding);
+
+        digest[12..].to_vec()
+    }
+
+    // NOTE: proptest is not a dependency of this crate, so cases are drawn from a deterministic
+    // rng. `simulate_signature_for_sk` uses the same nonce for every key, so recovery id is
+    // always 0
+    const NUM_RANDOMIZED_CASES: usize = 50;
+    const RANDOMIZED_CASES_PER_CS: usize = 10;
+
+    #[test]
+    fn test_ecrecover_randomized_valid_signatures() {
+        let mut rng = XorShiftRng::from_seed([0x1234567, 0x89abcdef, 0x2468ace, 0x13579bdf]);
+
+        let scalar_params = Arc::new(secp256k1_scalar_field_params());
+        let base_params = Arc::new(secp256k1_base_field_params());
+
+        for _ in 0..(NUM_RANDOMIZED_CASES / RANDOMIZED_CASES_PER_CS) {
+            let mut owned_cs = create_test_cs(1 << 21);
+            let cs = &mut owned_cs;
+
+            let valid_x_in_external_field = Secp256BaseNNField::allocated_constant(
+                cs,
+                Secp256Fq::from_str("9").unwrap(),
+                &base_params,
+            );
+            let valid_t_in_external_field = Secp256BaseNNField::allocated_constant(
+                cs,
+                Secp256Fq::from_str("16").unwrap(),
+                &base_params,
+            );
+            let valid_y_in_external_field = Secp256BaseNNField::allocated_constant(
+                cs,
+                Secp256Fq::from_str("4").unwrap(),
+                &base_params,
+            );
+
+            for _ in 0..RANDOMIZED_CASES_PER_CS {
+                let sk: Secp256Fr = rng.gen();
+                let (r, s, pk, digest) = simulate_signature_for_sk(sk);
+                let eth_address = address_for_pk(pk);
+
+                let rec_id = UInt8::allocate_checked(cs, 0);
+                let r = UInt256::allocate(cs, repr_into_u256(r.into_repr()));
+                let s = UInt256::allocate(cs, repr_into_u256(s.into_repr()));
+                let digest = UInt256::allocate(cs, repr_into_u256(digest.into_repr()));
+
+                let (no_error, digest) = ecrecover_precompile_inner_routine::<_, _, true>(
+                    cs,
+                    &rec_id,
+                    &r,
+                    &s,
+                    &digest,
+                    valid_x_in_external_field.clone(),
+                    valid_y_in_external_field.clone(),
+                    valid_t_in_external_field.clone(),
+                    &base_params,
+                    &scalar_params,
+                );
+
+                assert!(no_error.witness_hook(&*cs)().unwrap() == true);
+                let recovered_address = digest.to_be_bytes(cs);
+                let recovered_address = recovered_address.witness_hook(cs)().unwrap();
+                assert_eq!(&recovered_address[12..], &eth_address[..]);
+            }
+
+            cs.pad_and_shrink();
+            let worker = Worker::new();
+            let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+            assert!(owned_cs.check_if_satisfied(&worker));
+        }
+    }
+
+    #[test]
+    fn test_ecrecover_randomized_zero_r_or_s() {
+        // NOTE: only zero is rejected by the precompile itself, `r = 1` or `s = 1` are valid field
+        // elements and just recover some unrelated address
+        let mut rng = XorShiftRng::from_seed([0x7654321, 0xfedcba98, 0xeca8642, 0xfdb97531]);
+
+        let scalar_params = Arc::new(secp256k1_scalar_field_params());
+        let base_params = Arc::new(secp256k1_base_field_params());
+
+        let mut owned_cs = create_test_cs(1 << 21);
+        let cs = &mut owned_cs;
+
+        let valid_x_in_external_field = Secp256BaseNNField::allocated_constant(
+            cs,
+            Secp256Fq::from_str("9").unwrap(),
+            &base_params,
+        );
+        let valid_t_in_external_field = Secp256BaseNNField::allocated_constant(
+            cs,
+            Secp256Fq::from_str("16").unwrap(),
+            &base_params,
+        );
+        let valid_y_in_external_field = Secp256BaseNNField::allocated_constant(
+            cs,
+            Secp256Fq::from_str("4").unwrap(),
+            &base_params,
+        );
+
+        for i in 0..RANDOMIZED_CASES_PER_CS {
+            let sk: Secp256Fr = rng.gen();
+            let (mut r, mut s, _pk, digest) = simulate_signature_for_sk(sk);
+            match i % 3 {
+                0 => r = Secp256Fr::zero(),
+                1 => s = Secp256Fr::zero(),
+                _ => {
+                    r = Secp256Fr::zero();
+                    s = Secp256Fr::zero();
+                }
+            }
+
+            let rec_id = UInt8::allocate_checked(cs, rng.gen::<u8>() & 1);
+            let r = UInt256::allocate(cs, repr_into_u256(r.into_repr()));
+            let s = UInt256::allocate(cs, repr_into_u256(s.into_repr()));
+            let digest = UInt256::allocate(cs, repr_into_u256(digest.into_repr()));
+
+            let (no_error, _digest) = ecrecover_precompile_inner_routine::<_, _, true>(
+                cs,
+                &rec_id,
+                &r,
+                &s,
+                &digest,
+                valid_x_in_external_field.clone(),
+                valid_y_in_external_field.clone(),
+                valid_t_in_external_field.clone(),
+                &base_params,
+                &scalar_params,
+            );
+
+            assert!(no_error.witness_hook(&*cs)().unwrap() == false);
+        }
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+
+    // the table below must be revisited if the routine raises a different set of flags
+    const _: () = assert!(EXCEPTION_FLAGS_ARR_LEN == NUM_RAISED_EXCEPTION_FLAGS);
+
+    // order in which `ecrecover_precompile_inner_routine` raises exception flags
+    const X_OVERFLOW_FLAG: usize = 0;
+    const X_NOT_IN_RANGE_FLAG: usize = 1;
+    const R_IS_ZERO_FLAG: usize = 2;
+    const S_IS_ZERO_FLAG: usize = 3;
+    const MESSAGE_HASH_IS_ZERO_FLAG: usize = 4;
+    const T_IS_ZERO_FLAG: usize = 5;
+    const T_IS_NONRESIDUE_FLAG: usize = 6;
+    const IS_INFINITY_FLAG: usize = 7;
+    const NUM_RAISED_EXCEPTION_FLAGS: usize = 8;
+
+    /// Runs the routine in a fresh constraint system and returns the values of exception flags
+    /// together with `all_ok`
+    fn evaluate_exception_flags(rec_id: u8, r: U256, s: U256, digest: U256) -> (Vec<bool>, bool) {
+        let scalar_params = Arc::new(secp256k1_scalar_field_params());
+        let base_params = Arc::new(secp256k1_base_field_params());
+
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let valid_x_in_external_field = Secp256BaseNNField::allocated_constant(
+            cs,
+            Secp256Fq::from_str("9").unwrap(),
+            &base_params,
+        );
+        let valid_t_in_external_field = Secp256BaseNNField::allocated_constant(
+            cs,
+            Secp256Fq::from_str("16").unwrap(),
+            &base_params,
+        );
+        let valid_y_in_external_field = Secp256BaseNNField::allocated_constant(
+            cs,
+            Secp256Fq::from_str("4").unwrap(),
+            &base_params,
+        );
+
+        let rec_id = UInt8::allocate_checked(cs, rec_id);
+        let r = UInt256::allocate(cs, r);
+        let s = UInt256::allocate(cs, s);
+        let digest = UInt256::allocate(cs, digest);
+
+        let (exception_flags, all_ok, _) =
+            ecrecover_precompile_inner_routine_with_exception_flags::<_, _, false>(
+                cs,
+                &rec_id,
+                &r,
+                &s,
+                &digest,
+                valid_x_in_external_field,
+                valid_y_in_external_field,
+                valid_t_in_external_field,
+                &base_params,
+                &scalar_params,
+            );
+
+        assert_eq!(exception_flags.len(), NUM_RAISED_EXCEPTION_FLAGS);
+        let flags: Vec<bool> = exception_flags
+            .iter()
+            .map(|flag| flag.witness_hook(&*cs)().unwrap())
+            .collect();
+        let all_ok = all_ok.witness_hook(&*cs)().unwrap();
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+
+        (flags, all_ok)
+    }
+
+    #[test]
+    fn test_ecrecover_exception_flags() {
+        let digest = "38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e";
+        let r = "38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e";
+        let s = "789d1dd423d25f0772d2748d60f7e4b81bb14d086eba8e8e8efb6dcff8a4ae02";
+        let zero = "00";
+
+        // NOTE: `t = x^3 + 7` can never be zero, as -7 is not a cubic residue modulo p, so
+        // `T_IS_ZERO_FLAG` is never expected to fire
+        let cases: Vec<(&str, u8, &str, &str, &str, Vec<usize>)> = vec![
+            ("valid signature", 0, r, s, digest, vec![]),
+            ("zero r", 0, zero, s, digest, vec![R_IS_ZERO_FLAG, T_IS_NONRESIDUE_FLAG]),
+            ("zero s", 0, r, zero, digest, vec![S_IS_ZERO_FLAG]),
+            ("zero message hash", 0, r, s, zero, vec![MESSAGE_HASH_IS_ZERO_FLAG]),
+            ("r + n overflows", 2, r, s, digest, vec![X_OVERFLOW_FLAG]),
+            (
+                "r + n is not less than p",
+                2,
+                "014551231950b75fc4402da1722fc9baee",
+                s,
+                digest,
+                vec![X_NOT_IN_RANGE_FLAG, T_IS_NONRESIDUE_FLAG],
+            ),
+            ("x^3 + 7 is a nonresidue", 0, "05", s, digest, vec![T_IS_NONRESIDUE_FLAG]),
+            (
+                "recovered point is at infinity",
+                0,
+                "2f8bde4d1a07209355b4a7250a5c5128e88b84bddc619ab7cba8d569b240efe4",
+                s,
+                "5b119524b31bdb253e1c46c2e4d7779b1518c75ccb1388514b4467f63acae388",
+                vec![IS_INFINITY_FLAG],
+            ),
+        ];
+
+        for (name, rec_id, r, s, digest, expected_flags) in cases.into_iter() {
+            let decode = |value: &str| U256::from_big_endian(&hex::decode(value).unwrap());
+            let (flags, all_ok) =
+                evaluate_exception_flags(rec_id, decode(r), decode(s), decode(digest));
+
+            let raised_flags: Vec<usize> = flags
+                .iter()
+                .enumerate()
+                .filter(|(_, flag)| **flag)
+                .map(|(idx, _)| idx)
+                .collect();
+            assert_eq!(raised_flags, expected_flags, "{}", name);
+            assert_eq!(all_ok == false, expected_flags.is_empty() == false, "{}", name);
+        }
+    }
+
+    /// Synthesizes the routine in a fresh constraint system and returns the number of used rows
+    /// together with the variables of exception flags, `all_ok` and the written value
+    fn synthesize_with_exception_flags(
+        rec_id: u8,
+        r: U256,
+        s: U256,
+        digest: U256,
+    ) -> (usize, Vec<Variable>) {
+        let scalar_params = Arc::new(secp256k1_scalar_field_params());
+        let base_params = Arc::new(secp256k1_base_field_params());
+
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let valid_x_in_external_field = Secp256BaseNNField::allocated_constant(
+            cs,
+            Secp256Fq::from_str("9").unwrap(),
+            &base_params,
+        );
+        let valid_t_in_external_field = Secp256BaseNNField::allocated_constant(
+            cs,
+            Secp256Fq::from_str("16").unwrap(),
+            &base_params,
+        );
+        let valid_y_in_external_field = Secp256BaseNNField::allocated_constant(
+            cs,
+            Secp256Fq::from_str("4").unwrap(),
+            &base_params,
+        );
+
+        let rec_id = UInt8::allocate_checked(cs, rec_id);
+        let r = UInt256::allocate(cs, r);
+        let s = UInt256::allocate(cs, s);
+        let digest = UInt256::allocate(cs, digest);
+
+        let (exception_flags, all_ok, written_value) =
+            ecrecover_precompile_inner_routine_with_exception_flags::<_, _, false>(
+                cs,
+                &rec_id,
+                &r,
+                &s,
+                &digest,
+                valid_x_in_external_field,
+                valid_y_in_external_field,
+                valid_t_in_external_field,
+                &base_params,
+                &scalar_params,
+            );
+
+        let mut variables: Vec<Variable> = exception_flags
+            .iter()
+            .map(|flag| flag.get_variable())
+            .collect();
+        variables.push(all_ok.get_variable());
+        variables.extend(written_value.inner.iter().map(|el| el.get_variable()));
+
+        (cs.next_available_row(), variables)
+    }
+
+    #[test]
+    fn test_ecrecover_synthesis_is_deterministic() {
+        let decode = |value: &str| U256::from_big_endian(&hex::decode(value).unwrap());
+        let digest = decode("38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e");
+        let r = decode("38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e");
+        let s = decode("789d1dd423d25f0772d2748d60f7e4b81bb14d086eba8e8e8efb6dcff8a4ae02");
+
+        // the reference CS can not be reset, so every run starts from an identically configured
+        // fresh instance instead
+        let (first_num_rows, first_variables) = synthesize_with_exception_flags(0, r, s, digest);
+        let (second_num_rows, second_variables) = synthesize_with_exception_flags(0, r, s, digest);
+
+        assert_eq!(first_num_rows, second_num_rows);
+        assert_eq!(first_variables, second_variables);
+    }
+
+    #[test]
+    fn test_ecrecover_r_equals_p_minus_n() {
+        let secp_p = U256::from_str_radix(
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
+            16,
+        )
+        .unwrap();
+        let secp_n = U256::from_str_radix(
+            "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+            16,
+        )
+        .unwrap();
+        let s = U256::from_str_radix(
+            "789d1dd423d25f0772d2748d60f7e4b81bb14d086eba8e8e8efb6dcff8a4ae02",
+            16,
+        )
+        .unwrap();
+        let digest = U256::from_str_radix(
+            "38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e",
+            16,
+        )
+        .unwrap();
+
+        // v = 2 sets x_overflow, so x = r + n = p, that does not overflow 2^256, but is not a
+        // valid base field element
+        let r = secp_p - secp_n;
+        let (flags, all_ok) = evaluate_exception_flags(2, r, s, digest);
+        assert!(flags[X_OVERFLOW_FLAG] == false);
+        assert!(flags[X_NOT_IN_RANGE_FLAG] == true);
+        assert!(all_ok == false);
+
+        // one less gives x = p - 1, that is still in range
+        let (flags, _) = evaluate_exception_flags(2, r - U256::one(), s, digest);
+        assert!(flags[X_OVERFLOW_FLAG] == false);
+        assert!(flags[X_NOT_IN_RANGE_FLAG] == false);
+    }
+
+    #[test]
+    fn test_ecrecover_s_times_x_at_infinity() {
+        // secp256k1 has prime order, so there are no low order points and `(s / r) * X` is the
+        // point at infinity only if `s = 0 mod n`. Values equal to `n` pass the zero checks, as
+        // those are made over integers, but become zero after reduction modulo `n`
+        let secp_n = U256::from_str_radix(
+            "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+            16,
+        )
+        .unwrap();
+        let r = U256::from_str_radix(
+            "38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e",
+            16,
+        )
+        .unwrap();
+
+        // digest is also `0 mod n`, so `hash * G` is the point at infinity too and `q_acc` is
+        // taken from `s * X` as is
+        let (flags, all_ok) = evaluate_exception_flags(0, r, secp_n, secp_n);
+        assert!(flags[S_IS_ZERO_FLAG] == false);
+        assert!(flags[MESSAGE_HASH_IS_ZERO_FLAG] == false);
+        assert!(flags[IS_INFINITY_FLAG] == true);
+        assert!(all_ok == false);
+    }
+
+    #[test]
+    fn test_ecrecover_output_length() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        for (output_length, expected) in [(0, false), (1, false), (2, true), (32, true)] {
+            let output_length = UInt32::allocate(cs, output_length);
+            let fits = EcrecoverPrecompileCallParams::output_length_fits(cs, output_length);
+            assert_eq!(fits.witness_hook(&*cs)().unwrap(), expected);
+        }
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
 }
@@ -1,10 +1,15 @@
 use boojum::{
-    cs::implementations::lookup_table::LookupTable, field::SmallField, pairing::ff::PrimeField,
+    cs::{implementations::lookup_table::LookupTable, traits::cs::ConstraintSystem},
+    field::SmallField,
+    pairing::ff::PrimeField,
 };
 use derivative::*;
 
 use super::*;
-use crate::ecrecover::{secp256k1::fr::Fr, Secp256Affine};
+use crate::{
+    ecrecover::{secp256k1::fr::Fr, Secp256Affine},
+    tables::{get_typed_table_id, TableFamilyMember, TypedTableId},
+};
 
 const TABLE_NAME: &'static str = "Secp256k1 FIXEDBASEMUL table";
 
@@ -58,3 +63,33 @@ pub fn create_fixed_base_mul_table<
     assert_eq!(content.len(), 256);
     LookupTable::new_from_content(content, TABLE_NAME.to_string(), 1)
 }
+
+/// Marker of all `FixedBaseMulTable`s
+pub struct FixedBaseMulTables;
+
+impl<const U32_WORD_INDEX: usize, const BYTE_OFFSET: usize> TableFamilyMember
+    for FixedBaseMulTable<U32_WORD_INDEX, BYTE_OFFSET>
+{
+    type Family = FixedBaseMulTables;
+}
+
+/// Ids of all tables, grouped by the byte of the scalar they are used for
+pub fn get_fixed_base_mul_table_ids<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &CS,
+) -> Vec<[TypedTableId<FixedBaseMulTables>; 8]> {
+    let mut full_table_ids = Vec::with_capacity(32);
+    seq_macro::seq!(C in 0..32 {
+        full_table_ids.push([
+            get_typed_table_id::<FixedBaseMulTable<0, C>, F, CS>(cs).into_family(),
+            get_typed_table_id::<FixedBaseMulTable<1, C>, F, CS>(cs).into_family(),
+            get_typed_table_id::<FixedBaseMulTable<2, C>, F, CS>(cs).into_family(),
+            get_typed_table_id::<FixedBaseMulTable<3, C>, F, CS>(cs).into_family(),
+            get_typed_table_id::<FixedBaseMulTable<4, C>, F, CS>(cs).into_family(),
+            get_typed_table_id::<FixedBaseMulTable<5, C>, F, CS>(cs).into_family(),
+            get_typed_table_id::<FixedBaseMulTable<6, C>, F, CS>(cs).into_family(),
+            get_typed_table_id::<FixedBaseMulTable<7, C>, F, CS>(cs).into_family(),
+        ]);
+    });
+
+    full_table_ids
+}
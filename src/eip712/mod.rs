@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use boojum::{
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{boolean::Boolean, u256::UInt256, u8::UInt8},
+};
+
+use crate::{
+    ecrecover::{
+        new_optimized::{
+            allocate_masking_constants, ecrecover_precompile_inner_routine, ALLOW_ZERO_MESSAGE,
+        },
+        secp256k1_base_field_params, secp256k1_scalar_field_params,
+    },
+    utils::keccak256_of_bytes,
+};
+
+// 0x1901 prefix and two hashes
+const EIP712_MESSAGE_LEN: usize = 2 + 32 * 2;
+
+/// Computes `keccak256(0x1901 || domain_separator || struct_hash)` with hashes in big-endian form
+pub fn eip712_typed_data_hash<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    domain_separator: &UInt256<F>,
+    struct_hash: &UInt256<F>,
+) -> UInt256<F> {
+    let zero_u8 = UInt8::zero(cs);
+    let mut bytes_to_hash = [zero_u8; EIP712_MESSAGE_LEN];
+    bytes_to_hash[0] = UInt8::allocated_constant(cs, 0x19);
+    bytes_to_hash[1] = UInt8::allocated_constant(cs, 0x01);
+    bytes_to_hash[2..34].copy_from_slice(&domain_separator.to_be_bytes(cs));
+    bytes_to_hash[34..].copy_from_slice(&struct_hash.to_be_bytes(cs));
+
+    let mut digest_bytes = keccak256_of_bytes(cs, &bytes_to_hash);
+    digest_bytes.reverse();
+
+    UInt256::from_le_bytes(cs, digest_bytes)
+}
+
+/// Recovers the signer of EIP-712 typed data. Returns whether recovery succeeded, and the address
+/// in the lowest 20 bytes of the word, that is zero on failure
+pub fn verify_eip712_signature<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    domain_separator: &UInt256<F>,
+    struct_hash: &UInt256<F>,
+    recid: &UInt8<F>,
+    r: &UInt256<F>,
+    s: &UInt256<F>,
+) -> (Boolean<F>, UInt256<F>) {
+    let digest = eip712_typed_data_hash(cs, domain_separator, struct_hash);
+
+    let scalar_params = Arc::new(secp256k1_scalar_field_params());
+    let base_params = Arc::new(secp256k1_base_field_params());
+    let (valid_x_in_external_field, valid_y_in_external_field, valid_t_in_external_field) =
+        allocate_masking_constants(cs, &base_params);
+
+    ecrecover_precompile_inner_routine::<_, _, ALLOW_ZERO_MESSAGE>(
+        cs,
+        recid,
+        r,
+        s,
+        &digest,
+        valid_x_in_external_field,
+        valid_y_in_external_field,
+        valid_t_in_external_field,
+        &base_params,
+        &scalar_params,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use boojum::{
+        field::goldilocks::GoldilocksField,
+        gadgets::traits::{allocatable::CSAllocatable, witnessable::WitnessHookable},
+        worker::Worker,
+    };
+
+    use super::*;
+    use crate::{ethereum_types::U256, test_utils::create_test_cs};
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_eip712_typed_data_hash() {
+        use zkevm_opcode_defs::sha3::*;
+
+        let domain_separator = U256::from_str_radix(
+            "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f",
+            16,
+        )
+        .unwrap();
+        let struct_hash = U256::from_str_radix(
+            "c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e",
+            16,
+        )
+        .unwrap();
+
+        let mut buffer = [0u8; 32];
+        let mut hasher = Keccak256::new();
+        hasher.update(&[0x19, 0x01]);
+        domain_separator.to_big_endian(&mut buffer);
+        hasher.update(&buffer);
+        struct_hash.to_big_endian(&mut buffer);
+        hasher.update(&buffer);
+        let expected = U256::from_big_endian(hasher.finalize().as_slice());
+
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let domain_separator = UInt256::allocate(cs, domain_separator);
+        let struct_hash = UInt256::allocate(cs, struct_hash);
+        let digest = eip712_typed_data_hash(cs, &domain_separator, &struct_hash);
+        assert_eq!(digest.witness_hook(&*cs)().unwrap(), expected);
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+}
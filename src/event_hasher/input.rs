@@ -0,0 +1,134 @@
+use boojum::{
+    algebraic_props::round_function::AlgebraicRoundFunction,
+    cs::{traits::cs::ConstraintSystem, Variable},
+    field::SmallField,
+    gadgets::{
+        boolean::Boolean,
+        num::Num,
+        queue::*,
+        traits::{
+            allocatable::*, auxiliary::PrettyComparison, encodable::CircuitVarLengthEncodable,
+            selectable::Selectable, witnessable::WitnessHookable,
+        },
+        u32::UInt32,
+        u8::UInt8,
+    },
+    serde_utils::BigArraySerde,
+};
+use cs_derive::*;
+use derivative::*;
+use zkevm_opcode_defs::system_params::EVENT_AUX_BYTE;
+
+use super::{event_witness_into_bytes, MAX_EVENT_TOPICS};
+use crate::{
+    base_structures::log_query::{LogQuery, LogQueryWitness, LOG_QUERY_PACKED_WIDTH},
+    ethereum_types::U256,
+    linear_hasher::input::*,
+};
+
+#[derive(Derivative, CSAllocatable, CSSelectable, CSVarLengthEncodable, WitnessHookable)]
+#[derivative(Clone, Copy, Debug)]
+#[DerivePrettyComparison("true")]
+pub struct EventHasherOutputData<F: SmallField> {
+    pub keccak256_hash: [UInt8<F>; 32],
+    pub num_events: UInt32<F>,
+}
+
+impl<F: SmallField> CSPlaceholder<F> for EventHasherOutputData<F> {
+    fn placeholder<CS: ConstraintSystem<F>>(cs: &mut CS) -> Self {
+        Self {
+            keccak256_hash: [UInt8::<F>::placeholder(cs); 32],
+            num_events: UInt32::<F>::placeholder(cs),
+        }
+    }
+}
+
+pub type EventHasherInputOutput<F> = crate::fsm_input_output::ClosedFormInput<
+    F,
+    (),
+    LinearHasherInputData<F>,
+    EventHasherOutputData<F>,
+>;
+
+pub type EventHasherInputOutputWitness<F> = crate::fsm_input_output::ClosedFormInputWitness<
+    F,
+    (),
+    LinearHasherInputData<F>,
+    EventHasherOutputData<F>,
+>;
+
+#[derive(Derivative, serde::Serialize, serde::Deserialize)]
+#[derivative(Clone, Debug, Default)]
+#[serde(bound = "")]
+pub struct EventHasherCircuitInstanceWitness<F: SmallField> {
+    pub closed_form_input: EventHasherInputOutputWitness<F>,
+    pub queue_witness: CircuitQueueRawWitness<F, LogQuery<F>, 4, LOG_QUERY_PACKED_WIDTH>,
+}
+
+impl<F: SmallField> EventHasherCircuitInstanceWitness<F> {
+    /// Builds a witness for a single circuit instance that hashes all events in `queries`, and
+    /// skips the rest of them
+    pub fn from_log_queries<R: AlgebraicRoundFunction<F, 8, 12, 4>>(
+        queries: impl IntoIterator<Item = LogQueryWitness<F>>,
+        round_function: &R,
+    ) -> Self {
+        use zkevm_opcode_defs::sha3::*;
+
+        let queries: Vec<_> = queries.into_iter().collect();
+        let mut accumulator = [0u8; 32];
+        let mut num_events = 0u32;
+
+        let mut it = queries.iter();
+        while let Some(header) = it.next() {
+            if header.aux_byte != EVENT_AUX_BYTE {
+                continue;
+            }
+            let num_topics = header.key.as_usize();
+            let data_length = header.written_value.as_usize();
+
+            let mut topics = [U256::zero(); MAX_EVENT_TOPICS];
+            let topic_words = it
+                .by_ref()
+                .take(num_topics.div_ceil(2))
+                .flat_map(|el| [el.key, el.written_value]);
+            for (dst, word) in topics.iter_mut().zip(topic_words.take(num_topics)) {
+                *dst = word;
+            }
+
+            let mut data = vec![];
+            for el in it.by_ref().take(data_length.div_ceil(64)) {
+                for word in [el.key, el.written_value] {
+                    let mut bytes = [0u8; 32];
+                    word.to_big_endian(&mut bytes);
+                    data.extend(bytes);
+                }
+            }
+            data.truncate(data_length);
+            let data_hash: [u8; 32] = Keccak256::digest(&data).into();
+
+            let mut hasher = Keccak256::new();
+            hasher.update(&accumulator);
+            hasher.update(&event_witness_into_bytes(&topics, &data_hash, &header.address));
+            accumulator = hasher.finalize().into();
+            num_events += 1;
+        }
+
+        let LinearHasherCircuitInstanceWitness { closed_form_input, queue_witness } =
+            LinearHasherCircuitInstanceWitness::from_log_queries(queries, round_function);
+
+        Self {
+            closed_form_input: EventHasherInputOutputWitness {
+                start_flag: true,
+                completion_flag: true,
+                observable_input: closed_form_input.observable_input,
+                observable_output: EventHasherOutputDataWitness {
+                    keccak256_hash: accumulator,
+                    num_events,
+                },
+                hidden_fsm_input: (),
+                hidden_fsm_output: (),
+            },
+            queue_witness,
+        }
+    }
+}
@@ -0,0 +1,495 @@
+use std::sync::Arc;
+
+use boojum::{
+    algebraic_props::round_function::AlgebraicRoundFunction,
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{
+        boolean::Boolean,
+        keccak256,
+        num::Num,
+        queue::CircuitQueueWitness,
+        traits::{
+            allocatable::CSAllocatableExt, round_function::CircuitRoundFunction,
+            selectable::Selectable,
+        },
+        u160::UInt160,
+        u256::UInt256,
+        u32::UInt32,
+        u8::UInt8,
+    },
+};
+use zkevm_opcode_defs::system_params::EVENT_AUX_BYTE;
+
+use crate::{
+    base_structures::log_query::LogQuery,
+    demux_log_queue::StorageLogQueue,
+    ethereum_types::{Address, U256},
+    fsm_input_output::circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH,
+    linear_hasher::keccak256_squeeze,
+    storage_application::keccak256_conditionally_absorb_and_run_permutation,
+    utils::keccak256_of_bytes,
+};
+
+pub mod input;
+use self::input::*;
+
+/// Topics, hash of the data and emitter address
+pub const EVENT_BYTE_LENGTH: usize = MAX_EVENT_TOPICS * 32 + 32 + 20;
+
+pub const MAX_EVENT_TOPICS: usize = 4;
+
+/// Events with longer data can't be hashed by the circuit
+pub const MAX_EVENT_DATA_LENGTH: usize = 256;
+
+/// Every query after the header carries two words, in `key` and `written_value`
+const WORDS_PER_EVENT_QUERY: usize = 2;
+
+/// Serializes topic0..topic3, missing ones as zeroes, `keccak256` of the variable length data and
+/// the emitting address
+pub fn event_into_bytes<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    topics: &[UInt256<F>; MAX_EVENT_TOPICS],
+    data_hash: &[UInt8<F>; 32],
+    emitting_address: &UInt160<F>,
+) -> [UInt8<F>; EVENT_BYTE_LENGTH] {
+    let zero_u8 = UInt8::zero(cs);
+
+    let mut result = [zero_u8; EVENT_BYTE_LENGTH];
+    let mut offset = 0;
+
+    for topic in topics.iter() {
+        let bytes_be = topic.to_be_bytes(cs);
+        result[offset..(offset + bytes_be.len())].copy_from_slice(&bytes_be);
+        offset += bytes_be.len();
+    }
+
+    result[offset..(offset + data_hash.len())].copy_from_slice(data_hash);
+    offset += data_hash.len();
+
+    let bytes_be = emitting_address.to_be_bytes(cs);
+    result[offset..(offset + bytes_be.len())].copy_from_slice(&bytes_be);
+    offset += bytes_be.len();
+
+    assert_eq!(offset, EVENT_BYTE_LENGTH);
+
+    result
+}
+
+/// Out of circuit counterpart of `event_into_bytes`
+pub fn event_witness_into_bytes(
+    topics: &[U256; MAX_EVENT_TOPICS],
+    data_hash: &[u8; 32],
+    emitting_address: &Address,
+) -> [u8; EVENT_BYTE_LENGTH] {
+    let mut result = [0u8; EVENT_BYTE_LENGTH];
+    for (topic, dst) in topics.iter().zip(result.chunks_mut(32)) {
+        topic.to_big_endian(dst);
+    }
+    result[128..160].copy_from_slice(data_hash);
+    result[160..180].copy_from_slice(emitting_address.as_bytes());
+
+    result
+}
+
+/// Enforces that `value` is at most `max`, and returns flags of `value >= k` for every `k` from
+/// 0 to `max + 1`
+fn at_least_flags<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    value: UInt32<F>,
+    max: usize,
+) -> Vec<Boolean<F>> {
+    let boolean_false = Boolean::allocated_constant(cs, false);
+    let boolean_true = Boolean::allocated_constant(cs, true);
+
+    let max_u32 = UInt32::allocated_constant(cs, max as u32);
+    let (_, too_large) = max_u32.overflowing_sub(cs, value);
+    Boolean::enforce_equal(cs, &too_large, &boolean_false);
+
+    let mut result = Vec::with_capacity(max + 2);
+    result.push(boolean_true);
+    for k in 1..=max {
+        let k = UInt32::allocated_constant(cs, k as u32);
+        let (_, is_smaller) = value.overflowing_sub(cs, k);
+        result.push(is_smaller.negated(cs));
+    }
+    result.push(boolean_false);
+
+    result
+}
+
+/// keccak256 of the first `length` bytes, where `has_bytes` are `at_least_flags` of the length
+fn keccak256_of_prefix<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    bytes: &[UInt8<F>],
+    has_bytes: &[Boolean<F>],
+) -> [UInt8<F>; keccak256::KECCAK256_DIGEST_SIZE] {
+    use boojum::gadgets::keccak256::KECCAK_RATE_BYTES;
+
+    assert_eq!(has_bytes.len(), bytes.len() + 2);
+
+    let zero_u8 = UInt8::zero(cs);
+    let boolean_false = Boolean::allocated_constant(cs, false);
+
+    // block is absorbed if it starts before or at the padding
+    let num_blocks = bytes.len() / KECCAK_RATE_BYTES + 1;
+    let absorb_block: Vec<_> = (0..=num_blocks)
+        .map(|block| {
+            has_bytes
+                .get(block * KECCAK_RATE_BYTES)
+                .copied()
+                .unwrap_or(boolean_false)
+        })
+        .collect();
+
+    let keccak_accumulator_state =
+        [[[zero_u8; keccak256::BYTES_PER_WORD]; keccak256::LANE_WIDTH]; keccak256::LANE_WIDTH];
+    let mut keccak_accumulator_state =
+        keccak_accumulator_state.map(|el| el.map(|el| el.map(|el| el.get_variable())));
+
+    for block in 0..num_blocks {
+        let next_block_is_skipped = absorb_block[block + 1].negated(cs);
+        let is_last_block = Boolean::multi_and(cs, &[absorb_block[block], next_block_is_skipped]);
+
+        let mut buffer = [zero_u8.get_variable(); KECCAK_RATE_BYTES];
+        for (offset, dst) in buffer.iter_mut().enumerate() {
+            let position = block * KECCAK_RATE_BYTES + offset;
+            // bytes past the end are zeroes, and if the padding starts at the last byte of the
+            // block then the sum is 0x81 as it should be
+            let mut terms = Vec::with_capacity(3);
+            if position < bytes.len() {
+                let byte = UInt8::conditionally_select(
+                    cs,
+                    has_bytes[position + 1],
+                    &bytes[position],
+                    &zero_u8,
+                );
+                terms.push((byte.get_variable(), F::ONE));
+            }
+            if position <= bytes.len() {
+                let is_past_end = has_bytes[position + 1].negated(cs);
+                let is_padding_start = Boolean::multi_and(cs, &[has_bytes[position], is_past_end]);
+                terms.push((is_padding_start.get_variable(), F::ONE));
+            }
+            if offset == KECCAK_RATE_BYTES - 1 {
+                terms.push((is_last_block.get_variable(), F::from_u64_unchecked(0x80)));
+            }
+
+            *dst = match terms.as_slice() {
+                [] => zero_u8.get_variable(),
+                [(variable, coeff)] if *coeff == F::ONE => *variable,
+                _ => Num::linear_combination(cs, &terms).get_variable(),
+            };
+        }
+
+        keccak256_conditionally_absorb_and_run_permutation(
+            cs,
+            absorb_block[block],
+            &mut keccak_accumulator_state,
+            &buffer,
+        );
+    }
+
+    keccak256_squeeze(&keccak_accumulator_state)
+}
+
+/// Pops the topics and data of the event with the given header, if `is_event` is set. Every popped
+/// query must be an event of the same emitter
+fn pop_event_body<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+>(
+    cs: &mut CS,
+    queue: &mut StorageLogQueue<F, R>,
+    header: &LogQuery<F>,
+    is_event: Boolean<F>,
+) -> ([UInt256<F>; MAX_EVENT_TOPICS], [UInt8<F>; 32])
+where
+    [(); <LogQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+{
+    let zero_u32 = UInt32::zero(cs);
+    let zero_u256 = UInt256::zero(cs);
+    let event_aux_byte = UInt8::allocated_constant(cs, EVENT_AUX_BYTE);
+
+    // number of topics and data length are small numbers
+    for limb in header.key.inner[1..]
+        .iter()
+        .chain(header.written_value.inner[1..].iter())
+    {
+        Num::conditionally_enforce_equal(
+            cs,
+            is_event,
+            &Num::from_variable(limb.get_variable()),
+            &Num::from_variable(zero_u32.get_variable()),
+        );
+    }
+    let num_topics = UInt32::conditionally_select(cs, is_event, &header.key.inner[0], &zero_u32);
+    let data_length =
+        UInt32::conditionally_select(cs, is_event, &header.written_value.inner[0], &zero_u32);
+
+    let has_topics = at_least_flags(cs, num_topics, MAX_EVENT_TOPICS);
+    let has_data_bytes = at_least_flags(cs, data_length, MAX_EVENT_DATA_LENGTH);
+
+    let mut pop_body_query = |cs: &mut CS, should_pop: Boolean<F>| {
+        let (query, _) = queue.pop_front(cs, should_pop);
+
+        let is_event = UInt8::equals(cs, &query.aux_byte, &event_aux_byte);
+        is_event.conditionally_enforce_true(cs, should_pop);
+        for (a, b) in query.address.inner.iter().zip(header.address.inner.iter()) {
+            Num::conditionally_enforce_equal(
+                cs,
+                should_pop,
+                &Num::from_variable(a.get_variable()),
+                &Num::from_variable(b.get_variable()),
+            );
+        }
+
+        [query.key, query.written_value]
+    };
+
+    let mut topics = [zero_u256; MAX_EVENT_TOPICS];
+    for (idx, dst) in topics.chunks_mut(WORDS_PER_EVENT_QUERY).enumerate() {
+        let first_topic = idx * WORDS_PER_EVENT_QUERY;
+        let words = pop_body_query(cs, has_topics[first_topic + 1]);
+        for (offset, (dst, word)) in dst.iter_mut().zip(words.iter()).enumerate() {
+            *dst = UInt256::conditionally_select(
+                cs,
+                has_topics[first_topic + offset + 1],
+                word,
+                &zero_u256,
+            );
+        }
+    }
+
+    let mut data = Vec::with_capacity(MAX_EVENT_DATA_LENGTH);
+    for idx in 0..MAX_EVENT_DATA_LENGTH / (WORDS_PER_EVENT_QUERY * 32) {
+        let first_byte = idx * WORDS_PER_EVENT_QUERY * 32;
+        let words = pop_body_query(cs, has_data_bytes[first_byte + 1]);
+        for word in words.iter() {
+            data.extend(word.to_be_bytes(cs));
+        }
+    }
+    let data_hash = keccak256_of_prefix(cs, &data, &has_data_bytes);
+
+    (topics, data_hash)
+}
+
+/// Same as `linear_hasher_entry_point`, but for the events queue. An event is a header query with
+/// the number of topics in `key` and data length in bytes in `written_value`, followed by queries
+/// with two words each in `key` and `written_value`: topics first, then data. Queries that are not
+/// events, e.g. messages to L1, are skipped. Every event is serialized with `event_into_bytes` and
+/// absorbed into the accumulator as `keccak256(accumulator || event)`, that starts from zero. The
+/// number of events is output along with it
+pub fn event_log_hasher_entry_point<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+>(
+    cs: &mut CS,
+    witness: EventHasherCircuitInstanceWitness<F>,
+    round_function: &R,
+    params: usize,
+) -> [Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH]
+where
+    [(); <LogQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN + 1]:,
+{
+    let limit = params;
+
+    assert!(limit <= u32::MAX as usize);
+
+    let EventHasherCircuitInstanceWitness { closed_form_input, queue_witness } = witness;
+
+    let mut structured_input =
+        EventHasherInputOutput::alloc_ignoring_outputs(cs, closed_form_input.clone());
+    let start_flag = structured_input.start_flag;
+
+    let zero_u8: UInt8<F> = UInt8::zero(cs);
+    let zero_u32 = UInt32::zero(cs);
+    let boolean_true = Boolean::allocated_constant(cs, true);
+    let event_aux_byte = UInt8::allocated_constant(cs, EVENT_AUX_BYTE);
+
+    // only 1 instance of the circuit here for now
+    Boolean::enforce_equal(cs, &start_flag, &boolean_true);
+
+    let queue_state_from_input = structured_input.observable_input.queue_state;
+
+    // it must be trivial
+    queue_state_from_input.enforce_trivial_head(cs);
+
+    let mut queue = StorageLogQueue::<F, R>::from_state(cs, queue_state_from_input);
+    let queue_witness = CircuitQueueWitness::from_inner_witness(queue_witness);
+    queue.witness = Arc::new(queue_witness);
+
+    let mut accumulator = [zero_u8; 32];
+    let mut num_events = zero_u32;
+
+    for _cycle in 0..limit {
+        let queue_is_empty = queue.is_empty(cs);
+        let should_pop = queue_is_empty.negated(cs);
+
+        let (header, _) = queue.pop_front(cs, should_pop);
+
+        let aux_byte_matches = UInt8::equals(cs, &header.aux_byte, &event_aux_byte);
+        let is_event = Boolean::multi_and(cs, &[should_pop, aux_byte_matches]);
+
+        let (topics, data_hash) = pop_event_body(cs, &mut queue, &header, is_event);
+        let event_bytes = event_into_bytes(cs, &topics, &data_hash, &header.address);
+
+        let mut to_hash = Vec::with_capacity(accumulator.len() + EVENT_BYTE_LENGTH);
+        to_hash.extend(accumulator);
+        to_hash.extend(event_bytes);
+        let new_accumulator = keccak256_of_bytes(cs, &to_hash);
+
+        accumulator =
+            <[UInt8<F>; 32]>::conditionally_select(cs, is_event, &new_accumulator, &accumulator);
+        num_events = num_events.add_no_overflow(cs, unsafe {
+            UInt32::from_variable_unchecked(is_event.get_variable())
+        });
+    }
+
+    queue.enforce_consistency(cs);
+    let completed = queue.is_empty(cs);
+
+    Boolean::enforce_equal(cs, &completed, &boolean_true);
+
+    structured_input.completion_flag = completed;
+
+    let fsm_output = ();
+    structured_input.hidden_fsm_output = fsm_output;
+
+    structured_input.observable_output =
+        EventHasherOutputData { keccak256_hash: accumulator, num_events };
+
+    // self-check
+    structured_input.hook_compare_witness(cs, &closed_form_input);
+
+    use boojum::cs::gates::PublicInputGate;
+
+    use crate::fsm_input_output::{
+        commit_variable_length_encodable_item, ClosedFormInputCompactForm,
+    };
+
+    let compact_form =
+        ClosedFormInputCompactForm::from_full_form(cs, &structured_input, round_function);
+    let input_commitment = commit_variable_length_encodable_item(cs, &compact_form, round_function);
+    for el in input_commitment.iter() {
+        let gate = PublicInputGate::new(el.get_variable());
+        gate.add_to_cs(cs);
+    }
+
+    input_commitment
+}
+
+#[cfg(test)]
+mod test {
+    use boojum::{
+        field::goldilocks::GoldilocksField, implementations::poseidon2::Poseidon2Goldilocks,
+        worker::Worker,
+    };
+    use zkevm_opcode_defs::system_params::L1_MESSAGE_AUX_BYTE;
+
+    use super::*;
+    use crate::{base_structures::log_query::LogQueryWitness, test_utils::create_test_cs};
+
+    type F = GoldilocksField;
+
+    const EMITTER: u64 = 0x800a;
+
+    fn query(address: u64, key: U256, value: U256, aux_byte: u8) -> LogQueryWitness<F> {
+        LogQueryWitness {
+            address: Address::from_low_u64_be(address),
+            key,
+            read_value: U256::zero(),
+            written_value: value,
+            aux_byte,
+            rw_flag: true,
+            rollback: false,
+            is_service: false,
+            shard_id: 0,
+            tx_number_in_block: 5,
+            timestamp: 2048,
+        }
+    }
+
+    /// Header followed by topics and data, two words per query
+    fn event(topics: &[U256], data: &[u8]) -> Vec<LogQueryWitness<F>> {
+        let header =
+            query(EMITTER, U256::from(topics.len()), U256::from(data.len()), EVENT_AUX_BYTE);
+        let mut result = vec![header];
+        for pair in topics.chunks(2) {
+            let second = pair.get(1).copied().unwrap_or_default();
+            result.push(query(EMITTER, pair[0], second, EVENT_AUX_BYTE));
+        }
+        for chunk in data.chunks(64) {
+            let mut padded = [0u8; 64];
+            padded[..chunk.len()].copy_from_slice(chunk);
+            result.push(query(
+                EMITTER,
+                U256::from_big_endian(&padded[..32]),
+                U256::from_big_endian(&padded[32..]),
+                EVENT_AUX_BYTE,
+            ));
+        }
+
+        result
+    }
+
+    fn transfer_event() -> Vec<LogQueryWitness<F>> {
+        let topics = [
+            U256::from_str_radix(
+                "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+                16,
+            )
+            .unwrap(),
+            U256::from(0x1234),
+            U256::from(0x5678),
+        ];
+        let mut amount = [0u8; 32];
+        U256::from(1000).to_big_endian(&mut amount);
+
+        event(&topics, &amount)
+    }
+
+    fn synthesize_event_hasher(queries: Vec<LogQueryWitness<F>>, num_events: u32) -> bool {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+        let round_function = Poseidon2Goldilocks;
+
+        let limit = queries.len();
+        let witness = EventHasherCircuitInstanceWitness::from_log_queries(queries, &round_function);
+        assert_eq!(witness.closed_form_input.observable_output.num_events, num_events);
+        let _ = event_log_hasher_entry_point(cs, witness, &round_function, limit);
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        owned_cs.check_if_satisfied(&worker)
+    }
+
+    #[test]
+    fn test_event_log_hasher() {
+        let mut queries = transfer_event();
+        // no topics and data that ends in the middle of the word of the second data query
+        queries.extend(event(&[], &[0xab; 70]));
+        assert!(synthesize_event_hasher(queries, 2));
+    }
+
+    #[test]
+    fn test_event_log_hasher_skips_non_events() {
+        let mut queries = vec![query(0x8008, U256::from(1), U256::from(2), L1_MESSAGE_AUX_BYTE)];
+        queries.extend(transfer_event());
+        queries.push(query(0x8008, U256::from(3), U256::from(4), L1_MESSAGE_AUX_BYTE));
+        queries.extend(event(&[U256::from(7)], &[]));
+        assert!(synthesize_event_hasher(queries, 2));
+    }
+
+    #[test]
+    fn test_event_log_hasher_rejects_foreign_topics() {
+        let mut queries = transfer_event();
+        queries[1].address = Address::from_low_u64_be(0x8008);
+        assert!(synthesize_event_hasher(queries, 1) == false);
+    }
+}
@@ -1,7 +1,73 @@
 use boojum::cs::Variable;
 
 use super::*;
+use crate::base_structures::vm_state::{FULL_SPONGE_QUEUE_STATE_WIDTH, QUEUE_STATE_WIDTH};
 
 pub const INPUT_OUTPUT_COMMITMENT_LENGTH: usize = 4;
 
+// commitment is taken from the rate part of the sponge state after the last absorption, so it
+// can't be longer than the absorption rate
+const _: () =
+    assert!(INPUT_OUTPUT_COMMITMENT_LENGTH <= FULL_SPONGE_QUEUE_STATE_WIDTH - QUEUE_STATE_WIDTH);
+
 pub mod main_vm;
+
+#[cfg(test)]
+mod tests {
+    use boojum::{
+        algebraic_props::round_function::{AbsorptionModeOverwrite, AlgebraicRoundFunction},
+        field::{goldilocks::GoldilocksField, Field},
+        gadgets::traits::{allocatable::CSAllocatable, witnessable::WitnessHookable},
+        implementations::poseidon2::Poseidon2Goldilocks,
+    };
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+    type R = Poseidon2Goldilocks;
+
+    #[test]
+    fn test_compact_form_commitment_length() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+        let round_function = Poseidon2Goldilocks;
+
+        let boolean_true = Boolean::allocated_constant(cs, true);
+        let committments: [[Num<F>; CLOSED_FORM_COMMITTMENT_LENGTH]; 4] =
+            std::array::from_fn(|i| {
+                std::array::from_fn(|j| {
+                    Num::allocate(cs, F::from_u64_unchecked((i * 4 + j) as u64))
+                })
+            });
+        let compact_form = ClosedFormInputCompactForm {
+            start_flag: boolean_true,
+            completion_flag: boolean_true,
+            observable_input_committment: committments[0],
+            observable_output_committment: committments[1],
+            hidden_fsm_input_committment: committments[2],
+            hidden_fsm_output_committment: committments[3],
+        };
+        assert_eq!(
+            compact_form.encoding_length(),
+            <ClosedFormInputCompactForm<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN
+        );
+
+        let input_commitment: [Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH] =
+            commit_variable_length_encodable_item(cs, &compact_form, &round_function);
+
+        // flags go first, and then the commitments in the order of the fields
+        let mut encoding = vec![F::ONE, F::ONE];
+        encoding.extend((0..16).map(|el| F::from_u64_unchecked(el as u64)));
+        let mut state = R::initial_state();
+        R::specialize_for_len(encoding.len() as u32, &mut state);
+        encoding.resize(encoding.len().next_multiple_of(8), F::ZERO);
+        for chunk in encoding.array_chunks::<8>() {
+            R::absorb_into_state::<AbsorptionModeOverwrite>(&mut state, chunk);
+            R::round_function(&mut state);
+        }
+        let expected = R::state_into_commitment::<INPUT_OUTPUT_COMMITMENT_LENGTH>(&state);
+
+        assert_eq!(input_commitment.witness_hook(&*cs)().unwrap(), expected);
+    }
+}
@@ -0,0 +1,73 @@
+use super::circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH;
+
+/// Placement of the public inputs of every circuit in a batch, where the inputs of all circuits
+/// are concatenated in order. Every entry is `(circuit_name, start_index, length)`
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PublicInputLayout {
+    pub offsets: Vec<(String, usize, usize)>,
+}
+
+/// Builds the layout for a batch where every circuit exposes its input commitment only
+pub fn layout_for_batch(circuit_names: &[&str]) -> PublicInputLayout {
+    let offsets = circuit_names
+        .iter()
+        .enumerate()
+        .map(|(idx, name)| {
+            (name.to_string(), idx * INPUT_OUTPUT_COMMITMENT_LENGTH, INPUT_OUTPUT_COMMITMENT_LENGTH)
+        })
+        .collect();
+
+    PublicInputLayout { offsets }
+}
+
+impl PublicInputLayout {
+    pub fn total_length(&self) -> usize {
+        self.offsets
+            .iter()
+            .map(|(_, start, length)| start + length)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the public inputs of the first circuit with the given name. Panics if there is no
+    /// such circuit in the layout, or if `flat_inputs` are shorter than the layout
+    pub fn extract_for_circuit<'a, F>(&self, name: &str, flat_inputs: &'a [F]) -> &'a [F] {
+        let (_, start, length) = self
+            .offsets
+            .iter()
+            .find(|(circuit_name, _, _)| circuit_name == name)
+            .unwrap_or_else(|| panic!("circuit {} is not a part of the layout", name));
+        assert!(
+            flat_inputs.len() >= self.total_length(),
+            "expected at least {} public inputs, got {}",
+            self.total_length(),
+            flat_inputs.len()
+        );
+
+        &flat_inputs[*start..(*start + *length)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_for_circuit() {
+        let layout = layout_for_batch(&["main_vm", "ecrecover", "linear_hasher"]);
+        assert_eq!(layout.total_length(), 3 * INPUT_OUTPUT_COMMITMENT_LENGTH);
+
+        let flat_inputs: Vec<u64> = (0..layout.total_length() as u64).collect();
+        let inputs = layout.extract_for_circuit("ecrecover", &flat_inputs);
+        assert_eq!(inputs.len(), INPUT_OUTPUT_COMMITMENT_LENGTH);
+        assert_eq!(inputs[0], INPUT_OUTPUT_COMMITMENT_LENGTH as u64);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a part of the layout")]
+    fn test_extract_for_unknown_circuit() {
+        let layout = layout_for_batch(&["main_vm"]);
+        let flat_inputs = [0u64; INPUT_OUTPUT_COMMITMENT_LENGTH];
+        let _ = layout.extract_for_circuit("ecrecover", &flat_inputs);
+    }
+}
@@ -22,6 +22,8 @@ use super::*;
 use crate::boojum::cs::traits::cs::DstBuffer;
 
 pub mod circuit_inputs;
+pub mod layout;
+pub mod verify_pi_gates;
 
 #[derive(Derivative, CSAllocatable, WitnessHookable)]
 #[WitnessHookBound(
@@ -120,7 +122,12 @@ where
                     comparison_lines.join("\n")
                 );
             }
-            assert_eq!(&circuit_result, expected);
+            if &circuit_result != expected {
+                panic!(
+                    "Difference in closed form input. Left is circuit, right is expected:\n{}",
+                    ClosedFormInputWitness::pretty_diff(expected, &circuit_result)
+                );
+            }
         }
     }
 }
@@ -143,6 +150,59 @@ where
     }
 }
 
+impl<
+    F: SmallField,
+    T: Clone + std::fmt::Debug + CSAllocatable<F> + CircuitVarLengthEncodable<F> + WitnessHookable<F>,
+    IN: Clone + std::fmt::Debug + CSAllocatable<F> + CircuitVarLengthEncodable<F> + WitnessHookable<F>,
+    OUT: Clone + std::fmt::Debug + CSAllocatable<F> + CircuitVarLengthEncodable<F> + WitnessHookable<F>,
+> ClosedFormInputWitness<F, T, IN, OUT>
+where
+    <T as CSAllocatable<F>>::Witness: serde::Serialize + serde::de::DeserializeOwned + Eq,
+    <IN as CSAllocatable<F>>::Witness: serde::Serialize + serde::de::DeserializeOwned + Eq,
+    <OUT as CSAllocatable<F>>::Witness: serde::Serialize + serde::de::DeserializeOwned + Eq,
+{
+    /// Lists differing fields one per line, with `actual` on the left. Observable input has no
+    /// `PrettyComparison` bound, so it's printed as a whole if it differs
+    pub fn pretty_diff(expected: &Self, actual: &Self) -> String
+    where
+        T: PrettyComparison<F>,
+        OUT: PrettyComparison<F>,
+    {
+        let mut lines = vec![];
+        if actual.start_flag != expected.start_flag {
+            lines.push(format!("start_flag: {} != {}", actual.start_flag, expected.start_flag));
+        }
+        if actual.completion_flag != expected.completion_flag {
+            lines.push(format!(
+                "completion_flag: {} != {}",
+                actual.completion_flag, expected.completion_flag
+            ));
+        }
+        if actual.observable_input != expected.observable_input {
+            lines.push(format!(
+                "observable_input: {:?} != {:?}",
+                actual.observable_input, expected.observable_input
+            ));
+        }
+        for (name, actual, expected) in [
+            ("hidden_fsm_input", &actual.hidden_fsm_input, &expected.hidden_fsm_input),
+            ("hidden_fsm_output", &actual.hidden_fsm_output, &expected.hidden_fsm_output),
+        ] {
+            for line in <T as PrettyComparison<F>>::find_diffs(actual, expected) {
+                lines.push(format!("{}.{}", name, line));
+            }
+        }
+        for line in <OUT as PrettyComparison<F>>::find_diffs(
+            &actual.observable_output,
+            &expected.observable_output,
+        ) {
+            lines.push(format!("observable_output.{}", line));
+        }
+
+        lines.join("\n")
+    }
+}
+
 #[derive(Derivative, CSAllocatable, CSSelectable, CSVarLengthEncodable, WitnessHookable)]
 #[derivative(Clone, Debug)]
 pub struct ClosedFormInputCompactForm<F: SmallField> {
@@ -330,6 +390,44 @@ pub fn commit_variable_length_encodable_item<
     commit_encoding::<F, CS, AW, SW, CW, N, R>(cs, &buffer, _round_function)
 }
 
+/// Domain of the recursion leaf layer input commitments
+pub const LEAF_DOMAIN: u32 = 1;
+/// Domain of the recursion node layer input commitments
+pub const NODE_DOMAIN: u32 = 2;
+/// Domain of the recursion tip input commitments
+pub const TIP_DOMAIN: u32 = 3;
+/// Domain of the interblock recursion output commitments
+pub const INTERBLOCK_DOMAIN: u32 = 4;
+
+/// Same as `commit_variable_length_encodable_item`, but the encoding is prefixed by the `domain`
+/// tag, so the same data committed at different recursion levels gives different commitments
+pub fn commit_with_domain<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    T: CircuitVarLengthEncodable<F>,
+    const AW: usize,
+    const SW: usize,
+    const CW: usize,
+    const N: usize,
+    R: CircuitRoundFunction<F, AW, SW, CW>,
+>(
+    cs: &mut CS,
+    item: &T,
+    _round_function: &R,
+    domain: u32,
+) -> [Num<F>; N] {
+    let expected_length = item.encoding_length() + 1;
+
+    let mut buffer = Vec::with_capacity(expected_length);
+    let domain = Num::allocated_constant(cs, F::from_u64_unchecked(domain as u64));
+    buffer.push(domain.get_variable());
+    item.encode_to_buffer(cs, &mut buffer);
+
+    assert_eq!(buffer.len(), expected_length);
+
+    commit_encoding::<F, CS, AW, SW, CW, N, R>(cs, &buffer, _round_function)
+}
+
 pub fn commit_encoding<
     F: SmallField,
     CS: ConstraintSystem<F>,
@@ -0,0 +1,57 @@
+use boojum::{
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{num::Num, traits::witnessable::WitnessHookable},
+};
+
+/// Compares the witness of the variables placed into `PublicInputGate`s, e.g. the commitment
+/// returned by an entry point, against `expected`. It's much cheaper than
+/// `check_if_satisfied`, so tests can fail fast if only the input commitment is wrong.
+/// Returns `false` if lengths differ or if some witness is not resolved yet
+pub fn check_public_input_witness<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &CS,
+    public_inputs: &[Num<F>],
+    expected: &[F],
+) -> bool {
+    if public_inputs.len() != expected.len() {
+        return false;
+    }
+
+    public_inputs
+        .iter()
+        .zip(expected.iter())
+        .all(|(input, expected)| input.witness_hook(cs)() == Some(*expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use boojum::{
+        cs::gates::PublicInputGate, field::goldilocks::GoldilocksField,
+        gadgets::traits::allocatable::CSAllocatable,
+    };
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_check_public_input_witness() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let values = [1u64, 2, 3, 4].map(F::from_u64_unchecked);
+        let public_inputs = values.map(|el| Num::allocate(cs, el));
+        for el in public_inputs.iter() {
+            let gate = PublicInputGate::new(el.get_variable());
+            gate.add_to_cs(cs);
+        }
+
+        assert!(check_public_input_witness(&*cs, &public_inputs, &values));
+
+        let mut wrong_values = values;
+        wrong_values[2] = F::from_u64_unchecked(42);
+        assert!(!check_public_input_witness(&*cs, &public_inputs, &wrong_values));
+        assert!(!check_public_input_witness(&*cs, &public_inputs, &values[..3]));
+    }
+}
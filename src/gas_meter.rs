@@ -0,0 +1,83 @@
+use boojum::{
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{boolean::Boolean, traits::selectable::Selectable, u32::UInt32},
+};
+use derivative::*;
+
+// per-call costs charged by precompile circuits, that follow the L1 prices where those exist
+pub const KECCAK256_GAS_COST_PER_CALL: u32 = 30;
+pub const SHA256_GAS_COST_PER_CALL: u32 = 60;
+pub const ECRECOVER_GAS_COST_PER_CALL: u32 = 3000;
+pub const SECP256R1_VERIFY_GAS_COST_PER_CALL: u32 = 3450;
+pub const SCHNORR_VERIFY_GAS_COST_PER_CALL: u32 = 3450;
+pub const PEDERSEN_HASH_GAS_COST_PER_CALL: u32 = 2000;
+pub const BLS12_381_G1_ADD_GAS_COST_PER_CALL: u32 = 375;
+pub const NULLIFIER_GAS_COST_PER_CALL: u32 = 3500;
+
+/// Total gas charged so far. Precompiles continue it from the hidden FSM input, so it covers all
+/// the instances that processed the same queue
+#[derive(Derivative)]
+#[derivative(Clone, Copy, Debug)]
+pub struct GasMeter<F: SmallField> {
+    pub gas_used: UInt32<F>,
+}
+
+impl<F: SmallField> GasMeter<F> {
+    pub fn new(gas_used: UInt32<F>) -> Self {
+        Self { gas_used }
+    }
+
+    /// Adds `cost` to the total if `condition` is set. Returns whether the addition overflowed,
+    /// in which case the total is not meaningful anymore
+    pub fn charge<CS: ConstraintSystem<F>>(
+        &mut self,
+        cs: &mut CS,
+        cost: UInt32<F>,
+        condition: Boolean<F>,
+    ) -> Boolean<F> {
+        let (new_total, overflow) = self.gas_used.overflowing_add(cs, cost);
+        self.gas_used = UInt32::conditionally_select(cs, condition, &new_total, &self.gas_used);
+
+        overflow.and(cs, condition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use boojum::{
+        field::goldilocks::GoldilocksField,
+        gadgets::traits::{allocatable::CSAllocatable, witnessable::WitnessHookable},
+        worker::Worker,
+    };
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_gas_meter() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let boolean_false = Boolean::allocated_constant(cs, false);
+        let boolean_true = Boolean::allocated_constant(cs, true);
+        let cost = UInt32::allocate(cs, ECRECOVER_GAS_COST_PER_CALL);
+
+        let mut gas_meter = GasMeter::new(UInt32::allocate(cs, u32::MAX - 5000));
+        let overflow = gas_meter.charge(cs, cost, boolean_true);
+        assert!(overflow.witness_hook(&*cs)().unwrap() == false);
+        let overflow = gas_meter.charge(cs, cost, boolean_false);
+        assert!(overflow.witness_hook(&*cs)().unwrap() == false);
+        assert_eq!(gas_meter.gas_used.witness_hook(&*cs)().unwrap(), u32::MAX - 2000);
+
+        let overflow = gas_meter.charge(cs, cost, boolean_true);
+        assert!(overflow.witness_hook(&*cs)().unwrap() == true);
+
+        let worker = Worker::new();
+        owned_cs.pad_and_shrink();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+}
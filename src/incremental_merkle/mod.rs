@@ -0,0 +1,145 @@
+use boojum::{
+    algebraic_props::round_function::AlgebraicRoundFunction,
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{num::Num, traits::round_function::CircuitRoundFunction, u32::UInt32},
+};
+
+use crate::merkle_tree::{merkle_node_hash, merkle_root_from_path};
+
+// nodes are 4 elements, so both children fit into a single absorption
+pub const INCREMENTAL_MERKLE_NODE_WIDTH: usize = 4;
+
+/// Appends `new_leaf` at `leaf_index` of the append-only tree of the given `DEPTH`. The leaf
+/// must be empty (all zero) in the tree with the given `root`, and `auth_path` lists its
+/// siblings from the leaves up. Bits of `leaf_index` select the side at every level, so it must
+/// fit into `DEPTH` bits. Returns the new root
+pub fn append_leaf_entry_point<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+    const DEPTH: usize,
+>(
+    cs: &mut CS,
+    root: [Num<F>; INCREMENTAL_MERKLE_NODE_WIDTH],
+    leaf_index: UInt32<F>,
+    new_leaf: [Num<F>; INCREMENTAL_MERKLE_NODE_WIDTH],
+    auth_path: [[Num<F>; INCREMENTAL_MERKLE_NODE_WIDTH]; DEPTH],
+    round_function: &R,
+) -> [Num<F>; INCREMENTAL_MERKLE_NODE_WIDTH] {
+    assert!(DEPTH <= 32);
+
+    let zero_num = Num::zero(cs);
+    let side = leaf_index.into_num().spread_into_bits::<_, DEPTH>(cs);
+
+    // old and new trees only differ in the leaf, so they share the siblings
+    let empty_leaf = [zero_num; INCREMENTAL_MERKLE_NODE_WIDTH];
+    let old_root = merkle_root_from_path(cs, &empty_leaf, &auth_path, &side, round_function);
+    let new_root = merkle_root_from_path(cs, &new_leaf, &auth_path, &side, round_function);
+
+    for (a, b) in old_root.iter().zip(root.iter()) {
+        Num::enforce_equal(cs, a, b);
+    }
+
+    new_root
+}
+
+#[cfg(test)]
+mod tests {
+    use boojum::{
+        field::goldilocks::GoldilocksField,
+        gadgets::traits::{allocatable::CSAllocatable, witnessable::WitnessHookable},
+        implementations::poseidon2::Poseidon2Goldilocks,
+        worker::Worker,
+    };
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    const DEPTH: usize = 3;
+
+    // appends two leaves one after another to the empty tree, and then the third one at
+    // `third_index`
+    fn synthesize_appends(third_index: u32) -> bool {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+        let round_function = Poseidon2Goldilocks;
+
+        let zero_num = Num::zero(cs);
+        let mut empty_nodes = vec![[zero_num; INCREMENTAL_MERKLE_NODE_WIDTH]];
+        for level in 0..DEPTH {
+            let node = empty_nodes[level];
+            empty_nodes.push(merkle_node_hash(cs, &node, &node, &round_function));
+        }
+        let empty_root = empty_nodes[DEPTH];
+
+        let leaves: Vec<[Num<F>; INCREMENTAL_MERKLE_NODE_WIDTH]> = (0..3u64)
+            .map(|leaf_idx| {
+                std::array::from_fn(|i| {
+                    Num::allocate(cs, F::from_u64_unchecked(leaf_idx * 100 + i as u64 + 1))
+                })
+            })
+            .collect();
+
+        let leaf_index = UInt32::allocate(cs, 0);
+        let auth_path = [empty_nodes[0], empty_nodes[1], empty_nodes[2]];
+        let root = append_leaf_entry_point::<_, _, _, DEPTH>(
+            cs,
+            empty_root,
+            leaf_index,
+            leaves[0],
+            auth_path,
+            &round_function,
+        );
+
+        let expected_node = merkle_node_hash(cs, &leaves[0], &empty_nodes[0], &round_function);
+        let expected_node = merkle_node_hash(cs, &expected_node, &empty_nodes[1], &round_function);
+        let expected_root = merkle_node_hash(cs, &expected_node, &empty_nodes[2], &round_function);
+        assert_eq!(root.witness_hook(&*cs)().unwrap(), expected_root.witness_hook(&*cs)().unwrap());
+
+        let leaf_index = UInt32::allocate(cs, 1);
+        let auth_path = [leaves[0], empty_nodes[1], empty_nodes[2]];
+        let root = append_leaf_entry_point::<_, _, _, DEPTH>(
+            cs,
+            root,
+            leaf_index,
+            leaves[1],
+            auth_path,
+            &round_function,
+        );
+
+        // only the first two leaves are occupied now
+        let leaf_index = UInt32::allocate(cs, third_index);
+        let node = merkle_node_hash(cs, &leaves[0], &leaves[1], &round_function);
+        let auth_path = match third_index {
+            0 => [leaves[1], empty_nodes[1], empty_nodes[2]],
+            2 => [empty_nodes[0], node, empty_nodes[2]],
+            _ => unreachable!(),
+        };
+        let _ = append_leaf_entry_point::<_, _, _, DEPTH>(
+            cs,
+            root,
+            leaf_index,
+            leaves[2],
+            auth_path,
+            &round_function,
+        );
+
+        let worker = Worker::new();
+        owned_cs.pad_and_shrink();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        owned_cs.check_if_satisfied(&worker)
+    }
+
+    #[test]
+    fn test_append_leaf() {
+        assert!(synthesize_appends(2));
+    }
+
+    #[test]
+    fn test_append_leaf_to_occupied_index() {
+        assert!(synthesize_appends(0) == false);
+    }
+}
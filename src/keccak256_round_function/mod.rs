@@ -17,7 +17,6 @@ use boojum::{
             selectable::Selectable,
             witnessable::WitnessHookable,
         },
-        u160::UInt160,
         u256::UInt256,
         u32::UInt32,
         u8::UInt8,
@@ -30,10 +29,12 @@ use super::*;
 use crate::{
     base_structures::{
         log_query::*, memory_query::*, precompile_input_outputs::PrecompileFunctionOutputData,
+        precompile_kind::PrecompileKind,
     },
     demux_log_queue::StorageLogQueue,
     ethereum_types::U256,
     fsm_input_output::{circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH, *},
+    gas_meter::{GasMeter, KECCAK256_GAS_COST_PER_CALL},
     keccak256_round_function::buffer::ByteBuffer,
     storage_application::ConditionalWitnessAllocator,
 };
@@ -161,6 +162,7 @@ pub fn keccak256_precompile_inner<
     cs: &mut CS,
     memory_queue: &mut MemoryQueue<F, R>,
     precompile_calls_queue: &mut StorageLogQueue<F, R>,
+    gas_meter: &mut GasMeter<F>,
     memory_read_witness: ConditionalWitnessAllocator<F, UInt256<F>>,
     mut state: Keccak256RoundFunctionFSM<F>,
     _round_function: &R,
@@ -174,14 +176,12 @@ where
 {
     assert!(limit <= u32::MAX as usize);
 
-    let precompile_address = UInt160::allocated_constant(
-        cs,
-        *zkevm_opcode_defs::system_params::KECCAK256_ROUND_FUNCTION_PRECOMPILE_FORMAL_ADDRESS,
-    );
+    let precompile_address = PrecompileKind::Keccak256.to_address(cs);
     let aux_byte_for_precompile = UInt8::allocated_constant(cs, PRECOMPILE_AUX_BYTE);
 
     let boolean_false = Boolean::allocated_constant(cs, false);
     let boolean_true = Boolean::allocated_constant(cs, true);
+    let gas_cost_per_call = UInt32::allocated_constant(cs, KECCAK256_GAS_COST_PER_CALL);
     let zero_u8 = UInt8::zero(cs);
     let one_num = Num::allocated_constant(cs, F::ONE);
 
@@ -259,6 +259,8 @@ where
 
         // if we are in a proper state then get the ABI from the queue
         let (precompile_call, _) = precompile_calls_queue.pop_front(cs, state.read_precompile_call);
+        let gas_overflow = gas_meter.charge(cs, gas_cost_per_call, state.read_precompile_call);
+        Boolean::enforce_equal(cs, &gas_overflow, &boolean_false);
 
         Num::conditionally_enforce_equal(
             cs,
@@ -734,10 +736,19 @@ where
         &structured_input.hidden_fsm_input.internal_fsm,
     );
 
+    let zero_u32 = UInt32::zero(cs);
+    let mut gas_meter = GasMeter::new(UInt32::conditionally_select(
+        cs,
+        start_flag,
+        &zero_u32,
+        &structured_input.hidden_fsm_input.gas_used,
+    ));
+
     let final_state = keccak256_precompile_inner::<F, CS, R>(
         cs,
         &mut memory_queue,
         &mut requests_queue,
+        &mut gas_meter,
         read_queries_allocator,
         initial_state,
         round_function,
@@ -763,6 +774,35 @@ where
     structured_input.hidden_fsm_output.log_queue_state = final_requets_state;
     structured_input.hidden_fsm_output.memory_queue_state = final_memory_state;
 
+    // requests are popped by the inner routine, so we count them by the change of the queue length
+    let num_requests_popped = requests_queue_state
+        .tail
+        .length
+        .sub_no_overflow(cs, final_requets_state.tail.length);
+    let num_requests_processed = UInt32::conditionally_select(
+        cs,
+        start_flag,
+        &zero_u32,
+        &structured_input.hidden_fsm_input.num_requests_processed,
+    );
+    structured_input.hidden_fsm_output.num_requests_processed =
+        num_requests_processed.add_no_overflow(cs, num_requests_popped);
+
+    // hashing can not fail, so every processed request is a successful call
+    structured_input.observable_output.num_successful_calls = UInt32::conditionally_select(
+        cs,
+        structured_input.completion_flag,
+        &structured_input.hidden_fsm_output.num_requests_processed,
+        &structured_input.observable_output.num_successful_calls,
+    );
+    structured_input.hidden_fsm_output.gas_used = gas_meter.gas_used;
+    structured_input.observable_output.gas_used = UInt32::conditionally_select(
+        cs,
+        structured_input.completion_flag,
+        &gas_meter.gas_used,
+        &structured_input.observable_output.gas_used,
+    );
+
     // self-check
     structured_input.hook_compare_witness(cs, &closed_form_input);
 
@@ -826,132 +866,17 @@ pub(crate) fn keccak256_absorb_and_run_permutation<F: SmallField, CS: Constraint
 #[cfg(test)]
 mod test {
     use boojum::{
-        algebraic_props::poseidon2_parameters::*,
-        config::DevCSConfig,
-        cs::{
-            cs_builder::*, gates::*, implementations::reference_cs::CSReferenceImplementation,
-            traits::gate::*, *,
-        },
-        field::goldilocks::GoldilocksField,
-        gadgets::tables::*,
-        implementations::poseidon2::Poseidon2Goldilocks,
+        field::goldilocks::GoldilocksField, implementations::poseidon2::Poseidon2Goldilocks,
         worker::Worker,
     };
     use zkevm_opcode_defs::PrecompileCallABI;
 
     use super::*;
+    use crate::test_utils::create_test_cs;
 
     type F = GoldilocksField;
-    type P = GoldilocksField;
     type R = Poseidon2Goldilocks;
 
-    fn create_test_cs() -> CSReferenceImplementation<
-        GoldilocksField,
-        GoldilocksField,
-        DevCSConfig,
-        impl GateConfigurationHolder<GoldilocksField>,
-        impl StaticToolboxHolder,
-    > {
-        let geometry = CSGeometry {
-            num_columns_under_copy_permutation: 100,
-            num_witness_columns: 0,
-            num_constant_columns: 8,
-            max_allowed_constraint_degree: 4,
-        };
-
-        fn configure<
-            T: CsBuilderImpl<F, T>,
-            GC: GateConfigurationHolder<F>,
-            TB: StaticToolboxHolder,
-        >(
-            builder: CsBuilder<T, F, GC, TB>,
-        ) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
-            let builder = builder.allow_lookup(
-                LookupParameters::UseSpecializedColumnsWithTableIdAsConstant {
-                    width: 3,
-                    num_repetitions: 8,
-                    share_table_id: true,
-                },
-            );
-            let builder = ConstantsAllocatorGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = ReductionGate::<F, 4>::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = BooleanConstraintGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = UIntXAddGate::<32>::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = UIntXAddGate::<16>::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = UIntXAddGate::<8>::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = SelectionGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = ZeroCheckGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-                false,
-            );
-            let builder = DotProductGate::<4>::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = MatrixMultiplicationGate::<F, 12, Poseidon2GoldilocksExternalMatrix>::configure_builder(builder,GatePlacementStrategy::UseGeneralPurposeColumns);
-            let builder = MatrixMultiplicationGate::<F, 12, Poseidon2GoldilocksInnerMatrix>::configure_builder(builder,GatePlacementStrategy::UseGeneralPurposeColumns);
-            let builder = NopGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-
-            builder
-        }
-
-        use boojum::cs::cs_builder_reference::CsReferenceImplementationBuilder;
-
-        let builder_impl =
-            CsReferenceImplementationBuilder::<F, P, DevCSConfig>::new(geometry, 1 << 20);
-        let builder = new_builder::<_, F>(builder_impl);
-
-        let builder = configure(builder);
-        let mut owned_cs = builder.build(1 << 26);
-
-        // add tables for keccak
-        let table = create_xor8_table();
-        owned_cs.add_lookup_table::<Xor8Table, 3>(table);
-
-        let table = create_and8_table();
-        owned_cs.add_lookup_table::<And8Table, 3>(table);
-
-        let table = create_byte_split_table::<F, 1>();
-        owned_cs.add_lookup_table::<ByteSplitTable<1>, 3>(table);
-        let table = create_byte_split_table::<F, 2>();
-        owned_cs.add_lookup_table::<ByteSplitTable<2>, 3>(table);
-        let table = create_byte_split_table::<F, 3>();
-        owned_cs.add_lookup_table::<ByteSplitTable<3>, 3>(table);
-        let table = create_byte_split_table::<F, 4>();
-        owned_cs.add_lookup_table::<ByteSplitTable<4>, 3>(table);
-
-        owned_cs
-    }
-
     fn bytes_to_u256_words(input: Vec<u8>, unalignement: usize) -> Vec<U256> {
         let mut result = vec![];
         let mut it = std::iter::repeat(0xffu8)
@@ -994,7 +919,7 @@ mod test {
             .try_into()
             .unwrap();
 
-        let mut owned_cs = create_test_cs();
+        let mut owned_cs = create_test_cs(1 << 20);
         let cs = &mut owned_cs;
         let mut memory_queue = MemoryQueue::<F, R>::empty(cs);
 
@@ -1012,7 +937,7 @@ mod test {
 
         let mut precompile_calls_queue = StorageLogQueue::<F, R>::empty(cs);
         let el = LogQueryWitness {
-            address: *zkevm_opcode_defs::system_params::KECCAK256_ROUND_FUNCTION_PRECOMPILE_FORMAL_ADDRESS,
+            address: PrecompileKind::Keccak256.address(),
             key: encoded_precompile_abi,
             read_value: U256::zero(),
             written_value: U256::zero(),
@@ -1039,10 +964,12 @@ mod test {
             witness_source: std::sync::Arc::new(std::sync::RwLock::new(input_witness.into())),
         };
 
+        let mut gas_meter = GasMeter::new(UInt32::zero(cs));
         let new_state = keccak256_precompile_inner(
             cs,
             &mut memory_queue,
             &mut precompile_calls_queue,
+            &mut gas_meter,
             memory_read_witness,
             state,
             &round_function,
@@ -11,28 +11,57 @@ use derivative::*;
 
 pub mod config;
 
+pub mod aes128;
 pub mod base_structures;
+pub mod bls12_381;
+pub mod bn254;
+pub mod bytecode_verify;
+pub mod chacha20;
 pub mod code_unpacker_sha256;
+pub mod debug;
+pub mod decommitment;
 pub mod demux_log_queue;
 pub mod ecrecover;
+pub mod eip712;
 pub mod eip_4844;
+pub mod event_hasher;
 pub mod fsm_input_output;
+pub mod gas_meter;
+pub mod incremental_merkle;
 pub mod keccak256_round_function;
 pub mod linear_hasher;
 pub mod log_sorter;
 pub mod main_vm;
+pub mod merkle_tree;
+pub mod mpt_verify;
+pub mod nullifier;
+pub mod pedersen_hash;
+pub mod precompile_chain;
+pub mod precompile_loop;
 pub mod ram_permutation;
 pub mod recursion;
+pub mod rlp;
 pub mod scheduler;
+pub mod schnorr_verify;
 pub mod secp256r1_verify;
 pub mod sha256_round_function;
 pub mod sort_decommittment_requests;
+pub mod state_diff;
 pub mod storage_application;
+pub mod storage_auth;
 pub mod storage_validity_by_grand_product;
 pub mod tables;
+pub mod tools;
 pub mod transient_storage_validity_by_grand_product;
 pub mod utils;
 
+#[cfg(test)]
+mod constraint_counts;
+#[cfg(test)]
+mod test_serialization;
+#[cfg(test)]
+mod test_utils;
+
 use boojum::pairing::ff;
 
 pub const DEFAULT_NUM_PERMUTATION_ARGUMENT_REPETITIONS: usize = 2;
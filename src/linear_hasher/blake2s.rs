@@ -0,0 +1,528 @@
+use std::sync::Arc;
+
+use boojum::{
+    algebraic_props::round_function::AlgebraicRoundFunction,
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{
+        boolean::Boolean,
+        num::Num,
+        queue::{CircuitQueueRawWitness, CircuitQueueWitness},
+        tables::{ByteSplitTable, Xor8Table},
+        traits::{
+            allocatable::*, auxiliary::PrettyComparison, encodable::CircuitVarLengthEncodable,
+            round_function::CircuitRoundFunction, selectable::Selectable,
+            witnessable::WitnessHookable,
+        },
+        u256::UInt256,
+        u32::UInt32,
+        u8::UInt8,
+    },
+    serde_utils::BigArraySerde,
+};
+use cs_derive::*;
+use derivative::*;
+
+use super::input::*;
+use crate::{
+    base_structures::{
+        log_query::{
+            log_query_witness_into_bytes, LogQuery, LogQueryWitness, L2_TO_L1_MESSAGE_BYTE_LENGTH,
+            LOG_QUERY_PACKED_WIDTH,
+        },
+        ByteSerializable,
+    },
+    demux_log_queue::StorageLogQueue,
+    fsm_input_output::circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH,
+    tables::{get_typed_table_id, TypedTableId},
+    utils::{rotate_bits_right, rotate_bytes_right, xor_into_bytes},
+};
+
+pub const BLAKE2S_BLOCK_SIZE: usize = 64;
+pub const BLAKE2S_DIGEST_SIZE: usize = 32;
+pub const BLAKE2S_NUM_ROUNDS: usize = 10;
+
+pub const BLAKE2S_IV: [u32; 8] = [
+    0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19,
+];
+
+const BLAKE2S_SIGMA: [[usize; 16]; BLAKE2S_NUM_ROUNDS] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+// column and diagonal steps of the round, as `(a, b, c, d)` indexes into the working vector
+const BLAKE2S_MIXING_INDEXES: [[usize; 4]; 8] = [
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+    [0, 5, 10, 15],
+    [1, 6, 11, 12],
+    [2, 7, 8, 13],
+    [3, 4, 9, 14],
+];
+
+// parameter block for unkeyed hashing with 32 byte digest, fanout and depth are 1
+const BLAKE2S_PARAMS_WORD: u32 = 0x01010000 | BLAKE2S_DIGEST_SIZE as u32;
+
+// serialized message always fits into two blocks together with the carry from the previous one,
+// so we need at most two compressions per message before the final one
+const _: () = assert!(L2_TO_L1_MESSAGE_BYTE_LENGTH <= 2 * BLAKE2S_BLOCK_SIZE);
+
+#[derive(Derivative, CSAllocatable, CSSelectable, CSVarLengthEncodable, WitnessHookable)]
+#[derivative(Clone, Copy, Debug)]
+#[DerivePrettyComparison("true")]
+pub struct Blake2sLinearHasherOutputData<F: SmallField> {
+    pub hash: [UInt8<F>; BLAKE2S_DIGEST_SIZE],
+}
+
+impl<F: SmallField> CSPlaceholder<F> for Blake2sLinearHasherOutputData<F> {
+    fn placeholder<CS: ConstraintSystem<F>>(cs: &mut CS) -> Self {
+        Self { hash: [UInt8::<F>::placeholder(cs); BLAKE2S_DIGEST_SIZE] }
+    }
+}
+
+pub type Blake2sLinearHasherInputOutput<F> = crate::fsm_input_output::ClosedFormInput<
+    F,
+    (),
+    LinearHasherInputData<F>,
+    Blake2sLinearHasherOutputData<F>,
+>;
+
+pub type Blake2sLinearHasherInputOutputWitness<F> = crate::fsm_input_output::ClosedFormInputWitness<
+    F,
+    (),
+    LinearHasherInputData<F>,
+    Blake2sLinearHasherOutputData<F>,
+>;
+
+#[derive(Derivative, serde::Serialize, serde::Deserialize)]
+#[derivative(Clone, Debug, Default)]
+#[serde(bound = "")]
+pub struct Blake2sLinearHasherCircuitInstanceWitness<F: SmallField> {
+    pub closed_form_input: Blake2sLinearHasherInputOutputWitness<F>,
+    pub queue_witness: CircuitQueueRawWitness<F, LogQuery<F>, 4, LOG_QUERY_PACKED_WIDTH>,
+}
+
+impl<F: SmallField> Blake2sLinearHasherCircuitInstanceWitness<F> {
+    /// Builds a witness for a single circuit instance that hashes all `queries`
+    pub fn from_log_queries<R: AlgebraicRoundFunction<F, 8, 12, 4>>(
+        queries: impl IntoIterator<Item = LogQueryWitness<F>>,
+        round_function: &R,
+    ) -> Self {
+        let queries: Vec<_> = queries.into_iter().collect();
+        let mut bytes = Vec::with_capacity(queries.len() * L2_TO_L1_MESSAGE_BYTE_LENGTH);
+        for query in queries.iter() {
+            bytes.extend(log_query_witness_into_bytes(query));
+        }
+
+        let LinearHasherCircuitInstanceWitness { closed_form_input, queue_witness } =
+            LinearHasherCircuitInstanceWitness::from_log_queries(queries, round_function);
+
+        Self {
+            closed_form_input: Blake2sLinearHasherInputOutputWitness {
+                start_flag: true,
+                completion_flag: true,
+                observable_input: closed_form_input.observable_input,
+                observable_output: Blake2sLinearHasherOutputDataWitness {
+                    hash: blake2s_256_native(&bytes),
+                },
+                hidden_fsm_input: (),
+                hidden_fsm_output: (),
+            },
+            queue_witness,
+        }
+    }
+}
+
+struct Blake2sTables {
+    xor: TypedTableId<Xor8Table>,
+    split_at_3: TypedTableId<ByteSplitTable<3>>,
+    split_at_4: TypedTableId<ByteSplitTable<4>>,
+}
+
+impl Blake2sTables {
+    fn new<F: SmallField, CS: ConstraintSystem<F>>(cs: &CS) -> Self {
+        Self {
+            xor: get_typed_table_id::<Xor8Table, F, CS>(cs),
+            split_at_3: get_typed_table_id::<ByteSplitTable<3>, F, CS>(cs),
+            split_at_4: get_typed_table_id::<ByteSplitTable<4>, F, CS>(cs),
+        }
+    }
+}
+
+fn add_many<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    terms: &[&UInt32<F>],
+) -> UInt32<F> {
+    let mut result = *terms[0];
+    for term in terms[1..].iter() {
+        (result, _) = result.overflowing_add(cs, *term);
+    }
+
+    result
+}
+
+fn mixing_function<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: &Blake2sTables,
+    v: &mut [UInt32<F>; 16],
+    [a, b, c, d]: [usize; 4],
+    x: &UInt32<F>,
+    y: &UInt32<F>,
+) {
+    v[a] = add_many(cs, &[&v[a], &v[b], x]);
+    let bytes = xor_into_bytes(cs, &tables.xor, &v[d], &v[a]);
+    v[d] = UInt32::from_le_bytes(cs, rotate_bytes_right(bytes, 2));
+
+    v[c] = add_many(cs, &[&v[c], &v[d]]);
+    let bytes = xor_into_bytes(cs, &tables.xor, &v[b], &v[c]);
+    let bytes = rotate_bits_right(cs, &tables.split_at_4, rotate_bytes_right(bytes, 1));
+    v[b] = UInt32::from_le_bytes(cs, bytes);
+
+    v[a] = add_many(cs, &[&v[a], &v[b], y]);
+    let bytes = xor_into_bytes(cs, &tables.xor, &v[d], &v[a]);
+    v[d] = UInt32::from_le_bytes(cs, rotate_bytes_right(bytes, 1));
+
+    v[c] = add_many(cs, &[&v[c], &v[d]]);
+    let bytes = xor_into_bytes(cs, &tables.xor, &v[b], &v[c]);
+    let bytes = rotate_bits_right(cs, &tables.split_at_4, bytes);
+    let bytes = rotate_bits_right(cs, &tables.split_at_3, bytes);
+    v[b] = UInt32::from_le_bytes(cs, bytes);
+}
+
+/// BLAKE2s compression function `F`. The byte counter `t` is given as `[t_0, t_1]` words, and is
+/// xored into the working vector the same way as in the specification. Words are split into bytes
+/// for xors and rotations, so `Xor8Table`, `ByteSplitTable<3>` and `ByteSplitTable<4>` must be
+/// present in the CS
+pub fn blake2s_compress<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    h: [UInt32<F>; 8],
+    m: [UInt32<F>; 16],
+    t: [UInt32<F>; 2],
+    f: Boolean<F>,
+) -> [UInt32<F>; 8] {
+    let tables = Blake2sTables::new(cs);
+
+    let iv = BLAKE2S_IV.map(|el| UInt32::allocated_constant(cs, el));
+    let mut v = [iv[0]; 16];
+    v[..8].copy_from_slice(&h);
+    v[8..].copy_from_slice(&iv);
+
+    let bytes = xor_into_bytes(cs, &tables.xor, &v[12], &t[0]);
+    v[12] = UInt32::from_le_bytes(cs, bytes);
+    let bytes = xor_into_bytes(cs, &tables.xor, &v[13], &t[1]);
+    v[13] = UInt32::from_le_bytes(cs, bytes);
+
+    // finalization flag inverts all the bits
+    let inverted_iv_6 = UInt32::allocated_constant(cs, !BLAKE2S_IV[6]);
+    v[14] = UInt32::conditionally_select(cs, f, &inverted_iv_6, &v[14]);
+
+    for sigma in BLAKE2S_SIGMA.iter() {
+        for (step, indexes) in BLAKE2S_MIXING_INDEXES.iter().enumerate() {
+            let x = m[sigma[2 * step]];
+            let y = m[sigma[2 * step + 1]];
+            mixing_function(cs, &tables, &mut v, *indexes, &x, &y);
+        }
+    }
+
+    std::array::from_fn(|i| {
+        let tmp = xor_into_bytes(cs, &tables.xor, &v[i], &v[i + 8]);
+        let tmp = UInt32::from_le_bytes(cs, tmp);
+        let result = xor_into_bytes(cs, &tables.xor, &h[i], &tmp);
+
+        UInt32::from_le_bytes(cs, result)
+    })
+}
+
+fn compress_block<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    h: [UInt32<F>; 8],
+    block: &[UInt8<F>],
+    bytes_counter: usize,
+    is_final: bool,
+) -> [UInt32<F>; 8] {
+    let zero_u8 = UInt8::zero(cs);
+    let mut padded = [zero_u8; BLAKE2S_BLOCK_SIZE];
+    padded[..block.len()].copy_from_slice(block);
+
+    let mut m = [UInt32::zero(cs); 16];
+    for (dst, src) in m.iter_mut().zip(padded.array_chunks::<4>()) {
+        *dst = UInt32::from_le_bytes(cs, *src);
+    }
+    let t = [bytes_counter as u32, (bytes_counter as u64 >> 32) as u32]
+        .map(|el| UInt32::allocated_constant(cs, el));
+    let f = Boolean::allocated_constant(cs, is_final);
+
+    blake2s_compress(cs, h, m, t, f)
+}
+
+/// Same as `linear_hasher_entry_point`, but hashes serialized log entries with BLAKE2s-256. Every
+/// block is compressed as soon as it's known to be not the last one, so both the number of
+/// compressions and the byte counter only depend on the cycle
+pub fn blake2s_linear_hasher_entry_point<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+>(
+    cs: &mut CS,
+    witness: Blake2sLinearHasherCircuitInstanceWitness<F>,
+    round_function: &R,
+    params: usize,
+) -> [Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH]
+where
+    [(); <LogQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN + 1]:,
+{
+    let limit = params;
+
+    assert!(limit <= u32::MAX as usize);
+
+    let Blake2sLinearHasherCircuitInstanceWitness { closed_form_input, queue_witness } = witness;
+
+    let mut structured_input =
+        Blake2sLinearHasherInputOutput::alloc_ignoring_outputs(cs, closed_form_input.clone());
+    let start_flag = structured_input.start_flag;
+
+    let boolean_true = Boolean::allocated_constant(cs, true);
+
+    // only 1 instance of the circuit here for now
+    Boolean::enforce_equal(cs, &start_flag, &boolean_true);
+
+    let queue_state_from_input = structured_input.observable_input.queue_state;
+
+    // it must be trivial
+    queue_state_from_input.enforce_trivial_head(cs);
+
+    let mut queue = StorageLogQueue::<F, R>::from_state(cs, queue_state_from_input);
+    let queue_witness = CircuitQueueWitness::from_inner_witness(queue_witness);
+    queue.witness = Arc::new(queue_witness);
+
+    let mut initial_state = BLAKE2S_IV;
+    initial_state[0] ^= BLAKE2S_PARAMS_WORD;
+    let mut state = initial_state.map(|el| UInt32::allocated_constant(cs, el));
+
+    let empty_hash = blake2s_256_native(&[]).map(|el| UInt8::allocated_constant(cs, el));
+
+    let mut buffer = Vec::with_capacity(
+        BLAKE2S_BLOCK_SIZE
+            + <LogQuery<F> as ByteSerializable<F, L2_TO_L1_MESSAGE_BYTE_LENGTH>>::SERIALIZED_LEN,
+    );
+    let mut bytes_compressed = 0;
+
+    let mut done = queue.is_empty(cs);
+    let no_work = done;
+
+    for _cycle in 0..limit {
+        let queue_is_empty = queue.is_empty(cs);
+        let should_pop = queue_is_empty.negated(cs);
+
+        let (storage_log, _) = queue.pop_front(cs, should_pop);
+
+        let now_empty = queue.is_empty(cs);
+        let is_last_serialization = Boolean::multi_and(cs, &[should_pop, now_empty]);
+        let as_bytes = storage_log.into_bytes(cs);
+
+        buffer.extend(as_bytes);
+
+        let continue_to_absorb = done.negated(cs);
+
+        // last block must be compressed with the finalization flag, so we only take a block if
+        // something is left after it
+        while buffer.len() > BLAKE2S_BLOCK_SIZE {
+            let carry_on = buffer[BLAKE2S_BLOCK_SIZE..].to_vec();
+            bytes_compressed += BLAKE2S_BLOCK_SIZE;
+            let new_state =
+                compress_block(cs, state, &buffer[..BLAKE2S_BLOCK_SIZE], bytes_compressed, false);
+            buffer = carry_on;
+
+            // absorb if we are not done yet
+            state =
+                <[UInt32<F>; 8]>::conditionally_select(cs, continue_to_absorb, &new_state, &state);
+        }
+
+        // in case if we do last round
+        {
+            let absorb_as_last_round =
+                Boolean::multi_and(cs, &[continue_to_absorb, is_last_serialization]);
+            let new_state =
+                compress_block(cs, state, &buffer, bytes_compressed + buffer.len(), true);
+
+            state = <[UInt32<F>; 8]>::conditionally_select(
+                cs,
+                absorb_as_last_round,
+                &new_state,
+                &state,
+            );
+        }
+
+        done = Boolean::multi_or(cs, &[done, is_last_serialization]);
+    }
+
+    queue.enforce_consistency(cs);
+    let completed = queue.is_empty(cs);
+
+    Boolean::enforce_equal(cs, &completed, &boolean_true);
+
+    structured_input.completion_flag = completed.clone();
+
+    let fsm_output = ();
+    structured_input.hidden_fsm_output = fsm_output;
+
+    let mut hash = [UInt8::zero(cs); BLAKE2S_DIGEST_SIZE];
+    for (dst, src) in hash.array_chunks_mut::<4>().zip(state.iter()) {
+        *dst = src.to_le_bytes(cs);
+    }
+
+    let hash =
+        <[UInt8<F>; BLAKE2S_DIGEST_SIZE]>::conditionally_select(cs, no_work, &empty_hash, &hash);
+
+    let mut observable_output = Blake2sLinearHasherOutputData::placeholder(cs);
+    observable_output.hash = hash;
+    structured_input.observable_output = observable_output;
+
+    // self-check
+    structured_input.hook_compare_witness(cs, &closed_form_input);
+
+    use boojum::cs::gates::PublicInputGate;
+
+    use crate::fsm_input_output::{
+        commit_variable_length_encodable_item, ClosedFormInputCompactForm,
+    };
+
+    let compact_form =
+        ClosedFormInputCompactForm::from_full_form(cs, &structured_input, round_function);
+    let input_commitment = commit_variable_length_encodable_item(cs, &compact_form, round_function);
+    for el in input_commitment.iter() {
+        let gate = PublicInputGate::new(el.get_variable());
+        gate.add_to_cs(cs);
+    }
+
+    input_commitment
+}
+
+pub(crate) fn blake2s_compress_native(h: &mut [u32; 8], m: &[u32; 16], t: u64, f: bool) {
+    let mut v = [0u32; 16];
+    v[..8].copy_from_slice(&h[..]);
+    v[8..].copy_from_slice(&BLAKE2S_IV);
+    v[12] ^= t as u32;
+    v[13] ^= (t >> 32) as u32;
+    if f {
+        v[14] = !v[14];
+    }
+
+    for sigma in BLAKE2S_SIGMA.iter() {
+        for (step, [a, b, c, d]) in BLAKE2S_MIXING_INDEXES.iter().enumerate() {
+            let (x, y) = (m[sigma[2 * step]], m[sigma[2 * step + 1]]);
+            v[*a] = v[*a].wrapping_add(v[*b]).wrapping_add(x);
+            v[*d] = (v[*d] ^ v[*a]).rotate_right(16);
+            v[*c] = v[*c].wrapping_add(v[*d]);
+            v[*b] = (v[*b] ^ v[*c]).rotate_right(12);
+            v[*a] = v[*a].wrapping_add(v[*b]).wrapping_add(y);
+            v[*d] = (v[*d] ^ v[*a]).rotate_right(8);
+            v[*c] = v[*c].wrapping_add(v[*d]);
+            v[*b] = (v[*b] ^ v[*c]).rotate_right(7);
+        }
+    }
+
+    for i in 0..8 {
+        h[i] ^= v[i] ^ v[i + 8];
+    }
+}
+
+/// Unkeyed BLAKE2s with 32 byte digest
+pub(crate) fn blake2s_256_native(input: &[u8]) -> [u8; BLAKE2S_DIGEST_SIZE] {
+    let mut h = BLAKE2S_IV;
+    h[0] ^= BLAKE2S_PARAMS_WORD;
+
+    let num_blocks = std::cmp::max(1, (input.len() + BLAKE2S_BLOCK_SIZE - 1) / BLAKE2S_BLOCK_SIZE);
+    for block_idx in 0..num_blocks {
+        let start = block_idx * BLAKE2S_BLOCK_SIZE;
+        let end = std::cmp::min(start + BLAKE2S_BLOCK_SIZE, input.len());
+        let mut block = [0u8; BLAKE2S_BLOCK_SIZE];
+        block[..(end - start)].copy_from_slice(&input[start..end]);
+
+        let mut m = [0u32; 16];
+        for (dst, src) in m.iter_mut().zip(block.array_chunks::<4>()) {
+            *dst = u32::from_le_bytes(*src);
+        }
+        let is_last = block_idx + 1 == num_blocks;
+        blake2s_compress_native(&mut h, &m, end as u64, is_last);
+    }
+
+    let mut result = [0u8; BLAKE2S_DIGEST_SIZE];
+    for (dst, src) in result.array_chunks_mut::<4>().zip(h.iter()) {
+        *dst = src.to_le_bytes();
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use boojum::{implementations::poseidon2::Poseidon2Goldilocks, worker::Worker};
+
+    use super::*;
+    use crate::{linear_hasher::tests::log_query_fixtures, test_utils::create_test_cs};
+
+    #[test]
+    fn test_blake2s_compress() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        // test vector from RFC 7693
+        let expected =
+            hex::decode("508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982")
+                .unwrap();
+        assert_eq!(blake2s_256_native(b"abc").to_vec(), expected);
+
+        let mut h = BLAKE2S_IV;
+        h[0] ^= 0x01010020;
+        let mut m = [0u32; 16];
+        m[0] = u32::from_le_bytes([b'a', b'b', b'c', 0]);
+
+        let h_var = h.map(|el| UInt32::allocate(cs, el));
+        let m_var = m.map(|el| UInt32::allocate(cs, el));
+        let t_var = [UInt32::allocate(cs, 3), UInt32::allocate(cs, 0)];
+        let f_var = Boolean::allocate(cs, true);
+        let result = blake2s_compress(cs, h_var, m_var, t_var, f_var);
+
+        blake2s_compress_native(&mut h, &m, 3, true);
+        assert_eq!(result.map(|el| el.witness_hook(&*cs)().unwrap()), h);
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+
+    #[test]
+    fn test_blake2s_linear_hasher_from_log_queries() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+        let round_function = Poseidon2Goldilocks;
+
+        let queries = log_query_fixtures(3);
+
+        let witness =
+            Blake2sLinearHasherCircuitInstanceWitness::from_log_queries(queries, &round_function);
+
+        // the self-check in the entry point compares the hash against the native one
+        let _ = blake2s_linear_hasher_entry_point(cs, witness, &round_function, 3);
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+}
@@ -1,8 +1,13 @@
 use boojum::{
+    algebraic_props::round_function::{
+        absorb_into_state_vararg, AbsorptionModeOverwrite, AlgebraicRoundFunction,
+    },
     cs::{traits::cs::ConstraintSystem, Variable},
     field::SmallField,
     gadgets::{
         boolean::Boolean,
+        keccak256,
+        num::Num,
         queue::*,
         traits::{
             allocatable::*, auxiliary::PrettyComparison, encodable::CircuitVarLengthEncodable,
@@ -16,10 +21,25 @@ use cs_derive::*;
 use derivative::*;
 
 use crate::base_structures::{
-    log_query::{LogQuery, LOG_QUERY_PACKED_WIDTH},
+    log_query::{
+        log_query_witness_encoding, log_query_witness_into_bytes, LogQuery, LogQueryWitness,
+        L2_TO_L1_MESSAGE_BYTE_LENGTH, LOG_QUERY_ABSORBTION_ROUNDS, LOG_QUERY_PACKED_WIDTH,
+    },
     vm_state::*,
 };
 
+/// Number of serialized messages that take a whole number of keccak blocks
+pub const MESSAGES_PER_ALIGNED_CHUNK: usize = 17;
+
+const _: () = if (MESSAGES_PER_ALIGNED_CHUNK * L2_TO_L1_MESSAGE_BYTE_LENGTH)
+    % keccak256::KECCAK_RATE_BYTES
+    == 0
+{
+    ()
+} else {
+    panic!()
+};
+
 #[derive(Derivative, CSAllocatable, CSSelectable, CSVarLengthEncodable, WitnessHookable)]
 #[derivative(Clone, Copy, Debug)]
 #[DerivePrettyComparison("true")]
@@ -46,16 +66,39 @@ impl<F: SmallField> CSPlaceholder<F> for LinearHasherOutputData<F> {
     }
 }
 
+/// Keccak state carried between instances. Instances absorb whole blocks only, so the buffer of
+/// not yet absorbed bytes is empty at the boundary
+#[derive(Derivative, CSAllocatable, CSSelectable, CSVarLengthEncodable, WitnessHookable)]
+#[derivative(Clone, Copy, Debug)]
+#[DerivePrettyComparison("true")]
+pub struct LinearHasherFSMState<F: SmallField> {
+    pub queue_state: QueueState<F, QUEUE_STATE_WIDTH>,
+    pub accumulator_state:
+        [[[UInt8<F>; keccak256::BYTES_PER_WORD]; keccak256::LANE_WIDTH]; keccak256::LANE_WIDTH],
+    pub bytes_pending_in_buffer: UInt8<F>,
+}
+
+impl<F: SmallField> CSPlaceholder<F> for LinearHasherFSMState<F> {
+    fn placeholder<CS: ConstraintSystem<F>>(cs: &mut CS) -> Self {
+        Self {
+            queue_state: QueueState::<F, QUEUE_STATE_WIDTH>::placeholder(cs),
+            accumulator_state: [[[UInt8::<F>::placeholder(cs); keccak256::BYTES_PER_WORD];
+                keccak256::LANE_WIDTH]; keccak256::LANE_WIDTH],
+            bytes_pending_in_buffer: UInt8::<F>::placeholder(cs),
+        }
+    }
+}
+
 pub type LinearHasherInputOutput<F> = crate::fsm_input_output::ClosedFormInput<
     F,
-    (),
+    LinearHasherFSMState<F>,
     LinearHasherInputData<F>,
     LinearHasherOutputData<F>,
 >;
 
 pub type LinearHasherInputOutputWitness<F> = crate::fsm_input_output::ClosedFormInputWitness<
     F,
-    (),
+    LinearHasherFSMState<F>,
     LinearHasherInputData<F>,
     LinearHasherOutputData<F>,
 >;
@@ -73,3 +116,350 @@ pub struct LinearHasherCircuitInstanceWitness<F: SmallField> {
     // serde::de::DeserializeOwned" ))]
     pub queue_witness: CircuitQueueRawWitness<F, LogQuery<F>, 4, LOG_QUERY_PACKED_WIDTH>,
 }
+
+impl<F: SmallField> LinearHasherCircuitInstanceWitness<F> {
+    /// Builds a witness for a single circuit instance that hashes all `queries`. Queue states are
+    /// simulated the same way as `CircuitQueue::push` does it: every element's encoding is
+    /// absorbed together with the previous tail
+    pub fn from_log_queries<R: AlgebraicRoundFunction<F, 8, 12, 4>>(
+        queries: impl IntoIterator<Item = LogQueryWitness<F>>,
+        _round_function: &R,
+    ) -> Self {
+        use zkevm_opcode_defs::sha3::*;
+
+        let mut hasher = Keccak256::new();
+        let mut elements = std::collections::VecDeque::new();
+        let mut tail = [F::ZERO; QUEUE_STATE_WIDTH];
+        let mut length = 0u32;
+
+        for query in queries.into_iter() {
+            hasher.update(&log_query_witness_into_bytes(&query));
+
+            let mut to_absorb = Vec::with_capacity(LOG_QUERY_PACKED_WIDTH + QUEUE_STATE_WIDTH);
+            to_absorb.extend(log_query_witness_encoding(&query));
+            to_absorb.extend(tail);
+            let new_tail = absorb_into_state_vararg::<
+                F,
+                R,
+                AbsorptionModeOverwrite,
+                8,
+                12,
+                4,
+                QUEUE_STATE_WIDTH,
+            >(&to_absorb);
+
+            elements.push_back((query, tail));
+            tail = new_tail;
+            length += 1;
+        }
+
+        let queue_state = QueueStateWitness {
+            head: [F::ZERO; QUEUE_STATE_WIDTH],
+            tail: QueueTailStateWitness { tail, length },
+        };
+        let keccak256_hash: [u8; 32] = hasher.finalize().into();
+
+        Self {
+            closed_form_input: LinearHasherInputOutputWitness {
+                start_flag: true,
+                completion_flag: true,
+                observable_input: LinearHasherInputDataWitness { queue_state },
+                observable_output: LinearHasherOutputDataWitness { keccak256_hash },
+                hidden_fsm_input: LinearHasherFSMState::placeholder_witness(),
+                hidden_fsm_output: LinearHasherFSMState::placeholder_witness(),
+            },
+            queue_witness: CircuitQueueRawWitness { elements },
+        }
+    }
+
+    /// Builds witnesses for `incremental_linear_hasher_entry_point`, splitting `queries` into
+    /// instances of `limit` messages each. The keccak state at every boundary is simulated natively
+    pub fn from_log_queries_incremental<R: AlgebraicRoundFunction<F, 8, 12, 4>>(
+        queries: impl IntoIterator<Item = LogQueryWitness<F>>,
+        limit: usize,
+        round_function: &R,
+    ) -> Vec<Self> {
+        assert!(limit % MESSAGES_PER_ALIGNED_CHUNK == 0);
+
+        let Self { closed_form_input, queue_witness } =
+            Self::from_log_queries(queries, round_function);
+        let queue_state = closed_form_input.observable_input.queue_state;
+        let keccak256_hash = closed_form_input.observable_output.keccak256_hash;
+        let all_elements: Vec<_> = queue_witness.elements.into_iter().collect();
+
+        let mut result = vec![];
+        let mut keccak_state = [0u64; keccak256::LANE_WIDTH * keccak256::LANE_WIDTH];
+        let mut bytes = vec![];
+        let mut hidden_fsm_input = LinearHasherFSMState::placeholder_witness();
+        let mut chunks: Vec<&[_]> = all_elements.chunks(limit).collect();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+        let num_instances = chunks.len();
+
+        for (instance_idx, chunk) in chunks.into_iter().enumerate() {
+            let is_last = instance_idx + 1 == num_instances;
+            for (query, _) in chunk.iter() {
+                bytes.extend(log_query_witness_into_bytes(query));
+            }
+            // the last instance pads the tail, and the empty queue is not hashed at all
+            if is_last && !all_elements.is_empty() {
+                let tail_len = bytes.len() % keccak256::KECCAK_RATE_BYTES;
+                bytes.resize(bytes.len() + keccak256::KECCAK_RATE_BYTES - tail_len, 0);
+                let last_block_start = bytes.len() - keccak256::KECCAK_RATE_BYTES;
+                bytes[last_block_start + tail_len] ^= 0x01;
+                *bytes.last_mut().unwrap() ^= 0x80;
+            }
+            assert!(bytes.len() % keccak256::KECCAK_RATE_BYTES == 0);
+            for block in bytes.chunks(keccak256::KECCAK_RATE_BYTES) {
+                for (lane, word) in keccak_state
+                    .iter_mut()
+                    .zip(block.chunks(keccak256::BYTES_PER_WORD))
+                {
+                    *lane ^= u64::from_le_bytes(word.try_into().unwrap());
+                }
+                keccak_f1600(&mut keccak_state);
+            }
+            bytes.clear();
+
+            let popped = instance_idx * limit + chunk.len();
+            let head = if popped < all_elements.len() {
+                all_elements[popped].1
+            } else {
+                queue_state.tail.tail
+            };
+            let hidden_fsm_output = LinearHasherFSMStateWitness {
+                queue_state: QueueStateWitness {
+                    head,
+                    tail: QueueTailStateWitness {
+                        tail: queue_state.tail.tail,
+                        length: (all_elements.len() - popped) as u32,
+                    },
+                },
+                accumulator_state: std::array::from_fn(|i| {
+                    std::array::from_fn(|j| {
+                        keccak_state[i + keccak256::LANE_WIDTH * j].to_le_bytes()
+                    })
+                }),
+                bytes_pending_in_buffer: 0,
+            };
+
+            let observable_output = if is_last {
+                LinearHasherOutputDataWitness { keccak256_hash }
+            } else {
+                LinearHasherOutputData::placeholder_witness()
+            };
+
+            result.push(Self {
+                closed_form_input: LinearHasherInputOutputWitness {
+                    start_flag: instance_idx == 0,
+                    completion_flag: is_last,
+                    observable_input: LinearHasherInputDataWitness {
+                        queue_state: queue_state.clone(),
+                    },
+                    observable_output,
+                    hidden_fsm_input,
+                    hidden_fsm_output: hidden_fsm_output.clone(),
+                },
+                queue_witness: CircuitQueueRawWitness { elements: chunk.iter().cloned().collect() },
+            });
+            hidden_fsm_input = hidden_fsm_output;
+        }
+
+        result
+    }
+}
+
+const KECCAK_ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+const KECCAK_RHO_OFFSETS: [u32; 24] =
+    [1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44];
+
+const KECCAK_PI_LANES: [usize; 24] =
+    [10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1];
+
+/// Native keccak-f[1600] over lanes indexed as `x + 5 * y`, used to simulate the state that
+/// instances pass to each other
+fn keccak_f1600(state: &mut [u64; 25]) {
+    for round_constant in KECCAK_ROUND_CONSTANTS.iter() {
+        // theta
+        let mut c = [0u64; 5];
+        for x in 0..5 {
+            c[x] = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                state[x + 5 * y] ^= d;
+            }
+        }
+        // rho and pi
+        let mut last = state[1];
+        for (lane, offset) in KECCAK_PI_LANES.iter().zip(KECCAK_RHO_OFFSETS.iter()) {
+            let tmp = state[*lane];
+            state[*lane] = last.rotate_left(*offset);
+            last = tmp;
+        }
+        // chi
+        for y in 0..5 {
+            let mut row = [0u64; 5];
+            row.copy_from_slice(&state[5 * y..5 * y + 5]);
+            for x in 0..5 {
+                state[x + 5 * y] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+        // iota
+        state[0] ^= round_constant;
+    }
+}
+
+#[derive(Derivative, CSAllocatable, CSSelectable, CSVarLengthEncodable, WitnessHookable)]
+#[derivative(Clone, Copy, Debug)]
+#[DerivePrettyComparison("true")]
+pub struct Poseidon2LinearHasherOutputData<F: SmallField> {
+    pub hash: [Num<F>; QUEUE_STATE_WIDTH],
+}
+
+impl<F: SmallField> CSPlaceholder<F> for Poseidon2LinearHasherOutputData<F> {
+    fn placeholder<CS: ConstraintSystem<F>>(cs: &mut CS) -> Self {
+        Self { hash: [Num::<F>::zero(cs); QUEUE_STATE_WIDTH] }
+    }
+}
+
+/// The sponge state is kept as is between instances, so no byte buffer has to be carried over
+#[derive(Derivative, CSAllocatable, CSSelectable, CSVarLengthEncodable, WitnessHookable)]
+#[derivative(Clone, Copy, Debug)]
+#[DerivePrettyComparison("true")]
+pub struct Poseidon2LinearHasherFSMState<F: SmallField> {
+    pub queue_state: QueueState<F, QUEUE_STATE_WIDTH>,
+    pub sponge_state: [Num<F>; FULL_SPONGE_QUEUE_STATE_WIDTH],
+}
+
+impl<F: SmallField> CSPlaceholder<F> for Poseidon2LinearHasherFSMState<F> {
+    fn placeholder<CS: ConstraintSystem<F>>(cs: &mut CS) -> Self {
+        Self {
+            queue_state: QueueState::<F, QUEUE_STATE_WIDTH>::placeholder(cs),
+            sponge_state: [Num::<F>::zero(cs); FULL_SPONGE_QUEUE_STATE_WIDTH],
+        }
+    }
+}
+
+pub type Poseidon2LinearHasherInputOutput<F> = crate::fsm_input_output::ClosedFormInput<
+    F,
+    Poseidon2LinearHasherFSMState<F>,
+    LinearHasherInputData<F>,
+    Poseidon2LinearHasherOutputData<F>,
+>;
+
+pub type Poseidon2LinearHasherInputOutputWitness<F> =
+    crate::fsm_input_output::ClosedFormInputWitness<
+        F,
+        Poseidon2LinearHasherFSMState<F>,
+        LinearHasherInputData<F>,
+        Poseidon2LinearHasherOutputData<F>,
+    >;
+
+#[derive(Derivative, serde::Serialize, serde::Deserialize)]
+#[derivative(Clone, Debug, Default)]
+#[serde(bound = "")]
+pub struct Poseidon2LinearHasherCircuitInstanceWitness<F: SmallField> {
+    pub closed_form_input: Poseidon2LinearHasherInputOutputWitness<F>,
+    pub queue_witness: CircuitQueueRawWitness<F, LogQuery<F>, 4, LOG_QUERY_PACKED_WIDTH>,
+}
+
+impl<F: SmallField> Poseidon2LinearHasherCircuitInstanceWitness<F> {
+    /// Builds a witness for a single circuit instance that hashes all `queries`. Every encoding is
+    /// padded with zeroes to the full number of absorption rounds
+    pub fn from_log_queries<R: AlgebraicRoundFunction<F, 8, 12, 4>>(
+        queries: impl IntoIterator<Item = LogQueryWitness<F>>,
+        _round_function: &R,
+    ) -> Self {
+        let mut elements = std::collections::VecDeque::new();
+        let mut to_hash = vec![];
+        let mut tail = [F::ZERO; QUEUE_STATE_WIDTH];
+        let mut length = 0u32;
+
+        for query in queries.into_iter() {
+            let encoding = log_query_witness_encoding(&query);
+            to_hash.extend(encoding);
+            to_hash
+                .resize(to_hash.len() + LOG_QUERY_ABSORBTION_ROUNDS * 8 - encoding.len(), F::ZERO);
+
+            let mut to_absorb = Vec::with_capacity(LOG_QUERY_PACKED_WIDTH + QUEUE_STATE_WIDTH);
+            to_absorb.extend(encoding);
+            to_absorb.extend(tail);
+            let new_tail = absorb_into_state_vararg::<
+                F,
+                R,
+                AbsorptionModeOverwrite,
+                8,
+                12,
+                4,
+                QUEUE_STATE_WIDTH,
+            >(&to_absorb);
+
+            elements.push_back((query, tail));
+            tail = new_tail;
+            length += 1;
+        }
+
+        let queue_state = QueueStateWitness {
+            head: [F::ZERO; QUEUE_STATE_WIDTH],
+            tail: QueueTailStateWitness { tail, length },
+        };
+        let sponge_state = absorb_into_state_vararg::<
+            F,
+            R,
+            AbsorptionModeOverwrite,
+            8,
+            12,
+            4,
+            FULL_SPONGE_QUEUE_STATE_WIDTH,
+        >(&to_hash);
+        let mut hash = [F::ZERO; QUEUE_STATE_WIDTH];
+        hash.copy_from_slice(&sponge_state[..QUEUE_STATE_WIDTH]);
+
+        Self {
+            closed_form_input: Poseidon2LinearHasherInputOutputWitness {
+                start_flag: true,
+                completion_flag: true,
+                observable_input: LinearHasherInputDataWitness { queue_state },
+                observable_output: Poseidon2LinearHasherOutputDataWitness { hash },
+                hidden_fsm_input: Poseidon2LinearHasherFSMState::placeholder_witness(),
+                hidden_fsm_output: Poseidon2LinearHasherFSMStateWitness {
+                    queue_state: QueueStateWitness {
+                        head: tail,
+                        tail: QueueTailStateWitness { tail, length: 0 },
+                    },
+                    sponge_state,
+                },
+            },
+            queue_witness: CircuitQueueRawWitness { elements },
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::sync::Arc;
+
+use boojum::gadgets::{u160::UInt160, u256::UInt256};
+
+use super::*;
+use crate::{
+    ecrecover::{
+        new_optimized::{
+            allocate_masking_constants, ecrecover_precompile_inner_routine, ALLOW_ZERO_MESSAGE,
+        },
+        secp256k1_base_field_params, secp256k1_scalar_field_params,
+    },
+    utils::keccak256_of_bytes,
+};
+
+/// Prefix byte of the EIP-3074 AUTH message
+pub const AUTH_MAGIC: u8 = 0x04;
+
+// magic byte, then chain id, nonce, invoker address (padded to 32 bytes) and commit
+const AUTH_MESSAGE_LEN: usize = 1 + 32 * 4;
+
+/// Computes `keccak256(MAGIC || chain_id || nonce || invoker_address || commit)` with all words
+/// in big-endian form, and recovers the signer of it. `y_parity` must be 0 or 1, otherwise recovery
+/// fails. Returns `(success, authorized_address)`, and the address is zero on failure. The
+/// authorization is not a part of the VM state, so the caller decides where to keep it
+pub fn process_auth_opcode<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    chain_id: &UInt256<F>,
+    nonce: &UInt256<F>,
+    invoker_address: &UInt160<F>,
+    commit: &UInt256<F>,
+    y_parity: &UInt8<F>,
+    r: &UInt256<F>,
+    s: &UInt256<F>,
+) -> (Boolean<F>, UInt160<F>) {
+    let zero_u8 = UInt8::zero(cs);
+    let mut bytes_to_hash = [zero_u8; AUTH_MESSAGE_LEN];
+    bytes_to_hash[0] = UInt8::allocated_constant(cs, AUTH_MAGIC);
+
+    bytes_to_hash[1..33].copy_from_slice(&chain_id.to_be_bytes(cs));
+    bytes_to_hash[33..65].copy_from_slice(&nonce.to_be_bytes(cs));
+    // address takes lowest 20 bytes of the word, with most significant limb first
+    for (dst, limb) in bytes_to_hash[77..97]
+        .array_chunks_mut::<4>()
+        .zip(invoker_address.inner.iter().rev())
+    {
+        let mut be_bytes = limb.to_le_bytes(cs);
+        be_bytes.reverse();
+        *dst = be_bytes;
+    }
+    bytes_to_hash[97..].copy_from_slice(&commit.to_be_bytes(cs));
+
+    let mut digest_bytes = keccak256_of_bytes(cs, &bytes_to_hash);
+    digest_bytes.reverse();
+    let digest = UInt256::from_le_bytes(cs, digest_bytes);
+
+    let scalar_params = Arc::new(secp256k1_scalar_field_params());
+    let base_params = Arc::new(secp256k1_base_field_params());
+    let (valid_x_in_external_field, valid_y_in_external_field, valid_t_in_external_field) =
+        allocate_masking_constants(cs, &base_params);
+
+    // AUTH only takes the parity of `y`, so recovery ids 2 and 3 that select `x = r + n` must not
+    // reach the routine
+    let one_u8 = UInt8::allocated_constant(cs, 1);
+    let y_parity_is_zero = UInt8::equals(cs, y_parity, &zero_u8);
+    let y_parity_is_one = UInt8::equals(cs, y_parity, &one_u8);
+    let y_parity_is_valid = y_parity_is_zero.or(cs, y_parity_is_one);
+    let rec_id = y_parity.mask(cs, y_parity_is_valid);
+
+    let (success, recovered) = ecrecover_precompile_inner_routine::<_, _, ALLOW_ZERO_MESSAGE>(
+        cs,
+        &rec_id,
+        r,
+        s,
+        &digest,
+        valid_x_in_external_field,
+        valid_y_in_external_field,
+        valid_t_in_external_field,
+        &base_params,
+        &scalar_params,
+    );
+    let success = success.and(cs, y_parity_is_valid);
+    let recovered = recovered.mask(cs, success);
+
+    // routine returns the address in the lowest 20 bytes, and zero on failure
+    let recovered_address = UInt160 {
+        inner: [
+            recovered.inner[0],
+            recovered.inner[1],
+            recovered.inner[2],
+            recovered.inner[3],
+            recovered.inner[4],
+        ],
+    };
+
+    (success, recovered_address)
+}
+
+#[cfg(test)]
+mod tests {
+    use boojum::{
+        field::goldilocks::GoldilocksField,
+        gadgets::traits::{allocatable::CSAllocatable, witnessable::WitnessHookable},
+        pairing::{
+            ff::{Field, PrimeField, PrimeFieldRepr},
+            GenericCurveAffine, GenericCurveProjective,
+        },
+        worker::Worker,
+    };
+    use rand::{Rng, SeedableRng, XorShiftRng};
+
+    use super::*;
+    use crate::{
+        ecrecover::secp256k1::{
+            fq::Fq as Secp256Fq, fr::Fr as Secp256Fr, PointAffine as Secp256Affine,
+        },
+        ethereum_types::{Address, U256},
+        test_utils::create_test_cs,
+    };
+
+    type F = GoldilocksField;
+
+    fn repr_into_u256<T: PrimeFieldRepr>(repr: T) -> U256 {
+        let mut u256 = U256::zero();
+        u256.0.copy_from_slice(&repr.as_ref()[..4]);
+
+        u256
+    }
+
+    fn keccak256_of(parts: &[&[u8]]) -> [u8; 32] {
+        use zkevm_opcode_defs::sha3::*;
+
+        let mut hasher = Keccak256::new();
+        for part in parts.iter() {
+            hasher.update(part);
+        }
+
+        let mut result = [0u8; 32];
+        result.copy_from_slice(hasher.finalize().as_slice());
+
+        result
+    }
+
+    fn u256_to_be_bytes(value: U256) -> [u8; 32] {
+        let mut buffer = [0u8; 32];
+        value.to_big_endian(&mut buffer);
+
+        buffer
+    }
+
+    // signs the AUTH message natively and runs the opcode with the given recovery id offset added
+    // to the parity of `y`
+    fn check_auth(y_parity_offset: u8, expect_success: bool) {
+        let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        let chain_id = U256::from(324u64);
+        let nonce = U256::from(7u64);
+        let invoker = Address::from_low_u64_be(0x1234_5678_9abc_def0);
+        let commit = repr_into_u256(rng.gen::<Secp256Fr>().into_repr());
+
+        let mut invoker_word = [0u8; 32];
+        invoker_word[12..].copy_from_slice(invoker.as_bytes());
+        let digest = keccak256_of(&[
+            &[AUTH_MAGIC],
+            &u256_to_be_bytes(chain_id),
+            &u256_to_be_bytes(nonce),
+            &invoker_word,
+            &u256_to_be_bytes(commit),
+        ]);
+        let secp_n = repr_into_u256(Secp256Fr::char());
+        let digest_fr =
+            Secp256Fr::from_str(&(U256::from_big_endian(&digest) % secp_n).to_string()).unwrap();
+
+        // sign natively, `r` is the x coordinate of `k * G`, that is less than the group order for
+        // this seed
+        let sk: Secp256Fr = rng.gen();
+        let k: Secp256Fr = rng.gen();
+        let (r_x, r_y): (Secp256Fq, Secp256Fq) = Secp256Affine::one()
+            .mul(k.into_repr())
+            .into_affine()
+            .into_xy_unchecked();
+        let r = Secp256Fr::from_str(&repr_into_u256(r_x.into_repr()).to_string()).unwrap();
+        let y_parity = (r_y.into_repr().as_ref()[0] & 1) as u8 + y_parity_offset;
+
+        let mut s = r;
+        s.mul_assign(&sk);
+        s.add_assign(&digest_fr);
+        s.mul_assign(&k.inverse().unwrap());
+
+        let (pk_x, pk_y) = Secp256Affine::one()
+            .mul(sk.into_repr())
+            .into_affine()
+            .into_xy_unchecked();
+        let pk_hash = keccak256_of(&[
+            &u256_to_be_bytes(repr_into_u256(pk_x.into_repr())),
+            &u256_to_be_bytes(repr_into_u256(pk_y.into_repr())),
+        ]);
+        let expected_address = Address::from_slice(&pk_hash[12..]);
+
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let chain_id = UInt256::allocate(cs, chain_id);
+        let nonce = UInt256::allocate(cs, nonce);
+        let invoker = UInt160::allocate(cs, invoker);
+        let commit = UInt256::allocate(cs, commit);
+        let y_parity = UInt8::allocate_checked(cs, y_parity);
+        let r = UInt256::allocate(cs, repr_into_u256(r.into_repr()));
+        let s = UInt256::allocate(cs, repr_into_u256(s.into_repr()));
+
+        let (success, recovered_address) =
+            process_auth_opcode(cs, &chain_id, &nonce, &invoker, &commit, &y_parity, &r, &s);
+
+        let expected_address = if expect_success { expected_address } else { Address::zero() };
+        assert_eq!(success.witness_hook(&*cs)().unwrap(), expect_success);
+        assert_eq!(recovered_address.witness_hook(&*cs)().unwrap(), expected_address);
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+
+    #[test]
+    fn test_auth_recovers_signer() {
+        check_auth(0, true);
+    }
+
+    #[test]
+    fn test_auth_rejects_y_parity_above_one() {
+        // 2 and 3 are valid ecrecover ids, but select the `x = r + n` branch
+        check_auth(2, false);
+    }
+}
@@ -0,0 +1,30 @@
+use boojum::{cs::traits::cs::ConstraintSystem, field::SmallField, gadgets::boolean::Boolean};
+
+use crate::base_structures::register::VMRegister;
+
+/// Checks bounds of the fat pointer in the register. Fat pointer is laid out in the lowest
+/// 128 bits as [offset, memory page, start, length].
+///
+/// Returns `(is_valid_offset, is_no_overflow)`, where the first flag is `offset < length` and the
+/// second one is `start + length` fitting into u32. Both flags are `true` if the register is not
+/// a pointer
+pub fn validate_fat_pointer<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    reg: &VMRegister<F>,
+) -> (Boolean<F>, Boolean<F>) {
+    let offset = reg.value.inner[0];
+    let start = reg.value.inner[2];
+    let length = reg.value.inner[3];
+
+    let is_integer = reg.is_pointer.negated(cs);
+
+    // offset < length iff offset - length underflows
+    let (_, offset_is_strictly_in_slice) = offset.overflowing_sub(cs, length);
+    let is_valid_offset = Boolean::multi_or(cs, &[offset_is_strictly_in_slice, is_integer]);
+
+    let (_, end_overflows) = start.overflowing_add(cs, length);
+    let end_fits = end_overflows.negated(cs);
+    let is_no_overflow = Boolean::multi_or(cs, &[end_fits, is_integer]);
+
+    (is_valid_offset, is_no_overflow)
+}
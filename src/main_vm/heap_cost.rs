@@ -0,0 +1,24 @@
+use boojum::{
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{boolean::Boolean, u32::UInt32},
+};
+
+/// Computes the cost of growing heap (or aux heap) from `old_bound` to `new_bound`. EraVM charges
+/// one erg per byte of growth, and there is no cost if `new_bound <= old_bound`.
+///
+/// Returns the cost, and a flag whether the bound actually grows
+pub fn calculate_heap_growth_cost<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    old_bound: UInt32<F>,
+    new_bound: UInt32<F>,
+) -> (UInt32<F>, Boolean<F>) {
+    let (growth, uf) = new_bound.overflowing_sub(cs, old_bound);
+    // if we access in bounds then it's 0
+    let cost = growth.mask_negated(cs, uf);
+
+    let cost_is_zero = cost.is_zero(cs);
+    let grows = cost_is_zero.negated(cs);
+
+    (cost, grows)
+}
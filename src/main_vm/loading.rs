@@ -8,12 +8,15 @@ use boojum::{
 };
 
 use super::*;
-use crate::base_structures::{
-    register::VMRegister,
-    vm_state::{
-        callstack::{Callstack, FullExecutionContext},
-        VmLocalState, FULL_SPONGE_QUEUE_STATE_WIDTH, QUEUE_STATE_WIDTH,
+use crate::{
+    base_structures::{
+        register::VMRegister,
+        vm_state::{
+            callstack::{Callstack, FullExecutionContext},
+            VmLocalState, FULL_SPONGE_QUEUE_STATE_WIDTH, QUEUE_STATE_WIDTH,
+        },
     },
+    ethereum_types::U256,
 };
 
 pub fn initial_bootloader_state<
@@ -27,6 +30,7 @@ pub fn initial_bootloader_state<
     decommitment_queue_initial_length: UInt32<F>,
     decommitment_queue_initial_tail: [Num<F>; FULL_SPONGE_QUEUE_STATE_WIDTH],
     initial_rollback_queue_value: [Num<F>; QUEUE_STATE_WIDTH],
+    initial_registers: Option<&[(usize, bool, U256)]>,
     _round_function: &R,
 ) -> VmLocalState<F> {
     // first create the context
@@ -165,5 +169,16 @@ pub fn initial_bootloader_state<
         value: UInt256 { inner: [l0, l1, l2, l3, zero_u32, zero_u32, zero_u32, zero_u32] },
     };
 
+    // test circuits may want to start from some pre-populated registers
+    if let Some(initial_registers) = initial_registers {
+        for (idx, is_pointer, value) in initial_registers.iter().copied() {
+            assert!(idx < bootloaded_state.registers.len());
+            bootloaded_state.registers[idx] = VMRegister {
+                is_pointer: Boolean::allocated_constant(cs, is_pointer),
+                value: UInt256::allocated_constant(cs, value),
+            };
+        }
+    }
+
     bootloaded_state
 }
@@ -16,8 +16,11 @@ use boojum::{
 use super::*;
 use crate::base_structures::vm_state::VmLocalState;
 
+pub mod auth_opcode;
 pub mod cycle;
 pub mod decoded_opcode;
+pub mod fat_pointer;
+pub mod heap_cost;
 pub mod loading;
 pub mod opcode_bitmask;
 pub mod opcodes;
@@ -101,6 +104,7 @@ where
         decommitment_queue_initial_state.length,
         decommitment_queue_initial_state.tail,
         rollback_queue_tail_for_block,
+        None,
         round_function,
     );
 
@@ -25,6 +25,7 @@ use crate::{
         },
     },
     main_vm::{
+        heap_cost::calculate_heap_growth_cost,
         opcodes::call_ret_impl::far_call::log_query::LogQueryWitness,
         state_diffs::MAX_SPONGES_PER_CYCLE,
         witness_oracle::{SynchronizedWitnessOracle, WitnessOracle},
@@ -759,18 +760,17 @@ where
 
     let heap_max_accessed = upper_bound.mask(cs, forwarding_data.use_heap);
     let heap_bound = current_callstack_entry.heap_upper_bound;
-    let (mut heap_growth, uf) = heap_max_accessed.overflowing_sub(cs, heap_bound);
-    heap_growth = heap_growth.mask_negated(cs, uf); // if we access in bounds then it's 0
+    let (heap_growth, heap_grows) = calculate_heap_growth_cost(cs, heap_bound, heap_max_accessed);
     let new_heap_upper_bound =
-        UInt32::conditionally_select(cs, uf, &heap_bound, &heap_max_accessed);
+        UInt32::conditionally_select(cs, heap_grows, &heap_max_accessed, &heap_bound);
     let grow_heap = Boolean::multi_and(cs, &[forwarding_data.use_heap, execute]);
 
     let aux_heap_max_accessed = upper_bound.mask(cs, forwarding_data.use_aux_heap);
     let aux_heap_bound = current_callstack_entry.aux_heap_upper_bound;
-    let (mut aux_heap_growth, uf) = aux_heap_max_accessed.overflowing_sub(cs, aux_heap_bound);
-    aux_heap_growth = aux_heap_growth.mask_negated(cs, uf); // if we access in bounds then it's 0
+    let (aux_heap_growth, aux_heap_grows) =
+        calculate_heap_growth_cost(cs, aux_heap_bound, aux_heap_max_accessed);
     let new_aux_heap_upper_bound =
-        UInt32::conditionally_select(cs, uf, &aux_heap_bound, &aux_heap_max_accessed);
+        UInt32::conditionally_select(cs, aux_heap_grows, &aux_heap_max_accessed, &aux_heap_bound);
     let grow_aux_heap = Boolean::multi_and(cs, &[forwarding_data.use_aux_heap, execute]);
 
     let mut growth_cost = heap_growth.mask(cs, grow_heap);
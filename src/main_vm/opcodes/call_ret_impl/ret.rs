@@ -11,7 +11,10 @@ use crate::{
             saved_context::ExecutionContextRecord, FULL_SPONGE_QUEUE_STATE_WIDTH, QUEUE_STATE_WIDTH,
         },
     },
-    main_vm::witness_oracle::{SynchronizedWitnessOracle, WitnessOracle},
+    main_vm::{
+        heap_cost::calculate_heap_growth_cost,
+        witness_oracle::{SynchronizedWitnessOracle, WitnessOracle},
+    },
 };
 
 pub(crate) struct RetData<F: SmallField> {
@@ -257,14 +260,13 @@ where
 
     let heap_max_accessed = upper_bound.mask(cs, forwarding_data.use_heap);
     let heap_bound = current_callstack_entry.heap_upper_bound;
-    let (mut heap_growth, uf) = heap_max_accessed.overflowing_sub(cs, heap_bound);
-    heap_growth = heap_growth.mask_negated(cs, uf); // of we access in bounds then it's 0
+    let (heap_growth, _) = calculate_heap_growth_cost(cs, heap_bound, heap_max_accessed);
     let grow_heap = Boolean::multi_and(cs, &[forwarding_data.use_heap, execute, is_far_return]);
 
     let aux_heap_max_accessed = upper_bound.mask(cs, forwarding_data.use_aux_heap);
     let aux_heap_bound = current_callstack_entry.aux_heap_upper_bound;
-    let (mut aux_heap_growth, uf) = aux_heap_max_accessed.overflowing_sub(cs, aux_heap_bound);
-    aux_heap_growth = aux_heap_growth.mask_negated(cs, uf); // of we access in bounds then it's 0
+    let (aux_heap_growth, _) =
+        calculate_heap_growth_cost(cs, aux_heap_bound, aux_heap_max_accessed);
     let grow_aux_heap =
         Boolean::multi_and(cs, &[forwarding_data.use_aux_heap, execute, is_far_return]);
 
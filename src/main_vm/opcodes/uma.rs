@@ -19,6 +19,8 @@ use crate::{
         register::VMRegister,
     },
     main_vm::{
+        fat_pointer::validate_fat_pointer,
+        heap_cost::calculate_heap_growth_cost,
         pre_state::MemoryLocation,
         register_input_view::RegisterInputView,
         witness_oracle::{SynchronizedWitnessOracle, WitnessOracle},
@@ -133,11 +135,16 @@ pub(crate) fn apply_uma<
     let not_a_ptr_when_expected =
         Boolean::multi_and(cs, &[should_apply, is_uma_fat_ptr_read, src0_is_integer]);
 
+    // `start + length` is checked when far call forms the pointer, and pointer opcodes can only
+    // shrink it, so only the offset matters here
+    let (offset_is_in_slice, _) = validate_fat_pointer(cs, &common_opcode_state.src0);
+
     let quasi_fat_ptr = QuasiFatPtrInUMA::parse_and_validate(
         cs,
         &common_opcode_state.src0_view,
         not_a_ptr_when_expected,
         is_uma_fat_ptr_read,
+        offset_is_in_slice,
     );
 
     // this one could wrap around, so we account for it. In case if we wrapped we will skip
@@ -150,10 +157,9 @@ pub(crate) fn apply_uma<
         .current_context
         .saved_context
         .heap_upper_bound;
-    let (mut heap_growth, uf) = heap_max_accessed.overflowing_sub(cs, heap_bound);
-    heap_growth = heap_growth.mask_negated(cs, uf); // of we access in bounds then it's 0
+    let (heap_growth, heap_grows) = calculate_heap_growth_cost(cs, heap_bound, heap_max_accessed);
     let new_heap_upper_bound =
-        UInt32::conditionally_select(cs, uf, &heap_bound, &heap_max_accessed);
+        UInt32::conditionally_select(cs, heap_grows, &heap_max_accessed, &heap_bound);
     let grow_heap = Boolean::multi_and(cs, &[access_heap, should_apply]);
 
     let aux_heap_max_accessed = max_accessed.mask(cs, access_aux_heap);
@@ -162,10 +168,10 @@ pub(crate) fn apply_uma<
         .current_context
         .saved_context
         .aux_heap_upper_bound;
-    let (mut aux_heap_growth, uf) = aux_heap_max_accessed.overflowing_sub(cs, aux_heap_bound);
-    aux_heap_growth = aux_heap_growth.mask_negated(cs, uf); // of we access in bounds then it's 0
+    let (aux_heap_growth, aux_heap_grows) =
+        calculate_heap_growth_cost(cs, aux_heap_bound, aux_heap_max_accessed);
     let new_aux_heap_upper_bound =
-        UInt32::conditionally_select(cs, uf, &aux_heap_bound, &aux_heap_max_accessed);
+        UInt32::conditionally_select(cs, aux_heap_grows, &aux_heap_max_accessed, &aux_heap_bound);
     let grow_aux_heap = Boolean::multi_and(cs, &[access_aux_heap, should_apply]);
 
     let mut growth_cost = heap_growth.mask(cs, access_heap);
@@ -1021,6 +1027,7 @@ impl<F: SmallField> QuasiFatPtrInUMA<F> {
         input: &RegisterInputView<F>,
         already_panicked: Boolean<F>,
         is_fat_ptr: Boolean<F>,
+        offset_is_strictly_in_slice: Boolean<F>,
     ) -> Self {
         // we can never address a range [2^32 - 32..2^32] this way, but we don't care because
         // it's impossible to pay for such memory growth
@@ -1037,8 +1044,7 @@ impl<F: SmallField> QuasiFatPtrInUMA<F> {
         // }
 
         // we need to check whether we will or not deref the fat pointer.
-        // we only dereference if offset < length (or offset - length < 0)
-        let (_, offset_is_strictly_in_slice) = offset.overflowing_sub(cs, length);
+        // we only dereference if offset < length, that is checked by `validate_fat_pointer`
         let offset_is_beyond_the_slice = offset_is_strictly_in_slice.negated(cs);
         let skip_if_legitimate_fat_ptr =
             Boolean::multi_and(cs, &[offset_is_beyond_the_slice, is_fat_ptr]);
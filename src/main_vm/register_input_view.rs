@@ -49,4 +49,63 @@ impl<F: SmallField> RegisterInputView<F> {
 
         Self { u8x32_view, u32x8_view: register.value.inner, is_ptr: register.is_pointer }
     }
+
+    pub fn u64x4_view(&self) -> [UInt64<F>; 4] {
+        let [l0, l1, l2, l3, l4, l5, l6, l7] = self.u32x8_view;
+
+        [
+            UInt64 { low: l0, high: l1 },
+            UInt64 { low: l2, high: l3 },
+            UInt64 { low: l4, high: l5 },
+            UInt64 { low: l6, high: l7 },
+        ]
+    }
+}
+
+// NOTE: boojum has no 64-bit integer gadget, and not every u64 fits into the field, so a 64-bit
+// word is kept as a pair of range checked u32 limbs. Building it from the register is just a
+// regrouping and costs no constraints
+#[derive(Derivative, CSAllocatable, WitnessHookable)]
+#[derivative(Clone, Copy, Debug)]
+pub struct UInt64<F: SmallField> {
+    pub low: UInt32<F>,
+    pub high: UInt32<F>,
+}
+
+#[cfg(test)]
+mod tests {
+    use boojum::{
+        gadgets::{traits::witnessable::WitnessHookable, u256::UInt256},
+        worker::Worker,
+    };
+
+    use super::*;
+    use crate::{ethereum_types::U256, test_utils::create_test_cs};
+
+    #[test]
+    fn test_u64x4_view() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let value = U256::from_dec_str(
+            "86844066927987146567678238756515930889952488499230423029593188005934847229952",
+        )
+        .unwrap();
+        let register = VMRegister {
+            is_pointer: Boolean::allocate(cs, false),
+            value: UInt256::allocate(cs, value),
+        };
+        let view = RegisterInputView::from_input_value(cs, &register);
+
+        for (word, expected) in view.u64x4_view().iter().zip(value.0.iter()) {
+            let witness = word.witness_hook(&*cs)().unwrap();
+            let recomposed = (witness.high as u64) << 32 | witness.low as u64;
+            assert_eq!(recomposed, *expected);
+        }
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
 }
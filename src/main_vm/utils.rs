@@ -46,6 +46,19 @@ pub fn mask_into_panic<F: SmallField, CS: ConstraintSystem<F>>(
 pub(crate) const SUB_PC_BITS: usize = 2;
 pub(crate) const SUB_PC_MASK: u16 = (1u16 << SUB_PC_BITS) - 1;
 
+/// Adds two u32 values and saturates into `u32::MAX` on overflow, so exhausted ergs
+/// do not wrap around
+pub fn saturating_add_u32<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    a: UInt32<F>,
+    b: UInt32<F>,
+) -> UInt32<F> {
+    let (sum, of) = a.overflowing_add(cs, b);
+    let u32_max = UInt32::allocated_constant(cs, u32::MAX);
+
+    UInt32::conditionally_select(cs, of, &u32_max, &sum)
+}
+
 pub(crate) fn split_pc<F: SmallField, CS: ConstraintSystem<F>>(
     cs: &mut CS,
     pc: UInt16<F>,
@@ -499,3 +512,37 @@ pub fn may_be_read_memory_for_source_operand<
 
     (as_register, (initial_state, final_state, new_length, should_access))
 }
+
+#[cfg(test)]
+mod tests {
+    use boojum::{gadgets::traits::witnessable::WitnessHookable, worker::Worker};
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    #[test]
+    fn test_saturating_add_u32() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let cases = [
+            (1u32, 2u32),
+            (u32::MAX - 1, 1),
+            (u32::MAX - 1, 2),
+            (u32::MAX, u32::MAX),
+            (1 << 31, 1 << 31),
+        ];
+
+        for (a, b) in cases {
+            let a_var = UInt32::allocate_checked(cs, a);
+            let b_var = UInt32::allocate_checked(cs, b);
+            let result = saturating_add_u32(cs, a_var, b_var);
+            assert_eq!(result.witness_hook(&*cs)().unwrap(), a.saturating_add(b));
+        }
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+}
@@ -0,0 +1,157 @@
+use boojum::{
+    algebraic_props::round_function::AlgebraicRoundFunction,
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{
+        boolean::Boolean,
+        num::Num,
+        traits::{round_function::CircuitRoundFunction, selectable::Selectable},
+    },
+};
+
+use crate::base_structures::vm_state::FULL_SPONGE_QUEUE_STATE_WIDTH;
+
+/// Hashes two children of `N` elements into a parent node. Both of them are absorbed by 8
+/// elements with replacement, and the first `N` elements of the sponge state after the last
+/// permutation are the parent, so nodes of 4 elements take a single permutation
+pub fn merkle_node_hash<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+    const N: usize,
+>(
+    cs: &mut CS,
+    left: &[Num<F>; N],
+    right: &[Num<F>; N],
+    _round_function: &R,
+) -> [Num<F>; N] {
+    assert!(N % 4 == 0 && N <= FULL_SPONGE_QUEUE_STATE_WIDTH);
+
+    let zero_num = Num::zero(cs);
+
+    let mut input = Vec::with_capacity(N * 2);
+    input.extend_from_slice(left);
+    input.extend_from_slice(right);
+
+    let mut state = [zero_num; FULL_SPONGE_QUEUE_STATE_WIDTH];
+    for chunk in input.array_chunks::<8>() {
+        let mut state_to_keep = [zero_num; 4];
+        state_to_keep.copy_from_slice(&state[8..]);
+        state = R::absorb_with_replacement_over_nums(cs, *chunk, state_to_keep);
+        state = R::compute_round_function_over_nums(cs, state);
+    }
+
+    let mut node = [zero_num; N];
+    node.copy_from_slice(&state[..N]);
+
+    node
+}
+
+/// Recomputes the root from `leaf` and its siblings in `path`. `side[i]` is set if the current
+/// node is the right child at level `i`, counting from the leaves
+pub fn merkle_root_from_path<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+    const N: usize,
+    const DEPTH: usize,
+>(
+    cs: &mut CS,
+    leaf: &[Num<F>; N],
+    path: &[[Num<F>; N]; DEPTH],
+    side: &[Boolean<F>; DEPTH],
+    round_function: &R,
+) -> [Num<F>; N] {
+    let mut current = *leaf;
+    for (sibling, is_right) in path.iter().zip(side.iter()) {
+        let left = <[Num<F>; N]>::conditionally_select(cs, *is_right, sibling, &current);
+        let right = <[Num<F>; N]>::conditionally_select(cs, *is_right, &current, sibling);
+        current = merkle_node_hash(cs, &left, &right, round_function);
+    }
+
+    current
+}
+
+/// Checks that `leaf` is included into the tree with the given `root`, see
+/// `merkle_root_from_path` for the meaning of `side`
+pub fn verify_merkle_inclusion<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+    const DEPTH: usize,
+>(
+    cs: &mut CS,
+    root: &[Num<F>; FULL_SPONGE_QUEUE_STATE_WIDTH],
+    leaf: &[Num<F>; FULL_SPONGE_QUEUE_STATE_WIDTH],
+    path: &[[Num<F>; FULL_SPONGE_QUEUE_STATE_WIDTH]; DEPTH],
+    side: &[Boolean<F>; DEPTH],
+    round_function: &R,
+) -> Boolean<F> {
+    let current = merkle_root_from_path(cs, leaf, path, side, round_function);
+
+    let equalities: Vec<Boolean<F>> = current
+        .iter()
+        .zip(root.iter())
+        .map(|(a, b)| Num::equals(cs, a, b))
+        .collect();
+
+    Boolean::multi_and(cs, &equalities)
+}
+
+#[cfg(test)]
+mod tests {
+    use boojum::{
+        field::goldilocks::GoldilocksField,
+        gadgets::traits::{allocatable::CSAllocatable, witnessable::WitnessHookable},
+        implementations::poseidon2::Poseidon2Goldilocks,
+        worker::Worker,
+    };
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_merkle_inclusion() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+        let round_function = Poseidon2Goldilocks;
+
+        let leaves: Vec<[Num<F>; FULL_SPONGE_QUEUE_STATE_WIDTH]> = (0..4u64)
+            .map(|leaf_idx| {
+                std::array::from_fn(|i| {
+                    Num::allocate(cs, F::from_u64_unchecked(leaf_idx * 100 + i as u64))
+                })
+            })
+            .collect();
+
+        let node_0 = merkle_node_hash(cs, &leaves[0], &leaves[1], &round_function);
+        let node_1 = merkle_node_hash(cs, &leaves[2], &leaves[3], &round_function);
+        let root = merkle_node_hash(cs, &node_0, &node_1, &round_function);
+
+        let boolean_false = Boolean::allocated_constant(cs, false);
+        let boolean_true = Boolean::allocated_constant(cs, true);
+
+        // leaf 2 is the left child of the right node
+        let path = [leaves[3], node_0];
+        let side = [boolean_false, boolean_true];
+        let is_included =
+            verify_merkle_inclusion(cs, &root, &leaves[2], &path, &side, &round_function);
+        assert!(is_included.witness_hook(&*cs)().unwrap() == true);
+
+        let wrong_side = [boolean_true, boolean_true];
+        let is_included =
+            verify_merkle_inclusion(cs, &root, &leaves[2], &path, &wrong_side, &round_function);
+        assert!(is_included.witness_hook(&*cs)().unwrap() == false);
+
+        let is_included =
+            verify_merkle_inclusion(cs, &root, &leaves[1], &path, &side, &round_function);
+        assert!(is_included.witness_hook(&*cs)().unwrap() == false);
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+}
@@ -0,0 +1,468 @@
+use boojum::{
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{
+        boolean::Boolean, num::Num, tables::ByteSplitTable, traits::selectable::Selectable,
+        u32::UInt32, u8::UInt8,
+    },
+};
+
+use crate::{
+    rlp::decoder::{byte_at, rlp_decode_single_item},
+    tables::{get_typed_table_id, TypedTableId},
+    utils::keccak256_of_bytes,
+};
+
+pub const MPT_KEY_NIBBLES: usize = 64;
+pub const MPT_BRANCH_NUM_ITEMS: usize = 17;
+
+// hex-prefix encoded path of at most 64 nibbles with the flags nibble
+const MPT_MAX_ENCODED_PATH_LEN: usize = 33;
+
+// shorter nodes are inlined into the parent instead of being referenced by hash
+pub const MPT_MIN_HASHED_NODE_LEN: usize = 32;
+
+// flags nibble of the hex-prefix encoding
+const HP_FLAG_EXTENSION_EVEN: u8 = 0;
+const HP_FLAG_EXTENSION_ODD: u8 = 1;
+const HP_FLAG_LEAF_EVEN: u8 = 2;
+const HP_FLAG_LEAF_ODD: u8 = 3;
+
+#[derive(Clone, Copy)]
+struct RlpItem<F: SmallField> {
+    offset: UInt32<F>,
+    length: UInt32<F>,
+    end: UInt32<F>,
+}
+
+fn decode_item<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    node: &[UInt8<F>],
+    start_offset: UInt32<F>,
+) -> RlpItem<F> {
+    let (offset, length, _) = rlp_decode_single_item(cs, node, start_offset);
+    let end = offset.add_no_overflow(cs, length);
+
+    RlpItem { offset, length, end }
+}
+
+fn split_into_nibbles<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    table: &TypedTableId<ByteSplitTable<4>>,
+    byte: &UInt8<F>,
+) -> (UInt8<F>, UInt8<F>) {
+    let [low, high] = table.perform_lookup::<F, CS, 1, 2>(cs, &[byte.get_variable()]);
+
+    unsafe { (UInt8::from_variable_unchecked(high), UInt8::from_variable_unchecked(low)) }
+}
+
+fn key_into_nibbles<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    key: &[UInt8<F>; 32],
+) -> [UInt8<F>; MPT_KEY_NIBBLES] {
+    let table = get_typed_table_id::<ByteSplitTable<4>, F, CS>(cs);
+
+    let mut nibbles = [UInt8::zero(cs); MPT_KEY_NIBBLES];
+    for (dst, byte) in nibbles.array_chunks_mut::<2>().zip(key.iter()) {
+        let (high, low) = split_into_nibbles(cs, &table, byte);
+        *dst = [high, low];
+    }
+
+    nibbles
+}
+
+fn select_nibble<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    nibbles: &[UInt8<F>; MPT_KEY_NIBBLES],
+    depth: UInt32<F>,
+) -> UInt8<F> {
+    let mut result = Num::zero(cs);
+    for (position, nibble) in nibbles.iter().enumerate() {
+        let position = UInt32::allocated_constant(cs, position as u32);
+        let is_selected = UInt32::equals(cs, &depth, &position);
+        result = Num::fma(
+            cs,
+            &Num::from_variable(is_selected.get_variable()),
+            &nibble.into_num(),
+            &F::ONE,
+            &result,
+            &F::ONE,
+        );
+    }
+
+    // at most one position is selected
+    unsafe { UInt8::from_variable_unchecked(result.get_variable()) }
+}
+
+/// Nibble of the key at `depth`, counting from the high nibble of the first byte. Zero if
+/// `depth` is past the end of the key. `ByteSplitTable<4>` must be present in the CS
+pub fn key_nibble<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    key: &[UInt8<F>; 32],
+    depth: UInt32<F>,
+) -> UInt8<F> {
+    let nibbles = key_into_nibbles(cs, key);
+
+    select_nibble(cs, &nibbles, depth)
+}
+
+fn enforce_bytes_equal<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    a: &[UInt8<F>],
+    b: &[UInt8<F>],
+    condition: Boolean<F>,
+) {
+    assert_eq!(a.len(), b.len());
+    for (a, b) in a.iter().zip(b.iter()) {
+        let is_equal = UInt8::equals(cs, a, b);
+        is_equal.conditionally_enforce_true(cs, condition);
+    }
+}
+
+fn read_bytes<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    node: &[UInt8<F>],
+    offset: UInt32<F>,
+    len: usize,
+) -> Vec<UInt8<F>> {
+    (0..len)
+        .map(|i| {
+            let i = UInt32::allocated_constant(cs, i as u32);
+            let offset = offset.add_no_overflow(cs, i);
+            byte_at(cs, node, offset)
+        })
+        .collect()
+}
+
+/// Verifies that `value` is stored under `key` in the trie with `root_hash`. `nodes` is the path
+/// from the root to the leaf, where every node is referenced from its parent by keccak256. Branch,
+/// extension and leaf nodes are recognized in-circuit, but the number of nodes and their lengths
+/// are known at synthesis time. Inlined nodes and values stored in branch nodes are not supported
+pub fn ethereum_mpt_verify_entry_point<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    root_hash: [UInt8<F>; 32],
+    key: [UInt8<F>; 32],
+    value: &[UInt8<F>],
+    nodes: &[Vec<UInt8<F>>],
+) {
+    assert!(!nodes.is_empty());
+    assert!(nodes.iter().all(|el| el.len() >= MPT_MIN_HASHED_NODE_LEN));
+
+    let table = get_typed_table_id::<ByteSplitTable<4>, F, CS>(cs);
+    let boolean_true = Boolean::allocated_constant(cs, true);
+    let zero_u32 = UInt32::zero(cs);
+    let one_u32 = UInt32::allocated_constant(cs, 1);
+    let hash_len = UInt32::allocated_constant(cs, 32);
+    let value_len = UInt32::allocated_constant(cs, value.len() as u32);
+    let key_len_in_nibbles = UInt32::allocated_constant(cs, MPT_KEY_NIBBLES as u32);
+    let hp_flags =
+        [HP_FLAG_EXTENSION_EVEN, HP_FLAG_EXTENSION_ODD, HP_FLAG_LEAF_EVEN, HP_FLAG_LEAF_ODD]
+            .map(|el| UInt8::allocated_constant(cs, el));
+
+    let key_nibbles = key_into_nibbles(cs, &key);
+
+    let mut expected_hash = root_hash.to_vec();
+    let mut depth = zero_u32;
+
+    for (node_idx, node) in nodes.iter().enumerate() {
+        let is_last_node = node_idx + 1 == nodes.len();
+
+        let digest = keccak256_of_bytes(cs, node);
+        enforce_bytes_equal(cs, &digest, &expected_hash, boolean_true);
+
+        let (payload_offset, payload_length, is_list) = rlp_decode_single_item(cs, node, zero_u32);
+        Boolean::enforce_equal(cs, &is_list, &boolean_true);
+        let list_end = payload_offset.add_no_overflow(cs, payload_length);
+
+        // branch has 17 items, extension and leaf have 2
+        let mut items = Vec::with_capacity(MPT_BRANCH_NUM_ITEMS);
+        items.push(decode_item(cs, node, payload_offset));
+        let second_item = decode_item(cs, node, items[0].end);
+        items.push(second_item);
+        let has_two_items = UInt32::equals(cs, &second_item.end, &list_end);
+        let is_branch = has_two_items.negated(cs);
+
+        // the rest is garbage for extension and leaf nodes, but must be decodable
+        for _ in 2..MPT_BRANCH_NUM_ITEMS {
+            let previous_end = items.last().unwrap().end;
+            let offset =
+                UInt32::conditionally_select(cs, is_branch, &previous_end, &payload_offset);
+            items.push(decode_item(cs, node, offset));
+        }
+        let branch_end_is_valid =
+            UInt32::equals(cs, &items[MPT_BRANCH_NUM_ITEMS - 1].end, &list_end);
+        branch_end_is_valid.conditionally_enforce_true(cs, is_branch);
+
+        // child of the branch is selected by the current nibble
+        let nibble = select_nibble(cs, &key_nibbles, depth);
+        let mut child_offset = Num::zero(cs);
+        let mut child_length = Num::zero(cs);
+        for (idx, item) in items[..16].iter().enumerate() {
+            let idx = UInt8::allocated_constant(cs, idx as u8);
+            let is_selected = UInt8::equals(cs, &nibble, &idx);
+            let is_selected = Num::from_variable(is_selected.get_variable());
+            child_offset = Num::fma(
+                cs,
+                &is_selected,
+                &item.offset.into_num(),
+                &F::ONE,
+                &child_offset,
+                &F::ONE,
+            );
+            child_length = Num::fma(
+                cs,
+                &is_selected,
+                &item.length.into_num(),
+                &F::ONE,
+                &child_length,
+                &F::ONE,
+            );
+        }
+        // exactly one of the first 16 items is selected, as the nibble is at most 15
+        let child_offset = unsafe { UInt32::from_variable_unchecked(child_offset.get_variable()) };
+        let child_length = unsafe { UInt32::from_variable_unchecked(child_length.get_variable()) };
+
+        // hex-prefix encoded path of the extension or leaf
+        let path = items[0];
+        let path_is_empty = path.length.is_zero(cs);
+        let path_is_not_empty = path_is_empty.negated(cs);
+        path_is_not_empty.conditionally_enforce_true(cs, has_two_items);
+
+        let encoded_path = read_bytes(cs, node, path.offset, MPT_MAX_ENCODED_PATH_LEN);
+        let (flags, first_nibble) = split_into_nibbles(cs, &table, &encoded_path[0]);
+        let flag_matches = hp_flags.map(|el| UInt8::equals(cs, &flags, &el));
+        let [is_extension_even, is_extension_odd, is_leaf_even, is_leaf_odd] = flag_matches;
+        let flags_are_valid = Boolean::multi_or(cs, &flag_matches);
+        flags_are_valid.conditionally_enforce_true(cs, has_two_items);
+
+        let is_odd = Boolean::multi_or(cs, &[is_extension_odd, is_leaf_odd]);
+        let is_leaf = Boolean::multi_or(cs, &[is_leaf_even, is_leaf_odd]);
+        let is_leaf = Boolean::multi_and(cs, &[is_leaf, has_two_items]);
+        let is_extension = Boolean::multi_or(cs, &[is_extension_even, is_extension_odd]);
+        let is_extension = Boolean::multi_and(cs, &[is_extension, has_two_items]);
+
+        // `2 * length - 2 + is_odd` nibbles
+        let path_nibbles = Num::linear_combination(
+            cs,
+            &[(path.length.get_variable(), F::TWO), (is_odd.get_variable(), F::ONE)],
+        );
+        let path_nibbles = path_nibbles.sub(cs, &Num::allocated_constant(cs, F::TWO));
+        let path_nibbles =
+            Num::conditionally_select(cs, has_two_items, &path_nibbles, &Num::zero(cs));
+        // at most 64 for valid encoding
+        let path_nibbles = unsafe { UInt32::from_variable_unchecked(path_nibbles.get_variable()) };
+
+        // every nibble of the path must match the key, starting from the current depth
+        let check_first_nibble = Boolean::multi_and(cs, &[is_odd, has_two_items]);
+        let nibble_matches = UInt8::equals(cs, &first_nibble, &nibble);
+        nibble_matches.conditionally_enforce_true(cs, check_first_nibble);
+
+        for (byte_idx, byte) in encoded_path.iter().enumerate().skip(1) {
+            let byte_idx_u32 = UInt32::allocated_constant(cs, byte_idx as u32);
+            let (_, is_path_byte) = byte_idx_u32.overflowing_sub(cs, path.length);
+            let should_check = Boolean::multi_and(cs, &[is_path_byte, has_two_items]);
+
+            let (high, low) = split_into_nibbles(cs, &table, byte);
+            for (nibble_idx, nibble) in [high, low].iter().enumerate() {
+                let position =
+                    UInt32::allocated_constant(cs, (2 * (byte_idx - 1) + nibble_idx) as u32);
+                let position = Num::linear_combination(
+                    cs,
+                    &[
+                        (depth.get_variable(), F::ONE),
+                        (position.get_variable(), F::ONE),
+                        (is_odd.get_variable(), F::ONE),
+                    ],
+                );
+                let position = unsafe { UInt32::from_variable_unchecked(position.get_variable()) };
+                let key_nibble = select_nibble(cs, &key_nibbles, position);
+                let nibble_matches = UInt8::equals(cs, nibble, &key_nibble);
+                nibble_matches.conditionally_enforce_true(cs, should_check);
+            }
+        }
+
+        let depth_after_path = depth.add_no_overflow(cs, path_nibbles);
+        let depth_after_branch = depth.add_no_overflow(cs, one_u32);
+
+        if is_last_node {
+            Boolean::enforce_equal(cs, &is_leaf, &boolean_true);
+
+            // leaf consumes the rest of the key
+            let key_is_consumed = UInt32::equals(cs, &depth_after_path, &key_len_in_nibbles);
+            Boolean::enforce_equal(cs, &key_is_consumed, &boolean_true);
+
+            let stored_value = items[1];
+            let value_len_matches = UInt32::equals(cs, &stored_value.length, &value_len);
+            Boolean::enforce_equal(cs, &value_len_matches, &boolean_true);
+            let stored_value = read_bytes(cs, node, stored_value.offset, value.len());
+            enforce_bytes_equal(cs, &stored_value, value, boolean_true);
+        } else {
+            let is_inner_node = Boolean::multi_or(cs, &[is_branch, is_extension]);
+            Boolean::enforce_equal(cs, &is_inner_node, &boolean_true);
+
+            let reference_offset =
+                UInt32::conditionally_select(cs, is_branch, &child_offset, &items[1].offset);
+            let reference_length =
+                UInt32::conditionally_select(cs, is_branch, &child_length, &items[1].length);
+            let is_hash_reference = UInt32::equals(cs, &reference_length, &hash_len);
+            Boolean::enforce_equal(cs, &is_hash_reference, &boolean_true);
+
+            expected_hash = read_bytes(cs, node, reference_offset, 32);
+            depth =
+                UInt32::conditionally_select(cs, is_branch, &depth_after_branch, &depth_after_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use boojum::{
+        field::goldilocks::GoldilocksField,
+        gadgets::traits::{allocatable::CSAllocatable, witnessable::WitnessHookable},
+        worker::Worker,
+    };
+    use zkevm_opcode_defs::sha3::*;
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    fn rlp_encode_length(length: usize, offset: u8) -> Vec<u8> {
+        if length < 56 {
+            vec![offset + length as u8]
+        } else {
+            let length_be: Vec<u8> = length
+                .to_be_bytes()
+                .into_iter()
+                .skip_while(|el| *el == 0)
+                .collect();
+            let mut result = vec![offset + 55 + length_be.len() as u8];
+            result.extend(length_be);
+            result
+        }
+    }
+
+    fn rlp_encode_string(bytes: &[u8]) -> Vec<u8> {
+        if bytes.len() == 1 && bytes[0] < 0x80 {
+            return bytes.to_vec();
+        }
+        let mut result = rlp_encode_length(bytes.len(), 0x80);
+        result.extend_from_slice(bytes);
+        result
+    }
+
+    fn rlp_encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+        let payload = items.concat();
+        let mut result = rlp_encode_length(payload.len(), 0xc0);
+        result.extend(payload);
+        result
+    }
+
+    fn hex_prefix_encode(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+        let is_odd = nibbles.len() % 2 == 1;
+        let flags = 2 * (is_leaf as u8) + is_odd as u8;
+        let (mut result, rest) = if is_odd {
+            (vec![(flags << 4) | nibbles[0]], &nibbles[1..])
+        } else {
+            (vec![flags << 4], nibbles)
+        };
+        result.extend(rest.chunks(2).map(|el| (el[0] << 4) | el[1]));
+        result
+    }
+
+    fn keccak256(bytes: &[u8]) -> Vec<u8> {
+        Keccak256::digest(bytes).to_vec()
+    }
+
+    fn into_nibbles(key: &[u8; 32]) -> Vec<u8> {
+        key.iter().flat_map(|el| [el >> 4, el & 0x0f]).collect()
+    }
+
+    // two keys sharing the first nibble, so the root is an extension over a branch with two
+    // leaves
+    fn build_proof(key: &[u8; 32], other_key: &[u8; 32], value: &[u8]) -> (Vec<u8>, Vec<Vec<u8>>) {
+        let nibbles = into_nibbles(key);
+        let other_nibbles = into_nibbles(other_key);
+        assert_eq!(nibbles[0], other_nibbles[0]);
+        assert!(nibbles[1] != other_nibbles[1]);
+
+        let leaf = rlp_encode_list(&[
+            rlp_encode_string(&hex_prefix_encode(&nibbles[2..], true)),
+            rlp_encode_string(value),
+        ]);
+        let other_leaf = rlp_encode_list(&[
+            rlp_encode_string(&hex_prefix_encode(&other_nibbles[2..], true)),
+            rlp_encode_string(b"other value"),
+        ]);
+
+        let mut branch_items = vec![rlp_encode_string(&[]); MPT_BRANCH_NUM_ITEMS];
+        branch_items[nibbles[1] as usize] = rlp_encode_string(&keccak256(&leaf));
+        branch_items[other_nibbles[1] as usize] = rlp_encode_string(&keccak256(&other_leaf));
+        let branch = rlp_encode_list(&branch_items);
+
+        let extension = rlp_encode_list(&[
+            rlp_encode_string(&hex_prefix_encode(&nibbles[..1], false)),
+            rlp_encode_string(&keccak256(&branch)),
+        ]);
+
+        (keccak256(&extension), vec![extension, branch, leaf])
+    }
+
+    fn verify(root_hash: &[u8], key: &[u8; 32], value: &[u8], nodes: &[Vec<u8>]) -> bool {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let mut allocate = |bytes: &[u8]| -> Vec<UInt8<F>> {
+            bytes.iter().map(|el| UInt8::allocate(cs, *el)).collect()
+        };
+        let root_hash = allocate(root_hash).try_into().unwrap();
+        let key = allocate(key).try_into().unwrap();
+        let value = allocate(value);
+        let nodes: Vec<_> = nodes.iter().map(|el| allocate(el)).collect();
+
+        ethereum_mpt_verify_entry_point(cs, root_hash, key, &value, &nodes);
+
+        let worker = Worker::new();
+        owned_cs.pad_and_shrink();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        owned_cs.check_if_satisfied(&worker)
+    }
+
+    const KEY: [u8; 32] = [0x12; 32];
+    const OTHER_KEY: [u8; 32] = [0x1f; 32];
+    const VALUE: &[u8] = b"value stored under the first key";
+
+    #[test]
+    fn test_key_nibble() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let key = KEY.map(|el| UInt8::<F>::allocate(cs, el));
+        for (depth, expected) in [(0, 1), (1, 2), (63, 2), (64, 0)] {
+            let depth = UInt32::allocate(cs, depth);
+            let nibble = key_nibble(cs, &key, depth);
+            assert_eq!(nibble.witness_hook(&*cs)().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_mpt_verify() {
+        let (root_hash, nodes) = build_proof(&KEY, &OTHER_KEY, VALUE);
+        assert!(verify(&root_hash, &KEY, VALUE, &nodes));
+    }
+
+    #[test]
+    fn test_mpt_verify_wrong_value() {
+        let (root_hash, nodes) = build_proof(&KEY, &OTHER_KEY, VALUE);
+        assert!(verify(&root_hash, &KEY, b"value stored under the other key", &nodes) == false);
+    }
+
+    #[test]
+    fn test_mpt_verify_wrong_key() {
+        let (root_hash, nodes) = build_proof(&KEY, &OTHER_KEY, VALUE);
+        let mut key = KEY;
+        key[31] = 0x13;
+        assert!(verify(&root_hash, &key, VALUE, &nodes) == false);
+    }
+}
@@ -0,0 +1,385 @@
+use std::sync::Arc;
+
+use arrayvec::ArrayVec;
+use boojum::{
+    algebraic_props::round_function::AlgebraicRoundFunction,
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{
+        boolean::Boolean,
+        keccak256::keccak256,
+        num::Num,
+        traits::{
+            allocatable::CSAllocatableExt, round_function::CircuitRoundFunction,
+            witnessable::WitnessHookable,
+        },
+        u16::UInt16,
+        u256::UInt256,
+        u8::UInt8,
+    },
+    pairing::GenericCurveAffine,
+};
+
+use super::*;
+use crate::{
+    base_structures::{log_query::*, memory_query::*, precompile_kind::PrecompileKind},
+    ecrecover::{
+        new_optimized::{convert_uint256_to_field_element_masked, fixed_base_mul},
+        secp256k1::fixed_base_mul_table::get_fixed_base_mul_table_ids,
+        secp256k1_base_field_params, secp256k1_scalar_field_params, Secp256BaseNNFieldParams,
+        Secp256ScalarNNFieldParams, BASE_FIELD_CANONICAL_REPR_LIMBS,
+        SCALAR_FIELD_CANONICAL_REPR_LIMBS,
+    },
+    ethereum_types::U256,
+    fsm_input_output::circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH,
+    gas_meter::NULLIFIER_GAS_COST_PER_CALL,
+    precompile_loop::{boolean_into_u256, precompile_loop_entry_point},
+};
+
+pub mod input;
+pub use self::input::*;
+
+// secret key, token address, amount, salt, public key commitment
+pub const MEMORY_QUERIES_PER_CALL: usize = 4 + 1;
+
+// the address occupies the lowest 5 limbs of its word, so 8 + 5 + 8 + 8 elements are hashed
+pub const NULLIFIER_PREIMAGE_LEN: usize = 29;
+// padded to the whole number of absorptions
+const NULLIFIER_PREIMAGE_PADDED_LEN: usize = 32;
+
+// secret keys are secp256k1 scalars, and the public key is derived with its generator
+use crate::ecrecover::secp256k1::{
+    fq::Fq as Secp256Fq, fr::Fr as Secp256Fr, PointAffine as Secp256Affine,
+};
+
+const EXCEPTION_FLAGS_ARR_LEN: usize = 3;
+
+/// Poseidon2 hash of `secret_key || token_address || amount || salt`, where every value is
+/// given by its little-endian `u32` limbs. The sponge starts from the zero state and absorbs 8
+/// elements at a time with replacement, and the first 4 elements of the final state form the
+/// nullifier, the lowest one taking the lowest 64 bits
+pub fn nullifier_hash<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+>(
+    cs: &mut CS,
+    secret_key: &UInt256<F>,
+    token_address: &UInt256<F>,
+    amount: &UInt256<F>,
+    salt: &UInt256<F>,
+    _round_function: &R,
+) -> UInt256<F> {
+    let zero_num = Num::zero(cs);
+
+    let mut preimage = [zero_num; NULLIFIER_PREIMAGE_PADDED_LEN];
+    let it = secret_key
+        .inner
+        .iter()
+        .chain(token_address.inner[..5].iter())
+        .chain(amount.inner.iter())
+        .chain(salt.inner.iter());
+    for (dst, src) in preimage.iter_mut().zip(it) {
+        *dst = src.into_num();
+    }
+
+    let mut state = [zero_num; 12];
+    for chunk in preimage.array_chunks::<8>() {
+        let mut state_to_keep = [zero_num; 4];
+        state_to_keep.copy_from_slice(&state[8..]);
+        state = R::absorb_with_replacement_over_nums(cs, *chunk, state_to_keep);
+        state = R::compute_round_function_over_nums(cs, state);
+    }
+
+    let zero_u8 = UInt8::zero(cs);
+    let mut le_bytes = [zero_u8; 32];
+    for (dst, src) in le_bytes.array_chunks_mut::<8>().zip(state[..4].iter()) {
+        let bytes = src.constraint_bit_length_as_bytes(cs, 64);
+        dst.copy_from_slice(&bytes[..]);
+    }
+
+    UInt256::from_le_bytes(cs, le_bytes)
+}
+
+/// Derives `secret_key * G` on secp256k1 and checks that `keccak256(pk_x || pk_y)` with both
+/// coordinates taken as 32 bytes big-endian equals `commitment`. Returns whether the secret key
+/// is a valid non-zero scalar, and whether the commitment matches, that is `false` if it is not
+pub(crate) fn verify_public_key_commitment<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    secret_key: &UInt256<F>,
+    commitment: &UInt256<F>,
+    base_field_params: &Arc<Secp256BaseNNFieldParams>,
+    scalar_field_params: &Arc<Secp256ScalarNNFieldParams>,
+) -> (Boolean<F>, Boolean<F>) {
+    let secp_n_u256 = U256([
+        scalar_field_params.modulus_u1024.as_ref().as_words()[0],
+        scalar_field_params.modulus_u1024.as_ref().as_words()[1],
+        scalar_field_params.modulus_u1024.as_ref().as_words()[2],
+        scalar_field_params.modulus_u1024.as_ref().as_words()[3],
+    ]);
+    let secp_n_u256 = UInt256::allocated_constant(cs, secp_n_u256);
+
+    let (_res, is_in_range) = secret_key.overflowing_sub(cs, &secp_n_u256);
+    let secret_key = secret_key.mask(cs, is_in_range);
+    let is_not_in_range = is_in_range.negated(cs);
+
+    let (secret_key_fe, secret_key_is_zero) =
+        convert_uint256_to_field_element_masked(cs, &secret_key, &scalar_field_params);
+
+    let any_exception = Boolean::multi_or(cs, &[is_not_in_range, secret_key_is_zero]);
+
+    let full_table_ids = get_fixed_base_mul_table_ids::<F, CS>(&*cs);
+
+    let mut public_key = fixed_base_mul::<F, CS, Secp256Fr, Secp256Fq, Secp256Affine, _, 17>(
+        cs,
+        secret_key_fe,
+        &base_field_params,
+        SCALAR_FIELD_CANONICAL_REPR_LIMBS,
+        BASE_FIELD_CANONICAL_REPR_LIMBS,
+        &full_table_ids,
+    );
+
+    // the secret key is non-zero and less than the group order (or it's an exception), so the
+    // public key is never the point at infinity
+    let ((pk_x, pk_y), _) = public_key.convert_to_affine_or_default(cs, Secp256Affine::one());
+
+    let zero_u8 = UInt8::zero(cs);
+    let mut bytes_to_hash = [zero_u8; 64];
+    let it = pk_x.limbs[..16]
+        .iter()
+        .rev()
+        .chain(pk_y.limbs[..16].iter().rev());
+
+    for (dst, src) in bytes_to_hash.array_chunks_mut::<2>().zip(it) {
+        let limb = unsafe { UInt16::from_variable_unchecked(*src) };
+        *dst = limb.to_be_bytes(cs);
+    }
+
+    let mut digest_bytes = keccak256(cs, &bytes_to_hash);
+    digest_bytes.reverse();
+    let digest = UInt256::from_le_bytes(cs, digest_bytes);
+
+    let commitment_matches = UInt256::equals(cs, &digest, commitment);
+
+    if crate::config::CIRCUIT_VERSOBE {
+        dbg!(digest.witness_hook(cs)());
+        dbg!(commitment_matches.witness_hook(cs)());
+    }
+
+    let commitment_matches = commitment_matches.mask_negated(cs, any_exception);
+    let all_ok = any_exception.negated(cs);
+
+    (all_ok, commitment_matches)
+}
+
+/// Reads `(secret_key, token_address, amount, salt, public_key_commitment)` and writes the
+/// success flag and the nullifier. A call succeeds if the secret key is a valid secp256k1
+/// scalar, the address fits into 160 bits and the commitment matches the public key derived
+/// from the secret key. The nullifier is zero for failed calls
+pub fn nullifier_function_entry_point<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+>(
+    cs: &mut CS,
+    witness: NullifierCircuitInstanceWitness<F>,
+    round_function: &R,
+    limit: usize,
+) -> [Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH]
+where
+    [(); <LogQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <MemoryQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN + 1]:,
+{
+    let NullifierCircuitInstanceWitness {
+        closed_form_input,
+        requests_queue_witness,
+        memory_reads_witness,
+    } = witness;
+
+    let scalar_params = Arc::new(secp256k1_scalar_field_params());
+    let base_params = Arc::new(secp256k1_base_field_params());
+
+    precompile_loop_entry_point(
+        cs,
+        closed_form_input,
+        requests_queue_witness,
+        memory_reads_witness,
+        PrecompileKind::Nullifier,
+        NULLIFIER_GAS_COST_PER_CALL,
+        round_function,
+        limit,
+        |cs, read_values| {
+            let [secret_key, token_address, amount, salt, public_key_commitment] = read_values;
+
+            let mut exception_flags = ArrayVec::<_, EXCEPTION_FLAGS_ARR_LEN>::new();
+            let address_is_short = Boolean::multi_and(
+                cs,
+                &[
+                    token_address.inner[5].is_zero(cs),
+                    token_address.inner[6].is_zero(cs),
+                    token_address.inner[7].is_zero(cs),
+                ],
+            );
+            let address_is_not_short = address_is_short.negated(cs);
+            exception_flags.push(address_is_not_short);
+
+            let (key_is_valid, commitment_matches) = verify_public_key_commitment(
+                cs,
+                &secret_key,
+                &public_key_commitment,
+                &base_params,
+                &scalar_params,
+            );
+            let key_is_invalid = key_is_valid.negated(cs);
+            exception_flags.push(key_is_invalid);
+            let commitment_does_not_match = commitment_matches.negated(cs);
+            exception_flags.push(commitment_does_not_match);
+
+            debug_assert_eq!(exception_flags.len(), EXCEPTION_FLAGS_ARR_LEN);
+            let any_exception = Boolean::multi_or(cs, &exception_flags[..]);
+            let success = any_exception.negated(cs);
+
+            let nullifier =
+                nullifier_hash(cs, &secret_key, &token_address, &amount, &salt, round_function);
+            let nullifier = nullifier.mask(cs, success);
+
+            (success, [boolean_into_u256(cs, success), nullifier])
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use boojum::{
+        algebraic_props::round_function::{absorb_into_state_vararg, AbsorptionModeOverwrite},
+        field::goldilocks::GoldilocksField,
+        gadgets::traits::allocatable::CSAllocatable,
+        implementations::poseidon2::Poseidon2Goldilocks,
+        pairing::{
+            ff::{PrimeField, PrimeFieldRepr},
+            GenericCurveProjective,
+        },
+        worker::Worker,
+    };
+    use rand::{Rng, SeedableRng, XorShiftRng};
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+    type R = Poseidon2Goldilocks;
+
+    fn repr_into_u256<T: PrimeFieldRepr>(repr: T) -> U256 {
+        let mut u256 = U256::zero();
+        u256.0.copy_from_slice(&repr.as_ref()[..4]);
+
+        u256
+    }
+
+    fn native_nullifier(secret_key: U256, token_address: U256, amount: U256, salt: U256) -> U256 {
+        let limbs = |value: U256, num_limbs: usize| -> Vec<F> {
+            (0..num_limbs)
+                .map(|i| F::from_u64_unchecked((value >> (32 * i)).low_u32() as u64))
+                .collect()
+        };
+
+        let mut preimage = limbs(secret_key, 8);
+        preimage.extend(limbs(token_address, 5));
+        preimage.extend(limbs(amount, 8));
+        preimage.extend(limbs(salt, 8));
+        assert_eq!(preimage.len(), NULLIFIER_PREIMAGE_LEN);
+
+        let digest =
+            absorb_into_state_vararg::<F, R, AbsorptionModeOverwrite, 8, 12, 4, 4>(&preimage);
+
+        U256(digest.map(|el| el.as_u64_reduced()))
+    }
+
+    #[test]
+    fn test_nullifier_hash() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+        let round_function = Poseidon2Goldilocks;
+
+        let secret_key = U256::from_str_radix(
+            "52a1d0e17b5a6c1f64f1ab2e72b0a6b1d4c3cf5e1a0e9f3b4d6c7a8e9f0a1b2c",
+            16,
+        )
+        .unwrap();
+        let token_address =
+            U256::from_str_radix("5aeda56215b167893e80b4fe645ba6d5bab767de", 16).unwrap();
+        let amount = U256::from(1_000_000_000_000_000_000u64);
+        let salt = U256::from(0x0123_4567_89ab_cdefu64);
+
+        let [secret_key_u256, token_address_u256, amount_u256, salt_u256] =
+            [secret_key, token_address, amount, salt].map(|el| UInt256::allocate(cs, el));
+        let nullifier = nullifier_hash(
+            cs,
+            &secret_key_u256,
+            &token_address_u256,
+            &amount_u256,
+            &salt_u256,
+            &round_function,
+        );
+
+        assert_eq!(
+            nullifier.witness_hook(&*cs)().unwrap(),
+            native_nullifier(secret_key, token_address, amount, salt)
+        );
+
+        let worker = Worker::new();
+        owned_cs.pad_and_shrink();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+
+    #[test]
+    fn test_public_key_commitment() {
+        use zkevm_opcode_defs::sha3::*;
+
+        let mut owned_cs = create_test_cs(1 << 21);
+        let cs = &mut owned_cs;
+
+        let scalar_params = Arc::new(secp256k1_scalar_field_params());
+        let base_params = Arc::new(secp256k1_base_field_params());
+
+        let mut rng = XorShiftRng::from_seed([0x3f1d2a87, 0x9c4e6b10, 0x27d85e43, 0xb1a0f6c9]);
+        let sk: Secp256Fr = rng.gen();
+        let (pk_x, pk_y) = Secp256Affine::one()
+            .mul(sk.into_repr())
+            .into_affine()
+            .into_xy_unchecked();
+
+        let mut hasher = Keccak256::new();
+        for el in [pk_x, pk_y] {
+            let mut buffer = [0u8; 32];
+            repr_into_u256(el.into_repr()).to_big_endian(&mut buffer);
+            hasher.update(&buffer);
+        }
+        let commitment = U256::from_big_endian(hasher.finalize().as_slice());
+        let sk = repr_into_u256(sk.into_repr());
+
+        for (commitment, expect_match) in [(commitment, true), (commitment ^ U256::one(), false)] {
+            let secret_key = UInt256::allocate(cs, sk);
+            let commitment = UInt256::allocate(cs, commitment);
+
+            let (no_error, matches) = verify_public_key_commitment(
+                cs,
+                &secret_key,
+                &commitment,
+                &base_params,
+                &scalar_params,
+            );
+
+            assert!(no_error.witness_hook(&*cs)().unwrap() == true);
+            assert_eq!(matches.witness_hook(&*cs)().unwrap(), expect_match);
+        }
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+}
@@ -0,0 +1,19 @@
+use std::collections::VecDeque;
+
+use boojum::gadgets::queue::*;
+
+use super::*;
+use crate::precompile_loop::input::*;
+
+pub type PedersenHashCircuitFSMInputOutput<F> = PrecompileFunctionFSMInputOutput<F>;
+pub type PedersenHashCircuitInputOutput<F> = PrecompileFunctionCircuitInputOutput<F>;
+pub type PedersenHashCircuitInputOutputWitness<F> = PrecompileFunctionCircuitInputOutputWitness<F>;
+
+#[derive(Derivative, serde::Serialize, serde::Deserialize)]
+#[derivative(Clone, Debug, Default)]
+#[serde(bound = "")]
+pub struct PedersenHashCircuitInstanceWitness<F: SmallField> {
+    pub closed_form_input: PedersenHashCircuitInputOutputWitness<F>,
+    pub requests_queue_witness: CircuitQueueRawWitness<F, LogQuery<F>, 4, LOG_QUERY_PACKED_WIDTH>,
+    pub memory_reads_witness: VecDeque<[U256; MEMORY_QUERIES_PER_CALL]>,
+}
@@ -0,0 +1,498 @@
+use std::sync::Arc;
+
+use arrayvec::ArrayVec;
+use boojum::{
+    algebraic_props::round_function::AlgebraicRoundFunction,
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{
+        boolean::Boolean,
+        non_native_field::implementations::*,
+        num::Num,
+        traits::{
+            allocatable::CSAllocatableExt, round_function::CircuitRoundFunction,
+            selectable::Selectable, witnessable::WitnessHookable,
+        },
+        u256::UInt256,
+        u32::UInt32,
+    },
+    pairing::{
+        bn256::Fr as Bn256Fr,
+        ff::{Field, PrimeField, PrimeFieldRepr, SqrtField},
+    },
+};
+
+use super::*;
+use crate::{
+    base_structures::{log_query::*, memory_query::*, precompile_kind::PrecompileKind},
+    ecrecover::new_optimized::convert_field_element_to_uint256,
+    ethereum_types::U256,
+    fsm_input_output::circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH,
+    gas_meter::PEDERSEN_HASH_GAS_COST_PER_CALL,
+    precompile_loop::precompile_loop_entry_point,
+};
+
+pub mod input;
+pub use self::input::*;
+
+// number of field elements hashed per call
+pub const MEMORY_QUERIES_PER_CALL: usize = 2;
+
+// every element is split into 128-bit halves, that are less than the order of the prime subgroup,
+// and each half has its own generator
+pub const NUM_GENERATORS: usize = MEMORY_QUERIES_PER_CALL * 2;
+const WINDOWS_PER_HALF: usize = 32;
+
+// Baby JubJub is a twisted Edwards curve `a * x^2 + y^2 = 1 + d * x^2 * y^2` defined over the
+// scalar field of BN254. `a` is a square and `d` is not, so the addition law is complete
+const BABY_JUBJUB_A: u64 = 168700;
+const BABY_JUBJUB_D: u64 = 168696;
+
+const GENERATORS_DOMAIN_SEPARATOR: &[u8] = b"zksync_pedersen_hash_baby_jubjub";
+
+type BabyJubjubNNFieldParams = NonNativeFieldOverU16Params<Bn256Fr, 17>;
+type BabyJubjubNNField<F> = NonNativeFieldOverU16<F, Bn256Fr, 17>;
+
+fn baby_jubjub_base_field_params() -> BabyJubjubNNFieldParams {
+    NonNativeFieldOverU16Params::create()
+}
+
+fn repr_into_u256<T: PrimeFieldRepr>(repr: T) -> U256 {
+    let mut u256 = U256::zero();
+    u256.0.copy_from_slice(&repr.as_ref()[..4]);
+
+    u256
+}
+
+fn u256_into_field_element(value: U256) -> Bn256Fr {
+    let modulus = repr_into_u256(Bn256Fr::char());
+
+    Bn256Fr::from_str(&(value % modulus).to_string()).unwrap()
+}
+
+fn native_add(p: &(Bn256Fr, Bn256Fr), q: &(Bn256Fr, Bn256Fr)) -> (Bn256Fr, Bn256Fr) {
+    let a = Bn256Fr::from_str(&BABY_JUBJUB_A.to_string()).unwrap();
+    let d = Bn256Fr::from_str(&BABY_JUBJUB_D.to_string()).unwrap();
+
+    let mut x1x2 = p.0;
+    x1x2.mul_assign(&q.0);
+    let mut y1y2 = p.1;
+    y1y2.mul_assign(&q.1);
+    let mut x1y2 = p.0;
+    x1y2.mul_assign(&q.1);
+    let mut y1x2 = p.1;
+    y1x2.mul_assign(&q.0);
+    let mut dxy = d;
+    dxy.mul_assign(&x1x2);
+    dxy.mul_assign(&y1y2);
+
+    let mut x = x1y2;
+    x.add_assign(&y1x2);
+    let mut x_den = Bn256Fr::one();
+    x_den.add_assign(&dxy);
+    x.mul_assign(&x_den.inverse().unwrap());
+
+    let mut y = a;
+    y.mul_assign(&x1x2);
+    y.negate();
+    y.add_assign(&y1y2);
+    let mut y_den = Bn256Fr::one();
+    y_den.sub_assign(&dxy);
+    y.mul_assign(&y_den.inverse().unwrap());
+
+    (x, y)
+}
+
+/// Derives `num_generators` independent points of the prime order subgroup by try-and-increment:
+/// `x = keccak256(DOMAIN || index || counter)` until `x` belongs to some point, that is then
+/// multiplied by the cofactor 8
+pub fn pedersen_generators(num_generators: usize) -> Vec<(Bn256Fr, Bn256Fr)> {
+    use zkevm_opcode_defs::sha3::*;
+
+    let a = Bn256Fr::from_str(&BABY_JUBJUB_A.to_string()).unwrap();
+    let d = Bn256Fr::from_str(&BABY_JUBJUB_D.to_string()).unwrap();
+    let identity = (Bn256Fr::zero(), Bn256Fr::one());
+
+    let mut generators = Vec::with_capacity(num_generators);
+    for index in 0..num_generators as u32 {
+        let mut counter = 0u32;
+        let generator = loop {
+            let mut hasher = Keccak256::new();
+            hasher.update(GENERATORS_DOMAIN_SEPARATOR);
+            hasher.update(&index.to_be_bytes());
+            hasher.update(&counter.to_be_bytes());
+            let x = u256_into_field_element(U256::from_big_endian(hasher.finalize().as_slice()));
+            counter += 1;
+
+            // y^2 = (1 - a * x^2) / (1 - d * x^2), and the denominator is never zero as `d` is
+            // not a square
+            let mut x_squared = x;
+            x_squared.square();
+            let mut numerator = a;
+            numerator.mul_assign(&x_squared);
+            numerator.negate();
+            numerator.add_assign(&Bn256Fr::one());
+            let mut denominator = d;
+            denominator.mul_assign(&x_squared);
+            denominator.negate();
+            denominator.add_assign(&Bn256Fr::one());
+            numerator.mul_assign(&denominator.inverse().unwrap());
+
+            let Some(y) = numerator.sqrt() else {
+                continue;
+            };
+
+            let mut point = (x, y);
+            for _ in 0..3 {
+                point = native_add(&point, &point);
+            }
+            if point != identity {
+                break point;
+            }
+        };
+
+        generators.push(generator);
+    }
+
+    generators
+}
+
+/// Precomputed multiples `j * 16^w * G` for every 4-bit window `w` of a scalar, and `j` in
+/// `0..16`
+#[derive(Clone, Debug)]
+pub struct FixedBaseWindowTable {
+    pub windows: Vec<[(Bn256Fr, Bn256Fr); 16]>,
+}
+
+impl FixedBaseWindowTable {
+    pub fn new(base: (Bn256Fr, Bn256Fr), num_windows: usize) -> Self {
+        let identity = (Bn256Fr::zero(), Bn256Fr::one());
+
+        let mut windows = Vec::with_capacity(num_windows);
+        let mut window_base = base;
+        for _ in 0..num_windows {
+            let mut multiples = [identity; 16];
+            for j in 1..16 {
+                multiples[j] = native_add(&multiples[j - 1], &window_base);
+            }
+            windows.push(multiples);
+
+            for _ in 0..4 {
+                window_base = native_add(&window_base, &window_base);
+            }
+        }
+
+        Self { windows }
+    }
+}
+
+/// Tables for the low and high halves of every input element, in this order
+pub fn pedersen_generator_tables() -> Vec<FixedBaseWindowTable> {
+    pedersen_generators(NUM_GENERATORS)
+        .into_iter()
+        .map(|generator| FixedBaseWindowTable::new(generator, WINDOWS_PER_HALF))
+        .collect()
+}
+
+/// Point of Baby JubJub in affine coordinates
+#[derive(Derivative)]
+#[derivative(Clone, Debug)]
+pub struct TwistedEdwardsPoint<F: SmallField> {
+    pub x: BabyJubjubNNField<F>,
+    pub y: BabyJubjubNNField<F>,
+}
+
+impl<F: SmallField> Selectable<F> for TwistedEdwardsPoint<F> {
+    fn conditionally_select<CS: ConstraintSystem<F>>(
+        cs: &mut CS,
+        flag: Boolean<F>,
+        a: &Self,
+        b: &Self,
+    ) -> Self {
+        let x = Selectable::conditionally_select(cs, flag, &a.x, &b.x);
+        let y = Selectable::conditionally_select(cs, flag, &a.y, &b.y);
+
+        Self { x, y }
+    }
+}
+
+impl<F: SmallField> TwistedEdwardsPoint<F> {
+    pub fn constant<CS: ConstraintSystem<F>>(
+        cs: &mut CS,
+        point: (Bn256Fr, Bn256Fr),
+        params: &Arc<BabyJubjubNNFieldParams>,
+    ) -> Self {
+        let x = BabyJubjubNNField::allocated_constant(cs, point.0, params);
+        let y = BabyJubjubNNField::allocated_constant(cs, point.1, params);
+
+        Self { x, y }
+    }
+
+    pub fn identity<CS: ConstraintSystem<F>>(
+        cs: &mut CS,
+        params: &Arc<BabyJubjubNNFieldParams>,
+    ) -> Self {
+        Self::constant(cs, (Bn256Fr::zero(), Bn256Fr::one()), params)
+    }
+
+    /// Complete addition, it's well defined for any pair of points on the curve including the
+    /// identity and doubling
+    pub fn add<CS: ConstraintSystem<F>>(&mut self, cs: &mut CS, other: &mut Self) -> Self {
+        let params = self.x.params.clone();
+        let mut a_nn = BabyJubjubNNField::allocated_constant(
+            cs,
+            Bn256Fr::from_str(&BABY_JUBJUB_A.to_string()).unwrap(),
+            &params,
+        );
+        let mut d_nn = BabyJubjubNNField::allocated_constant(
+            cs,
+            Bn256Fr::from_str(&BABY_JUBJUB_D.to_string()).unwrap(),
+            &params,
+        );
+        let mut one_nn = BabyJubjubNNField::allocated_constant(cs, Bn256Fr::one(), &params);
+
+        let mut x1x2 = self.x.mul(cs, &mut other.x);
+        let mut y1y2 = self.y.mul(cs, &mut other.y);
+        let mut x1y2 = self.x.mul(cs, &mut other.y);
+        let mut y1x2 = self.y.mul(cs, &mut other.x);
+        let mut d_x1x2 = d_nn.mul(cs, &mut x1x2);
+        let mut dxy = d_x1x2.mul(cs, &mut y1y2);
+
+        let mut x_numerator = x1y2.add(cs, &mut y1x2);
+        let mut x_denominator = one_nn.add(cs, &mut dxy);
+        let mut a_x1x2 = a_nn.mul(cs, &mut x1x2);
+        let mut y_numerator = y1y2.sub(cs, &mut a_x1x2);
+        let mut y_denominator = one_nn.sub(cs, &mut dxy);
+
+        // denominators are never zero due to completeness
+        let x = x_numerator.div_unchecked(cs, &mut x_denominator);
+        let y = y_numerator.div_unchecked(cs, &mut y_denominator);
+
+        Self { x, y }
+    }
+
+    pub fn double<CS: ConstraintSystem<F>>(&mut self, cs: &mut CS) -> Self {
+        let mut other = self.clone();
+        self.add(cs, &mut other)
+    }
+
+    /// Multiplies the base of `table` by the scalar given as little-endian 32-bit limbs. Every
+    /// 4-bit window selects one of the precomputed multiples, so only one addition per window is
+    /// needed
+    pub fn scalar_mul_fixed_base<CS: ConstraintSystem<F>>(
+        cs: &mut CS,
+        scalar_limbs: &[UInt32<F>],
+        table: &FixedBaseWindowTable,
+        params: &Arc<BabyJubjubNNFieldParams>,
+    ) -> Self {
+        assert!(table.windows.len() >= scalar_limbs.len() * 8);
+
+        let mut acc = Self::identity(cs, params);
+        for (limb, windows) in scalar_limbs.iter().zip(table.windows.array_chunks::<8>()) {
+            let bits = Num::<F>::from_variable(limb.get_variable()).spread_into_bits::<_, 32>(cs);
+            for (window_bits, multiples) in bits.array_chunks::<4>().zip(windows.iter()) {
+                let mut candidates: Vec<_> = multiples
+                    .iter()
+                    .map(|point| Self::constant(cs, *point, params))
+                    .collect();
+                // lowest bit first, so at every level the pair is (even, odd)
+                for bit in window_bits.iter() {
+                    candidates = candidates
+                        .chunks(2)
+                        .map(|pair| Self::conditionally_select(cs, *bit, &pair[1], &pair[0]))
+                        .collect();
+                }
+                let mut selected = candidates.pop().unwrap();
+                acc = acc.add(cs, &mut selected);
+            }
+        }
+
+        acc
+    }
+}
+
+/// Computes the x coordinate of `sum(m_i_low * G_2i + m_i_high * G_2i+1)`, where `m_i` are
+/// elements of the BN254 scalar field and halves are 128 bits each. Returns whether all elements
+/// are in range, and the hash that is zero if they are not
+pub(crate) fn pedersen_hash_function_inner<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    inputs: &[UInt256<F>; MEMORY_QUERIES_PER_CALL],
+    generator_tables: &[FixedBaseWindowTable],
+    params: &Arc<BabyJubjubNNFieldParams>,
+) -> (Boolean<F>, UInt256<F>) {
+    assert_eq!(generator_tables.len(), NUM_GENERATORS);
+
+    let modulus_u256 = U256([
+        params.modulus_u1024.as_ref().as_words()[0],
+        params.modulus_u1024.as_ref().as_words()[1],
+        params.modulus_u1024.as_ref().as_words()[2],
+        params.modulus_u1024.as_ref().as_words()[3],
+    ]);
+    let modulus_u256 = UInt256::allocated_constant(cs, modulus_u256);
+
+    let mut exception_flags = ArrayVec::<_, MEMORY_QUERIES_PER_CALL>::new();
+    let mut acc = TwistedEdwardsPoint::identity(cs, params);
+    for (input, tables) in inputs.iter().zip(generator_tables.array_chunks::<2>()) {
+        let (_res, is_in_range) = input.overflowing_sub(cs, &modulus_u256);
+        let input = input.mask(cs, is_in_range);
+        let is_not_in_range = is_in_range.negated(cs);
+        exception_flags.push(is_not_in_range);
+
+        for (half, table) in input.inner.array_chunks::<4>().zip(tables.iter()) {
+            let mut term = TwistedEdwardsPoint::scalar_mul_fixed_base(cs, half, table, params);
+            acc = acc.add(cs, &mut term);
+        }
+    }
+
+    let any_exception = Boolean::multi_or(cs, &exception_flags[..]);
+
+    acc.x.normalize(cs);
+    let hash = convert_field_element_to_uint256(cs, acc.x);
+
+    if crate::config::CIRCUIT_VERSOBE {
+        dbg!(hash.witness_hook(cs)());
+    }
+
+    let hash = hash.mask_negated(cs, any_exception);
+    let all_ok = any_exception.negated(cs);
+
+    (all_ok, hash)
+}
+
+pub fn pedersen_hash_entry_point<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+>(
+    cs: &mut CS,
+    witness: PedersenHashCircuitInstanceWitness<F>,
+    round_function: &R,
+    limit: usize,
+) -> [Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH]
+where
+    [(); <LogQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <MemoryQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN + 1]:,
+{
+    let PedersenHashCircuitInstanceWitness {
+        closed_form_input,
+        requests_queue_witness,
+        memory_reads_witness,
+    } = witness;
+
+    let params = Arc::new(baby_jubjub_base_field_params());
+    let generator_tables = pedersen_generator_tables();
+
+    precompile_loop_entry_point(
+        cs,
+        closed_form_input,
+        requests_queue_witness,
+        memory_reads_witness,
+        PrecompileKind::PedersenHash,
+        PEDERSEN_HASH_GAS_COST_PER_CALL,
+        round_function,
+        limit,
+        |cs, read_values| {
+            let (success, hash) =
+                pedersen_hash_function_inner(cs, &read_values, &generator_tables, &params);
+
+            (success, [hash])
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use boojum::{
+        field::goldilocks::GoldilocksField, gadgets::traits::allocatable::CSAllocatable,
+        worker::Worker,
+    };
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    fn native_mul(point: &(Bn256Fr, Bn256Fr), scalar: U256) -> (Bn256Fr, Bn256Fr) {
+        let mut result = (Bn256Fr::zero(), Bn256Fr::one());
+        for i in (0..256).rev() {
+            result = native_add(&result, &result);
+            if scalar.bit(i) {
+                result = native_add(&result, point);
+            }
+        }
+
+        result
+    }
+
+    #[test]
+    fn test_generators_are_on_curve() {
+        let a = Bn256Fr::from_str(&BABY_JUBJUB_A.to_string()).unwrap();
+        let d = Bn256Fr::from_str(&BABY_JUBJUB_D.to_string()).unwrap();
+
+        for (x, y) in pedersen_generators(NUM_GENERATORS) {
+            let mut x_squared = x;
+            x_squared.square();
+            let mut y_squared = y;
+            y_squared.square();
+
+            let mut lhs = a;
+            lhs.mul_assign(&x_squared);
+            lhs.add_assign(&y_squared);
+            let mut rhs = d;
+            rhs.mul_assign(&x_squared);
+            rhs.mul_assign(&y_squared);
+            rhs.add_assign(&Bn256Fr::one());
+
+            assert_eq!(lhs, rhs);
+        }
+    }
+
+    #[test]
+    fn test_pedersen_hash() {
+        let mut owned_cs = create_test_cs(1 << 22);
+        let cs = &mut owned_cs;
+
+        let params = Arc::new(baby_jubjub_base_field_params());
+        let generators = pedersen_generators(NUM_GENERATORS);
+        let generator_tables = pedersen_generator_tables();
+
+        let modulus = repr_into_u256(Bn256Fr::char());
+        let inputs = [
+            U256::from_str_radix(
+                "1f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090",
+                16,
+            )
+            .unwrap(),
+            modulus - U256::one(),
+        ];
+
+        let mut expected = (Bn256Fr::zero(), Bn256Fr::one());
+        for (input, pair) in inputs.iter().zip(generators.array_chunks::<2>()) {
+            let low = *input & ((U256::one() << 128) - U256::one());
+            let high = *input >> 128;
+            expected = native_add(&expected, &native_mul(&pair[0], low));
+            expected = native_add(&expected, &native_mul(&pair[1], high));
+        }
+        let expected = repr_into_u256(expected.0.into_repr());
+
+        let inputs_vars = inputs.map(|el| UInt256::allocate(cs, el));
+        let (all_ok, hash) =
+            pedersen_hash_function_inner(cs, &inputs_vars, &generator_tables, &params);
+        assert!(all_ok.witness_hook(&*cs)().unwrap() == true);
+        assert_eq!(hash.witness_hook(&*cs)().unwrap(), expected);
+
+        // elements outside of the field are rejected
+        let out_of_range = [inputs[0], modulus].map(|el| UInt256::allocate(cs, el));
+        let (all_ok, hash) =
+            pedersen_hash_function_inner(cs, &out_of_range, &generator_tables, &params);
+        assert!(all_ok.witness_hook(&*cs)().unwrap() == false);
+        assert_eq!(hash.witness_hook(&*cs)().unwrap(), U256::zero());
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+}
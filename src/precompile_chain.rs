@@ -0,0 +1,220 @@
+use boojum::{
+    algebraic_props::round_function::AlgebraicRoundFunction,
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{
+        num::Num,
+        traits::{
+            allocatable::{CSAllocatable, CSAllocatableExt},
+            round_function::CircuitRoundFunction,
+        },
+        u256::UInt256,
+    },
+};
+
+use crate::{
+    base_structures::{log_query::LogQuery, memory_query::MemoryQuery},
+    ecrecover::{
+        ecrecover_function_entry_point, EcrecoverCircuitFSMInputOutput,
+        EcrecoverCircuitInstanceWitness,
+    },
+    fsm_input_output::circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH,
+};
+
+/// Precompile circuit, that can split a large batch of requests over multiple instances. Every
+/// instance continues from the hidden FSM output of the previous one
+pub trait ChainablePrecompile<F: SmallField> {
+    type FSMState: CSAllocatable<F>;
+    type Witness;
+
+    fn hidden_fsm_output(witness: &Self::Witness) -> <Self::FSMState as CSAllocatable<F>>::Witness;
+
+    /// Makes the instance continue from `previous` instead of starting from scratch. Instances of
+    /// the same batch share the observable input, and the hidden FSM input is the hidden FSM
+    /// output of `previous`, that its circuit compares against the state it computes
+    fn continue_from(witness: &mut Self::Witness, previous: &Self::Witness);
+
+    fn synthesize<
+        CS: ConstraintSystem<F>,
+        R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+    >(
+        cs: &mut CS,
+        witness: Self::Witness,
+        round_function: &R,
+        limit: usize,
+    ) -> [Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH]
+    where
+        [(); <LogQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+        [(); <MemoryQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+        [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+        [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN + 1]:;
+}
+
+pub struct EcrecoverPrecompile;
+
+impl<F: SmallField> ChainablePrecompile<F> for EcrecoverPrecompile {
+    type FSMState = EcrecoverCircuitFSMInputOutput<F>;
+    type Witness = EcrecoverCircuitInstanceWitness<F>;
+
+    fn hidden_fsm_output(witness: &Self::Witness) -> <Self::FSMState as CSAllocatable<F>>::Witness {
+        witness.closed_form_input.hidden_fsm_output.clone()
+    }
+
+    fn continue_from(witness: &mut Self::Witness, previous: &Self::Witness) {
+        witness.closed_form_input.start_flag = false;
+        witness.closed_form_input.observable_input =
+            previous.closed_form_input.observable_input.clone();
+        witness.closed_form_input.hidden_fsm_input = Self::hidden_fsm_output(previous);
+    }
+
+    fn synthesize<
+        CS: ConstraintSystem<F>,
+        R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+    >(
+        cs: &mut CS,
+        witness: Self::Witness,
+        round_function: &R,
+        limit: usize,
+    ) -> [Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH]
+    where
+        [(); <LogQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+        [(); <MemoryQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+        [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+        [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN + 1]:,
+    {
+        ecrecover_function_entry_point(cs, witness, round_function, limit)
+    }
+}
+
+/// Makes every instance but the first one continue from the previous instance
+pub fn thread_fsm_states<F: SmallField, P: ChainablePrecompile<F>>(witnesses: &mut [P::Witness]) {
+    for i in 1..witnesses.len() {
+        let (previous, rest) = witnesses.split_at_mut(i);
+        P::continue_from(&mut rest[0], &previous[i - 1]);
+    }
+}
+
+/// Threads the FSM state through the instances and synthesizes every one of them into its own
+/// constraint system, that is created by `new_cs`. Returns the constraint systems along with the
+/// input commitments in the same order
+pub fn chain_precompile_instances<
+    F: SmallField,
+    P: ChainablePrecompile<F>,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+>(
+    mut witnesses: Vec<P::Witness>,
+    round_function: &R,
+    limit: usize,
+    mut new_cs: impl FnMut() -> CS,
+) -> Vec<(CS, [Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH])>
+where
+    [(); <LogQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <MemoryQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN + 1]:,
+{
+    thread_fsm_states::<F, P>(&mut witnesses);
+
+    witnesses
+        .into_iter()
+        .map(|witness| {
+            let mut cs = new_cs();
+            let commitment = P::synthesize(&mut cs, witness, round_function, limit);
+
+            (cs, commitment)
+        })
+        .collect()
+}
+
+pub fn chain_ecrecover_instances<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+>(
+    witnesses: Vec<EcrecoverCircuitInstanceWitness<F>>,
+    round_function: &R,
+    limit: usize,
+    new_cs: impl FnMut() -> CS,
+) -> Vec<(CS, [Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH])>
+where
+    [(); <LogQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <MemoryQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN + 1]:,
+{
+    chain_precompile_instances::<F, EcrecoverPrecompile, CS, R>(
+        witnesses,
+        round_function,
+        limit,
+        new_cs,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use boojum::{
+        field::goldilocks::GoldilocksField, implementations::poseidon2::Poseidon2Goldilocks,
+        worker::Worker,
+    };
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_thread_fsm_states() {
+        let mut witnesses = vec![EcrecoverCircuitInstanceWitness::<F>::default(); 3];
+        for (i, witness) in witnesses.iter_mut().enumerate() {
+            witness.closed_form_input.start_flag = true;
+            witness
+                .closed_form_input
+                .observable_input
+                .initial_log_queue_state
+                .tail
+                .length = i as u32;
+            witness
+                .closed_form_input
+                .hidden_fsm_output
+                .num_requests_processed = (i as u32 + 1) * 10;
+        }
+
+        thread_fsm_states::<F, EcrecoverPrecompile>(&mut witnesses);
+
+        assert!(witnesses[0].closed_form_input.start_flag);
+        for pair in witnesses.windows(2) {
+            assert!(pair[1].closed_form_input.start_flag == false);
+            assert_eq!(
+                pair[1].closed_form_input.observable_input,
+                witnesses[0].closed_form_input.observable_input
+            );
+            assert_eq!(
+                pair[1].closed_form_input.hidden_fsm_input,
+                pair[0].closed_form_input.hidden_fsm_output
+            );
+        }
+    }
+
+    #[test]
+    fn test_chain_ecrecover_instances() {
+        // every instance finds the queue empty, so it has to reproduce the state it's given
+        let mut witnesses = vec![EcrecoverCircuitInstanceWitness::<F>::default(); 2];
+        for witness in witnesses.iter_mut() {
+            witness.closed_form_input.completion_flag = true;
+        }
+        witnesses[0].closed_form_input.start_flag = true;
+
+        let round_function = Poseidon2Goldilocks;
+        let instances =
+            chain_ecrecover_instances(witnesses, &round_function, 1, || create_test_cs(1 << 20));
+        assert_eq!(instances.len(), 2);
+
+        let worker = Worker::new();
+        for (mut owned_cs, _) in instances.into_iter() {
+            owned_cs.pad_and_shrink();
+            let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+            assert!(owned_cs.check_if_satisfied(&worker));
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use boojum::{
+    cs::Variable,
+    gadgets::{
+        queue::*,
+        traits::{
+            allocatable::{CSAllocatable, CSPlaceholder},
+            auxiliary::PrettyComparison,
+            encodable::CircuitVarLengthEncodable,
+        },
+        u32::UInt32,
+    },
+};
+
+use super::*;
+use crate::base_structures::{precompile_input_outputs::*, vm_state::*};
+
+#[derive(Derivative, CSAllocatable, CSSelectable, CSVarLengthEncodable, WitnessHookable)]
+#[derivative(Clone, Copy, Debug)]
+#[DerivePrettyComparison("true")]
+pub struct PrecompileFunctionFSMInputOutput<F: SmallField> {
+    pub log_queue_state: QueueState<F, QUEUE_STATE_WIDTH>,
+    pub memory_queue_state: QueueState<F, FULL_SPONGE_QUEUE_STATE_WIDTH>,
+    pub num_requests_processed: UInt32<F>,
+    pub num_successful_calls: UInt32<F>,
+    pub gas_used: UInt32<F>,
+}
+
+impl<F: SmallField> CSPlaceholder<F> for PrecompileFunctionFSMInputOutput<F> {
+    fn placeholder<CS: ConstraintSystem<F>>(cs: &mut CS) -> Self {
+        Self {
+            log_queue_state: QueueState::<F, QUEUE_STATE_WIDTH>::placeholder(cs),
+            memory_queue_state: QueueState::<F, FULL_SPONGE_QUEUE_STATE_WIDTH>::placeholder(cs),
+            num_requests_processed: UInt32::zero(cs),
+            num_successful_calls: UInt32::zero(cs),
+            gas_used: UInt32::zero(cs),
+        }
+    }
+}
+
+pub type PrecompileFunctionCircuitInputOutput<F> = ClosedFormInput<
+    F,
+    PrecompileFunctionFSMInputOutput<F>,
+    PrecompileFunctionInputData<F>,
+    PrecompileFunctionOutputData<F>,
+>;
+pub type PrecompileFunctionCircuitInputOutputWitness<F> = ClosedFormInputWitness<
+    F,
+    PrecompileFunctionFSMInputOutput<F>,
+    PrecompileFunctionInputData<F>,
+    PrecompileFunctionOutputData<F>,
+>;
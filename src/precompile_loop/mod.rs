@@ -0,0 +1,320 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, RwLock},
+};
+
+use boojum::{
+    algebraic_props::round_function::AlgebraicRoundFunction,
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{
+        boolean::Boolean,
+        num::Num,
+        queue::{CircuitQueueRawWitness, CircuitQueueWitness, QueueState},
+        traits::{
+            allocatable::{CSAllocatableExt, CSPlaceholder},
+            round_function::CircuitRoundFunction,
+            selectable::Selectable,
+            witnessable::WitnessHookable,
+        },
+        u256::UInt256,
+        u32::UInt32,
+        u8::UInt8,
+    },
+};
+use cs_derive::*;
+use zkevm_opcode_defs::system_params::PRECOMPILE_AUX_BYTE;
+
+use super::*;
+use crate::{
+    base_structures::{
+        log_query::*, memory_query::*, precompile_input_outputs::PrecompileFunctionOutputData,
+        precompile_kind::PrecompileKind,
+    },
+    demux_log_queue::StorageLogQueue,
+    ethereum_types::U256,
+    fsm_input_output::{circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH, *},
+    gas_meter::GasMeter,
+    storage_application::ConditionalWitnessAllocator,
+};
+
+pub mod input;
+pub use self::input::*;
+
+#[derive(Derivative, CSSelectable)]
+#[derivative(Clone, Debug)]
+pub struct PrecompileCallParams<F: SmallField> {
+    pub input_page: UInt32<F>,
+    pub input_offset: UInt32<F>,
+    pub output_page: UInt32<F>,
+    pub output_offset: UInt32<F>,
+}
+
+impl<F: SmallField> PrecompileCallParams<F> {
+    pub fn from_encoding<CS: ConstraintSystem<F>>(_cs: &mut CS, encoding: UInt256<F>) -> Self {
+        let input_offset = encoding.inner[0];
+        let output_offset = encoding.inner[2];
+        let input_page = encoding.inner[4];
+        let output_page = encoding.inner[5];
+
+        let new = Self { input_page, input_offset, output_page, output_offset };
+
+        new
+    }
+}
+
+/// Word with the flag in the lowest bit, as it's written to memory
+pub fn boolean_into_u256<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    flag: Boolean<F>,
+) -> UInt256<F> {
+    let mut result = UInt256::zero(cs);
+    result.inner[0] = unsafe { UInt32::from_variable_unchecked(flag.get_variable()) };
+
+    result
+}
+
+/// Circuit of a precompile that reads `NUM_READS` words from the input range of every call and
+/// writes `NUM_WRITES` words to its output range. Requests to `precompile` are processed until
+/// the queue is empty or `limit` is reached, and `gas_cost_per_call` is charged for every one of
+/// them, failed calls included. `call` maps the words that are read into the success flag and the
+/// words to write, and it's invoked for padding cycles as well
+pub fn precompile_loop_entry_point<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+    const NUM_READS: usize,
+    const NUM_WRITES: usize,
+>(
+    cs: &mut CS,
+    closed_form_input: PrecompileFunctionCircuitInputOutputWitness<F>,
+    requests_queue_witness: CircuitQueueRawWitness<F, LogQuery<F>, 4, LOG_QUERY_PACKED_WIDTH>,
+    memory_reads_witness: VecDeque<[U256; NUM_READS]>,
+    precompile: PrecompileKind,
+    gas_cost_per_call: u32,
+    round_function: &R,
+    limit: usize,
+    mut call: impl FnMut(&mut CS, [UInt256<F>; NUM_READS]) -> (Boolean<F>, [UInt256<F>; NUM_WRITES]),
+) -> [Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH]
+where
+    [(); <LogQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <MemoryQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN + 1]:,
+{
+    assert!(limit <= u32::MAX as usize);
+
+    let memory_reads_witness: VecDeque<_> = memory_reads_witness.into_iter().flatten().collect();
+
+    let precompile_address = precompile.to_address(cs);
+    let aux_byte_for_precompile = UInt8::allocated_constant(cs, PRECOMPILE_AUX_BYTE);
+
+    let mut structured_input =
+        PrecompileFunctionCircuitInputOutput::alloc_ignoring_outputs(cs, closed_form_input.clone());
+    let start_flag = structured_input.start_flag;
+
+    let requests_queue_state_from_input = structured_input.observable_input.initial_log_queue_state;
+
+    // it must be trivial
+    requests_queue_state_from_input.enforce_trivial_head(cs);
+
+    let requests_queue_state_from_fsm = structured_input.hidden_fsm_input.log_queue_state;
+
+    let requests_queue_state = QueueState::conditionally_select(
+        cs,
+        start_flag,
+        &requests_queue_state_from_input,
+        &requests_queue_state_from_fsm,
+    );
+
+    let memory_queue_state_from_input =
+        structured_input.observable_input.initial_memory_queue_state;
+
+    // it must be trivial
+    memory_queue_state_from_input.enforce_trivial_head(cs);
+
+    let memory_queue_state_from_fsm = structured_input.hidden_fsm_input.memory_queue_state;
+
+    let memory_queue_state = QueueState::conditionally_select(
+        cs,
+        start_flag,
+        &memory_queue_state_from_input,
+        &memory_queue_state_from_fsm,
+    );
+
+    let mut requests_queue = StorageLogQueue::<F, R>::from_state(cs, requests_queue_state);
+    let queue_witness = CircuitQueueWitness::from_inner_witness(requests_queue_witness);
+    requests_queue.witness = Arc::new(queue_witness);
+
+    let mut memory_queue = MemoryQueue::<F, R>::from_state(cs, memory_queue_state);
+
+    let one_u32 = UInt32::allocated_constant(cs, 1u32);
+    let zero_u256 = UInt256::zero(cs);
+    let boolean_false = Boolean::allocated_constant(cs, false);
+    let boolean_true = Boolean::allocated_constant(cs, true);
+
+    let read_queries_allocator = ConditionalWitnessAllocator::<F, UInt256<F>> {
+        witness_source: Arc::new(RwLock::new(memory_reads_witness)),
+    };
+
+    let zero_u32 = UInt32::zero(cs);
+    let mut num_requests_processed = UInt32::conditionally_select(
+        cs,
+        start_flag,
+        &zero_u32,
+        &structured_input.hidden_fsm_input.num_requests_processed,
+    );
+    let mut num_successful_calls = UInt32::conditionally_select(
+        cs,
+        start_flag,
+        &zero_u32,
+        &structured_input.hidden_fsm_input.num_successful_calls,
+    );
+    let mut gas_meter = GasMeter::new(UInt32::conditionally_select(
+        cs,
+        start_flag,
+        &zero_u32,
+        &structured_input.hidden_fsm_input.gas_used,
+    ));
+    let gas_cost_per_call = UInt32::allocated_constant(cs, gas_cost_per_call);
+
+    for _cycle in 0..limit {
+        let is_empty = requests_queue.is_empty(cs);
+        let should_process = is_empty.negated(cs);
+        let (request, _) = requests_queue.pop_front(cs, should_process);
+
+        // failed calls are charged too
+        let gas_overflow = gas_meter.charge(cs, gas_cost_per_call, should_process);
+        Boolean::enforce_equal(cs, &gas_overflow, &boolean_false);
+
+        num_requests_processed = num_requests_processed.add_no_overflow(cs, unsafe {
+            UInt32::from_variable_unchecked(should_process.get_variable())
+        });
+
+        let mut precompile_call_params = PrecompileCallParams::from_encoding(cs, request.key);
+
+        let timestamp_to_use_for_read = request.timestamp;
+        let timestamp_to_use_for_write = timestamp_to_use_for_read.add_no_overflow(cs, one_u32);
+
+        Num::conditionally_enforce_equal(
+            cs,
+            should_process,
+            &Num::from_variable(request.aux_byte.get_variable()),
+            &Num::from_variable(aux_byte_for_precompile.get_variable()),
+        );
+        for (a, b) in request
+            .address
+            .inner
+            .iter()
+            .zip(precompile_address.inner.iter())
+        {
+            Num::conditionally_enforce_equal(
+                cs,
+                should_process,
+                &Num::from_variable(a.get_variable()),
+                &Num::from_variable(b.get_variable()),
+            );
+        }
+
+        let mut read_values = [zero_u256; NUM_READS];
+        let mut bias_variable = should_process.get_variable();
+        for dst in read_values.iter_mut() {
+            let read_query_value: UInt256<F> = read_queries_allocator
+                .conditionally_allocate_biased(cs, should_process, bias_variable);
+            bias_variable = read_query_value.inner[0].get_variable();
+
+            *dst = read_query_value;
+
+            let read_query = MemoryQuery {
+                timestamp: timestamp_to_use_for_read,
+                memory_page: precompile_call_params.input_page,
+                index: precompile_call_params.input_offset,
+                rw_flag: boolean_false,
+                is_ptr: boolean_false,
+                value: read_query_value,
+            };
+
+            let _ = memory_queue.push(cs, read_query, should_process);
+
+            precompile_call_params.input_offset = precompile_call_params
+                .input_offset
+                .add_no_overflow(cs, one_u32);
+        }
+
+        let (success, values_to_write) = call(cs, read_values);
+
+        let call_succeeded = Boolean::multi_and(cs, &[success, should_process]);
+        num_successful_calls = num_successful_calls.add_no_overflow(cs, unsafe {
+            UInt32::from_variable_unchecked(call_succeeded.get_variable())
+        });
+
+        for (idx, value) in values_to_write.into_iter().enumerate() {
+            if idx != 0 {
+                precompile_call_params.output_offset = precompile_call_params
+                    .output_offset
+                    .add_no_overflow(cs, one_u32);
+            }
+
+            let write_query = MemoryQuery {
+                timestamp: timestamp_to_use_for_write,
+                memory_page: precompile_call_params.output_page,
+                index: precompile_call_params.output_offset,
+                rw_flag: boolean_true,
+                value,
+                is_ptr: boolean_false,
+            };
+
+            let _ = memory_queue.push(cs, write_query, should_process);
+        }
+    }
+
+    requests_queue.enforce_consistency(cs);
+
+    // form the final state
+    let done = requests_queue.is_empty(cs);
+    structured_input.completion_flag = done;
+    structured_input.observable_output = PrecompileFunctionOutputData::placeholder(cs);
+
+    let final_memory_state = memory_queue.into_state();
+    let final_requets_state = requests_queue.into_state();
+
+    structured_input.observable_output.final_memory_state = QueueState::conditionally_select(
+        cs,
+        structured_input.completion_flag,
+        &final_memory_state,
+        &structured_input.observable_output.final_memory_state,
+    );
+    structured_input.observable_output.num_successful_calls = UInt32::conditionally_select(
+        cs,
+        structured_input.completion_flag,
+        &num_successful_calls,
+        &structured_input.observable_output.num_successful_calls,
+    );
+    structured_input.observable_output.gas_used = UInt32::conditionally_select(
+        cs,
+        structured_input.completion_flag,
+        &gas_meter.gas_used,
+        &structured_input.observable_output.gas_used,
+    );
+
+    structured_input.hidden_fsm_output.log_queue_state = final_requets_state;
+    structured_input.hidden_fsm_output.memory_queue_state = final_memory_state;
+    structured_input.hidden_fsm_output.num_requests_processed = num_requests_processed;
+    structured_input.hidden_fsm_output.num_successful_calls = num_successful_calls;
+    structured_input.hidden_fsm_output.gas_used = gas_meter.gas_used;
+
+    // self-check
+    structured_input.hook_compare_witness(cs, &closed_form_input);
+
+    use boojum::cs::gates::PublicInputGate;
+
+    let compact_form =
+        ClosedFormInputCompactForm::from_full_form(cs, &structured_input, round_function);
+    let input_commitment = commit_variable_length_encodable_item(cs, &compact_form, round_function);
+    for el in input_commitment.iter() {
+        let gate = PublicInputGate::new(el.get_variable());
+        gate.add_to_cs(cs);
+    }
+
+    input_commitment
+}
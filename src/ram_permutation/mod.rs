@@ -379,18 +379,38 @@ mod tests {
         algebraic_props::poseidon2_parameters::Poseidon2GoldilocksExternalMatrix,
         cs::{gates::*, traits::gate::GatePlacementStrategy, CSGeometry, *},
         field::goldilocks::GoldilocksField,
-        gadgets::{tables::*, u256::UInt256},
+        gadgets::{tables::*, traits::allocatable::CSAllocatable, u256::UInt256},
         implementations::poseidon2::Poseidon2Goldilocks,
         worker::Worker,
     };
     use ethereum_types::U256;
 
     use super::*;
+    use crate::base_structures::memory_query::MemoryQueryWitness;
     type F = GoldilocksField;
     type P = GoldilocksField;
 
-    #[test]
-    fn test_ram_permutation_inner() {
+    fn query(
+        timestamp: u32,
+        memory_page: u32,
+        index: u32,
+        rw_flag: bool,
+        value: u64,
+    ) -> MemoryQueryWitness<F> {
+        MemoryQueryWitness {
+            timestamp,
+            memory_page,
+            index,
+            rw_flag,
+            is_ptr: false,
+            value: U256::from(value),
+        }
+    }
+
+    fn check_ram_permutation(
+        unsorted_input: &[MemoryQueryWitness<F>],
+        sorted_input: &[MemoryQueryWitness<F>],
+    ) {
         let geometry = CSGeometry {
             num_columns_under_copy_permutation: 100,
             num_witness_columns: 0,
@@ -480,13 +500,13 @@ mod tests {
 
         let execute = Boolean::allocated_constant(cs, true);
         let mut original_queue = MemoryQueriesQueue::<F, Poseidon2Goldilocks>::empty(cs);
-        let unsorted_input = witness_input_unsorted(cs);
         for el in unsorted_input {
+            let el = MemoryQuery::allocate(cs, el.clone());
             original_queue.push(cs, el, execute);
         }
         let mut sorted_queue = MemoryQueriesQueue::<F, Poseidon2Goldilocks>::empty(cs);
-        let sorted_input = witness_input_sorted(cs);
         for el in sorted_input {
+            let el = MemoryQuery::allocate(cs, el.clone());
             sorted_queue.push(cs, el, execute);
         }
 
@@ -532,80 +552,49 @@ mod tests {
         assert!(owned_cs.check_if_satisfied(&worker));
     }
 
-    fn witness_input_unsorted<CS: ConstraintSystem<F>>(cs: &mut CS) -> Vec<MemoryQuery<F>> {
-        let mut unsorted_querie = vec![];
-        let bool_false = Boolean::allocated_constant(cs, false);
-        let bool_true = Boolean::allocated_constant(cs, true);
-
-        let q = MemoryQuery::<F> {
-            timestamp: UInt32::allocated_constant(cs, 1025),
-            memory_page: UInt32::allocated_constant(cs, 30),
-            index: UInt32::allocated_constant(cs, 0),
-            rw_flag: bool_false,
-            is_ptr: bool_false,
-            value: UInt256::allocated_constant(cs, U256::from_dec_str("1125899906842626").unwrap()),
-        };
-        unsorted_querie.push(q);
-
-        let q = MemoryQuery::<F> {
-            timestamp: UInt32::allocated_constant(cs, 1024),
-            memory_page: UInt32::allocated_constant(cs, 30),
-            index: UInt32::allocated_constant(cs, 0),
-            rw_flag: bool_true,
-            is_ptr: bool_false,
-            value: UInt256::allocated_constant(cs, U256::from_dec_str("1125899906842626").unwrap()),
-        };
-
-        unsorted_querie.push(q);
-
-        let q = MemoryQuery::<F> {
-            timestamp: UInt32::allocated_constant(cs, 0),
-            memory_page: UInt32::allocated_constant(cs, BOOTLOADER_HEAP_PAGE),
-            index: UInt32::allocated_constant(cs, 695),
-            rw_flag: bool_true,
-            is_ptr: bool_false,
-            value: UInt256::allocated_constant(cs, U256::from_dec_str("12345678").unwrap()),
-        };
-        unsorted_querie.push(q);
-
-        unsorted_querie
+    #[test]
+    fn test_ram_permutation_inner() {
+        let unsorted = [
+            query(1025, 30, 0, false, 1125899906842626),
+            query(1024, 30, 0, true, 1125899906842626),
+            query(0, BOOTLOADER_HEAP_PAGE, 695, true, 12345678),
+        ];
+        let sorted = [
+            query(0, BOOTLOADER_HEAP_PAGE, 695, true, 12345678),
+            query(1024, 30, 0, true, 1125899906842626),
+            query(1025, 30, 0, false, 1125899906842626),
+        ];
+
+        check_ram_permutation(&unsorted, &sorted);
     }
 
-    fn witness_input_sorted<CS: ConstraintSystem<F>>(cs: &mut CS) -> Vec<MemoryQuery<F>> {
-        let mut sorted_querie = vec![];
-        let bool_false = Boolean::allocated_constant(cs, false);
-        let bool_true = Boolean::allocated_constant(cs, true);
-
-        let q = MemoryQuery::<F> {
-            timestamp: UInt32::allocated_constant(cs, 0),
-            memory_page: UInt32::allocated_constant(cs, BOOTLOADER_HEAP_PAGE),
-            index: UInt32::allocated_constant(cs, 695),
-            rw_flag: bool_true,
-            is_ptr: bool_false,
-            value: UInt256::allocated_constant(cs, U256::from_dec_str("12345678").unwrap()),
-        };
-        sorted_querie.push(q);
-
-        let q = MemoryQuery::<F> {
-            timestamp: UInt32::allocated_constant(cs, 1024),
-            memory_page: UInt32::allocated_constant(cs, 30),
-            index: UInt32::allocated_constant(cs, 0),
-            rw_flag: bool_true,
-            is_ptr: bool_false,
-            value: UInt256::allocated_constant(cs, U256::from_dec_str("1125899906842626").unwrap()),
-        };
-        sorted_querie.push(q);
-
-        let q = MemoryQuery::<F> {
-            timestamp: UInt32::allocated_constant(cs, 1025),
-            memory_page: UInt32::allocated_constant(cs, 30),
-            index: UInt32::allocated_constant(cs, 0),
-            rw_flag: bool_false,
-            is_ptr: bool_false,
-            value: UInt256::allocated_constant(cs, U256::from_dec_str("1125899906842626").unwrap()),
-        };
-        sorted_querie.push(q);
+    #[test]
+    fn test_ram_permutation_vm_write_precompile_read_vm_read() {
+        // the VM writes a cell, a precompile reads it and then the VM reads it again
+        let unsorted = [
+            query(1024, 30, 7, true, 42),
+            query(1026, 30, 7, false, 42),
+            query(1028, 30, 7, false, 42),
+        ];
+        let sorted = unsorted.clone();
+
+        check_ram_permutation(&unsorted, &sorted);
+    }
 
-        sorted_querie
+    #[test]
+    fn test_ram_permutation_precompile_write_vm_overwrite() {
+        // a precompile writes its output and the VM overwrites and then reads the same cell
+        let unsorted = [
+            query(1026, 30, 7, true, 9),
+            query(1028, 30, 7, false, 9),
+            query(1024, 30, 7, true, 5),
+        ];
+        let sorted = [
+            query(1024, 30, 7, true, 5),
+            query(1026, 30, 7, true, 9),
+            query(1028, 30, 7, false, 9),
+        ];
+
+        check_ram_permutation(&unsorted, &sorted);
     }
 }
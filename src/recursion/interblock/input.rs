@@ -10,6 +10,7 @@ use boojum::{
 };
 
 use super::*;
+use crate::scheduler::block_header::BlockHeaderWitness;
 
 #[derive(Derivative, serde::Serialize, serde::Deserialize)]
 #[derivative(Clone, Debug, Default(bound = ""))]
@@ -21,6 +22,9 @@ pub struct InterblockRecursionCircuitInstanceWitness<
     H: RecursiveTreeHasher<F, Num<F>>,
     EXT: FieldExtension<2, BaseField = F>,
 > {
+    // headers of the blocks whose proofs are being verified, only used by the
+    // `interblock_recursion_entry_point`
+    pub block_headers: VecDeque<BlockHeaderWitness<F>>,
     #[derivative(Debug = "ignore")]
     pub proof_witnesses: VecDeque<Proof<F, H::NonCircuitSimulator, EXT>>,
 }
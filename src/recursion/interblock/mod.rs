@@ -19,11 +19,19 @@ use boojum::{
             allocated_proof::AllocatedProof, allocated_vk::AllocatedVerificationKey,
             circuit_pow::RecursivePoWRunner, recursive_transcript::*, recursive_tree_hasher::*,
         },
-        traits::allocatable::CSAllocatable,
+        traits::{allocatable::CSAllocatable, round_function::CircuitRoundFunction},
     },
 };
 
-use crate::fsm_input_output::circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH;
+use crate::{
+    fsm_input_output::{
+        circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH, commit_with_domain, INTERBLOCK_DOMAIN,
+    },
+    scheduler::{
+        block_header::BlockHeader, scheduler_public_input_from_block_hashes,
+        NUM_SCHEDULER_PUBLIC_INPUTS,
+    },
+};
 
 // performs recursion between "independent" units for FIXED verification key
 
@@ -78,7 +86,7 @@ pub fn interblock_recursion_function<
     transcript_params: TR::TransciptParameters,
     aggregation_params: AGG::Params,
 ) {
-    let InterblockRecursionCircuitInstanceWitness { proof_witnesses } = witness;
+    let InterblockRecursionCircuitInstanceWitness { proof_witnesses, .. } = witness;
     let mut proof_witnesses = proof_witnesses;
 
     // as usual - create verifier for FIXED VK, verify, aggregate inputs, output inputs
@@ -143,3 +151,128 @@ pub fn interblock_recursion_function<
         gate.add_to_cs(cs);
     }
 }
+
+// verifies proofs of two consecutive blocks for FIXED verification key, and ensures that
+// the block that is proven second indeed continues the one that is proven first
+pub fn interblock_recursion_entry_point<
+    F: SmallField,
+    CS: ConstraintSystem<F> + 'static,
+    R: CircuitRoundFunction<F, 8, 12, 4>,
+    H: RecursiveTreeHasher<F, Num<F>>,
+    EXT: FieldExtension<2, BaseField = F>,
+    TR: RecursiveTranscript<
+            F,
+            CompatibleCap = <H::NonCircuitSimulator as TreeHasher<F>>::Output,
+            CircuitReflection = CTR,
+        >,
+    CTR: CircuitTranscript<
+            F,
+            CircuitCompatibleCap = <H as CircuitTreeHasher<F, Num<F>>>::CircuitOutput,
+            TransciptParameters = TR::TransciptParameters,
+        >,
+    POW: RecursivePoWRunner<F>,
+>(
+    cs: &mut CS,
+    witness: InterblockRecursionCircuitInstanceWitness<F, H, EXT>,
+    round_function: &R,
+    config: InterblockRecursionConfig<F, H::NonCircuitSimulator, EXT>,
+    verifier_builder: Box<dyn ErasedBuilderForRecursiveVerifier<F, EXT, CS>>,
+    transcript_params: TR::TransciptParameters,
+) -> [Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH] {
+    let InterblockRecursionCircuitInstanceWitness { block_headers, proof_witnesses } = witness;
+    let mut block_headers = block_headers;
+    let mut proof_witnesses = proof_witnesses;
+
+    let InterblockRecursionConfig { proof_config, verification_key, capacity, .. } = config;
+    assert_eq!(capacity, 2, "we always verify a pair of blocks");
+    assert_eq!(NUM_SCHEDULER_PUBLIC_INPUTS, INPUT_OUTPUT_COMMITMENT_LENGTH);
+
+    // use this and deal with borrow checker
+
+    let r = cs as *mut CS;
+
+    assert_eq!(verification_key.fixed_parameters.parameters, verifier_builder.geometry());
+
+    let fixed_parameters = verification_key.fixed_parameters.clone();
+
+    let verifier = verifier_builder.create_recursive_verifier(cs);
+
+    let cs = unsafe { &mut *r };
+
+    let vk = AllocatedVerificationKey::allocate_constant(cs, verification_key);
+
+    let boolean_true = Boolean::allocated_constant(cs, true);
+
+    let mut headers = Vec::with_capacity(capacity);
+    let mut block_commitments = Vec::with_capacity(capacity);
+
+    for _ in 0..capacity {
+        let header_witness = block_headers
+            .pop_front()
+            .unwrap_or(BlockHeader::placeholder_witness());
+        let header = BlockHeader::allocate(cs, header_witness);
+
+        let proof_witness = proof_witnesses.pop_front();
+
+        let proof = AllocatedProof::allocate_from_witness(
+            cs,
+            proof_witness,
+            &verifier,
+            &fixed_parameters,
+            &proof_config,
+        );
+
+        // verify the proof
+        let (is_valid, public_inputs) = verifier.verify::<H, TR, CTR, POW>(
+            cs,
+            transcript_params.clone(),
+            &proof,
+            &fixed_parameters,
+            &proof_config,
+            &vk,
+        );
+
+        assert_eq!(public_inputs.len(), INPUT_OUTPUT_COMMITMENT_LENGTH);
+        assert_eq!(public_inputs.len(), fixed_parameters.num_public_inputs());
+
+        Boolean::enforce_equal(cs, &is_valid, &boolean_true);
+
+        // public input of the block proof must open to the header we use
+        let expected_public_input = scheduler_public_input_from_block_hashes(
+            cs,
+            header.previous_block_content_hash,
+            header.new_block_content_hash,
+        );
+        for (a, b) in expected_public_input.iter().zip(public_inputs.iter()) {
+            Num::enforce_equal(cs, a, b);
+        }
+
+        let mut block_commitment = [public_inputs[0]; INPUT_OUTPUT_COMMITMENT_LENGTH];
+        block_commitment.copy_from_slice(&public_inputs);
+
+        headers.push(header);
+        block_commitments.push(block_commitment);
+    }
+
+    // state after the previous block is the state before the current one
+    let [prev_block_header, curr_block_header] = [headers[0], headers[1]];
+    for (a, b) in prev_block_header
+        .new_block_content_hash
+        .iter()
+        .zip(curr_block_header.previous_block_content_hash.iter())
+    {
+        Num::enforce_equal(cs, &a.into_num(), &b.into_num());
+    }
+
+    let prev_block_commitment = block_commitments[0];
+    let curr_block_commitment = block_commitments[1];
+
+    let output_commitment: [_; INPUT_OUTPUT_COMMITMENT_LENGTH] = commit_with_domain(
+        cs,
+        &[prev_block_commitment, curr_block_commitment],
+        round_function,
+        INTERBLOCK_DOMAIN,
+    );
+
+    output_commitment
+}
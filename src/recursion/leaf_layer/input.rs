@@ -13,6 +13,7 @@ use boojum::{
             allocatable::*, auxiliary::PrettyComparison, encodable::CircuitVarLengthEncodable,
             selectable::Selectable, witnessable::WitnessHookable,
         },
+        u32::UInt32,
     },
     serde_utils::BigArraySerde,
 };
@@ -28,6 +29,7 @@ pub struct RecursionLeafParameters<F: SmallField> {
     pub circuit_type: Num<F>,
     pub basic_circuit_vk_commitment: [Num<F>; VK_COMMITMENT_LENGTH],
     pub leaf_layer_vk_commitment: [Num<F>; VK_COMMITMENT_LENGTH],
+    pub circuit_version: UInt32<F>,
 }
 
 impl<F: SmallField> CSPlaceholder<F> for RecursionLeafParameters<F> {
@@ -37,6 +39,7 @@ impl<F: SmallField> CSPlaceholder<F> for RecursionLeafParameters<F> {
             circuit_type: zero,
             basic_circuit_vk_commitment: [zero; VK_COMMITMENT_LENGTH],
             leaf_layer_vk_commitment: [zero; VK_COMMITMENT_LENGTH],
+            circuit_version: UInt32::zero(cs),
         }
     }
 }
@@ -53,8 +56,23 @@ impl<F: SmallField> RecursionLeafParameters<F> {
         let leaf_layer_vk_commitment = value
             .leaf_layer_vk_commitment
             .map(|el| Num::allocated_constant(cs, el));
+        let circuit_version = UInt32::allocated_constant(cs, value.circuit_version);
 
-        Self { circuit_type, basic_circuit_vk_commitment, leaf_layer_vk_commitment }
+        Self {
+            circuit_type,
+            basic_circuit_vk_commitment,
+            leaf_layer_vk_commitment,
+            circuit_version,
+        }
+    }
+
+    /// Enforces that these parameters point to the expected leaf layer verification key
+    pub fn verify_consistency<CS: ConstraintSystem<F>>(
+        &self,
+        cs: &mut CS,
+        expected_leaf_vk_hash: &[Num<F>; VK_COMMITMENT_LENGTH],
+    ) {
+        enforce_vk_commitment_equal(cs, &self.leaf_layer_vk_commitment, expected_leaf_vk_hash);
     }
 }
 
@@ -64,6 +82,7 @@ impl<F: SmallField> RecursionLeafParameters<F> {
 pub struct RecursionLeafInput<F: SmallField> {
     pub params: RecursionLeafParameters<F>,
     pub queue_state: QueueState<F, FULL_SPONGE_QUEUE_STATE_WIDTH>,
+    pub depth: UInt32<F>,
 }
 
 impl<F: SmallField> CSPlaceholder<F> for RecursionLeafInput<F> {
@@ -71,6 +90,7 @@ impl<F: SmallField> CSPlaceholder<F> for RecursionLeafInput<F> {
         Self {
             params: RecursionLeafParameters::placeholder(cs),
             queue_state: QueueState::<F, FULL_SPONGE_QUEUE_STATE_WIDTH>::placeholder(cs),
+            depth: UInt32::zero(cs),
         }
     }
 }
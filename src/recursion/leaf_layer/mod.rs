@@ -19,6 +19,7 @@ use boojum::{
             allocatable::{CSAllocatable, CSAllocatableExt},
             round_function::CircuitRoundFunction,
         },
+        u32::UInt32,
     },
 };
 
@@ -27,6 +28,7 @@ use crate::{
     base_structures::recursion_query::{RecursionQuery, RecursionQueue},
     fsm_input_output::{
         circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH, commit_variable_length_encodable_item,
+        commit_with_domain, LEAF_DOMAIN,
     },
 };
 
@@ -91,13 +93,19 @@ where
         witness;
 
     let input = RecursionLeafInput::allocate(cs, input);
-    let RecursionLeafInput { params, queue_state } = input;
+    let RecursionLeafInput { params, queue_state, depth } = input;
+
+    // leafs are always at the bottom of the recursion tree
+    let one_u32 = UInt32::allocated_constant(cs, 1);
+    Num::enforce_equal(cs, &depth.into_num(), &one_u32.into_num());
+
     let mut queue = RecursionQueue::<F, R>::from_state(cs, queue_state);
 
     let RecursionLeafParameters {
         circuit_type,
         leaf_layer_vk_commitment: _,
         basic_circuit_vk_commitment,
+        circuit_version,
     } = params;
 
     queue.witness = Arc::new(FullStateCircuitQueueWitness::from_inner_witness(queue_witness));
@@ -151,6 +159,8 @@ where
             &recursive_request.circuit_type,
             &circuit_type,
         );
+        // and that it was produced by the same version of the circuit
+        recursive_request.conditionally_enforce_version(cs, can_pop, circuit_version);
 
         // verify the proof
         let (is_valid, public_inputs) = verifier.verify::<H, TR, CTR, POW>(
@@ -185,7 +195,7 @@ where
     Boolean::enforce_equal(cs, &queue_is_empty, &boolean_true);
 
     let input_commitment: [_; INPUT_OUTPUT_COMMITMENT_LENGTH] =
-        commit_variable_length_encodable_item(cs, &input, round_function);
+        commit_with_domain(cs, &input, round_function, LEAF_DOMAIN);
     // for el in input_commitment.iter() {
     //     let gate = PublicInputGate::new(el.get_variable());
     //     gate.add_to_cs(cs);
@@ -8,3 +8,105 @@ pub mod recursion_tip;
 
 pub const VK_COMMITMENT_LENGTH: usize = 4;
 pub const NUM_BASE_LAYER_CIRCUITS: usize = 16;
+
+use std::collections::VecDeque;
+
+use boojum::{
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{boolean::Boolean, num::Num, traits::selectable::Selectable, u32::UInt32},
+};
+
+/// Updates the running maximum of recursion depths with `candidate` if `should_include` is set
+pub(crate) fn conditionally_update_max_depth<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    current_max: UInt32<F>,
+    candidate: UInt32<F>,
+    should_include: Boolean<F>,
+) -> UInt32<F> {
+    // candidate > current_max iff current_max - candidate underflows
+    let (_, uf) = current_max.overflowing_sub(cs, candidate);
+    let should_update = Boolean::multi_and(cs, &[uf, should_include]);
+
+    UInt32::conditionally_select(cs, should_update, &candidate, &current_max)
+}
+
+/// Checks that a verification key commitment computed in the circuit is the expected one
+pub(crate) fn enforce_vk_commitment_equal<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    const N: usize,
+>(
+    cs: &mut CS,
+    computed: &[Num<F>; N],
+    expected: &[Num<F>; N],
+) {
+    for (a, b) in computed.iter().zip(expected.iter()) {
+        Num::enforce_equal(cs, a, b);
+    }
+}
+
+/// Proofs of empty branches are never checked, but their witness must still be resolvable. Any
+/// proof made for the same verification key has the right shape, so witnesses may omit the
+/// trailing ones and the first proof is copied in their place
+pub(crate) fn pad_proof_witnesses<T: Clone>(
+    proof_witnesses: &mut VecDeque<T>,
+    num_branches: usize,
+) {
+    let Some(dummy_proof) = proof_witnesses.front().cloned() else {
+        return;
+    };
+    while proof_witnesses.len() < num_branches {
+        proof_witnesses.push_back(dummy_proof.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use boojum::{
+        field::goldilocks::GoldilocksField, gadgets::traits::allocatable::CSAllocatable,
+        worker::Worker,
+    };
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    fn synthesize_vk_commitment_check(
+        computed: [u64; VK_COMMITMENT_LENGTH],
+        expected: [u64; VK_COMMITMENT_LENGTH],
+    ) -> bool {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let computed = computed.map(|el| Num::allocate(cs, F::from_u64_unchecked(el)));
+        let expected = expected.map(|el| Num::allocate(cs, F::from_u64_unchecked(el)));
+        enforce_vk_commitment_equal(cs, &computed, &expected);
+
+        let worker = Worker::new();
+        owned_cs.pad_and_shrink();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        owned_cs.check_if_satisfied(&worker)
+    }
+
+    #[test]
+    fn test_enforce_vk_commitment_equal() {
+        assert!(synthesize_vk_commitment_check([1, 2, 3, 4], [1, 2, 3, 4]));
+        assert!(synthesize_vk_commitment_check([1, 2, 3, 4], [1, 2, 5, 4]) == false);
+    }
+
+    #[test]
+    fn test_pad_proof_witnesses() {
+        let mut proof_witnesses = VecDeque::from([7u32, 8]);
+        pad_proof_witnesses(&mut proof_witnesses, 4);
+        assert_eq!(proof_witnesses, VecDeque::from([7, 8, 7, 7]));
+
+        pad_proof_witnesses(&mut proof_witnesses, 3);
+        assert_eq!(proof_witnesses.len(), 4);
+
+        let mut proof_witnesses = VecDeque::<u32>::new();
+        pad_proof_witnesses(&mut proof_witnesses, 4);
+        assert!(proof_witnesses.is_empty());
+    }
+}
@@ -12,6 +12,7 @@ use boojum::{
             allocatable::*, auxiliary::PrettyComparison, encodable::CircuitVarLengthEncodable,
             selectable::Selectable, witnessable::WitnessHookable,
         },
+        u32::UInt32,
     },
     serde_utils::BigArraySerde,
 };
@@ -28,6 +29,7 @@ pub struct RecursionNodeInput<F: SmallField> {
     pub leaf_layer_parameters: [RecursionLeafParameters<F>; NUM_BASE_LAYER_CIRCUITS],
     pub node_layer_vk_commitment: [Num<F>; VK_COMMITMENT_LENGTH],
     pub queue_state: QueueState<F, FULL_SPONGE_QUEUE_STATE_WIDTH>,
+    pub depth: UInt32<F>,
 }
 
 impl<F: SmallField> CSPlaceholder<F> for RecursionNodeInput<F> {
@@ -39,6 +41,7 @@ impl<F: SmallField> CSPlaceholder<F> for RecursionNodeInput<F> {
             leaf_layer_parameters: [leaf_layer_param; NUM_BASE_LAYER_CIRCUITS],
             node_layer_vk_commitment: [zero; VK_COMMITMENT_LENGTH],
             queue_state: QueueState::<F, FULL_SPONGE_QUEUE_STATE_WIDTH>::placeholder(cs),
+            depth: UInt32::zero(cs),
         }
     }
 }
@@ -56,5 +59,7 @@ pub struct RecursionNodeInstanceWitness<
     pub input: RecursionNodeInputWitness<F>,
     pub vk_witness: VerificationKey<F, H::NonCircuitSimulator>,
     pub split_points: VecDeque<QueueTailStateWitness<F, FULL_SPONGE_QUEUE_STATE_WIDTH>>,
+    // depths of the child proofs if the next layer aggregates nodes
+    pub child_depths: VecDeque<u32>,
     pub proof_witnesses: VecDeque<Proof<F, H::NonCircuitSimulator, EXT>>,
 }
@@ -26,6 +26,7 @@ use crate::{
     base_structures::recursion_query::{RecursionQuery, RecursionQueue},
     fsm_input_output::{
         circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH, commit_variable_length_encodable_item,
+        commit_with_domain, LEAF_DOMAIN, NODE_DOMAIN,
     },
 };
 
@@ -89,7 +90,13 @@ pub fn node_layer_recursion_entry_point<
 where
     [(); <RecursionQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
 {
-    let RecursionNodeInstanceWitness { input, vk_witness, split_points, proof_witnesses } = witness;
+    let RecursionNodeInstanceWitness {
+        input,
+        vk_witness,
+        split_points,
+        child_depths,
+        proof_witnesses,
+    } = witness;
 
     let input = RecursionNodeInput::allocate(cs, input);
     let RecursionNodeInput {
@@ -97,6 +104,7 @@ where
         leaf_layer_parameters,
         node_layer_vk_commitment,
         queue_state,
+        depth,
     } = input;
 
     assert_eq!(config.vk_fixed_parameters, vk_witness.fixed_parameters,);
@@ -166,6 +174,10 @@ where
 
     let subqueues = split_queue_state_into_n(cs, queue_state, node_layer_capacity, split_points);
 
+    let mut child_depths = child_depths;
+    let leaf_depth = UInt32::allocated_constant(cs, 1);
+    let mut max_child_depth = UInt32::zero(cs);
+
     let leaf_layer_capacity = UInt32::allocated_constant(cs, leaf_layer_capacity as u32);
     for el in subqueues.iter() {
         // if we aggregate leafs, then we ensure length to be small enough.
@@ -193,6 +205,17 @@ where
         let chunk_is_empty = subqueue.tail.length.is_zero(cs);
         let chunk_is_meaningful = chunk_is_empty.negated(cs);
 
+        // leafs are always at depth 1, and nodes report their depth via input
+        let child_depth_if_node = UInt32::allocate(cs, child_depths.pop_front().unwrap_or(0));
+        let child_depth = UInt32::conditionally_select(
+            cs,
+            next_layer_aggregates_nodes,
+            &child_depth_if_node,
+            &leaf_depth,
+        );
+        max_child_depth =
+            conditionally_update_max_depth(cs, max_child_depth, child_depth, chunk_is_meaningful);
+
         // verify the proof
         let (is_valid, public_inputs) = verifier.verify::<H, TR, CTR, POW>(
             cs,
@@ -212,15 +235,16 @@ where
             leaf_layer_parameters: leaf_layer_parameters,
             node_layer_vk_commitment: node_layer_vk_commitment,
             queue_state: subqueue,
+            depth: child_depth,
         };
         let input_commitment_if_node: [_; INPUT_OUTPUT_COMMITMENT_LENGTH] =
-            commit_variable_length_encodable_item(cs, &next_layer_input_if_node, round_function);
+            commit_with_domain(cs, &next_layer_input_if_node, round_function, NODE_DOMAIN);
 
         use crate::recursion::leaf_layer::input::RecursionLeafInput;
         let next_layer_input_if_leaf =
-            RecursionLeafInput { params: leaf_params, queue_state: subqueue };
+            RecursionLeafInput { params: leaf_params, queue_state: subqueue, depth: child_depth };
         let input_commitment_if_leaf: [_; INPUT_OUTPUT_COMMITMENT_LENGTH] =
-            commit_variable_length_encodable_item(cs, &next_layer_input_if_leaf, round_function);
+            commit_with_domain(cs, &next_layer_input_if_leaf, round_function, LEAF_DOMAIN);
 
         let input_commitment = <[Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH]>::conditionally_select(
             cs,
@@ -235,8 +259,17 @@ where
         }
     }
 
+    // our depth is one more than the deepest subtree below
+    let expected_depth = max_child_depth.add_no_overflow(cs, leaf_depth);
+    Num::conditionally_enforce_equal(
+        cs,
+        is_meaningful,
+        &depth.into_num(),
+        &expected_depth.into_num(),
+    );
+
     let input_commitment: [_; INPUT_OUTPUT_COMMITMENT_LENGTH] =
-        commit_variable_length_encodable_item(cs, &input, round_function);
+        commit_with_domain(cs, &input, round_function, NODE_DOMAIN);
     // for el in input_commitment.iter() {
     //     let gate = PublicInputGate::new(el.get_variable());
     //     gate.add_to_cs(cs);
@@ -12,6 +12,7 @@ use boojum::{
             allocatable::*, auxiliary::PrettyComparison, encodable::CircuitVarLengthEncodable,
             selectable::Selectable, witnessable::WitnessHookable,
         },
+        u32::UInt32,
     },
     serde_utils::BigArraySerde,
 };
@@ -30,11 +31,14 @@ pub struct RecursionTipInput<F: SmallField> {
     pub node_layer_vk_commitment: [Num<F>; VK_COMMITMENT_LENGTH],
     pub branch_circuit_type_set: [Num<F>; RECURSION_TIP_ARITY],
     pub queue_set: [QueueState<F, FULL_SPONGE_QUEUE_STATE_WIDTH>; RECURSION_TIP_ARITY],
+    pub branch_depth_set: [UInt32<F>; RECURSION_TIP_ARITY],
+    pub depth: UInt32<F>,
 }
 
 impl<F: SmallField> CSPlaceholder<F> for RecursionTipInput<F> {
     fn placeholder<CS: ConstraintSystem<F>>(cs: &mut CS) -> Self {
         let zero = Num::zero(cs);
+        let zero_u32 = UInt32::zero(cs);
         let leaf_layer_param = RecursionLeafParameters::placeholder(cs);
         Self {
             leaf_layer_parameters: [leaf_layer_param; NUM_BASE_LAYER_CIRCUITS],
@@ -42,6 +46,8 @@ impl<F: SmallField> CSPlaceholder<F> for RecursionTipInput<F> {
             branch_circuit_type_set: [zero; RECURSION_TIP_ARITY],
             queue_set: [QueueState::<F, FULL_SPONGE_QUEUE_STATE_WIDTH>::placeholder(cs);
                 RECURSION_TIP_ARITY],
+            branch_depth_set: [zero_u32; RECURSION_TIP_ARITY],
+            depth: zero_u32,
         }
     }
 }
@@ -5,6 +5,7 @@ use boojum::{
     cs::{implementations::prover::ProofConfig, traits::cs::ConstraintSystem},
     field::SmallField,
     gadgets::{
+        boolean::Boolean,
         num::Num,
         queue::*,
         recursion::{
@@ -15,21 +16,26 @@ use boojum::{
             allocatable::{CSAllocatable, CSAllocatableExt},
             round_function::CircuitRoundFunction,
         },
+        u32::UInt32,
     },
 };
 
 use super::*;
 use crate::{
-    base_structures::recursion_query::RecursionQuery,
+    base_structures::{recursion_query::RecursionQuery, vm_state::FULL_SPONGE_QUEUE_STATE_WIDTH},
     fsm_input_output::{
         circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH, commit_variable_length_encodable_item,
+        commit_with_domain, NODE_DOMAIN, TIP_DOMAIN,
     },
 };
 
 pub mod input;
 
 use boojum::{
-    cs::{implementations::verifier::VerificationKeyCircuitGeometry, oracle::TreeHasher},
+    cs::{
+        implementations::verifier::{VerificationKey, VerificationKeyCircuitGeometry},
+        oracle::TreeHasher,
+    },
     field::FieldExtension,
     gadgets::recursion::{
         circuit_pow::RecursivePoWRunner, recursive_transcript::CircuitTranscript,
@@ -49,9 +55,114 @@ pub struct RecursionTipConfig<
 > {
     pub proof_config: ProofConfig,
     pub vk_fixed_parameters: VerificationKeyCircuitGeometry,
+    /// If set, every leaf parameters entry of the input must commit to these leaf layer VKs
+    #[serde(default)]
+    pub leaf_layer_vk_commitments: Option<[[F; VK_COMMITMENT_LENGTH]; NUM_BASE_LAYER_CIRCUITS]>,
     pub _marker: std::marker::PhantomData<(F, H, EXT)>,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    MissingProofConfig,
+    MissingVkGeometry,
+    FriLdeFactorMismatch,
+    CapSizeMismatch,
+    VkGeometryMismatch,
+    VkCapLengthMismatch,
+    VerifierGeometryMismatch,
+}
+
+fn validate_proof_config_against_geometry(
+    proof_config: &ProofConfig,
+    vk_fixed_parameters: &VerificationKeyCircuitGeometry,
+) -> Result<(), ConfigError> {
+    if proof_config.fri_lde_factor != vk_fixed_parameters.fri_lde_factor {
+        return Err(ConfigError::FriLdeFactorMismatch);
+    }
+    if proof_config.merkle_tree_cap_size != vk_fixed_parameters.cap_size {
+        return Err(ConfigError::CapSizeMismatch);
+    }
+
+    Ok(())
+}
+
+impl<F: SmallField, H: TreeHasher<F>, EXT: FieldExtension<2, BaseField = F>>
+    RecursionTipConfig<F, H, EXT>
+{
+    /// Checks that the config is consistent by itself and describes the given verification key
+    pub fn validate_against_vk(
+        &self,
+        vk_witness: &VerificationKey<F, H>,
+    ) -> Result<(), ConfigError> {
+        validate_proof_config_against_geometry(&self.proof_config, &self.vk_fixed_parameters)?;
+        if self.vk_fixed_parameters != vk_witness.fixed_parameters {
+            return Err(ConfigError::VkGeometryMismatch);
+        }
+        if vk_witness.setup_merkle_tree_cap.len() != self.vk_fixed_parameters.cap_size {
+            return Err(ConfigError::VkCapLengthMismatch);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Clone, Debug(bound = ""), Default(bound = ""))]
+pub struct RecursionTipConfigBuilder<
+    F: SmallField,
+    H: TreeHasher<F>,
+    EXT: FieldExtension<2, BaseField = F>,
+> {
+    proof_config: Option<ProofConfig>,
+    vk_fixed_parameters: Option<VerificationKeyCircuitGeometry>,
+    leaf_layer_vk_commitments: Option<[[F; VK_COMMITMENT_LENGTH]; NUM_BASE_LAYER_CIRCUITS]>,
+    _marker: std::marker::PhantomData<(F, H, EXT)>,
+}
+
+impl<F: SmallField, H: TreeHasher<F>, EXT: FieldExtension<2, BaseField = F>>
+    RecursionTipConfigBuilder<F, H, EXT>
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_proof_config(mut self, proof_config: ProofConfig) -> Self {
+        self.proof_config = Some(proof_config);
+
+        self
+    }
+
+    pub fn with_vk_geometry(mut self, vk_fixed_parameters: VerificationKeyCircuitGeometry) -> Self {
+        self.vk_fixed_parameters = Some(vk_fixed_parameters);
+
+        self
+    }
+
+    pub fn with_leaf_layer_vk_commitments(
+        mut self,
+        leaf_layer_vk_commitments: [[F; VK_COMMITMENT_LENGTH]; NUM_BASE_LAYER_CIRCUITS],
+    ) -> Self {
+        self.leaf_layer_vk_commitments = Some(leaf_layer_vk_commitments);
+
+        self
+    }
+
+    pub fn build(self) -> Result<RecursionTipConfig<F, H, EXT>, ConfigError> {
+        let proof_config = self.proof_config.ok_or(ConfigError::MissingProofConfig)?;
+        let vk_fixed_parameters = self
+            .vk_fixed_parameters
+            .ok_or(ConfigError::MissingVkGeometry)?;
+        validate_proof_config_against_geometry(&proof_config, &vk_fixed_parameters)?;
+
+        Ok(RecursionTipConfig {
+            proof_config,
+            vk_fixed_parameters,
+            leaf_layer_vk_commitments: self.leaf_layer_vk_commitments,
+            _marker: std::marker::PhantomData,
+        })
+    }
+}
+
 use boojum::cs::traits::circuit::*;
 
 pub fn recursion_tip_entry_point<
@@ -78,45 +189,63 @@ pub fn recursion_tip_entry_point<
     config: RecursionTipConfig<F, H::NonCircuitSimulator, EXT>,
     verifier_builder: Box<dyn ErasedBuilderForRecursiveVerifier<F, EXT, CS>>,
     transcript_params: TR::TransciptParameters,
-) -> [Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH]
+) -> Result<[Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH], ConfigError>
 where
     [(); <RecursionQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
 {
     let RecursionTipInstanceWitness { input, vk_witness, proof_witnesses } = witness;
 
+    // all the configuration checks go first, so an invalid config never touches the CS
+    config.validate_against_vk(&vk_witness)?;
+    if config.vk_fixed_parameters.parameters != verifier_builder.geometry() {
+        return Err(ConfigError::VerifierGeometryMismatch);
+    }
+
     let input = RecursionTipInput::allocate(cs, input);
     let RecursionTipInput {
         node_layer_vk_commitment,
         leaf_layer_parameters,
         branch_circuit_type_set,
         queue_set,
+        branch_depth_set,
+        depth,
     } = input;
 
-    assert_eq!(config.vk_fixed_parameters, vk_witness.fixed_parameters,);
+    if let Some(expected_commitments) = config.leaf_layer_vk_commitments {
+        for (params, expected) in leaf_layer_parameters
+            .iter()
+            .zip(expected_commitments.iter())
+        {
+            let expected = expected.map(|el| Num::allocated_constant(cs, el));
+            params.verify_consistency(cs, &expected);
+        }
+    }
 
+    enforce_branch_types_are_sorted(cs, &branch_circuit_type_set, &queue_set);
+
+    // NOTE: VK is allocated only once and shared by all the branches. We can not skip allocation
+    // for empty branches based on `chunk_is_empty` as the circuit layout must not depend on the
+    // witness, and empty branches already skip proof validity via `chunk_is_meaningful`
     let vk = AllocatedVerificationKey::<F, H>::allocate(cs, vk_witness);
-    assert_eq!(vk.setup_merkle_tree_cap.len(), config.vk_fixed_parameters.cap_size);
     let vk_commitment_computed: [_; VK_COMMITMENT_LENGTH] =
         commit_variable_length_encodable_item(cs, &vk, round_function);
     // self-check that it's indeed NODE
-    for (a, b) in node_layer_vk_commitment
-        .iter()
-        .zip(vk_commitment_computed.iter())
-    {
-        Num::enforce_equal(cs, a, b);
-    }
+    enforce_vk_commitment_equal(cs, &vk_commitment_computed, &node_layer_vk_commitment);
     // from that moment we can just use allocated key to verify below
 
     let RecursionTipConfig { proof_config, vk_fixed_parameters, .. } = config;
 
     let mut proof_witnesses = proof_witnesses;
+    pad_proof_witnesses(&mut proof_witnesses, RECURSION_TIP_ARITY);
 
-    assert_eq!(vk_fixed_parameters.parameters, verifier_builder.geometry());
     let verifier = verifier_builder.create_recursive_verifier(cs);
 
-    for (branch_type, initial_queue) in branch_circuit_type_set
+    let mut max_branch_depth = UInt32::zero(cs);
+
+    for ((branch_type, initial_queue), branch_depth) in branch_circuit_type_set
         .into_iter()
         .zip(queue_set.into_iter())
+        .zip(branch_depth_set.into_iter())
     {
         if crate::config::CIRCUIT_VERSOBE {
             use boojum::gadgets::traits::witnessable::WitnessHookable;
@@ -137,6 +266,9 @@ where
         let chunk_is_empty = initial_queue.tail.length.is_zero(cs);
         let chunk_is_meaningful = chunk_is_empty.negated(cs);
 
+        max_branch_depth =
+            conditionally_update_max_depth(cs, max_branch_depth, branch_depth, chunk_is_meaningful);
+
         // verify the proof
         let (is_valid, public_inputs) = verifier.verify::<H, TR, CTR, POW>(
             cs,
@@ -155,9 +287,10 @@ where
             leaf_layer_parameters: leaf_layer_parameters,
             node_layer_vk_commitment: node_layer_vk_commitment,
             queue_state: initial_queue,
+            depth: branch_depth,
         };
         let input_commitment: [_; INPUT_OUTPUT_COMMITMENT_LENGTH] =
-            commit_variable_length_encodable_item(cs, &input, round_function);
+            commit_with_domain(cs, &input, round_function, NODE_DOMAIN);
 
         assert_eq!(public_inputs.len(), INPUT_OUTPUT_COMMITMENT_LENGTH);
         for (a, b) in input_commitment.iter().zip(public_inputs.into_iter()) {
@@ -165,8 +298,13 @@ where
         }
     }
 
+    // depth is a part of the input, so it's exposed via the input commitment below
+    let one_u32 = UInt32::allocated_constant(cs, 1);
+    let expected_depth = max_branch_depth.add_no_overflow(cs, one_u32);
+    Num::enforce_equal(cs, &depth.into_num(), &expected_depth.into_num());
+
     let input_commitment: [_; INPUT_OUTPUT_COMMITMENT_LENGTH] =
-        commit_variable_length_encodable_item(cs, &input, round_function);
+        commit_with_domain(cs, &input, round_function, TIP_DOMAIN);
     // NOTE: we usually put inputs as fixed places for all recursive circuits, even though for this
     // type we do not have to do it strictly speaking
 
@@ -175,5 +313,97 @@ where
     //     gate.add_to_cs(cs);
     // }
 
-    input_commitment
+    Ok(input_commitment)
+}
+
+/// Branch types must be sorted, so the same circuit type can not be placed into multiple slots.
+/// The scheduler fills the slots with its sequence of circuit types and pads the rest with type 0
+/// and empty queues, so the order is only checked over non-empty slots: every non-empty slot must
+/// have a type strictly greater than the previous non-empty one
+fn enforce_branch_types_are_sorted<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    branch_circuit_type_set: &[Num<F>; RECURSION_TIP_ARITY],
+    queue_set: &[QueueState<F, FULL_SPONGE_QUEUE_STATE_WIDTH>; RECURSION_TIP_ARITY],
+) {
+    let zero_num = Num::zero(cs);
+    let one_num = Num::allocated_constant(cs, F::ONE);
+    let mut previous_type = zero_num;
+    let mut any_previous_is_non_empty = Boolean::allocated_constant(cs, false);
+
+    for (branch_type, queue) in branch_circuit_type_set.iter().zip(queue_set.iter()) {
+        let is_empty = queue.tail.length.is_zero(cs);
+        let is_non_empty = is_empty.negated(cs);
+        let should_check = Boolean::multi_and(cs, &[is_non_empty, any_previous_is_non_empty]);
+
+        // if next <= previous it will underflow and fail the range check
+        let difference = branch_type.sub(cs, &previous_type).sub(cs, &one_num);
+        let difference = Num::conditionally_select(cs, should_check, &difference, &zero_num);
+        let _ = UInt32::from_variable_checked(cs, difference.get_variable());
+
+        previous_type = Num::conditionally_select(cs, is_non_empty, branch_type, &previous_type);
+        any_previous_is_non_empty = any_previous_is_non_empty.or(cs, is_non_empty);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use boojum::{gadgets::u8::UInt8, worker::Worker};
+
+    use super::*;
+    use crate::{
+        scheduler::{auxiliary::BaseLayerCircuitType, SEQUENCE_OF_CIRCUIT_TYPES},
+        test_utils::create_test_cs,
+    };
+
+    fn branch_types_are_sorted(
+        circuit_types: &[BaseLayerCircuitType],
+        queue_lengths: &[u32],
+    ) -> bool {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let zero_num = Num::zero(cs);
+        let mut branch_circuit_type_set = [zero_num; RECURSION_TIP_ARITY];
+        let mut queue_set = [QueueState::empty(cs); RECURSION_TIP_ARITY];
+        for (dst, circuit_type) in branch_circuit_type_set.iter_mut().zip(circuit_types.iter()) {
+            *dst = UInt8::allocated_constant(cs, *circuit_type as u8).into_num();
+        }
+        for (dst, length) in queue_set.iter_mut().zip(queue_lengths.iter()) {
+            dst.tail.length = UInt32::allocate(cs, *length);
+        }
+
+        enforce_branch_types_are_sorted(cs, &branch_circuit_type_set, &queue_set);
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        owned_cs.check_if_satisfied(&worker)
+    }
+
+    fn scheduler_circuit_types() -> Vec<BaseLayerCircuitType> {
+        SEQUENCE_OF_CIRCUIT_TYPES
+            .into_iter()
+            .chain(std::iter::once(BaseLayerCircuitType::EIP4844Repack))
+            .collect()
+    }
+
+    #[test]
+    fn test_scheduler_branch_types_are_sorted() {
+        let circuit_types = scheduler_circuit_types();
+        // some of the base layer circuits may have no instances in the batch
+        let queue_lengths: Vec<u32> = (0..circuit_types.len())
+            .map(|idx| if idx % 3 == 2 { 0 } else { 1 + idx as u32 })
+            .collect();
+
+        assert!(branch_types_are_sorted(&circuit_types, &queue_lengths));
+    }
+
+    #[test]
+    fn test_duplicate_branch_types_are_rejected() {
+        let mut circuit_types = scheduler_circuit_types();
+        circuit_types[2] = circuit_types[1];
+        let queue_lengths = vec![1; circuit_types.len()];
+
+        assert!(!branch_types_are_sorted(&circuit_types, &queue_lengths));
+    }
 }
@@ -0,0 +1,201 @@
+use boojum::{
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{boolean::Boolean, num::Num, traits::selectable::Selectable, u32::UInt32, u8::UInt8},
+};
+
+pub const RLP_SHORT_STRING_PREFIX: u8 = 0x80;
+pub const RLP_LONG_STRING_PREFIX: u8 = 0xb8;
+pub const RLP_SHORT_LIST_PREFIX: u8 = 0xc0;
+pub const RLP_LONG_LIST_PREFIX: u8 = 0xf8;
+
+// longer items can not be addressed with 32-bit offsets anyway
+pub const RLP_MAX_LENGTH_OF_LENGTH: u8 = 4;
+
+// the byte at `offset`, or zero if it's out of bounds
+pub(crate) fn byte_at<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    bytes: &[UInt8<F>],
+    offset: UInt32<F>,
+) -> UInt8<F> {
+    let mut result = Num::zero(cs);
+    for (index, byte) in bytes.iter().enumerate() {
+        let index = UInt32::allocated_constant(cs, index as u32);
+        let is_selected = UInt32::equals(cs, &offset, &index);
+        result = Num::fma(
+            cs,
+            &Num::from_variable(is_selected.get_variable()),
+            &byte.into_num(),
+            &F::ONE,
+            &result,
+            &F::ONE,
+        );
+    }
+
+    // at most one index is selected
+    unsafe { UInt8::from_variable_unchecked(result.get_variable()) }
+}
+
+fn is_below<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    byte: &UInt8<F>,
+    bound: u8,
+) -> Boolean<F> {
+    let bound = UInt8::allocated_constant(cs, bound);
+    let (_, borrow) = byte.overflowing_sub(cs, &bound);
+
+    borrow
+}
+
+fn into_u32<F: SmallField>(byte: UInt8<F>) -> UInt32<F> {
+    unsafe { UInt32::from_variable_unchecked(byte.get_variable()) }
+}
+
+/// Decodes the prefix of the RLP item at `start_offset` and returns the offset of its payload,
+/// the payload length and whether it's a list. Enforces that the payload is within `bytes` and
+/// that the length of a long item takes at most 4 bytes. Non-canonical encodings, like a single
+/// byte below `0x80` encoded as a string, are accepted
+pub fn rlp_decode_single_item<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    bytes: &[UInt8<F>],
+    start_offset: UInt32<F>,
+) -> (UInt32<F>, UInt32<F>, Boolean<F>) {
+    assert!(bytes.len() < u32::MAX as usize);
+
+    let prefix = byte_at(cs, bytes, start_offset);
+
+    let is_single_byte = is_below(cs, &prefix, RLP_SHORT_STRING_PREFIX);
+    let below_long_string = is_below(cs, &prefix, RLP_LONG_STRING_PREFIX);
+    let below_short_list = is_below(cs, &prefix, RLP_SHORT_LIST_PREFIX);
+    let below_long_list = is_below(cs, &prefix, RLP_LONG_LIST_PREFIX);
+
+    let is_list = below_short_list.negated(cs);
+    let is_long_string = Boolean::multi_and(cs, &[below_short_list, below_long_string.negated(cs)]);
+    let is_long_list = below_long_list.negated(cs);
+    let is_long = Boolean::multi_or(cs, &[is_long_string, is_long_list]);
+
+    // payload length for the short forms, and length of length for the long ones
+    let short_string_base = UInt8::allocated_constant(cs, RLP_SHORT_STRING_PREFIX);
+    let short_list_base = UInt8::allocated_constant(cs, RLP_SHORT_LIST_PREFIX);
+    let short_base = UInt8::conditionally_select(cs, is_list, &short_list_base, &short_string_base);
+    let (short_length, _) = prefix.overflowing_sub(cs, &short_base);
+
+    let long_string_base = UInt8::allocated_constant(cs, RLP_LONG_STRING_PREFIX - 1);
+    let long_list_base = UInt8::allocated_constant(cs, RLP_LONG_LIST_PREFIX - 1);
+    let long_base = UInt8::conditionally_select(cs, is_list, &long_list_base, &long_string_base);
+    let (length_of_length, _) = prefix.overflowing_sub(cs, &long_base);
+
+    let length_of_length_is_supported =
+        is_below(cs, &length_of_length, RLP_MAX_LENGTH_OF_LENGTH + 1);
+    length_of_length_is_supported.conditionally_enforce_true(cs, is_long);
+
+    // big-endian length follows the prefix
+    let one_u32 = UInt32::allocated_constant(cs, 1);
+    let length_offset = start_offset.add_no_overflow(cs, one_u32);
+    let mut long_length = Num::zero(cs);
+    for i in 0..RLP_MAX_LENGTH_OF_LENGTH {
+        let i_u32 = UInt32::allocated_constant(cs, i as u32);
+        let offset = length_offset.add_no_overflow(cs, i_u32);
+        let byte = byte_at(cs, bytes, offset);
+        let shifted = Num::fma(
+            cs,
+            &long_length,
+            &Num::allocated_constant(cs, F::ONE),
+            &F::from_u64_unchecked(1 << 8),
+            &byte.into_num(),
+            &F::ONE,
+        );
+        let i_u8 = UInt8::allocated_constant(cs, i);
+        let (_, is_length_byte) = i_u8.overflowing_sub(cs, &length_of_length);
+        long_length = Num::conditionally_select(cs, is_length_byte, &shifted, &long_length);
+    }
+    // at most 4 bytes
+    let long_length = unsafe { UInt32::from_variable_unchecked(long_length.get_variable()) };
+
+    let long_offset = length_offset.add_no_overflow(cs, into_u32(length_of_length));
+
+    let item_offset = UInt32::conditionally_select(cs, is_long, &long_offset, &length_offset);
+    let item_offset = UInt32::conditionally_select(cs, is_single_byte, &start_offset, &item_offset);
+
+    let item_length =
+        UInt32::conditionally_select(cs, is_long, &long_length, &into_u32(short_length));
+    let item_length = UInt32::conditionally_select(cs, is_single_byte, &one_u32, &item_length);
+
+    // payload has to end within the bytes
+    let item_end = item_offset.add_no_overflow(cs, item_length);
+    let bound = UInt32::allocated_constant(cs, bytes.len() as u32 + 1);
+    let (_, is_within_bytes) = item_end.overflowing_sub(cs, bound);
+    let boolean_true = Boolean::allocated_constant(cs, true);
+    Boolean::enforce_equal(cs, &is_within_bytes, &boolean_true);
+
+    (item_offset, item_length, is_list)
+}
+
+#[cfg(test)]
+mod test {
+    use boojum::{
+        field::goldilocks::GoldilocksField,
+        gadgets::traits::{allocatable::CSAllocatable, witnessable::WitnessHookable},
+        worker::Worker,
+    };
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    fn decode(encoding: &[u8], start_offset: u32) -> ((u32, u32, bool), bool) {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let bytes: Vec<_> = encoding
+            .iter()
+            .map(|el| UInt8::<F>::allocate(cs, *el))
+            .collect();
+        let start_offset = UInt32::allocate(cs, start_offset);
+
+        let (item_offset, item_length, is_list) = rlp_decode_single_item(cs, &bytes, start_offset);
+        let result = (
+            item_offset.witness_hook(&*cs)().unwrap(),
+            item_length.witness_hook(&*cs)().unwrap(),
+            is_list.witness_hook(&*cs)().unwrap(),
+        );
+
+        let worker = Worker::new();
+        owned_cs.pad_and_shrink();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        let is_satisfied = owned_cs.check_if_satisfied(&worker);
+
+        (result, is_satisfied)
+    }
+
+    #[test]
+    fn test_rlp_decode_single_byte() {
+        assert_eq!(decode(&[0x0f], 0), ((0, 1, false), true));
+    }
+
+    #[test]
+    fn test_rlp_decode_short_items() {
+        // ["cat", "dog"]
+        let encoding = [0xc8, 0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g'];
+        assert_eq!(decode(&encoding, 0), ((1, 8, true), true));
+        assert_eq!(decode(&encoding, 5), ((6, 3, false), true));
+    }
+
+    #[test]
+    fn test_rlp_decode_long_items() {
+        let mut string = vec![0xb8, 56];
+        string.extend([b'a'; 56]);
+        assert_eq!(decode(&string, 0), ((2, 56, false), true));
+
+        let mut list = vec![0xf9, 0x01, 0x00];
+        list.extend([0x01; 256]);
+        assert_eq!(decode(&list, 0), ((3, 256, true), true));
+    }
+
+    #[test]
+    fn test_rlp_decode_truncated_item() {
+        let encoding = [0x83, b'd', b'o'];
+        assert!(decode(&encoding, 0).1 == false);
+    }
+}
@@ -93,6 +93,8 @@ pub(crate) fn compute_precompile_commitment<
     precompile_queue_state: &QueueState<F, QUEUE_STATE_WIDTH>,
     mem_queue_state_before: &QueueState<F, FULL_SPONGE_QUEUE_STATE_WIDTH>,
     mem_queue_state_after: &QueueState<F, FULL_SPONGE_QUEUE_STATE_WIDTH>,
+    num_successful_calls: &UInt32<F>,
+    gas_used: &UInt32<F>,
     round_function: &R,
 ) -> ([Num<F>; CLOSED_FORM_COMMITTMENT_LENGTH], [Num<F>; CLOSED_FORM_COMMITTMENT_LENGTH]) {
     let input_data = PrecompileFunctionInputData {
@@ -102,8 +104,11 @@ pub(crate) fn compute_precompile_commitment<
     let input_data_commitment =
         commit_variable_length_encodable_item(cs, &input_data, round_function);
 
-    let output_data =
-        PrecompileFunctionOutputData { final_memory_state: mem_queue_state_after.clone() };
+    let output_data = PrecompileFunctionOutputData {
+        final_memory_state: mem_queue_state_after.clone(),
+        num_successful_calls: *num_successful_calls,
+        gas_used: *gas_used,
+    };
     let output_data_commitment =
         commit_variable_length_encodable_item(cs, &output_data, round_function);
 
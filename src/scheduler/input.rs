@@ -18,7 +18,7 @@ use crate::{
     },
     linear_hasher::input::LinearHasherOutputDataWitness,
     log_sorter::input::EventsDeduplicatorOutputDataWitness,
-    recursion::{leaf_layer::input::*, *},
+    recursion::{leaf_layer::input::*, recursion_tip::input::RECURSION_TIP_ARITY, *},
     storage_application::input::StorageApplicationOutputDataWitness,
     storage_validity_by_grand_product::input::StorageDeduplicatorOutputDataWitness,
 };
@@ -77,6 +77,10 @@ pub struct SchedulerCircuitInstanceWitness<
     // eip4844 witnesses
     pub eip4844_witnesses: [Option<EIP4844OutputDataWitness<F>>; MAX_4844_BLOBS_PER_BLOCK],
 
+    // recursion depths reported by every recursion tip and its branches
+    pub recursion_tip_depths: VecDeque<u32>,
+    pub recursion_tip_branch_depths: VecDeque<[u32; RECURSION_TIP_ARITY]>,
+
     // proofs for every individual circuit type's aggregation subtree
     #[derivative(Debug = "ignore")]
     pub proof_witnesses: VecDeque<Proof<F, H::NonCircuitSimulator, EXT>>,
@@ -128,6 +132,9 @@ impl<F: SmallField, H: RecursiveTreeHasher<F, Num<F>>, EXT: FieldExtension<2, Ba
 
             eip4844_witnesses: std::array::from_fn(|_| None),
 
+            recursion_tip_depths: VecDeque::new(),
+            recursion_tip_branch_depths: VecDeque::new(),
+
             proof_witnesses: VecDeque::new(),
         }
     }
@@ -151,6 +151,7 @@ pub fn scheduler_function<
     let boolean_false = Boolean::allocated_constant(cs, false);
     let boolean_true = Boolean::allocated_constant(cs, true);
     let zero_u8 = UInt8::zero(cs);
+    let circuit_version = UInt32::allocated_constant(cs, CURRENT_CIRCUIT_VERSION);
 
     Boolean::enforce_equal(cs, &block_meta_parameters.zkporter_is_available, &boolean_false);
 
@@ -334,6 +335,8 @@ pub fn scheduler_function<
             &keccak256_access_queue_state,
             &code_decommitter_observable_output.memory_queue_final_state,
             &keccak256_observable_output.final_memory_state,
+            &keccak256_observable_output.num_successful_calls,
+            &keccak256_observable_output.gas_used,
             round_function,
         );
     let (sha256_circuit_observable_input_commitment, sha256_circuit_observable_output_commitment) =
@@ -342,6 +345,8 @@ pub fn scheduler_function<
             &sha256_access_queue_state,
             &keccak256_observable_output.final_memory_state,
             &sha256_observable_output.final_memory_state,
+            &sha256_observable_output.num_successful_calls,
+            &sha256_observable_output.gas_used,
             round_function,
         );
     let (
@@ -352,6 +357,8 @@ pub fn scheduler_function<
         &ecrecover_access_queue_state,
         &sha256_observable_output.final_memory_state,
         &ecrecover_observable_output.final_memory_state,
+        &ecrecover_observable_output.num_successful_calls,
+        &ecrecover_observable_output.gas_used,
         round_function,
     );
     let (
@@ -362,6 +369,8 @@ pub fn scheduler_function<
         &secp256r1_verify_access_queue_state,
         &ecrecover_observable_output.final_memory_state,
         &secp256r1_verify_observable_output.final_memory_state,
+        &secp256r1_verify_observable_output.num_successful_calls,
+        &secp256r1_verify_observable_output.gas_used,
         round_function,
     );
 
@@ -987,6 +996,7 @@ pub fn scheduler_function<
         let query = RecursionQuery {
             circuit_type: circuit_type_to_use,
             input_commitment: closed_form_input_comm,
+            circuit_version,
         };
         // push
         let mut tmp_queue = RecursionQueue::<F, R>::empty(cs);
@@ -1101,8 +1111,11 @@ pub fn scheduler_function<
             let input_commitment =
                 commit_variable_length_encodable_item(cs, &closed_form_input, round_function);
             // add to the queue
-            let recursion_query =
-                RecursionQuery { circuit_type: eip4844_circuit_type, input_commitment };
+            let recursion_query = RecursionQuery {
+                circuit_type: eip4844_circuit_type,
+                input_commitment,
+                circuit_version,
+            };
 
             let _ = eip4844_recursion_queue.push(cs, recursion_query, should_verify);
 
@@ -1118,6 +1131,8 @@ pub fn scheduler_function<
     let eip4844_recursion_queue_state = eip4844_recursion_queue.into_state().tail;
 
     let mut proof_witnesses = witness.proof_witnesses;
+    let mut recursion_tip_depths = witness.recursion_tip_depths;
+    let mut recursion_tip_branch_depths = witness.recursion_tip_branch_depths;
 
     assert_eq!(config.vk_fixed_parameters.parameters, verifier_builder.geometry());
 
@@ -1142,6 +1157,15 @@ pub fn scheduler_function<
             let mut recursion_tip_input = RecursionTipInput::placeholder(cs);
             recursion_tip_input.leaf_layer_parameters = leaf_layer_parameters;
             recursion_tip_input.node_layer_vk_commitment = node_layer_vk_commitment;
+            // depths are checked by the recursion tip itself, we only pass them through
+            recursion_tip_input.depth =
+                UInt32::allocate(cs, recursion_tip_depths.pop_front().unwrap_or(0));
+            recursion_tip_input.branch_depth_set = <[UInt32<F>; RECURSION_TIP_ARITY]>::allocate(
+                cs,
+                recursion_tip_branch_depths
+                    .pop_front()
+                    .unwrap_or([0; RECURSION_TIP_ARITY]),
+            );
 
             for (circuit_type_dst, state_dst) in recursion_tip_input
                 .branch_circuit_type_set
@@ -1162,7 +1186,7 @@ pub fn scheduler_function<
             }
 
             let expected_input_commitment: [_; INPUT_OUTPUT_COMMITMENT_LENGTH] =
-                commit_variable_length_encodable_item(cs, &recursion_tip_input, round_function);
+                commit_with_domain(cs, &recursion_tip_input, round_function, TIP_DOMAIN);
 
             let proof_witness = proof_witnesses.pop_front();
 
@@ -1268,6 +1292,25 @@ pub fn scheduler_function<
         previous_block_aux_hash,
     );
 
+    let public_input = scheduler_public_input_from_block_hashes(
+        cs,
+        previous_block_content_hash,
+        this_block_content_hash,
+    );
+
+    for el in public_input.into_iter() {
+        use boojum::cs::gates::PublicInputGate;
+        let gate = PublicInputGate::new(el.get_variable());
+        gate.add_to_cs(cs);
+    }
+}
+
+/// Packs the hash of previous and new block content hashes into the scheduler's public input
+pub fn scheduler_public_input_from_block_hashes<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    previous_block_content_hash: [UInt8<F>; 32],
+    this_block_content_hash: [UInt8<F>; 32],
+) -> [Num<F>; NUM_SCHEDULER_PUBLIC_INPUTS] {
     // form full block hash, it's just a hash of concatenation of previous and new full content
     // hashes
     let mut flattened_public_input = vec![];
@@ -1277,18 +1320,20 @@ pub fn scheduler_function<
     let input_keccak_hash = keccak256::keccak256(cs, &flattened_public_input);
     let take_by = F::CAPACITY_BITS / 8;
 
-    for chunk in input_keccak_hash
-        .chunks_exact(take_by)
-        .take(NUM_SCHEDULER_PUBLIC_INPUTS)
+    let zero_num = Num::zero(cs);
+    let mut result = [zero_num; NUM_SCHEDULER_PUBLIC_INPUTS];
+
+    for (dst, chunk) in result
+        .iter_mut()
+        .zip(input_keccak_hash.chunks_exact(take_by))
     {
         let mut lc = Vec::with_capacity(chunk.len());
         // treat as BE
         for (idx, el) in chunk.iter().rev().enumerate() {
             lc.push((el.get_variable(), F::SHIFTS[idx * 8]));
         }
-        let as_num = Num::linear_combination(cs, &lc);
-        use boojum::cs::gates::PublicInputGate;
-        let gate = PublicInputGate::new(as_num.get_variable());
-        gate.add_to_cs(cs);
+        *dst = Num::linear_combination(cs, &lc);
     }
+
+    result
 }
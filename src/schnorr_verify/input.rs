@@ -0,0 +1,19 @@
+use std::collections::VecDeque;
+
+use boojum::gadgets::queue::*;
+
+use super::*;
+use crate::precompile_loop::input::*;
+
+pub type SchnorrVerifyCircuitFSMInputOutput<F> = PrecompileFunctionFSMInputOutput<F>;
+pub type SchnorrVerifyCircuitInputOutput<F> = PrecompileFunctionCircuitInputOutput<F>;
+pub type SchnorrVerifyCircuitInputOutputWitness<F> = PrecompileFunctionCircuitInputOutputWitness<F>;
+
+#[derive(Derivative, serde::Serialize, serde::Deserialize)]
+#[derivative(Clone, Debug, Default)]
+#[serde(bound = "")]
+pub struct SchnorrVerifyCircuitInstanceWitness<F: SmallField> {
+    pub closed_form_input: SchnorrVerifyCircuitInputOutputWitness<F>,
+    pub requests_queue_witness: CircuitQueueRawWitness<F, LogQuery<F>, 4, LOG_QUERY_PACKED_WIDTH>,
+    pub memory_reads_witness: VecDeque<[U256; MEMORY_QUERIES_PER_CALL]>,
+}
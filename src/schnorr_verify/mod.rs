@@ -0,0 +1,371 @@
+use std::sync::Arc;
+
+use arrayvec::ArrayVec;
+use boojum::{
+    algebraic_props::round_function::AlgebraicRoundFunction,
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{
+        boolean::Boolean,
+        curves::sw_projective::SWProjectivePoint,
+        non_native_field::implementations::*,
+        num::Num,
+        traits::{
+            allocatable::CSAllocatableExt, round_function::CircuitRoundFunction,
+            selectable::Selectable, witnessable::WitnessHookable,
+        },
+        u256::UInt256,
+        u8::UInt8,
+    },
+    pairing::{ff::PrimeField, GenericCurveAffine},
+};
+
+use super::*;
+use crate::{
+    base_structures::{log_query::*, memory_query::*, precompile_kind::PrecompileKind},
+    ecrecover::{
+        new_optimized::{
+            check_on_curve, convert_uint256_to_field_element,
+            convert_uint256_to_field_element_masked, fixed_base_mul, sub_mixed,
+            width_4_windowed_multiplication,
+        },
+        secp256k1::fixed_base_mul_table::get_fixed_base_mul_table_ids,
+    },
+    ethereum_types::U256,
+    fsm_input_output::circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH,
+    gas_meter::SCHNORR_VERIFY_GAS_COST_PER_CALL,
+    precompile_loop::{boolean_into_u256, precompile_loop_entry_point},
+    utils::keccak256_of_bytes,
+};
+
+pub mod input;
+pub use self::input::*;
+
+// message hash, R (x, y), s, public key (x, y)
+pub const MEMORY_QUERIES_PER_CALL: usize = 6;
+
+// Schnorr signatures are verified over secp256k1, so we reuse its parameters
+use crate::ecrecover::secp256k1::{
+    fq::Fq as Secp256Fq, fr::Fr as Secp256Fr, PointAffine as Secp256Affine,
+};
+
+const BASE_FIELD_CANONICAL_REPR_LIMBS: usize = 16;
+const SCALAR_FIELD_CANONICAL_REPR_LIMBS: usize = 16;
+
+type Secp256BaseNNFieldParams = NonNativeFieldOverU16Params<Secp256Fq, 17>;
+type Secp256ScalarNNFieldParams = NonNativeFieldOverU16Params<Secp256Fr, 17>;
+
+type Secp256BaseNNField<F> = NonNativeFieldOverU16<F, Secp256Fq, 17>;
+
+fn secp256k1_base_field_params() -> Secp256BaseNNFieldParams {
+    NonNativeFieldOverU16Params::create()
+}
+
+fn secp256k1_scalar_field_params() -> Secp256ScalarNNFieldParams {
+    NonNativeFieldOverU16Params::create()
+}
+
+const SECP_B_COEF: u64 = 7;
+const EXCEPTION_FLAGS_ARR_LEN: usize = 8;
+
+/// Verifies `s * G = R + e * P`, where `e = keccak256(R_x || R_y || P_x || P_y || message_hash)`
+/// is taken modulo the group order and all values are 32 bytes big-endian. Returns whether the
+/// inputs are well formed, and the verification result that is `false` if they are not
+pub(crate) fn schnorr_verify_function_inner<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    message_hash: &UInt256<F>,
+    r_x: &UInt256<F>,
+    r_y: &UInt256<F>,
+    s: &UInt256<F>,
+    pk_x: &UInt256<F>,
+    pk_y: &UInt256<F>,
+    base_field_params: &Arc<Secp256BaseNNFieldParams>,
+    scalar_field_params: &Arc<Secp256ScalarNNFieldParams>,
+) -> (Boolean<F>, Boolean<F>) {
+    let mut curve_b_nn = Secp256BaseNNField::<F>::allocated_constant(
+        cs,
+        Secp256Fq::from_str(&SECP_B_COEF.to_string()).unwrap(),
+        &base_field_params,
+    );
+
+    let generator = Secp256Affine::one();
+    let (gen_x, gen_y) = generator.into_xy_unchecked();
+    let gen_x_nn = Secp256BaseNNField::allocated_constant(cs, gen_x, base_field_params);
+    let gen_y_nn = Secp256BaseNNField::allocated_constant(cs, gen_y, base_field_params);
+
+    let secp_n_u256 = U256([
+        scalar_field_params.modulus_u1024.as_ref().as_words()[0],
+        scalar_field_params.modulus_u1024.as_ref().as_words()[1],
+        scalar_field_params.modulus_u1024.as_ref().as_words()[2],
+        scalar_field_params.modulus_u1024.as_ref().as_words()[3],
+    ]);
+    let secp_n_u256 = UInt256::allocated_constant(cs, secp_n_u256);
+
+    let secp_p_u256 = U256([
+        base_field_params.modulus_u1024.as_ref().as_words()[0],
+        base_field_params.modulus_u1024.as_ref().as_words()[1],
+        base_field_params.modulus_u1024.as_ref().as_words()[2],
+        base_field_params.modulus_u1024.as_ref().as_words()[3],
+    ]);
+    let secp_p_u256 = UInt256::allocated_constant(cs, secp_p_u256);
+
+    let mut exception_flags = ArrayVec::<_, EXCEPTION_FLAGS_ARR_LEN>::new();
+
+    // both points are given in non-compressed form, so we check ranges of coordinates and that
+    // points are on curve, and mask them otherwise
+    let mut coordinates = [*r_x, *r_y, *pk_x, *pk_y];
+    for coordinate in coordinates.iter_mut() {
+        let (_res, is_in_range) = coordinate.overflowing_sub(cs, &secp_p_u256);
+        *coordinate = coordinate.mask(cs, is_in_range);
+        let is_not_in_range = is_in_range.negated(cs);
+        exception_flags.push(is_not_in_range);
+    }
+    let [r_x_as_u256, r_y_as_u256, pk_x_as_u256, pk_y_as_u256] = coordinates;
+
+    let mut s_as_u256 = *s;
+    let (_res, is_in_range) = s_as_u256.overflowing_sub(cs, &secp_n_u256);
+    s_as_u256 = s_as_u256.mask(cs, is_in_range);
+    let s_is_not_in_range = is_in_range.negated(cs);
+    exception_flags.push(s_is_not_in_range);
+
+    let (s_fe, s_is_zero) =
+        convert_uint256_to_field_element_masked(cs, &s_as_u256, &scalar_field_params);
+    exception_flags.push(s_is_zero);
+
+    let mut r_x_fe = convert_uint256_to_field_element(cs, &r_x_as_u256, &base_field_params);
+    let mut r_y_fe = convert_uint256_to_field_element(cs, &r_y_as_u256, &base_field_params);
+    let mut pk_x_fe = convert_uint256_to_field_element(cs, &pk_x_as_u256, &base_field_params);
+    let mut pk_y_fe = convert_uint256_to_field_element(cs, &pk_y_as_u256, &base_field_params);
+
+    let r_is_on_curve = check_on_curve(cs, &mut r_x_fe, &mut r_y_fe, &mut curve_b_nn);
+    let r_is_not_on_curve = r_is_on_curve.negated(cs);
+    exception_flags.push(r_is_not_on_curve);
+
+    let pk_is_on_curve = check_on_curve(cs, &mut pk_x_fe, &mut pk_y_fe, &mut curve_b_nn);
+    let pk_is_not_on_curve = pk_is_on_curve.negated(cs);
+    exception_flags.push(pk_is_not_on_curve);
+
+    debug_assert_eq!(exception_flags.len(), EXCEPTION_FLAGS_ARR_LEN);
+    let any_exception = Boolean::multi_or(cs, &exception_flags[..]);
+
+    // masking keeps our arithmetic formulas well defined
+    let r_x_fe = Selectable::conditionally_select(cs, r_is_on_curve, &r_x_fe, &gen_x_nn);
+    let r_y_fe = Selectable::conditionally_select(cs, r_is_on_curve, &r_y_fe, &gen_y_nn);
+    let pk_x_fe = Selectable::conditionally_select(cs, pk_is_on_curve, &pk_x_fe, &gen_x_nn);
+    let pk_y_fe = Selectable::conditionally_select(cs, pk_is_on_curve, &pk_y_fe, &gen_y_nn);
+
+    // challenge is computed over the original inputs
+    let zero_u8 = UInt8::zero(cs);
+    let mut bytes_to_hash = [zero_u8; 32 * 5];
+    for (dst, src) in bytes_to_hash
+        .array_chunks_mut::<32>()
+        .zip([r_x, r_y, pk_x, pk_y, message_hash].into_iter())
+    {
+        *dst = src.to_be_bytes(cs);
+    }
+
+    let mut digest_bytes = keccak256_of_bytes(cs, &bytes_to_hash);
+    digest_bytes.reverse();
+    let challenge = UInt256::from_le_bytes(cs, digest_bytes);
+    let mut challenge_fe = convert_uint256_to_field_element(cs, &challenge, &scalar_field_params);
+    challenge_fe.normalize(cs);
+
+    // R + e * P
+    let pk_point = SWProjectivePoint::<F, Secp256Affine, Secp256BaseNNField<F>>::from_xy_unchecked(
+        cs, pk_x_fe, pk_y_fe,
+    );
+    let mut e_times_pk = width_4_windowed_multiplication(
+        cs,
+        pk_point,
+        challenge_fe,
+        &base_field_params,
+        &scalar_field_params,
+    );
+    let mut rhs = e_times_pk.add_mixed(cs, &mut (r_x_fe, r_y_fe));
+
+    // s * G
+    let full_table_ids = get_fixed_base_mul_table_ids::<F, CS>(&*cs);
+
+    let mut s_times_g = fixed_base_mul::<F, CS, Secp256Fr, Secp256Fq, Secp256Affine, _, 17>(
+        cs,
+        s_fe,
+        &base_field_params,
+        SCALAR_FIELD_CANONICAL_REPR_LIMBS,
+        BASE_FIELD_CANONICAL_REPR_LIMBS,
+        &full_table_ids,
+    );
+
+    // s is non-zero and less than the group order (or it's an exception), so s * G is never
+    // the point at infinity
+    let (mut s_times_g_affine, _) =
+        s_times_g.convert_to_affine_or_default(cs, Secp256Affine::one());
+    let mut difference = sub_mixed(cs, &mut rhs, &mut s_times_g_affine);
+    let (_, equation_holds) = difference.convert_to_affine_or_default(cs, Secp256Affine::one());
+
+    if crate::config::CIRCUIT_VERSOBE {
+        dbg!(challenge.witness_hook(cs)());
+        dbg!(equation_holds.witness_hook(cs)());
+    }
+
+    let is_valid = equation_holds.mask_negated(cs, any_exception);
+    let all_ok = any_exception.negated(cs);
+
+    (all_ok, is_valid)
+}
+
+pub fn schnorr_verify_function_entry_point<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+>(
+    cs: &mut CS,
+    witness: SchnorrVerifyCircuitInstanceWitness<F>,
+    round_function: &R,
+    limit: usize,
+) -> [Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH]
+where
+    [(); <LogQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <MemoryQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN + 1]:,
+{
+    let SchnorrVerifyCircuitInstanceWitness {
+        closed_form_input,
+        requests_queue_witness,
+        memory_reads_witness,
+    } = witness;
+
+    let scalar_params = Arc::new(secp256k1_scalar_field_params());
+    let base_params = Arc::new(secp256k1_base_field_params());
+
+    precompile_loop_entry_point(
+        cs,
+        closed_form_input,
+        requests_queue_witness,
+        memory_reads_witness,
+        PrecompileKind::SchnorrVerify,
+        SCHNORR_VERIFY_GAS_COST_PER_CALL,
+        round_function,
+        limit,
+        |cs, read_values| {
+            let [message_hash, r_x, r_y, s, pk_x, pk_y] = read_values;
+
+            let (_no_error, success) = schnorr_verify_function_inner(
+                cs,
+                &message_hash,
+                &r_x,
+                &r_y,
+                &s,
+                &pk_x,
+                &pk_y,
+                &base_params,
+                &scalar_params,
+            );
+
+            (success, [boolean_into_u256(cs, success)])
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use boojum::{
+        field::goldilocks::GoldilocksField,
+        gadgets::traits::allocatable::CSAllocatable,
+        pairing::{
+            ff::{Field, PrimeFieldRepr},
+            GenericCurveProjective,
+        },
+        worker::Worker,
+    };
+    use rand::{Rng, SeedableRng, XorShiftRng};
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    fn repr_into_u256<T: PrimeFieldRepr>(repr: T) -> U256 {
+        let mut u256 = U256::zero();
+        u256.0.copy_from_slice(&repr.as_ref()[..4]);
+
+        u256
+    }
+
+    // returns (message hash, R_x, R_y, s, P_x, P_y)
+    fn simulate_signature() -> [U256; MEMORY_QUERIES_PER_CALL] {
+        use zkevm_opcode_defs::sha3::*;
+
+        let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let sk: Secp256Fr = rng.gen();
+        let k: Secp256Fr = rng.gen();
+        let message_hash = repr_into_u256(rng.gen::<Secp256Fr>().into_repr());
+
+        let (pk_x, pk_y) = Secp256Affine::one()
+            .mul(sk.into_repr())
+            .into_affine()
+            .into_xy_unchecked();
+        let (r_x, r_y) = Secp256Affine::one()
+            .mul(k.into_repr())
+            .into_affine()
+            .into_xy_unchecked();
+        let [r_x, r_y, pk_x, pk_y] =
+            [r_x, r_y, pk_x, pk_y].map(|el| repr_into_u256(el.into_repr()));
+
+        let mut hasher = Keccak256::new();
+        for el in [r_x, r_y, pk_x, pk_y, message_hash] {
+            let mut buffer = [0u8; 32];
+            el.to_big_endian(&mut buffer);
+            hasher.update(&buffer);
+        }
+        let challenge = U256::from_big_endian(hasher.finalize().as_slice());
+        let secp_n = repr_into_u256(Secp256Fr::char());
+        let challenge = Secp256Fr::from_str(&(challenge % secp_n).to_string()).unwrap();
+
+        // s = k + e * sk
+        let mut s = challenge;
+        s.mul_assign(&sk);
+        s.add_assign(&k);
+        let s = repr_into_u256(s.into_repr());
+
+        [message_hash, r_x, r_y, s, pk_x, pk_y]
+    }
+
+    #[test]
+    fn test_schnorr_verification() {
+        let mut owned_cs = create_test_cs(1 << 21);
+        let cs = &mut owned_cs;
+
+        let scalar_params = Arc::new(secp256k1_scalar_field_params());
+        let base_params = Arc::new(secp256k1_base_field_params());
+
+        let signature = simulate_signature();
+        let mut tampered_signature = signature;
+        tampered_signature[3] = tampered_signature[3] + U256::one();
+
+        for (inputs, expect_valid) in [(signature, true), (tampered_signature, false)] {
+            let [message_hash, r_x, r_y, s, pk_x, pk_y] =
+                inputs.map(|el| UInt256::allocate(cs, el));
+
+            let (no_error, is_valid) = schnorr_verify_function_inner(
+                cs,
+                &message_hash,
+                &r_x,
+                &r_y,
+                &s,
+                &pk_x,
+                &pk_y,
+                &base_params,
+                &scalar_params,
+            );
+
+            assert!(no_error.witness_hook(&*cs)().unwrap() == true);
+            assert_eq!(is_valid.witness_hook(&*cs)().unwrap(), expect_valid);
+        }
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+}
@@ -18,7 +18,6 @@ use boojum::{
             round_function::CircuitRoundFunction,
             selectable::Selectable,
         },
-        u160::UInt160,
         u256::UInt256,
         u32::UInt32,
         u8::UInt8,
@@ -28,7 +27,9 @@ use zkevm_opcode_defs::system_params::PRECOMPILE_AUX_BYTE;
 
 use super::*;
 use crate::{
-    base_structures::precompile_input_outputs::PrecompileFunctionOutputData,
+    base_structures::{
+        precompile_input_outputs::PrecompileFunctionOutputData, precompile_kind::PrecompileKind,
+    },
     demux_log_queue::StorageLogQueue,
     ecrecover::{
         baseline::{convert_uint256_to_field_element, convert_uint256_to_field_element_masked},
@@ -36,6 +37,8 @@ use crate::{
     },
     ethereum_types::U256,
     fsm_input_output::circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH,
+    gas_meter::{GasMeter, SECP256R1_VERIFY_GAS_COST_PER_CALL},
+    tables::get_typed_table_id,
 };
 
 const WINDOW_WIDTH: usize = 4;
@@ -65,9 +68,13 @@ impl<F: SmallField> Secp256r1VerifyPrecompileCallParams<F> {
 }
 
 const NUM_WORDS: usize = 17;
-const EXCEPTION_FLAGS_ARR_LEN: usize = 8;
+const EXCEPTION_FLAGS_ARR_LEN: usize = 9;
 
-fn secp256r1_verify_function_inner<F: SmallField, CS: ConstraintSystem<F>>(
+pub(crate) fn secp256r1_verify_function_inner<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    const LOW_S_ENFORCE: bool,
+>(
     cs: &mut CS,
     r: &UInt256<F>,
     s: &UInt256<F>,
@@ -91,13 +98,13 @@ fn secp256r1_verify_function_inner<F: SmallField, CS: ConstraintSystem<F>>(
     let gen_x_nn = Secp256BaseNNField::allocated_constant(cs, gen_x, base_field_params);
     let gen_y_nn = Secp256BaseNNField::allocated_constant(cs, gen_y, base_field_params);
 
-    let secp_n_u256 = U256([
+    let secp_n = U256([
         scalar_field_params.modulus_u1024.as_ref().as_words()[0],
         scalar_field_params.modulus_u1024.as_ref().as_words()[1],
         scalar_field_params.modulus_u1024.as_ref().as_words()[2],
         scalar_field_params.modulus_u1024.as_ref().as_words()[3],
     ]);
-    let secp_n_u256 = UInt256::allocated_constant(cs, secp_n_u256);
+    let secp_n_u256 = UInt256::allocated_constant(cs, secp_n);
 
     let secp_p_u256 = U256([
         base_field_params.modulus_u1024.as_ref().as_words()[0],
@@ -151,6 +158,15 @@ fn secp256r1_verify_function_inner<F: SmallField, CS: ConstraintSystem<F>>(
         convert_uint256_to_field_element_masked(cs, &s_as_u256, &scalar_field_params);
     exception_flags.push(s_is_zero);
 
+    if LOW_S_ENFORCE {
+        // signature is malleable, as (r, n - s) is also valid, so we only accept s < (n + 1) / 2
+        let n_half = (secp_n + U256::one()) / U256::from(2u64);
+        let n_half = UInt256::allocated_constant(cs, n_half);
+        let (_res, is_low) = s.overflowing_sub(cs, &n_half);
+        let s_exceeds_half = is_low.negated(cs);
+        exception_flags.push(s_exceeds_half);
+    }
+
     let mut message_hash_fe =
         convert_uint256_to_field_element(cs, &message_hash, &scalar_field_params);
 
@@ -191,30 +207,9 @@ fn secp256r1_verify_function_inner<F: SmallField, CS: ConstraintSystem<F>>(
     let mut r_by_s_inv_mul_by_pubkey =
         width_4_windowed_multiplication(cs, point, r_by_s_inv.clone(), &base_field_params);
 
-    let mut full_table_ids = vec![];
-    seq_macro::seq!(C in 0..32 {
-        let ids = [
-            cs.get_table_id_for_marker::<Secp256r1FixedBaseMulTable<0, C>>()
-                .expect("table must exist"),
-            cs.get_table_id_for_marker::<Secp256r1FixedBaseMulTable<1, C>>()
-                .expect("table must exist"),
-            cs.get_table_id_for_marker::<Secp256r1FixedBaseMulTable<2, C>>()
-                .expect("table must exist"),
-            cs.get_table_id_for_marker::<Secp256r1FixedBaseMulTable<3, C>>()
-                .expect("table must exist"),
-            cs.get_table_id_for_marker::<Secp256r1FixedBaseMulTable<4, C>>()
-                .expect("table must exist"),
-            cs.get_table_id_for_marker::<Secp256r1FixedBaseMulTable<5, C>>()
-                .expect("table must exist"),
-            cs.get_table_id_for_marker::<Secp256r1FixedBaseMulTable<6, C>>()
-                .expect("table must exist"),
-            cs.get_table_id_for_marker::<Secp256r1FixedBaseMulTable<7, C>>()
-                .expect("table must exist"),
-        ];
-        full_table_ids.push(ids);
-    });
-
-    let mut hash_times_g = fixed_base_mul::<F, CS, Secp256Fr, Secp256Fq, Secp256Affine, 17>(
+    let full_table_ids = get_secp256r1_fixed_base_mul_table_ids::<F, CS>(&*cs);
+
+    let mut hash_times_g = fixed_base_mul::<F, CS, Secp256Fr, Secp256Fq, Secp256Affine, _, 17>(
         cs,
         message_hash_by_s_inv,
         &base_field_params,
@@ -263,6 +258,7 @@ pub fn secp256r1_verify_function_entry_point<
     F: SmallField,
     CS: ConstraintSystem<F>,
     R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+    const LOW_S_ENFORCE: bool,
 >(
     cs: &mut CS,
     witness: Secp256r1VerifyCircuitInstanceWitness<F>,
@@ -285,14 +281,11 @@ where
 
     let memory_reads_witness: VecDeque<_> = memory_reads_witness.into_iter().flatten().collect();
 
-    let precompile_address = UInt160::allocated_constant(
-        cs,
-        *zkevm_opcode_defs::system_params::SECP256R1_VERIFY_INNER_FUNCTION_PRECOMPILE_FORMAL_ADDRESS,
-    );
+    let precompile_address = PrecompileKind::Secp256r1Verify.to_address(cs);
     let aux_byte_for_precompile = UInt8::allocated_constant(cs, PRECOMPILE_AUX_BYTE);
 
-    let scalar_params = Arc::new(secp256r1_scalar_field_params());
-    let base_params = Arc::new(secp256r1_base_field_params());
+    let scalar_params = secp256r1_scalar_field_params();
+    let base_params = secp256r1_base_field_params();
 
     let mut structured_input =
         Secp256r1VerifyCircuitInputOutput::alloc_ignoring_outputs(cs, closed_form_input.clone());
@@ -343,11 +336,40 @@ where
         witness_source: Arc::new(RwLock::new(memory_reads_witness)),
     };
 
+    let zero_u32 = UInt32::zero(cs);
+    let mut num_requests_processed = UInt32::conditionally_select(
+        cs,
+        start_flag,
+        &zero_u32,
+        &structured_input.hidden_fsm_input.num_requests_processed,
+    );
+    let mut num_successful_calls = UInt32::conditionally_select(
+        cs,
+        start_flag,
+        &zero_u32,
+        &structured_input.hidden_fsm_input.num_successful_calls,
+    );
+    let mut gas_meter = GasMeter::new(UInt32::conditionally_select(
+        cs,
+        start_flag,
+        &zero_u32,
+        &structured_input.hidden_fsm_input.gas_used,
+    ));
+    let gas_cost_per_call = UInt32::allocated_constant(cs, SECP256R1_VERIFY_GAS_COST_PER_CALL);
+
     for _cycle in 0..limit {
         let is_empty = requests_queue.is_empty(cs);
         let should_process = is_empty.negated(cs);
         let (request, _) = requests_queue.pop_front(cs, should_process);
 
+        // failed calls are charged too
+        let gas_overflow = gas_meter.charge(cs, gas_cost_per_call, should_process);
+        Boolean::enforce_equal(cs, &gas_overflow, &boolean_false);
+
+        num_requests_processed = num_requests_processed.add_no_overflow(cs, unsafe {
+            UInt32::from_variable_unchecked(should_process.get_variable())
+        });
+
         let mut precompile_call_params =
             Secp256r1VerifyPrecompileCallParams::from_encoding(cs, request.key);
 
@@ -401,7 +423,7 @@ where
 
         let [message_hash_as_u256, r_as_u256, s_as_u256, x_as_u256, y_as_u256] = read_values;
 
-        let (success, written_value) = secp256r1_verify_function_inner(
+        let (success, written_value) = secp256r1_verify_function_inner::<_, _, LOW_S_ENFORCE>(
             cs,
             &r_as_u256,
             &s_as_u256,
@@ -412,6 +434,11 @@ where
             &scalar_params,
         );
 
+        let call_succeeded = Boolean::multi_and(cs, &[success, should_process]);
+        num_successful_calls = num_successful_calls.add_no_overflow(cs, unsafe {
+            UInt32::from_variable_unchecked(call_succeeded.get_variable())
+        });
+
         let success_as_u32 = unsafe { UInt32::from_variable_unchecked(success.get_variable()) };
         let mut success_as_u256 = zero_u256;
         success_as_u256.inner[0] = success_as_u32;
@@ -459,9 +486,24 @@ where
         &final_memory_state,
         &structured_input.observable_output.final_memory_state,
     );
+    structured_input.observable_output.num_successful_calls = UInt32::conditionally_select(
+        cs,
+        structured_input.completion_flag,
+        &num_successful_calls,
+        &structured_input.observable_output.num_successful_calls,
+    );
+    structured_input.observable_output.gas_used = UInt32::conditionally_select(
+        cs,
+        structured_input.completion_flag,
+        &gas_meter.gas_used,
+        &structured_input.observable_output.gas_used,
+    );
 
     structured_input.hidden_fsm_output.log_queue_state = final_requets_state;
     structured_input.hidden_fsm_output.memory_queue_state = final_memory_state;
+    structured_input.hidden_fsm_output.num_requests_processed = num_requests_processed;
+    structured_input.hidden_fsm_output.num_successful_calls = num_successful_calls;
+    structured_input.hidden_fsm_output.gas_used = gas_meter.gas_used;
 
     // self-check
     structured_input.hook_compare_witness(cs, &closed_form_input);
@@ -554,15 +596,13 @@ fn to_width_4_window_form<F: SmallField, CS: ConstraintSystem<F>>(
     }
 
     use boojum::gadgets::{tables::ByteSplitTable, u16::UInt16};
-    let byte_split_id = cs
-        .get_table_id_for_marker::<ByteSplitTable<4>>()
-        .expect("table should exist");
+    let byte_split_id = get_typed_table_id::<ByteSplitTable<4>, F, CS>(cs);
     let mut result = Vec::with_capacity(NUM_MULTIPLICATION_STEPS_FOR_WIDTH_4);
     for word in limited_width_scalar.limbs[..16].iter().rev() {
         let word = unsafe { UInt16::from_variable_unchecked(*word) };
         let [high, low] = word.to_be_bytes(cs);
         for t in [high, low].into_iter() {
-            let [l, h] = cs.perform_lookup::<1, 2>(byte_split_id, &[t.get_variable()]);
+            let [l, h] = byte_split_id.perform_lookup::<F, CS, 1, 2>(cs, &[t.get_variable()]);
             let h = Num::from_variable(h);
             let l = Num::from_variable(l);
             result.push(h);
@@ -582,144 +622,13 @@ mod test {
     };
 
     use super::*;
+    use crate::test_utils::create_test_cs;
 
     type F = GoldilocksField;
-    type P = GoldilocksField;
-
-    use boojum::{
-        config::DevCSConfig,
-        cs::{
-            cs_builder::*, cs_builder_reference::CsReferenceImplementationBuilder, gates::*,
-            traits::gate::GatePlacementStrategy, CSGeometry, *,
-        },
-        gadgets::tables::*,
-    };
 
     #[test]
     fn test_secp256r1_verification() {
-        let geometry = CSGeometry {
-            num_columns_under_copy_permutation: 80,
-            num_witness_columns: 0,
-            num_constant_columns: 4,
-            max_allowed_constraint_degree: 8,
-        };
-
-        let max_variables = 1 << 26;
-        let max_trace_len = 1 << 20;
-
-        fn configure<
-            F: SmallField,
-            T: CsBuilderImpl<F, T>,
-            GC: GateConfigurationHolder<F>,
-            TB: StaticToolboxHolder,
-        >(
-            builder: CsBuilder<T, F, GC, TB>,
-        ) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
-            let builder = builder.allow_lookup(
-                LookupParameters::UseSpecializedColumnsWithTableIdAsConstant {
-                    width: 3,
-                    num_repetitions: 16,
-                    share_table_id: true,
-                },
-            );
-
-            let builder = ConstantsAllocatorGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = BooleanConstraintGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseSpecializedColumns {
-                    num_repetitions: 1,
-                    share_constants: false,
-                },
-            );
-            let builder = U8x4FMAGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = ZeroCheckGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-                false,
-            );
-            let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = UIntXAddGate::<32>::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = UIntXAddGate::<16>::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = UIntXAddGate::<8>::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = DotProductGate::<4>::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = SelectionGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = ParallelSelectionGate::<4>::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = PublicInputGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = ReductionGate::<_, 4>::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-            let builder = NopGate::configure_builder(
-                builder,
-                GatePlacementStrategy::UseGeneralPurposeColumns,
-            );
-
-            builder
-        }
-
-        let builder_impl =
-            CsReferenceImplementationBuilder::<F, P, DevCSConfig>::new(geometry, max_trace_len);
-        let builder = new_builder::<_, F>(builder_impl);
-
-        let builder = configure(builder);
-        let mut owned_cs = builder.build(max_variables);
-
-        // add tables
-        let table = create_xor8_table();
-        owned_cs.add_lookup_table::<Xor8Table, 3>(table);
-
-        seq_macro::seq!(C in 0..32 {
-            let table = create_secp256r1_fixed_base_mul_table::<F, 0, C>();
-            owned_cs.add_lookup_table::<Secp256r1FixedBaseMulTable<0, C>, 3>(table);
-            let table = create_secp256r1_fixed_base_mul_table::<F, 1, C>();
-            owned_cs.add_lookup_table::<Secp256r1FixedBaseMulTable<1, C>, 3>(table);
-            let table = create_secp256r1_fixed_base_mul_table::<F, 2, C>();
-            owned_cs.add_lookup_table::<Secp256r1FixedBaseMulTable<2, C>, 3>(table);
-            let table = create_secp256r1_fixed_base_mul_table::<F, 3, C>();
-            owned_cs.add_lookup_table::<Secp256r1FixedBaseMulTable<3, C>, 3>(table);
-            let table = create_secp256r1_fixed_base_mul_table::<F, 4, C>();
-            owned_cs.add_lookup_table::<Secp256r1FixedBaseMulTable<4, C>, 3>(table);
-            let table = create_secp256r1_fixed_base_mul_table::<F, 5, C>();
-            owned_cs.add_lookup_table::<Secp256r1FixedBaseMulTable<5, C>, 3>(table);
-            let table = create_secp256r1_fixed_base_mul_table::<F, 6, C>();
-            owned_cs.add_lookup_table::<Secp256r1FixedBaseMulTable<6, C>, 3>(table);
-            let table = create_secp256r1_fixed_base_mul_table::<F, 7, C>();
-            owned_cs.add_lookup_table::<Secp256r1FixedBaseMulTable<7, C>, 3>(table);
-        });
-
-        let table = create_byte_split_table::<F, 4>();
-        owned_cs.add_lookup_table::<ByteSplitTable<4>, 3>(table);
-
+        let mut owned_cs = create_test_cs(1 << 20);
         let cs = &mut owned_cs;
 
         let digest =
@@ -749,10 +658,7 @@ mod test {
         let s = UInt256::allocate(cs, s_u256);
         let digest = UInt256::allocate(cs, digest_u256);
 
-        let scalar_params = Arc::new(scalar_params);
-        let base_params = Arc::new(base_params);
-
-        let (no_error, is_valid) = secp256r1_verify_function_inner(
+        let (no_error, is_valid) = secp256r1_verify_function_inner::<_, _, false>(
             cs,
             &r,
             &s,
@@ -775,4 +681,65 @@ mod test {
         let worker = Worker::new();
         assert!(cs.check_if_satisfied(&worker));
     }
+
+    #[test]
+    fn test_secp256r1_low_s_enforcement() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        // same signature as above, it has high s
+        let digest =
+            hex::decode("3fec5769b5cf4e310a7d150508e82fb8e3eda1c2c94c61492d3bd8aea99e06c9")
+                .unwrap();
+        let pk_x = hex::decode("31a80482dadf89de6302b1988c82c29544c9c07bb910596158f6062517eb089a")
+            .unwrap();
+        let pk_y = hex::decode("2f54c9a0f348752950094d3228d3b940258c75fe2a413cb70baa21dc2e352fc5")
+            .unwrap();
+        let r = hex::decode("e22466e928fdccef0de49e3503d2657d00494a00e764fd437bdafa05f5922b1f")
+            .unwrap();
+        let s = hex::decode("bbb77c6817ccf50748419477e843d5bac67e6a70e97dde5a57e0c983b777e1ad")
+            .unwrap();
+
+        let secp_n = U256::from_str_radix(
+            "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551",
+            16,
+        )
+        .unwrap();
+        let high_s = U256::from_big_endian(&s);
+        assert!(high_s > secp_n / U256::from(2u64));
+        let low_s = secp_n - high_s;
+
+        let scalar_params = secp256r1_scalar_field_params();
+        let base_params = secp256r1_base_field_params();
+
+        let pk_x = UInt256::allocate(cs, U256::from_big_endian(&pk_x));
+        let pk_y = UInt256::allocate(cs, U256::from_big_endian(&pk_y));
+        let r = UInt256::allocate(cs, U256::from_big_endian(&r));
+        let digest = UInt256::allocate(cs, U256::from_big_endian(&digest));
+
+        // (r, s) and (r, n - s) are both valid, and only the second one passes the low-S check
+        for (s_value, expect_no_error) in [(high_s, false), (low_s, true)] {
+            let s = UInt256::allocate(cs, s_value);
+            let (no_error, is_valid) = secp256r1_verify_function_inner::<_, _, true>(
+                cs,
+                &r,
+                &s,
+                &digest,
+                &pk_x,
+                &pk_y,
+                &base_params,
+                &scalar_params,
+            );
+
+            assert_eq!(no_error.witness_hook(&*cs)().unwrap(), expect_no_error);
+            let expected_output = if expect_no_error { U256::one() } else { U256::zero() };
+            assert_eq!(is_valid.witness_hook(&*cs)().unwrap(), expected_output);
+        }
+
+        cs.pad_and_shrink();
+
+        let mut cs = owned_cs.into_assembly::<std::alloc::Global>();
+        let worker = Worker::new();
+        assert!(cs.check_if_satisfied(&worker));
+    }
 }
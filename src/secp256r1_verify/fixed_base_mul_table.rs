@@ -1,5 +1,5 @@
 use boojum::{
-    cs::implementations::lookup_table::LookupTable,
+    cs::{implementations::lookup_table::LookupTable, traits::cs::ConstraintSystem},
     field::SmallField,
     pairing::{
         ff::{Field, PrimeField},
@@ -8,6 +8,7 @@ use boojum::{
 };
 
 use super::{secp256r1::fr::Fr, *};
+use crate::tables::{get_typed_table_id, TableFamilyMember, TypedTableId};
 
 const TABLE_NAME: &'static str = "Secp256k1 FIXEDBASEMUL table";
 
@@ -61,3 +62,33 @@ pub fn create_secp256r1_fixed_base_mul_table<
     assert_eq!(content.len(), 256);
     LookupTable::new_from_content(content, TABLE_NAME.to_string(), 1)
 }
+
+/// Marker of all `Secp256r1FixedBaseMulTable`s
+pub struct Secp256r1FixedBaseMulTables;
+
+impl<const U32_WORD_INDEX: usize, const BYTE_OFFSET: usize> TableFamilyMember
+    for Secp256r1FixedBaseMulTable<U32_WORD_INDEX, BYTE_OFFSET>
+{
+    type Family = Secp256r1FixedBaseMulTables;
+}
+
+/// Ids of all tables, grouped by the byte of the scalar they are used for
+pub fn get_secp256r1_fixed_base_mul_table_ids<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &CS,
+) -> Vec<[TypedTableId<Secp256r1FixedBaseMulTables>; 8]> {
+    let mut full_table_ids = Vec::with_capacity(32);
+    seq_macro::seq!(C in 0..32 {
+        full_table_ids.push([
+            get_typed_table_id::<Secp256r1FixedBaseMulTable<0, C>, F, CS>(cs).into_family(),
+            get_typed_table_id::<Secp256r1FixedBaseMulTable<1, C>, F, CS>(cs).into_family(),
+            get_typed_table_id::<Secp256r1FixedBaseMulTable<2, C>, F, CS>(cs).into_family(),
+            get_typed_table_id::<Secp256r1FixedBaseMulTable<3, C>, F, CS>(cs).into_family(),
+            get_typed_table_id::<Secp256r1FixedBaseMulTable<4, C>, F, CS>(cs).into_family(),
+            get_typed_table_id::<Secp256r1FixedBaseMulTable<5, C>, F, CS>(cs).into_family(),
+            get_typed_table_id::<Secp256r1FixedBaseMulTable<6, C>, F, CS>(cs).into_family(),
+            get_typed_table_id::<Secp256r1FixedBaseMulTable<7, C>, F, CS>(cs).into_family(),
+        ]);
+    });
+
+    full_table_ids
+}
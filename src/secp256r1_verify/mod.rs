@@ -1,3 +1,5 @@
+use std::sync::{Arc, OnceLock};
+
 use boojum::{
     cs::traits::cs::ConstraintSystem,
     field::SmallField,
@@ -29,6 +31,9 @@ pub const MEMORY_QUERIES_PER_CALL: usize = 5;
 
 pub mod baseline;
 
+#[cfg(test)]
+mod wycheproof_tests;
+
 // characteristics of the base field for secp curve
 use self::secp256r1::fq::Fq as Secp256Fq;
 // order of group of points for secp curve
@@ -47,12 +52,20 @@ type Secp256ScalarNNFieldParams = NonNativeFieldOverU16Params<Secp256Fr, 17>;
 type Secp256BaseNNField<F> = NonNativeFieldOverU16<F, Secp256Fq, 17>;
 type Secp256ScalarNNField<F> = NonNativeFieldOverU16<F, Secp256Fr, 17>;
 
-fn secp256r1_base_field_params() -> Secp256BaseNNFieldParams {
-    NonNativeFieldOverU16Params::create()
+// params are only derived from the field modulus, so they are created once and shared
+static SECP256R1_BASE_PARAMS: OnceLock<Arc<Secp256BaseNNFieldParams>> = OnceLock::new();
+static SECP256R1_SCALAR_PARAMS: OnceLock<Arc<Secp256ScalarNNFieldParams>> = OnceLock::new();
+
+fn secp256r1_base_field_params() -> Arc<Secp256BaseNNFieldParams> {
+    SECP256R1_BASE_PARAMS
+        .get_or_init(|| Arc::new(NonNativeFieldOverU16Params::create()))
+        .clone()
 }
 
-fn secp256r1_scalar_field_params() -> Secp256ScalarNNFieldParams {
-    NonNativeFieldOverU16Params::create()
+fn secp256r1_scalar_field_params() -> Arc<Secp256ScalarNNFieldParams> {
+    SECP256R1_SCALAR_PARAMS
+        .get_or_init(|| Arc::new(NonNativeFieldOverU16Params::create()))
+        .clone()
 }
 
 // re-exports for integration
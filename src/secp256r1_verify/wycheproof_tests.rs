@@ -0,0 +1,100 @@
+use boojum::{
+    field::goldilocks::GoldilocksField,
+    gadgets::{
+        traits::{allocatable::CSAllocatable, witnessable::WitnessHookable},
+        u256::UInt256,
+    },
+    worker::Worker,
+};
+
+use super::{baseline::secp256r1_verify_function_inner, *};
+use crate::test_utils::create_test_cs;
+
+type F = GoldilocksField;
+
+const WYCHEPROOF_VECTORS_PATH: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/test_vectors/secp256r1_wycheproof.json");
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WycheproofTestSuite {
+    tests: Vec<WycheproofTestVector>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WycheproofTestVector {
+    tc_id: u64,
+    comment: String,
+    flags: Vec<String>,
+    hash: String,
+    r: String,
+    s: String,
+    x: String,
+    y: String,
+    result: String,
+}
+
+impl WycheproofTestVector {
+    fn expected_success(&self) -> bool {
+        match self.result.as_str() {
+            "valid" | "acceptable" => true,
+            "invalid" => false,
+            _ => panic!("unknown result `{}` for test case {}", self.result, self.tc_id),
+        }
+    }
+}
+
+fn decode_u256(value: &str) -> U256 {
+    U256::from_big_endian(&hex::decode(value).unwrap())
+}
+
+// NOTE: the entry point also needs a full memory queue and oracle for every request, so we feed
+// vectors directly into the verification function that it calls per request
+#[test]
+fn test_secp256r1_verify_against_wycheproof_vectors() {
+    let content = std::fs::read_to_string(WYCHEPROOF_VECTORS_PATH).unwrap();
+    let suite: WycheproofTestSuite = serde_json::from_str(&content).unwrap();
+
+    let scalar_params = secp256r1_scalar_field_params();
+    let base_params = secp256r1_base_field_params();
+
+    for vector in suite.tests.iter() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let r = UInt256::allocate(cs, decode_u256(&vector.r));
+        let s = UInt256::allocate(cs, decode_u256(&vector.s));
+        let digest = UInt256::allocate(cs, decode_u256(&vector.hash));
+        let pk_x = UInt256::allocate(cs, decode_u256(&vector.x));
+        let pk_y = UInt256::allocate(cs, decode_u256(&vector.y));
+
+        let (no_error, is_valid) = secp256r1_verify_function_inner::<_, _, false>(
+            cs,
+            &r,
+            &s,
+            &digest,
+            &pk_x,
+            &pk_y,
+            &base_params,
+            &scalar_params,
+        );
+
+        let no_error = no_error.witness_hook(&*cs)().unwrap();
+        let is_valid = is_valid.witness_hook(&*cs)().unwrap();
+        let success = no_error && is_valid == U256::one();
+        assert_eq!(
+            success,
+            vector.expected_success(),
+            "test case {} ({}, flags {:?})",
+            vector.tc_id,
+            vector.comment,
+            vector.flags
+        );
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+}
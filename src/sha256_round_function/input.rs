@@ -56,6 +56,8 @@ pub struct Sha256RoundFunctionFSMInputOutput<F: SmallField> {
     pub internal_fsm: Sha256RoundFunctionFSM<F>,
     pub log_queue_state: QueueState<F, QUEUE_STATE_WIDTH>,
     pub memory_queue_state: QueueState<F, FULL_SPONGE_QUEUE_STATE_WIDTH>,
+    pub num_requests_processed: UInt32<F>,
+    pub gas_used: UInt32<F>,
 }
 
 impl<F: SmallField> CSPlaceholder<F> for Sha256RoundFunctionFSMInputOutput<F> {
@@ -64,6 +66,8 @@ impl<F: SmallField> CSPlaceholder<F> for Sha256RoundFunctionFSMInputOutput<F> {
             internal_fsm: Sha256RoundFunctionFSM::placeholder(cs),
             log_queue_state: QueueState::<F, QUEUE_STATE_WIDTH>::placeholder(cs),
             memory_queue_state: QueueState::<F, FULL_SPONGE_QUEUE_STATE_WIDTH>::placeholder(cs),
+            num_requests_processed: UInt32::zero(cs),
+            gas_used: UInt32::zero(cs),
         }
     }
 }
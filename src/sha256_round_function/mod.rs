@@ -16,7 +16,6 @@ use boojum::{
             selectable::Selectable,
             witnessable::WitnessHookable,
         },
-        u160::UInt160,
         u256::UInt256,
         u32::UInt32,
         u8::UInt8,
@@ -29,10 +28,12 @@ use super::*;
 use crate::{
     base_structures::{
         log_query::*, memory_query::*, precompile_input_outputs::PrecompileFunctionOutputData,
+        precompile_kind::PrecompileKind,
     },
     demux_log_queue::StorageLogQueue,
     ethereum_types::U256,
     fsm_input_output::{circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH, *},
+    gas_meter::{GasMeter, SHA256_GAS_COST_PER_CALL},
     storage_application::ConditionalWitnessAllocator,
 };
 
@@ -88,6 +89,7 @@ pub fn sha256_precompile_inner<
     cs: &mut CS,
     memory_queue: &mut MemoryQueue<F, R>,
     precompile_calls_queue: &mut StorageLogQueue<F, R>,
+    gas_meter: &mut GasMeter<F>,
     memory_read_witness: ConditionalWitnessAllocator<F, UInt256<F>>,
     mut state: Sha256RoundFunctionFSM<F>,
     _round_function: &R,
@@ -101,14 +103,12 @@ where
 {
     assert!(limit <= u32::MAX as usize);
 
-    let precompile_address = UInt160::allocated_constant(
-        cs,
-        *zkevm_opcode_defs::system_params::SHA256_ROUND_FUNCTION_PRECOMPILE_FORMAL_ADDRESS,
-    );
+    let precompile_address = PrecompileKind::Sha256.to_address(cs);
     let aux_byte_for_precompile = UInt8::allocated_constant(cs, PRECOMPILE_AUX_BYTE);
 
     let boolean_false = Boolean::allocated_constant(cs, false);
     let boolean_true = Boolean::allocated_constant(cs, true);
+    let gas_cost_per_call = UInt32::allocated_constant(cs, SHA256_GAS_COST_PER_CALL);
     let zero_u32 = UInt32::zero(cs);
     let zero_u256 = UInt256::zero(cs);
 
@@ -146,6 +146,8 @@ where
         }
         // if we are in a proper state then get the ABI from the queue
         let (precompile_call, _) = precompile_calls_queue.pop_front(cs, state.read_precompile_call);
+        let gas_overflow = gas_meter.charge(cs, gas_cost_per_call, state.read_precompile_call);
+        Boolean::enforce_equal(cs, &gas_overflow, &boolean_false);
 
         Num::conditionally_enforce_equal(
             cs,
@@ -412,10 +414,19 @@ where
         &structured_input.hidden_fsm_input.internal_fsm,
     );
 
+    let zero_u32 = UInt32::zero(cs);
+    let mut gas_meter = GasMeter::new(UInt32::conditionally_select(
+        cs,
+        start_flag,
+        &zero_u32,
+        &structured_input.hidden_fsm_input.gas_used,
+    ));
+
     let final_state = sha256_precompile_inner::<F, CS, R>(
         cs,
         &mut memory_queue,
         &mut requests_queue,
+        &mut gas_meter,
         read_queries_allocator,
         initial_state,
         round_function,
@@ -441,6 +452,35 @@ where
     structured_input.hidden_fsm_output.log_queue_state = final_requets_state;
     structured_input.hidden_fsm_output.memory_queue_state = final_memory_state;
 
+    // requests are popped by the inner routine, so we count them by the change of the queue length
+    let num_requests_popped = requests_queue_state
+        .tail
+        .length
+        .sub_no_overflow(cs, final_requets_state.tail.length);
+    let num_requests_processed = UInt32::conditionally_select(
+        cs,
+        start_flag,
+        &zero_u32,
+        &structured_input.hidden_fsm_input.num_requests_processed,
+    );
+    structured_input.hidden_fsm_output.num_requests_processed =
+        num_requests_processed.add_no_overflow(cs, num_requests_popped);
+
+    // hashing can not fail, so every processed request is a successful call
+    structured_input.observable_output.num_successful_calls = UInt32::conditionally_select(
+        cs,
+        structured_input.completion_flag,
+        &structured_input.hidden_fsm_output.num_requests_processed,
+        &structured_input.observable_output.num_successful_calls,
+    );
+    structured_input.hidden_fsm_output.gas_used = gas_meter.gas_used;
+    structured_input.observable_output.gas_used = UInt32::conditionally_select(
+        cs,
+        structured_input.completion_flag,
+        &gas_meter.gas_used,
+        &structured_input.observable_output.gas_used,
+    );
+
     // self-check
     structured_input.hook_compare_witness(cs, &closed_form_input);
 
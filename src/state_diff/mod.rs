@@ -0,0 +1,401 @@
+use std::sync::Arc;
+
+use boojum::{
+    algebraic_props::round_function::AlgebraicRoundFunction,
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{
+        boolean::Boolean,
+        keccak256,
+        num::Num,
+        queue::CircuitQueueWitness,
+        traits::{
+            allocatable::{CSAllocatable, CSAllocatableExt, CSPlaceholder},
+            round_function::CircuitRoundFunction,
+            selectable::Selectable,
+        },
+        u256::UInt256,
+        u32::UInt32,
+        u8::UInt8,
+    },
+};
+
+use crate::{
+    base_structures::log_query::LogQuery,
+    demux_log_queue::StorageLogQueue,
+    ethereum_types::U256,
+    fsm_input_output::circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH,
+    linear_hasher::{
+        input::*, keccak256_absorb_log_queries, keccak256_empty_hash, keccak256_squeeze,
+    },
+};
+
+// value compression operations, the metadata byte is `(length << 3) | operation`, where `length`
+// is the number of significant bytes of the payload. Zero metadata means that the final value is
+// put as is
+pub const STATE_DIFF_COMPRESSION_NONE: u8 = 0;
+pub const STATE_DIFF_COMPRESSION_ADD: u8 = 1;
+pub const STATE_DIFF_COMPRESSION_SUB: u8 = 2;
+pub const STATE_DIFF_COMPRESSION_TRANSFORM: u8 = 3;
+
+/// Address, key, metadata byte and the payload. The payload always takes 32 bytes, so every entry
+/// has the same length and `length` from the metadata tells how many of its low bytes matter
+pub const STATE_DIFF_ENTRY_BYTE_LENGTH: usize = 20 + 32 + 1 + 32;
+
+fn significant_bytes_length<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    value: &UInt256<F>,
+) -> UInt32<F> {
+    let mut all_zero_so_far = Boolean::allocated_constant(cs, true);
+    let mut num_leading_zeroes = Num::zero(cs);
+    for byte in value.to_be_bytes(cs).iter() {
+        let is_zero = byte.is_zero(cs);
+        all_zero_so_far = all_zero_so_far.and(cs, is_zero);
+        num_leading_zeroes =
+            num_leading_zeroes.add(cs, &Num::from_variable(all_zero_so_far.get_variable()));
+    }
+
+    let max_length = Num::allocated_constant(cs, F::from_u64_unchecked(32));
+    let length = max_length.sub(cs, &num_leading_zeroes);
+
+    // at most 32 by construction
+    unsafe { UInt32::from_variable_unchecked(length.get_variable()) }
+}
+
+fn is_less_than<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    a: UInt32<F>,
+    b: UInt32<F>,
+) -> Boolean<F> {
+    let (_, borrow) = a.overflowing_sub(cs, b);
+
+    borrow
+}
+
+/// Serializes a deduplicated storage write as `address || key || metadata || payload`. The
+/// operation that gives the shortest payload is used, preferring add, then sub, then transform
+pub fn encode_state_diff<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    storage_log: &LogQuery<F>,
+) -> [UInt8<F>; STATE_DIFF_ENTRY_BYTE_LENGTH] {
+    let initial_value = storage_log.read_value;
+    let final_value = storage_log.written_value;
+
+    let (add_payload, _) = final_value.overflowing_sub(cs, &initial_value);
+    let (sub_payload, _) = initial_value.overflowing_sub(cs, &final_value);
+
+    let add_length = significant_bytes_length(cs, &add_payload);
+    let sub_length = significant_bytes_length(cs, &sub_payload);
+    let transform_length = significant_bytes_length(cs, &final_value);
+
+    let sub_is_shorter_than_add = is_less_than(cs, sub_length, add_length);
+    let transform_is_shorter_than_add = is_less_than(cs, transform_length, add_length);
+    let transform_is_shorter_than_sub = is_less_than(cs, transform_length, sub_length);
+
+    let add_is_not_longer =
+        Boolean::multi_or(cs, &[sub_is_shorter_than_add, transform_is_shorter_than_add])
+            .negated(cs);
+    let sub_is_not_longer = transform_is_shorter_than_sub.negated(cs);
+
+    let add_op = UInt32::allocated_constant(cs, STATE_DIFF_COMPRESSION_ADD as u32);
+    let sub_op = UInt32::allocated_constant(cs, STATE_DIFF_COMPRESSION_SUB as u32);
+    let transform_op = UInt32::allocated_constant(cs, STATE_DIFF_COMPRESSION_TRANSFORM as u32);
+
+    let op = UInt32::conditionally_select(cs, sub_is_not_longer, &sub_op, &transform_op);
+    let length =
+        UInt32::conditionally_select(cs, sub_is_not_longer, &sub_length, &transform_length);
+    let payload = UInt256::conditionally_select(cs, sub_is_not_longer, &sub_payload, &final_value);
+
+    let op = UInt32::conditionally_select(cs, add_is_not_longer, &add_op, &op);
+    let length = UInt32::conditionally_select(cs, add_is_not_longer, &add_length, &length);
+    let payload = UInt256::conditionally_select(cs, add_is_not_longer, &add_payload, &payload);
+
+    // nothing is gained if the shortest payload takes the whole word
+    let full_length = UInt32::allocated_constant(cs, 32);
+    let is_uncompressed = UInt32::equals(cs, &length, &full_length);
+
+    let metadata = Num::fma(
+        cs,
+        &length.into_num(),
+        &Num::allocated_constant(cs, F::ONE),
+        &F::from_u64_unchecked(8),
+        &op.into_num(),
+        &F::ONE,
+    );
+    // at most `(31 << 3) | 3` once masked
+    let metadata = unsafe { UInt8::from_variable_unchecked(metadata.get_variable()) };
+    let metadata = metadata.mask_negated(cs, is_uncompressed);
+    let payload = UInt256::conditionally_select(cs, is_uncompressed, &final_value, &payload);
+
+    let zero_u8 = UInt8::zero(cs);
+    let mut result = [zero_u8; STATE_DIFF_ENTRY_BYTE_LENGTH];
+    let mut offset = 0;
+
+    let bytes_be = storage_log.address.to_be_bytes(cs);
+    result[offset..(offset + bytes_be.len())].copy_from_slice(&bytes_be);
+    offset += bytes_be.len();
+
+    let bytes_be = storage_log.key.to_be_bytes(cs);
+    result[offset..(offset + bytes_be.len())].copy_from_slice(&bytes_be);
+    offset += bytes_be.len();
+
+    result[offset] = metadata;
+    offset += 1;
+
+    let bytes_be = payload.to_be_bytes(cs);
+    result[offset..(offset + bytes_be.len())].copy_from_slice(&bytes_be);
+    offset += bytes_be.len();
+
+    assert_eq!(offset, STATE_DIFF_ENTRY_BYTE_LENGTH);
+
+    result
+}
+
+/// Out of circuit counterpart of `encode_state_diff`
+pub fn state_diff_witness_into_bytes<F: SmallField>(
+    witness: &<LogQuery<F> as CSAllocatable<F>>::Witness,
+) -> [u8; STATE_DIFF_ENTRY_BYTE_LENGTH] {
+    fn significant_bytes_length(value: U256) -> usize {
+        (value.bits() + 7) / 8
+    }
+
+    let initial_value = witness.read_value;
+    let final_value = witness.written_value;
+
+    let candidates = [
+        (STATE_DIFF_COMPRESSION_ADD, final_value.overflowing_sub(initial_value).0),
+        (STATE_DIFF_COMPRESSION_SUB, initial_value.overflowing_sub(final_value).0),
+        (STATE_DIFF_COMPRESSION_TRANSFORM, final_value),
+    ];
+    // first one wins on ties
+    let (op, payload) = candidates
+        .into_iter()
+        .min_by_key(|(_, payload)| significant_bytes_length(*payload))
+        .unwrap();
+    let length = significant_bytes_length(payload);
+    let (metadata, payload) = if length == 32 {
+        (STATE_DIFF_COMPRESSION_NONE, final_value)
+    } else {
+        (((length as u8) << 3) | op, payload)
+    };
+
+    let mut result = [0u8; STATE_DIFF_ENTRY_BYTE_LENGTH];
+    result[..20].copy_from_slice(witness.address.as_bytes());
+    witness.key.to_big_endian(&mut result[20..52]);
+    result[52] = metadata;
+    payload.to_big_endian(&mut result[53..]);
+
+    result
+}
+
+/// Hashes the sorted and deduplicated storage writes encoded by `encode_state_diff` with
+/// keccak256. Same as `linear_hasher_entry_point` otherwise, so only a single instance is
+/// supported and the hash of the empty queue is the hash of empty bytes
+pub fn state_diff_encode_entry_point<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+>(
+    cs: &mut CS,
+    witness: LinearHasherCircuitInstanceWitness<F>,
+    round_function: &R,
+    limit: usize,
+) -> [Num<F>; INPUT_OUTPUT_COMMITMENT_LENGTH]
+where
+    [(); <LogQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+    [(); <UInt256<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN + 1]:,
+{
+    assert!(limit <= u32::MAX as usize);
+
+    let LinearHasherCircuitInstanceWitness { closed_form_input, queue_witness } = witness;
+
+    let mut structured_input =
+        LinearHasherInputOutput::alloc_ignoring_outputs(cs, closed_form_input.clone());
+    let start_flag = structured_input.start_flag;
+
+    let zero_u8: UInt8<F> = UInt8::zero(cs);
+    let boolean_true = Boolean::allocated_constant(cs, true);
+
+    // only 1 instance of the circuit here for now
+    Boolean::enforce_equal(cs, &start_flag, &boolean_true);
+
+    let queue_state_from_input = structured_input.observable_input.queue_state;
+
+    // it must be trivial
+    queue_state_from_input.enforce_trivial_head(cs);
+
+    let mut queue = StorageLogQueue::<F, R>::from_state(cs, queue_state_from_input);
+    let queue_witness = CircuitQueueWitness::from_inner_witness(queue_witness);
+    queue.witness = Arc::new(queue_witness);
+
+    let keccak_accumulator_state =
+        [[[zero_u8; keccak256::BYTES_PER_WORD]; keccak256::LANE_WIDTH]; keccak256::LANE_WIDTH];
+
+    let mut keccak_accumulator_state =
+        keccak_accumulator_state.map(|el| el.map(|el| el.map(|el| el.get_variable())));
+
+    let empty_hash = keccak256_empty_hash(cs);
+
+    let no_work = queue.is_empty(cs);
+    let _ = keccak256_absorb_log_queries(
+        cs,
+        &mut queue,
+        &mut keccak_accumulator_state,
+        limit,
+        |cs, storage_log, should_pop| {
+            // reads are removed by deduplication, so we only expect writes here
+            storage_log
+                .rw_flag
+                .conditionally_enforce_true(cs, should_pop);
+
+            encode_state_diff(cs, storage_log)
+        },
+    );
+
+    queue.enforce_consistency(cs);
+    let completed = queue.is_empty(cs);
+
+    Boolean::enforce_equal(cs, &completed, &boolean_true);
+
+    structured_input.completion_flag = completed;
+
+    let fsm_output = LinearHasherFSMState::placeholder(cs);
+    structured_input.hidden_fsm_output = fsm_output;
+
+    let keccak256_hash = keccak256_squeeze(&keccak_accumulator_state);
+
+    let keccak256_hash =
+        <[UInt8<F>; 32]>::conditionally_select(cs, no_work, &empty_hash, &keccak256_hash);
+
+    let mut observable_output = LinearHasherOutputData::placeholder(cs);
+    observable_output.keccak256_hash = keccak256_hash;
+    structured_input.observable_output = observable_output;
+
+    // self-check
+    structured_input.hook_compare_witness(cs, &closed_form_input);
+
+    use boojum::cs::gates::PublicInputGate;
+
+    use crate::fsm_input_output::{
+        commit_variable_length_encodable_item, ClosedFormInputCompactForm,
+    };
+
+    let compact_form =
+        ClosedFormInputCompactForm::from_full_form(cs, &structured_input, round_function);
+    let input_commitment = commit_variable_length_encodable_item(cs, &compact_form, round_function);
+    for el in input_commitment.iter() {
+        let gate = PublicInputGate::new(el.get_variable());
+        gate.add_to_cs(cs);
+    }
+
+    input_commitment
+}
+
+#[cfg(test)]
+mod test {
+    use boojum::{
+        field::goldilocks::GoldilocksField,
+        gadgets::{queue::CircuitQueueRawWitness, traits::witnessable::WitnessHookable},
+        implementations::poseidon2::Poseidon2Goldilocks,
+        worker::Worker,
+    };
+    use zkevm_opcode_defs::sha3::*;
+
+    use super::*;
+    use crate::{
+        base_structures::log_query::LogQueryWitness, ethereum_types::Address,
+        test_utils::create_test_cs,
+    };
+
+    type F = GoldilocksField;
+    type R = Poseidon2Goldilocks;
+
+    fn storage_write(key: u64, read_value: U256, written_value: U256) -> LogQueryWitness<F> {
+        LogQueryWitness {
+            address: Address::from_low_u64_be(0x8008),
+            key: U256::from(key),
+            read_value,
+            written_value,
+            aux_byte: 0,
+            rw_flag: true,
+            rollback: false,
+            is_service: false,
+            shard_id: 0,
+            tx_number_in_block: 3,
+            timestamp: 1024,
+        }
+    }
+
+    #[test]
+    fn test_state_diff_native_compression() {
+        let encoded = state_diff_witness_into_bytes(&storage_write(
+            1,
+            U256::from(1000u64),
+            U256::from(1001u64),
+        ));
+        assert_eq!(encoded[52], (1 << 3) | STATE_DIFF_COMPRESSION_ADD);
+
+        let encoded =
+            state_diff_witness_into_bytes(&storage_write(1, U256::from(1000u64), U256::from(1u64)));
+        assert_eq!(encoded[52], (1 << 3) | STATE_DIFF_COMPRESSION_TRANSFORM);
+
+        let encoded = state_diff_witness_into_bytes(&storage_write(
+            1,
+            U256::from(70000u64),
+            U256::from(69999u64),
+        ));
+        assert_eq!(encoded[52], (1 << 3) | STATE_DIFF_COMPRESSION_SUB);
+
+        let encoded = state_diff_witness_into_bytes(&storage_write(1, U256::zero(), U256::MAX));
+        assert_eq!(encoded[52], STATE_DIFF_COMPRESSION_NONE);
+        assert!(encoded[53..].iter().all(|el| *el == 0xff));
+    }
+
+    #[test]
+    fn test_state_diff_encode() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+        let round_function = Poseidon2Goldilocks;
+
+        let storage_writes = vec![
+            storage_write(1, U256::from(1000u64), U256::from(1001u64)),
+            storage_write(2, U256::from(70000u64), U256::from(69999u64)),
+            storage_write(3, U256::MAX, U256::from(1u64)),
+            storage_write(4, U256::zero(), U256::MAX),
+        ];
+
+        let boolean_true = Boolean::allocated_constant(cs, true);
+        let mut queue = StorageLogQueue::<F, R>::empty(cs);
+        let mut expected_encoding = vec![];
+        for storage_write in storage_writes.iter() {
+            expected_encoding.extend(state_diff_witness_into_bytes(storage_write));
+            let el = LogQuery::allocate(cs, storage_write.clone());
+            queue.push(cs, el, boolean_true);
+        }
+
+        let queue_witness =
+            CircuitQueueRawWitness { elements: queue.witness.elements.read().unwrap().clone() };
+        let queue_state = queue.into_state().witness_hook(&*cs)().unwrap();
+
+        let expected_hash: [u8; 32] = Keccak256::digest(&expected_encoding).into();
+
+        let closed_form_input = LinearHasherInputOutputWitness {
+            start_flag: true,
+            completion_flag: true,
+            observable_input: LinearHasherInputDataWitness { queue_state },
+            observable_output: LinearHasherOutputDataWitness { keccak256_hash: expected_hash },
+            hidden_fsm_input: LinearHasherFSMState::placeholder_witness(),
+            hidden_fsm_output: LinearHasherFSMState::placeholder_witness(),
+        };
+        let witness = LinearHasherCircuitInstanceWitness { closed_form_input, queue_witness };
+
+        // the in-circuit encoding has to match the native one for the self-check to pass
+        let _ = state_diff_encode_entry_point(cs, witness, &round_function, storage_writes.len());
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+}
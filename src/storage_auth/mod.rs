@@ -0,0 +1,321 @@
+use std::sync::Arc;
+
+use boojum::{
+    algebraic_props::round_function::AlgebraicRoundFunction,
+    cs::traits::cs::ConstraintSystem,
+    field::SmallField,
+    gadgets::{
+        boolean::Boolean,
+        num::Num,
+        traits::{allocatable::CSAllocatableExt, round_function::CircuitRoundFunction},
+        u160::UInt160,
+        u256::UInt256,
+        u32::UInt32,
+        u8::UInt8,
+    },
+};
+
+use crate::{
+    base_structures::memory_query::{MemoryQuery, MemoryQueue},
+    ecrecover::{
+        new_optimized::{
+            allocate_masking_constants, ecrecover_precompile_inner_routine, ALLOW_ZERO_MESSAGE,
+        },
+        secp256k1_base_field_params, secp256k1_scalar_field_params,
+    },
+    utils::keccak256_of_bytes,
+};
+
+// slot key, old value, new value, recovery id, r, s
+pub const STORAGE_AUTH_MEMORY_QUERIES: usize = 6;
+
+// three words are signed
+const AUTHORIZATION_MESSAGE_LEN: usize = 32 * 3;
+
+/// Computes `keccak256(slot_key || old_value || new_value)` with all values in big-endian form
+pub fn storage_update_auth_digest<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    slot_key: &UInt256<F>,
+    old_value: &UInt256<F>,
+    new_value: &UInt256<F>,
+) -> UInt256<F> {
+    let zero_u8 = UInt8::zero(cs);
+    let mut bytes_to_hash = [zero_u8; AUTHORIZATION_MESSAGE_LEN];
+    for (dst, src) in bytes_to_hash
+        .array_chunks_mut::<32>()
+        .zip([slot_key, old_value, new_value].into_iter())
+    {
+        *dst = src.to_be_bytes(cs);
+    }
+
+    let mut digest_bytes = keccak256_of_bytes(cs, &bytes_to_hash);
+    digest_bytes.reverse();
+
+    UInt256::from_le_bytes(cs, digest_bytes)
+}
+
+/// Recovers the signer of the authorization digest, and returns whether recovery succeeded and
+/// the signer is `authorized_address`
+pub fn verify_storage_update_auth<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    slot_key: &UInt256<F>,
+    old_value: &UInt256<F>,
+    new_value: &UInt256<F>,
+    recid: &UInt8<F>,
+    r: &UInt256<F>,
+    s: &UInt256<F>,
+    authorized_address: &UInt160<F>,
+) -> Boolean<F> {
+    let digest = storage_update_auth_digest(cs, slot_key, old_value, new_value);
+
+    let scalar_params = Arc::new(secp256k1_scalar_field_params());
+    let base_params = Arc::new(secp256k1_base_field_params());
+    let (valid_x_in_external_field, valid_y_in_external_field, valid_t_in_external_field) =
+        allocate_masking_constants(cs, &base_params);
+
+    let (success, signer) = ecrecover_precompile_inner_routine::<_, _, ALLOW_ZERO_MESSAGE>(
+        cs,
+        recid,
+        r,
+        s,
+        &digest,
+        valid_x_in_external_field,
+        valid_y_in_external_field,
+        valid_t_in_external_field,
+        &base_params,
+        &scalar_params,
+    );
+
+    // recovered address is in the lowest 20 bytes, and the top ones are always zero
+    let mut checks = Vec::with_capacity(1 + 5);
+    checks.push(success);
+    for (a, b) in signer.inner[..5]
+        .iter()
+        .zip(authorized_address.inner.iter())
+    {
+        checks.push(UInt32::equals(cs, a, b));
+    }
+
+    Boolean::multi_and(cs, &checks)
+}
+
+/// Reads `(slot_key, old_value, new_value, recid, r, s)` from `page` starting at `offset`, with
+/// the recovery id in the lowest byte of its word and the rest of the word zero, and checks that
+/// the update is signed by `authorized_address`. The caller exposes the address as a public input
+/// of the circuit
+pub fn storage_update_auth_entry_point<
+    F: SmallField,
+    CS: ConstraintSystem<F>,
+    R: CircuitRoundFunction<F, 8, 12, 4> + AlgebraicRoundFunction<F, 8, 12, 4>,
+>(
+    cs: &mut CS,
+    memory_queue: &mut MemoryQueue<F, R>,
+    page: UInt32<F>,
+    offset: UInt32<F>,
+    timestamp: UInt32<F>,
+    read_values: [UInt256<F>; STORAGE_AUTH_MEMORY_QUERIES],
+    authorized_address: UInt160<F>,
+) -> Boolean<F>
+where
+    [(); <MemoryQuery<F> as CSAllocatableExt<F>>::INTERNAL_STRUCT_LEN]:,
+{
+    let boolean_false = Boolean::allocated_constant(cs, false);
+    let boolean_true = Boolean::allocated_constant(cs, true);
+    let one_u32 = UInt32::allocated_constant(cs, 1u32);
+
+    let mut index = offset;
+    for value in read_values.iter() {
+        let read_query = MemoryQuery {
+            timestamp,
+            memory_page: page,
+            index,
+            rw_flag: boolean_false,
+            is_ptr: boolean_false,
+            value: *value,
+        };
+
+        let _ = memory_queue.push(cs, read_query, boolean_true);
+
+        index = index.add_no_overflow(cs, one_u32);
+    }
+
+    let [slot_key, old_value, new_value, recid_as_u256, r, s] = read_values;
+    let recid = recid_as_u256.inner[0].to_le_bytes(cs)[0];
+    // the recovery id must be the whole word and not only its lowest byte
+    let zero_num = Num::zero(cs);
+    Num::enforce_equal(cs, &recid_as_u256.inner[0].into_num(), &recid.into_num());
+    for word in recid_as_u256.inner[1..].iter() {
+        Num::enforce_equal(cs, &word.into_num(), &zero_num);
+    }
+
+    verify_storage_update_auth(
+        cs,
+        &slot_key,
+        &old_value,
+        &new_value,
+        &recid,
+        &r,
+        &s,
+        &authorized_address,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use boojum::{
+        field::goldilocks::GoldilocksField,
+        gadgets::traits::{allocatable::CSAllocatable, witnessable::WitnessHookable},
+        implementations::poseidon2::Poseidon2Goldilocks,
+        pairing::{
+            ff::{Field, PrimeField, PrimeFieldRepr},
+            GenericCurveAffine, GenericCurveProjective,
+        },
+        worker::Worker,
+    };
+    use rand::{Rng, SeedableRng, XorShiftRng};
+    use zkevm_opcode_defs::sha3::*;
+
+    use super::*;
+    use crate::{
+        ecrecover::secp256k1::{fr::Fr as Secp256Fr, PointAffine as Secp256Affine},
+        ethereum_types::{Address, U256},
+        test_utils::create_test_cs,
+    };
+
+    type F = GoldilocksField;
+
+    fn repr_into_u256<T: PrimeFieldRepr>(repr: T) -> U256 {
+        let mut u256 = U256::zero();
+        u256.0.copy_from_slice(&repr.as_ref()[..4]);
+
+        u256
+    }
+
+    fn native_digest(words: [U256; 3]) -> U256 {
+        let mut hasher = Keccak256::new();
+        for word in words {
+            let mut buffer = [0u8; 32];
+            word.to_big_endian(&mut buffer);
+            hasher.update(&buffer);
+        }
+
+        U256::from_big_endian(hasher.finalize().as_slice())
+    }
+
+    // returns the signer address and (recid, r, s)
+    fn sign_digest(digest: U256) -> (Address, u8, U256, U256) {
+        let mut rng = XorShiftRng::from_seed([0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a]);
+        let sk: Secp256Fr = rng.gen();
+        let k: Secp256Fr = rng.gen();
+
+        let (pk_x, pk_y) = Secp256Affine::one()
+            .mul(sk.into_repr())
+            .into_affine()
+            .into_xy_unchecked();
+        let mut encoding = Vec::with_capacity(64);
+        pk_x.into_repr().write_be(&mut encoding).unwrap();
+        pk_y.into_repr().write_be(&mut encoding).unwrap();
+        let address = Address::from_slice(&Keccak256::digest(&encoding)[12..]);
+
+        let (r_x, r_y) = Secp256Affine::one()
+            .mul(k.into_repr())
+            .into_affine()
+            .into_xy_unchecked();
+        let secp_n = repr_into_u256(Secp256Fr::char());
+        let r_x = repr_into_u256(r_x.into_repr());
+        assert!(r_x < secp_n);
+        let recid = r_y.into_repr().is_odd() as u8;
+
+        // s = (digest + r * sk) / k
+        let r = Secp256Fr::from_str(&r_x.to_string()).unwrap();
+        let digest = Secp256Fr::from_str(&(digest % secp_n).to_string()).unwrap();
+        let mut s = r;
+        s.mul_assign(&sk);
+        s.add_assign(&digest);
+        s.mul_assign(&k.inverse().unwrap());
+
+        (address, recid, r_x, repr_into_u256(s.into_repr()))
+    }
+
+    #[test]
+    fn test_storage_update_auth() {
+        let mut owned_cs = create_test_cs(1 << 21);
+        let cs = &mut owned_cs;
+
+        let slot_key = U256::from(3u64);
+        let old_value = U256::from(1_000u64);
+        let new_value = U256::from(750u64);
+        let (address, recid, r, s) = sign_digest(native_digest([slot_key, old_value, new_value]));
+
+        let cases = [
+            (new_value, address, true),
+            (new_value + U256::one(), address, false),
+            (new_value, Address::from_low_u64_be(0x8001), false),
+        ];
+        for (new_value, authorized_address, expected) in cases {
+            let [slot_key, old_value, new_value, r, s] =
+                [slot_key, old_value, new_value, r, s].map(|el| UInt256::allocate(cs, el));
+            let recid = UInt8::allocate_checked(cs, recid);
+            let authorized_address = UInt160::allocate(cs, authorized_address);
+
+            let is_authorized = verify_storage_update_auth(
+                cs,
+                &slot_key,
+                &old_value,
+                &new_value,
+                &recid,
+                &r,
+                &s,
+                &authorized_address,
+            );
+            assert_eq!(is_authorized.witness_hook(&*cs)().unwrap(), expected);
+        }
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+
+    fn check_entry_point_with_recid_word(recid_word: U256) -> bool {
+        let mut owned_cs = create_test_cs(1 << 21);
+        let cs = &mut owned_cs;
+
+        let slot_key = U256::from(3u64);
+        let old_value = U256::from(1_000u64);
+        let new_value = U256::from(750u64);
+        let (address, recid, r, s) = sign_digest(native_digest([slot_key, old_value, new_value]));
+
+        let mut memory_queue = MemoryQueue::<F, Poseidon2Goldilocks>::empty(cs);
+        let page = UInt32::allocate(cs, 1);
+        let offset = UInt32::allocate(cs, 0);
+        let timestamp = UInt32::allocate(cs, 1);
+        let read_values = [slot_key, old_value, new_value, recid_word + U256::from(recid), r, s]
+            .map(|el| UInt256::allocate(cs, el));
+        let authorized_address = UInt160::allocate(cs, address);
+
+        let is_authorized = storage_update_auth_entry_point(
+            cs,
+            &mut memory_queue,
+            page,
+            offset,
+            timestamp,
+            read_values,
+            authorized_address,
+        );
+        assert!(is_authorized.witness_hook(&*cs)().unwrap());
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        owned_cs.check_if_satisfied(&worker)
+    }
+
+    #[test]
+    fn test_recid_word_high_bytes_must_be_zero() {
+        assert!(check_entry_point_with_recid_word(U256::zero()));
+        // garbage in the same limb and in the higher limbs of the word
+        assert!(!check_entry_point_with_recid_word(U256::from(1u64 << 8)));
+        assert!(!check_entry_point_with_recid_word(U256::one() << 255));
+    }
+}
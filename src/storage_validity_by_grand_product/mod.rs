@@ -33,7 +33,10 @@ use boojum::{
 
 use crate::{
     base_structures::{
-        log_query::{log_query_witness_from_values, LogQuery, LOG_QUERY_PACKED_WIDTH},
+        log_query::{
+            log_query_witness_from_values, verify_adjacent_storage_writes_consistent, LogQuery,
+            LOG_QUERY_PACKED_WIDTH,
+        },
         vm_state::*,
     },
     demux_log_queue::StorageLogQueue,
@@ -571,6 +574,10 @@ where
     // - otherwise we continue, and then it's not trivial
     let no_work = original_queue.is_empty(cs);
     let mut previous_item_is_trivial = no_work.or(cs, is_start);
+    // we don't carry the previous record over instance boundaries, so the first item of every
+    // instance is not checked against its predecessor
+    let mut previous_record = LogQuery::placeholder(cs);
+    let mut previous_is_write = Boolean::allocated_constant(cs, false);
 
     // we simultaneously pop, accumulate partial product,
     // and decide whether or not we should move to the next cell
@@ -645,6 +652,19 @@ where
         let must_enforce = keys_are_equal.and(cs, not_item_is_trivial);
         previous_timestamp_is_less.conditionally_enforce_true(cs, must_enforce);
 
+        // adjacent writes to the same slot must be strictly ordered in time. Rollbacks share the
+        // timestamp of the write they revert, so only forward writes are taken into account
+        let not_rollback = record.rollback.negated(cs);
+        let is_write = Boolean::multi_and(cs, &[record.rw_flag, not_rollback, not_item_is_trivial]);
+        let writes_are_consistent = verify_adjacent_storage_writes_consistent(
+            cs,
+            &previous_record,
+            &record,
+            previous_is_write,
+            is_write,
+        );
+        writes_are_consistent.conditionally_enforce_true(cs, not_item_is_trivial);
+
         // we follow the procedure:
         // if keys are different then we finish with a previous one and update parameters
         // else we just update parameters
@@ -831,6 +851,8 @@ where
         previous_item_is_trivial = item_is_trivial;
         previous_timestamp = timestamp;
         previous_packed_key = packed_key;
+        previous_record = record;
+        previous_is_write = is_write;
     }
 
     // finalization step - out of cycle, and only if we are done just yet
@@ -947,10 +969,9 @@ pub fn unpacked_long_comparison<F: SmallField, CS: ConstraintSystem<F>, const N:
 #[cfg(test)]
 mod tests {
     // use boojum::cs::EmptyToolbox;
-    use boojum::cs::*;
     use boojum::{
         algebraic_props::poseidon2_parameters::Poseidon2GoldilocksExternalMatrix,
-        cs::{traits::gate::GatePlacementStrategy, CSGeometry},
+        cs::{traits::gate::GatePlacementStrategy, CSGeometry, *},
         field::goldilocks::GoldilocksField,
         gadgets::tables::*,
         implementations::poseidon2::Poseidon2Goldilocks,
@@ -1,15 +1,110 @@
+use std::marker::PhantomData;
+
+use boojum::{
+    cs::{traits::cs::ConstraintSystem, Variable},
+    field::SmallField,
+    gadgets::tables::{ByteSplitTable, Xor8Table},
+};
 use derivative::*;
 
+use crate::{
+    aes128::sbox::{Aes128MulBy2Table, Aes128SboxTable},
+    ecrecover::secp256k1::fixed_base_mul_table::{FixedBaseMulTable, FixedBaseMulTables},
+    secp256r1_verify::fixed_base_mul_table::{
+        Secp256r1FixedBaseMulTable, Secp256r1FixedBaseMulTables,
+    },
+};
+
 pub mod bitshift;
 pub mod call_costs_and_stipends;
 pub mod conditional;
 pub mod integer_to_boolean_mask;
 pub mod opcodes_decoding;
+pub mod or_nand;
 pub mod pubdata_cost_validity;
 pub mod test_bit;
 pub mod uma_ptr_read_cleanup;
 
 pub use self::{
     bitshift::*, call_costs_and_stipends::*, conditional::*, integer_to_boolean_mask::*,
-    opcodes_decoding::*, pubdata_cost_validity::*, test_bit::*, uma_ptr_read_cleanup::*,
+    opcodes_decoding::*, or_nand::*, pubdata_cost_validity::*, test_bit::*,
+    uma_ptr_read_cleanup::*,
 };
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Markers of the lookup tables that circuits look up through `TypedTableId`. The trait is sealed,
+/// so ids can only be typed by the tables listed below
+pub trait TableMarker: sealed::Sealed + 'static {}
+
+macro_rules! impl_table_marker {
+    ($([$($generics:tt)*] $marker:ty),* $(,)?) => {
+        $(
+            impl<$($generics)*> sealed::Sealed for $marker {}
+            impl<$($generics)*> TableMarker for $marker {}
+        )*
+    };
+}
+
+impl_table_marker!(
+    [] Xor8Table,
+    [const SPLIT_AT: usize] ByteSplitTable<SPLIT_AT>,
+    [] Aes128SboxTable,
+    [] Aes128MulBy2Table,
+    [const WORD: usize, const BYTE: usize] FixedBaseMulTable<WORD, BYTE>,
+    [] FixedBaseMulTables,
+    [const WORD: usize, const BYTE: usize] Secp256r1FixedBaseMulTable<WORD, BYTE>,
+    [] Secp256r1FixedBaseMulTables,
+);
+
+/// Lookup table id that remembers the marker of the table, so ids of different tables can not be
+/// mixed up
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""), Copy(bound = ""), Debug(bound = ""))]
+pub struct TypedTableId<T: TableMarker> {
+    id: u32,
+    _marker: PhantomData<T>,
+}
+
+impl<T: TableMarker> TypedTableId<T> {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn perform_lookup<
+        F: SmallField,
+        CS: ConstraintSystem<F>,
+        const N: usize,
+        const M: usize,
+    >(
+        &self,
+        cs: &mut CS,
+        inputs: &[Variable; N],
+    ) -> [Variable; M] {
+        cs.perform_lookup::<N, M>(self.id, inputs)
+    }
+}
+
+/// Tables that only differ in the part of the content they hold and are always used together, so
+/// ids of all members can be stored under the marker of the family
+pub trait TableFamilyMember: TableMarker {
+    type Family: TableMarker;
+}
+
+impl<T: TableFamilyMember> TypedTableId<T> {
+    pub fn into_family(self) -> TypedTableId<T::Family> {
+        TypedTableId { id: self.id, _marker: PhantomData }
+    }
+}
+
+pub fn get_typed_table_id<T: TableMarker, F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &CS,
+) -> TypedTableId<T> {
+    let id = cs
+        .get_table_id_for_marker::<T>()
+        .expect("table should exist");
+
+    TypedTableId { id, _marker: PhantomData }
+}
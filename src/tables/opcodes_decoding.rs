@@ -10,28 +10,107 @@ pub const VM_OPCODE_DECODING_AND_PRICING_TABLE_NAME: &'static str =
 #[derivative(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct VMOpcodeDecodingTable;
 
+/// Collects `(opcode, price, properties bitmask)` rows of the decoding table, so tables with
+/// custom subsets of opcodes can be created in tests
+#[derive(Derivative)]
+#[derivative(Clone, Debug, Default)]
+pub struct OpcodeDecodingTableBuilder {
+    pub entries: Vec<(u64, u64, u64)>,
+}
+
+impl OpcodeDecodingTableBuilder {
+    pub fn add_opcode(
+        &mut self,
+        opcode_as_integer: u64,
+        price: u32,
+        props_encoding: u64,
+    ) -> &mut Self {
+        self.entries
+            .push((opcode_as_integer, price as u64, props_encoding));
+
+        self
+    }
+
+    pub fn build<F: SmallField>(&self) -> LookupTable<F, 3> {
+        let all_keys = self
+            .entries
+            .iter()
+            .map(|(opcode_as_integer, price, props_encoding)| {
+                [
+                    F::from_u64(*opcode_as_integer).unwrap(),
+                    F::from_u64(*price).unwrap(),
+                    F::from_u64(*props_encoding).unwrap(),
+                ]
+            })
+            .collect();
+
+        LookupTable::new_from_content(
+            all_keys,
+            VM_OPCODE_DECODING_AND_PRICING_TABLE_NAME.to_string(),
+            1,
+        )
+    }
+}
+
 pub fn create_opcodes_decoding_and_pricing_table<F: SmallField>() -> LookupTable<F, 3> {
-    let mut all_keys = Vec::with_capacity(1 << OPCODES_TABLE_WIDTH);
     let num_rows = zkevm_opcode_defs::OPCODES_TABLE.len();
     assert_eq!(num_rows, 1 << OPCODES_TABLE_WIDTH);
 
+    let mut builder = OpcodeDecodingTableBuilder::default();
+    builder.entries.reserve(num_rows);
     for x in 0..num_rows {
-        let opcode_as_integer = x as u64;
-        let opcode_props_encoding = zkevm_opcode_defs::OPCODES_PROPS_INTEGER_BITMASKS[x];
-        let price = zkevm_opcode_defs::OPCODES_PRICES[x];
+        builder.add_opcode(
+            x as u64,
+            zkevm_opcode_defs::OPCODES_PRICES[x],
+            zkevm_opcode_defs::OPCODES_PROPS_INTEGER_BITMASKS[x],
+        );
+    }
 
-        let row = [
-            F::from_u64(opcode_as_integer).unwrap(),
-            F::from_u64(price as u64).unwrap(),
-            F::from_u64(opcode_props_encoding).unwrap(),
-        ];
+    builder.build()
+}
 
-        all_keys.push(row);
-    }
+#[cfg(test)]
+mod tests {
+    use boojum::{
+        cs::traits::cs::ConstraintSystem,
+        field::goldilocks::GoldilocksField,
+        gadgets::{num::Num, traits::witnessable::WitnessHookable},
+        worker::Worker,
+    };
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
 
-    LookupTable::new_from_content(
-        all_keys,
-        VM_OPCODE_DECODING_AND_PRICING_TABLE_NAME.to_string(),
-        1,
-    )
+    #[test]
+    fn test_opcode_decoding_table_subset() {
+        let entries = [(0u64, 6u32, 0b1011u64), (5, 12, 1 << 40), (17, 4, 0)];
+
+        let mut builder = OpcodeDecodingTableBuilder::default();
+        for (opcode, price, props) in entries {
+            builder.add_opcode(opcode, price, props);
+        }
+
+        let mut owned_cs = create_test_cs(1 << 20);
+        owned_cs.add_lookup_table::<VMOpcodeDecodingTable, 3>(builder.build());
+        let cs = &mut owned_cs;
+
+        let table_id = cs
+            .get_table_id_for_marker::<VMOpcodeDecodingTable>()
+            .expect("table must exist");
+        for (opcode, price, props) in entries {
+            let key = cs.allocate_constant(F::from_u64_unchecked(opcode));
+            let [price_var, props_var] = cs.perform_lookup::<1, 2>(table_id, &[key]);
+            let price_value = Num::from_variable(price_var).witness_hook(&*cs)().unwrap();
+            let props_value = Num::from_variable(props_var).witness_hook(&*cs)().unwrap();
+            assert_eq!(price_value.as_u64_reduced(), price as u64);
+            assert_eq!(props_value.as_u64_reduced(), props);
+        }
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
 }
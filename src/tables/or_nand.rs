@@ -0,0 +1,95 @@
+use boojum::{cs::implementations::lookup_table::LookupTable, field::SmallField};
+
+use super::*;
+
+pub const OR8_TABLE_NAME: &'static str = "OR8 table";
+pub const NAND8_TABLE_NAME: &'static str = "NAND8 table";
+
+#[derive(Derivative)]
+#[derivative(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Or8Table;
+
+#[derive(Derivative)]
+#[derivative(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Nand8Table;
+
+pub fn create_or8_table<F: SmallField>() -> LookupTable<F, 3> {
+    create_binop_table(OR8_TABLE_NAME, |a, b| a | b)
+}
+
+pub fn create_nand8_table<F: SmallField>() -> LookupTable<F, 3> {
+    create_binop_table(NAND8_TABLE_NAME, |a, b| !(a & b))
+}
+
+fn create_binop_table<F: SmallField>(name: &str, op: fn(u8, u8) -> u8) -> LookupTable<F, 3> {
+    // two u8 words as keys, and the result of the bitwise operation as the value
+    let num_rows = 1 << 16;
+    let mut all_keys = Vec::with_capacity(num_rows);
+
+    for a in 0..=u8::MAX {
+        for b in 0..=u8::MAX {
+            let row = [
+                F::from_u64_unchecked(a as u64),
+                F::from_u64_unchecked(b as u64),
+                F::from_u64_unchecked(op(a, b) as u64),
+            ];
+            all_keys.push(row);
+        }
+    }
+
+    LookupTable::new_from_content(all_keys, name.to_string(), 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use boojum::{
+        cs::traits::cs::ConstraintSystem,
+        field::goldilocks::GoldilocksField,
+        gadgets::{num::Num, traits::witnessable::WitnessHookable},
+        worker::Worker,
+    };
+
+    use super::*;
+    use crate::test_utils::create_test_cs;
+
+    type F = GoldilocksField;
+
+    fn check_table<CS: ConstraintSystem<F>>(cs: &mut CS, table_id: u32, op: fn(u8, u8) -> u8) {
+        let bytes: Vec<_> = (0..=u8::MAX)
+            .map(|el| cs.allocate_constant(F::from_u64_unchecked(el as u64)))
+            .collect();
+        for a in 0..=u8::MAX {
+            for b in 0..=u8::MAX {
+                let [result] =
+                    cs.perform_lookup::<2, 1>(table_id, &[bytes[a as usize], bytes[b as usize]]);
+                let result = Num::from_variable(result).witness_hook(&*cs)().unwrap();
+                assert_eq!(result.as_u64_reduced(), op(a, b) as u64);
+            }
+        }
+    }
+
+    #[test]
+    fn test_or8_and_nand8_tables() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let table = create_or8_table::<F>();
+        owned_cs.add_lookup_table::<Or8Table, 3>(table);
+        let table = create_nand8_table::<F>();
+        owned_cs.add_lookup_table::<Nand8Table, 3>(table);
+        let cs = &mut owned_cs;
+
+        let or8_table_id = cs
+            .get_table_id_for_marker::<Or8Table>()
+            .expect("table must exist");
+        check_table(cs, or8_table_id, |a, b| a | b);
+
+        let nand8_table_id = cs
+            .get_table_id_for_marker::<Nand8Table>()
+            .expect("table must exist");
+        check_table(cs, nand8_table_id, |a, b| !(a & b));
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+}
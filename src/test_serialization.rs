@@ -0,0 +1,148 @@
+use std::collections::VecDeque;
+
+use boojum::{
+    field::{goldilocks::GoldilocksField, SmallField},
+    gadgets::{
+        queue::{CircuitQueueRawWitness, QueueStateWitness, QueueTailStateWitness},
+        traits::allocatable::CSAllocatable,
+    },
+};
+
+use crate::{
+    base_structures::log_query::{LogQuery, LogQueryWitness, LOG_QUERY_PACKED_WIDTH},
+    ecrecover::{EcrecoverCircuitInputOutput, EcrecoverCircuitInstanceWitness},
+    ethereum_types::{Address, U256},
+    linear_hasher::input::{LinearHasherCircuitInstanceWitness, LinearHasherInputOutput},
+    secp256r1_verify::{Secp256r1VerifyCircuitInputOutput, Secp256r1VerifyCircuitInstanceWitness},
+};
+
+type F = GoldilocksField;
+
+fn round_trip<T: serde::Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+    let encoding = serde_json::to_string(value).unwrap();
+
+    serde_json::from_str(&encoding).unwrap()
+}
+
+fn queue_state<const N: usize>(seed: u64) -> QueueStateWitness<F, N> {
+    QueueStateWitness {
+        head: std::array::from_fn(|i| F::from_u64_unchecked(seed + i as u64)),
+        tail: QueueTailStateWitness {
+            tail: std::array::from_fn(|i| F::from_u64_unchecked(seed * 100 + i as u64)),
+            length: seed as u32,
+        },
+    }
+}
+
+fn log_query(seed: u64) -> LogQueryWitness<F> {
+    LogQueryWitness {
+        address: Address::from_low_u64_be(0x8000 + seed),
+        key: U256::from(seed),
+        read_value: U256::from(seed * 2),
+        written_value: U256::MAX - U256::from(seed),
+        aux_byte: 1,
+        rw_flag: seed % 2 == 0,
+        rollback: false,
+        is_service: true,
+        shard_id: 0,
+        tx_number_in_block: seed as u32,
+        timestamp: 1000 + seed as u32,
+    }
+}
+
+fn queue_witness(
+    num_elements: u64,
+) -> CircuitQueueRawWitness<F, LogQuery<F>, 4, LOG_QUERY_PACKED_WIDTH> {
+    let elements = (0..num_elements)
+        .map(|i| (log_query(i), std::array::from_fn(|j| F::from_u64_unchecked(i * 4 + j as u64))))
+        .collect();
+
+    CircuitQueueRawWitness { elements }
+}
+
+fn assert_queue_witness_eq(
+    a: &CircuitQueueRawWitness<F, LogQuery<F>, 4, LOG_QUERY_PACKED_WIDTH>,
+    b: &CircuitQueueRawWitness<F, LogQuery<F>, 4, LOG_QUERY_PACKED_WIDTH>,
+) {
+    assert_eq!(a.elements.len(), b.elements.len());
+    for ((a_el, a_tail), (b_el, b_tail)) in a.elements.iter().zip(b.elements.iter()) {
+        assert_eq!(a_el, b_el);
+        assert_eq!(a_tail, b_tail);
+    }
+}
+
+fn memory_reads<const N: usize>(num_calls: u64) -> VecDeque<[U256; N]> {
+    (0..num_calls)
+        .map(|i| std::array::from_fn(|j| U256::from((i << 32) | j as u64) << 128))
+        .collect()
+}
+
+#[test]
+fn test_linear_hasher_witness_round_trip() {
+    let mut closed_form_input = LinearHasherInputOutput::<F>::placeholder_witness();
+    closed_form_input.start_flag = true;
+    closed_form_input.completion_flag = true;
+    closed_form_input.observable_input.queue_state = queue_state(3);
+    closed_form_input.observable_output.keccak256_hash = std::array::from_fn(|i| i as u8);
+
+    let witness =
+        LinearHasherCircuitInstanceWitness { closed_form_input, queue_witness: queue_witness(3) };
+    let decoded = round_trip(&witness);
+
+    assert_eq!(decoded.closed_form_input, witness.closed_form_input);
+    assert_queue_witness_eq(&decoded.queue_witness, &witness.queue_witness);
+}
+
+#[test]
+fn test_ecrecover_witness_round_trip() {
+    let mut closed_form_input = EcrecoverCircuitInputOutput::<F>::placeholder_witness();
+    closed_form_input.start_flag = true;
+    closed_form_input.observable_input.initial_log_queue_state = queue_state(2);
+    closed_form_input
+        .observable_input
+        .initial_memory_queue_state = queue_state(5);
+    closed_form_input.observable_output.final_memory_state = queue_state(7);
+    closed_form_input.hidden_fsm_output.log_queue_state = queue_state(1);
+    closed_form_input.hidden_fsm_output.memory_queue_state = queue_state(6);
+    closed_form_input.hidden_fsm_output.num_requests_processed = 2;
+    closed_form_input.hidden_fsm_output.num_successful_calls = 1;
+    closed_form_input.hidden_fsm_output.gas_used = 6000;
+
+    let witness = EcrecoverCircuitInstanceWitness {
+        closed_form_input,
+        requests_queue_witness: queue_witness(2),
+        memory_reads_witness: memory_reads(2),
+    };
+    let decoded = round_trip(&witness);
+
+    assert_eq!(decoded.closed_form_input, witness.closed_form_input);
+    assert_queue_witness_eq(&decoded.requests_queue_witness, &witness.requests_queue_witness);
+    assert_eq!(decoded.memory_reads_witness, witness.memory_reads_witness);
+}
+
+#[test]
+fn test_secp256r1_verify_witness_round_trip() {
+    let mut closed_form_input = Secp256r1VerifyCircuitInputOutput::<F>::placeholder_witness();
+    closed_form_input.start_flag = true;
+    closed_form_input.completion_flag = true;
+    closed_form_input.observable_input.initial_log_queue_state = queue_state(4);
+    closed_form_input
+        .observable_input
+        .initial_memory_queue_state = queue_state(8);
+    closed_form_input.observable_output.final_memory_state = queue_state(9);
+    closed_form_input.observable_output.num_successful_calls = 3;
+    closed_form_input.observable_output.gas_used = 10350;
+    closed_form_input.hidden_fsm_input.log_queue_state = queue_state(2);
+    closed_form_input.hidden_fsm_input.memory_queue_state = queue_state(3);
+
+    let witness = Secp256r1VerifyCircuitInstanceWitness {
+        closed_form_input,
+        requests_queue_witness: queue_witness(4),
+        memory_reads_witness: memory_reads(4),
+    };
+    let decoded = round_trip(&witness);
+
+    assert_eq!(decoded.closed_form_input, witness.closed_form_input);
+    assert_queue_witness_eq(&decoded.requests_queue_witness, &witness.requests_queue_witness);
+    assert_eq!(decoded.memory_reads_witness, witness.memory_reads_witness);
+}
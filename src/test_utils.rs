@@ -0,0 +1,182 @@
+//! Constraint system shared by the unit tests. It has every gate and lookup table used by the
+//! circuits in the crate, so any gadget or entry point can be synthesized and checked in it
+
+use boojum::{
+    algebraic_props::poseidon2_parameters::*,
+    config::DevCSConfig,
+    cs::{
+        cs_builder::*, cs_builder_reference::CsReferenceImplementationBuilder, gates::*,
+        implementations::reference_cs::CSReferenceImplementation, traits::gate::*, *,
+    },
+    field::goldilocks::GoldilocksField,
+    gadgets::tables::{byte_split::ByteSplitTable, *},
+};
+
+use crate::{
+    ecrecover::secp256k1::fixed_base_mul_table::{create_fixed_base_mul_table, FixedBaseMulTable},
+    secp256r1_verify::{create_secp256r1_fixed_base_mul_table, Secp256r1FixedBaseMulTable},
+};
+
+type F = GoldilocksField;
+type P = GoldilocksField;
+
+pub(crate) fn create_test_cs(
+    max_trace_len: usize,
+) -> CSReferenceImplementation<
+    F,
+    P,
+    DevCSConfig,
+    impl GateConfigurationHolder<F>,
+    impl StaticToolboxHolder,
+> {
+    let geometry = CSGeometry {
+        num_columns_under_copy_permutation: 100,
+        num_witness_columns: 0,
+        num_constant_columns: 8,
+        max_allowed_constraint_degree: 4,
+    };
+    let max_variables = 1 << 26;
+
+    fn configure<
+        T: CsBuilderImpl<F, T>,
+        GC: GateConfigurationHolder<F>,
+        TB: StaticToolboxHolder,
+    >(
+        builder: CsBuilder<T, F, GC, TB>,
+    ) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+        let builder =
+            builder.allow_lookup(LookupParameters::UseSpecializedColumnsWithTableIdAsConstant {
+                width: 3,
+                num_repetitions: 8,
+                share_table_id: true,
+            });
+        let builder = ConstantsAllocatorGate::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = PublicInputGate::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = ReductionGate::<F, 4>::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = BooleanConstraintGate::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = U8x4FMAGate::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = UIntXAddGate::<32>::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = UIntXAddGate::<16>::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = UIntXAddGate::<8>::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = SelectionGate::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = ParallelSelectionGate::<4>::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = ZeroCheckGate::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+            false,
+        );
+        let builder = DotProductGate::<4>::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder =
+            MatrixMultiplicationGate::<F, 12, Poseidon2GoldilocksExternalMatrix>::configure_builder(
+                builder,
+                GatePlacementStrategy::UseGeneralPurposeColumns,
+            );
+        let builder =
+            MatrixMultiplicationGate::<F, 12, Poseidon2GoldilocksInnerMatrix>::configure_builder(
+                builder,
+                GatePlacementStrategy::UseGeneralPurposeColumns,
+            );
+        let builder =
+            NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+        builder
+    }
+
+    let builder_impl =
+        CsReferenceImplementationBuilder::<F, P, DevCSConfig>::new(geometry, max_trace_len);
+    let builder = new_builder::<_, F>(builder_impl);
+
+    let builder = configure(builder);
+    let mut owned_cs = builder.build(max_variables);
+
+    // bitwise tables for keccak and the other hash functions
+    let table = create_xor8_table();
+    owned_cs.add_lookup_table::<Xor8Table, 3>(table);
+
+    let table = create_and8_table();
+    owned_cs.add_lookup_table::<And8Table, 3>(table);
+
+    // widths above 4 are for wider wNAF windows
+    seq_macro::seq!(N in 1..=6 {
+        let table = create_byte_split_table::<F, N>();
+        owned_cs.add_lookup_table::<ByteSplitTable<N>, 3>(table);
+    });
+
+    // fixed base multiplication tables for both curves
+    seq_macro::seq!(C in 0..32 {
+        let table = create_fixed_base_mul_table::<F, 0, C>();
+        owned_cs.add_lookup_table::<FixedBaseMulTable<0, C>, 3>(table);
+        let table = create_fixed_base_mul_table::<F, 1, C>();
+        owned_cs.add_lookup_table::<FixedBaseMulTable<1, C>, 3>(table);
+        let table = create_fixed_base_mul_table::<F, 2, C>();
+        owned_cs.add_lookup_table::<FixedBaseMulTable<2, C>, 3>(table);
+        let table = create_fixed_base_mul_table::<F, 3, C>();
+        owned_cs.add_lookup_table::<FixedBaseMulTable<3, C>, 3>(table);
+        let table = create_fixed_base_mul_table::<F, 4, C>();
+        owned_cs.add_lookup_table::<FixedBaseMulTable<4, C>, 3>(table);
+        let table = create_fixed_base_mul_table::<F, 5, C>();
+        owned_cs.add_lookup_table::<FixedBaseMulTable<5, C>, 3>(table);
+        let table = create_fixed_base_mul_table::<F, 6, C>();
+        owned_cs.add_lookup_table::<FixedBaseMulTable<6, C>, 3>(table);
+        let table = create_fixed_base_mul_table::<F, 7, C>();
+        owned_cs.add_lookup_table::<FixedBaseMulTable<7, C>, 3>(table);
+    });
+
+    seq_macro::seq!(C in 0..32 {
+        let table = create_secp256r1_fixed_base_mul_table::<F, 0, C>();
+        owned_cs.add_lookup_table::<Secp256r1FixedBaseMulTable<0, C>, 3>(table);
+        let table = create_secp256r1_fixed_base_mul_table::<F, 1, C>();
+        owned_cs.add_lookup_table::<Secp256r1FixedBaseMulTable<1, C>, 3>(table);
+        let table = create_secp256r1_fixed_base_mul_table::<F, 2, C>();
+        owned_cs.add_lookup_table::<Secp256r1FixedBaseMulTable<2, C>, 3>(table);
+        let table = create_secp256r1_fixed_base_mul_table::<F, 3, C>();
+        owned_cs.add_lookup_table::<Secp256r1FixedBaseMulTable<3, C>, 3>(table);
+        let table = create_secp256r1_fixed_base_mul_table::<F, 4, C>();
+        owned_cs.add_lookup_table::<Secp256r1FixedBaseMulTable<4, C>, 3>(table);
+        let table = create_secp256r1_fixed_base_mul_table::<F, 5, C>();
+        owned_cs.add_lookup_table::<Secp256r1FixedBaseMulTable<5, C>, 3>(table);
+        let table = create_secp256r1_fixed_base_mul_table::<F, 6, C>();
+        owned_cs.add_lookup_table::<Secp256r1FixedBaseMulTable<6, C>, 3>(table);
+        let table = create_secp256r1_fixed_base_mul_table::<F, 7, C>();
+        owned_cs.add_lookup_table::<Secp256r1FixedBaseMulTable<7, C>, 3>(table);
+    });
+
+    owned_cs
+}
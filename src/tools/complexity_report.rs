@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use boojum::{cs::traits::cs::ConstraintSystem, field::SmallField};
+
+/// Summary of the circuit size. `ConstraintSystem` doesn't expose per gate type statistics, that
+/// are only printed by `print_gate_stats` of the finalized assembly, so `gate_counts` is filled by
+/// the caller with rows of the regions measured by `CircuitSpan`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ComplexityReport {
+    pub circuit_name: String,
+    pub total_rows: usize,
+    pub gate_counts: HashMap<String, usize>,
+    pub lookup_utilization_pct: f64,
+}
+
+pub fn generate_complexity_report<F: SmallField>(
+    circuit_name: &str,
+    cs: &impl ConstraintSystem<F>,
+) -> ComplexityReport {
+    ComplexityReport {
+        circuit_name: circuit_name.to_string(),
+        total_rows: cs.next_available_row(),
+        gate_counts: HashMap::new(),
+        lookup_utilization_pct: 0.0,
+    }
+}
+
+impl ComplexityReport {
+    pub fn record_gates(&mut self, label: &str, num_gates: usize) {
+        *self.gate_counts.entry(label.to_string()).or_default() += num_gates;
+    }
+
+    /// Sets utilization as the share of `num_lookups` in the total capacity of the lookup columns
+    pub fn set_lookup_utilization(&mut self, num_lookups: usize, lookups_capacity: usize) {
+        assert!(lookups_capacity > 0);
+        self.lookup_utilization_pct = (num_lookups as f64) * 100.0 / (lookups_capacity as f64);
+    }
+
+    /// Serializes the report with sorted gate labels, so the output is stable between runs
+    pub fn as_json(&self) -> String {
+        let mut labels: Vec<_> = self.gate_counts.iter().collect();
+        labels.sort();
+        let gate_counts: Vec<String> = labels
+            .into_iter()
+            .map(|(label, count)| format!("{}:{}", json_string(label), count))
+            .collect();
+
+        let fields = [
+            format!("\"circuit_name\":{}", json_string(&self.circuit_name)),
+            format!("\"total_rows\":{}", self.total_rows),
+            format!("\"gate_counts\":{{{}}}", gate_counts.join(",")),
+            format!("\"lookup_utilization_pct\":{}", self.lookup_utilization_pct),
+        ];
+
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complexity_report_as_json() {
+        let mut report = ComplexityReport {
+            circuit_name: "ecrecover".to_string(),
+            total_rows: 100,
+            ..Default::default()
+        };
+        report.record_gates("scalar \"mul\"", 60);
+        report.record_gates("decomposition", 30);
+        report.record_gates("decomposition", 10);
+        report.set_lookup_utilization(1, 4);
+
+        let json: serde_json::Value = serde_json::from_str(&report.as_json()).unwrap();
+        assert_eq!(json["circuit_name"], "ecrecover");
+        assert_eq!(json["total_rows"], 100);
+        assert_eq!(json["gate_counts"]["decomposition"], 40);
+        assert_eq!(json["gate_counts"]["scalar \"mul\""], 60);
+        assert_eq!(json["lookup_utilization_pct"], 25.0);
+
+        // labels are sorted
+        assert!(report.as_json().find("decomposition") < report.as_json().find("scalar"));
+    }
+}
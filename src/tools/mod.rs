@@ -0,0 +1,3 @@
+pub mod complexity_report;
+pub mod proof_size;
+pub mod witness_profiler;
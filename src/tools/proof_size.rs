@@ -0,0 +1,53 @@
+/// Size of the Goldilocks field element in the serialized proof
+pub const FIELD_ELEMENT_BYTES: usize = 8;
+/// Size of the Merkle tree cap element, that is 4 field elements of the Poseidon2 digest
+pub const DIGEST_BYTES: usize = 4 * FIELD_ELEMENT_BYTES;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofSizeBytes {
+    pub merkle_caps: usize,
+    pub query_openings: usize,
+    pub total: usize,
+}
+
+/// Estimation of the proof size, where every query opens `num_columns` elements at each of the
+/// `log2(num_rows * fri_lde_factor)` levels and the caps are counted for a single tree. FRI
+/// intermediate oracles and the proof of work are not counted, so it is not the size of the
+/// serialized proof
+pub fn estimate_proof_size(
+    num_columns: usize,
+    num_rows: usize,
+    cap_size: usize,
+    num_public_inputs: usize,
+    fri_lde_factor: usize,
+    num_queries: usize,
+) -> ProofSizeBytes {
+    assert!(num_rows.is_power_of_two());
+    assert!(fri_lde_factor.is_power_of_two());
+
+    let lde_domain_log_size = (num_rows * fri_lde_factor).trailing_zeros() as usize;
+    let query_openings = num_queries * lde_domain_log_size * num_columns * FIELD_ELEMENT_BYTES;
+    let merkle_caps = cap_size * DIGEST_BYTES;
+    let public_inputs = num_public_inputs * FIELD_ELEMENT_BYTES;
+
+    ProofSizeBytes {
+        merkle_caps,
+        query_openings,
+        total: merkle_caps + query_openings + public_inputs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_proof_size() {
+        // LDE domain is 2^21, so every query opens 21 levels
+        let size = estimate_proof_size(130, 1 << 20, 16, 4, 2, 100);
+
+        assert_eq!(size.merkle_caps, 16 * 32);
+        assert_eq!(size.query_openings, 100 * 21 * 130 * 8);
+        assert_eq!(size.total, size.merkle_caps + size.query_openings + 4 * 8);
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::VecDeque;
+
+use boojum::{field::SmallField, gadgets::traits::allocatable::CSAllocatableExt};
+
+/// Wrapper over the witness source that counts how many field elements are consumed from it.
+/// Every item is accounted with the same size, that is `INTERNAL_STRUCT_LEN` for allocatable
+/// witnesses, or an estimation for items like proofs that don't have a fixed encoding
+pub struct WitnessProfiler<T> {
+    inner: VecDeque<T>,
+    elements_per_item: usize,
+    consumed_elements: usize,
+    high_water_mark: usize,
+}
+
+impl<T> WitnessProfiler<T> {
+    pub fn new(inner: VecDeque<T>, elements_per_item: usize) -> Self {
+        let high_water_mark = inner.len() * elements_per_item;
+
+        Self { inner, elements_per_item, consumed_elements: 0, high_water_mark }
+    }
+
+    /// Accounts every item with the internal structure length of the allocatable type
+    pub fn for_allocatable<F: SmallField, A: CSAllocatableExt<F, Witness = T>>(
+        inner: VecDeque<T>,
+    ) -> Self {
+        Self::new(inner, A::INTERNAL_STRUCT_LEN)
+    }
+
+    pub fn push_back(&mut self, item: T) {
+        self.inner.push_back(item);
+        self.high_water_mark =
+            std::cmp::max(self.high_water_mark, self.inner.len() * self.elements_per_item);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let item = self.inner.pop_front();
+        if item.is_some() {
+            self.consumed_elements += self.elements_per_item;
+        }
+
+        item
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn consumed_elements(&self) -> usize {
+        self.consumed_elements
+    }
+
+    /// Largest number of field elements held by the source at once
+    pub fn high_water_mark(&self) -> usize {
+        self.high_water_mark
+    }
+
+    pub fn report(&self) -> String {
+        format!(
+            "consumed {} field elements, high-water mark is {} field elements ({} items left)",
+            self.consumed_elements,
+            self.high_water_mark,
+            self.inner.len()
+        )
+    }
+
+    pub fn into_inner(self) -> VecDeque<T> {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use boojum::{field::goldilocks::GoldilocksField, gadgets::u256::UInt256};
+
+    use super::*;
+    use crate::ethereum_types::U256;
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_witness_profiler() {
+        let source: VecDeque<U256> = (0..3u64).map(U256::from).collect();
+        let mut profiler = WitnessProfiler::for_allocatable::<F, UInt256<F>>(source);
+        assert_eq!(profiler.high_water_mark(), 3 * 8);
+
+        assert_eq!(profiler.pop_front(), Some(U256::from(0u64)));
+        profiler.push_back(U256::from(3u64));
+        profiler.push_back(U256::from(4u64));
+        assert_eq!(profiler.high_water_mark(), 4 * 8);
+
+        while profiler.pop_front().is_some() {}
+        assert_eq!(profiler.pop_front(), None);
+        assert_eq!(profiler.consumed_elements(), 5 * 8);
+        assert_eq!(
+            profiler.report(),
+            "consumed 40 field elements, high-water mark is 32 field elements (0 items left)"
+        );
+    }
+}
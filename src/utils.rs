@@ -1,16 +1,29 @@
+use std::sync::Arc;
+
 use boojum::{
     algebraic_props::round_function::AlgebraicRoundFunction,
+    crypto_bigint::{Zero, U1024},
     cs::{traits::cs::ConstraintSystem, Variable},
     field::SmallField,
     gadgets::{
         boolean::Boolean,
+        keccak256::keccak256,
+        non_native_field::{implementations::*, traits::NonNativeField},
         num::Num,
-        queue::{QueueState, QueueTailState},
-        traits::{round_function::CircuitRoundFunction, selectable::Selectable},
+        queue::{CircuitQueueRawWitness, CircuitQueueWitness, QueueState, QueueTailState},
+        tables::{ByteSplitTable, Xor8Table},
+        traits::{
+            encodable::CircuitEncodableExt, round_function::CircuitRoundFunction,
+            selectable::Selectable,
+        },
+        u256::UInt256,
         u32::UInt32,
+        u8::UInt8,
     },
 };
 
+use crate::tables::TypedTableId;
+
 pub fn produce_fs_challenges<
     F: SmallField,
     CS: ConstraintSystem<F>,
@@ -155,3 +168,662 @@ pub fn is_equal_queue_state<F: SmallField, CS: ConstraintSystem<F>, const N: usi
 
     Boolean::multi_and(cs, &[heads_are_equal, tail_are_equal, lengths_are_equal])
 }
+
+/// Incremental construction of queue witnesses. Every element is stored together with the queue
+/// tail before it was pushed, same as `CircuitQueue::push` records it
+pub trait CircuitQueueWitnessExt<
+    F: SmallField,
+    I: CircuitEncodableExt<F, EL>,
+    const N: usize,
+    const EL: usize,
+>
+{
+    fn from_iterator<IT: IntoIterator<Item = (I::Witness, [F; N])>>(iter: IT) -> Self;
+    fn append(&self, item: I::Witness, previous_tail: [F; N]);
+}
+
+impl<F: SmallField, I: CircuitEncodableExt<F, EL>, const N: usize, const EL: usize>
+    CircuitQueueWitnessExt<F, I, N, EL> for CircuitQueueWitness<F, I, N, EL>
+{
+    fn from_iterator<IT: IntoIterator<Item = (I::Witness, [F; N])>>(iter: IT) -> Self {
+        Self::from_inner_witness(CircuitQueueRawWitness { elements: iter.into_iter().collect() })
+    }
+
+    fn append(&self, item: I::Witness, previous_tail: [F; N]) {
+        self.elements
+            .write()
+            .unwrap()
+            .push_back((item, previous_tail));
+    }
+}
+
+/// 256-bit arithmetic with EVM semantics: results are taken modulo 2^256 and carries, borrows and
+/// high halves of products are dropped
+pub trait UInt256EvmArithmetic<F: SmallField>: Sized {
+    fn evm_add<CS: ConstraintSystem<F>>(cs: &mut CS, a: &Self, b: &Self) -> Self;
+    fn evm_sub<CS: ConstraintSystem<F>>(cs: &mut CS, a: &Self, b: &Self) -> Self;
+    fn evm_mul<CS: ConstraintSystem<F>>(cs: &mut CS, a: &Self, b: &Self) -> Self;
+    /// Returns `(a / b, a % b)`, and `(0, 0)` if `b` is zero as the EVM does
+    fn div_rem<CS: ConstraintSystem<F>>(cs: &mut CS, a: &Self, b: &Self) -> (Self, Self);
+}
+
+impl<F: SmallField> UInt256EvmArithmetic<F> for UInt256<F> {
+    fn evm_add<CS: ConstraintSystem<F>>(cs: &mut CS, a: &Self, b: &Self) -> Self {
+        let (result, _of) = a.overflowing_add(cs, b);
+
+        result
+    }
+
+    fn evm_sub<CS: ConstraintSystem<F>>(cs: &mut CS, a: &Self, b: &Self) -> Self {
+        let (result, _uf) = a.overflowing_sub(cs, b);
+
+        result
+    }
+
+    fn evm_mul<CS: ConstraintSystem<F>>(cs: &mut CS, a: &Self, b: &Self) -> Self {
+        a.widening_mul(cs, b, 8, 8).to_low()
+    }
+
+    fn div_rem<CS: ConstraintSystem<F>>(cs: &mut CS, a: &Self, b: &Self) -> (Self, Self) {
+        use crate::main_vm::opcodes::mul_div::{
+            all_limbs_are_equal, all_limbs_are_zero, allocate_div_result_unchecked,
+        };
+
+        // for zero divisor the witness is `(0, a)`, so the relation below is satisfiable in all
+        // cases, and results are masked afterwards
+        let (quotient, remainder) = allocate_div_result_unchecked(cs, &a.inner, &b.inner);
+        let quotient = UInt256 {
+            inner: quotient.map(|el| UInt32::from_variable_checked(cs, el.get_variable())),
+        };
+        let remainder = UInt256 {
+            inner: remainder.map(|el| UInt32::from_variable_checked(cs, el.get_variable())),
+        };
+
+        let boolean_false = Boolean::allocated_constant(cs, false);
+        let boolean_true = Boolean::allocated_constant(cs, true);
+
+        // b * q + r == a, so the product fits into 256 bits and the addition doesn't overflow
+        let product = b.widening_mul(cs, &quotient, 8, 8);
+        let high_is_zero = all_limbs_are_zero(cs, &product.to_high().inner);
+        Boolean::enforce_equal(cs, &high_is_zero, &boolean_true);
+        let (sum, of) = product.to_low().overflowing_add(cs, &remainder);
+        Boolean::enforce_equal(cs, &of, &boolean_false);
+        let sum_is_dividend = all_limbs_are_equal(cs, &sum.inner, &a.inner);
+        Boolean::enforce_equal(cs, &sum_is_dividend, &boolean_true);
+
+        // r < b unless b is zero
+        let b_is_zero = all_limbs_are_zero(cs, &b.inner);
+        let b_is_non_zero = b_is_zero.negated(cs);
+        let (_, remainder_is_less_than_divisor) = remainder.overflowing_sub(cs, b);
+        remainder_is_less_than_divisor.conditionally_enforce_true(cs, b_is_non_zero);
+
+        let zero_u256 = UInt256::zero(cs);
+        let quotient = UInt256::conditionally_select(cs, b_is_zero, &zero_u256, &quotient);
+        let remainder = UInt256::conditionally_select(cs, b_is_zero, &zero_u256, &remainder);
+
+        (quotient, remainder)
+    }
+}
+
+/// Reduction of 256-bit values by small constant moduli
+pub trait UInt256SmallModulusReduction<F: SmallField>: Sized {
+    /// Returns `value mod P` for `2 <= P <= 2^16`
+    fn reduce_mod_small_prime<CS: ConstraintSystem<F>, const P: u32>(
+        cs: &mut CS,
+        value: &Self,
+    ) -> UInt32<F>;
+}
+
+impl<F: SmallField> UInt256SmallModulusReduction<F> for UInt256<F> {
+    fn reduce_mod_small_prime<CS: ConstraintSystem<F>, const P: u32>(
+        cs: &mut CS,
+        value: &Self,
+    ) -> UInt32<F> {
+        use boojum::{config::*, cs::Place};
+
+        assert!(P >= 2 && P <= 1 << 16);
+        // `r * 2^32 + limb` is less than 2^48 and must not wrap around the field
+        assert!(F::CAPACITY_BITS >= 49);
+
+        if P == 2 {
+            let lowest_byte = value.inner[0].to_le_bytes(cs)[0];
+            let bits = Num::from_variable(lowest_byte.get_variable()).spread_into_bits::<_, 8>(cs);
+
+            return unsafe { UInt32::from_variable_unchecked(bits[0].get_variable()) };
+        }
+
+        let boolean_true = Boolean::allocated_constant(cs, true);
+        let modulus = UInt32::allocated_constant(cs, P);
+
+        // Horner's scheme from the highest limb: `r' = (r * 2^32 + limb) mod P`. Quotients and
+        // remainders come from the witness, and as `r < P` every quotient fits into 32 bits
+        let mut remainder = UInt32::zero(cs);
+        for limb in value.inner.iter().rev() {
+            let [quotient_var, remainder_var] = cs.alloc_multiple_variables_without_values::<2>();
+
+            if <CS::Config as CSConfig>::WitnessConfig::EVALUATE_WITNESS {
+                let value_fn = move |inputs: [F; 2]| {
+                    let dividend = (inputs[0].as_u64_reduced() << 32) + inputs[1].as_u64_reduced();
+
+                    [
+                        F::from_u64_unchecked(dividend / P as u64),
+                        F::from_u64_unchecked(dividend % P as u64),
+                    ]
+                };
+
+                let dependencies =
+                    Place::from_variables([remainder.get_variable(), limb.get_variable()]);
+                let outputs = Place::from_variables([quotient_var, remainder_var]);
+                cs.set_values_with_dependencies(&dependencies, &outputs, value_fn);
+            }
+
+            let quotient = UInt32::from_variable_checked(cs, quotient_var);
+            let next_remainder = UInt32::from_variable_checked(cs, remainder_var);
+            let (_, is_less_than_modulus) = next_remainder.overflowing_sub(cs, &modulus);
+            Boolean::enforce_equal(cs, &is_less_than_modulus, &boolean_true);
+
+            let lhs = Num::linear_combination(
+                cs,
+                &[
+                    (remainder.get_variable(), F::from_u64_unchecked(1u64 << 32)),
+                    (limb.get_variable(), F::ONE),
+                ],
+            );
+            let rhs = Num::linear_combination(
+                cs,
+                &[
+                    (quotient.get_variable(), F::from_u64_unchecked(P as u64)),
+                    (next_remainder.get_variable(), F::ONE),
+                ],
+            );
+            Num::enforce_equal(cs, &lhs, &rhs);
+
+            remainder = next_remainder;
+        }
+
+        remainder
+    }
+}
+
+pub const KECCAK256_OF_BYTES_MAX_INPUT_LEN: usize = 4096;
+
+/// Computes keccak256 of up to `KECCAK256_OF_BYTES_MAX_INPUT_LEN` bytes, the digest is big-endian.
+/// The length is known at synthesis time, so padding and absorption are left to the `keccak256`
+/// gadget
+pub fn keccak256_of_bytes<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    bytes: &[UInt8<F>],
+) -> [UInt8<F>; 32] {
+    assert!(bytes.len() <= KECCAK256_OF_BYTES_MAX_INPUT_LEN);
+
+    keccak256(cs, bytes)
+}
+
+/// Xors two words byte by byte with the table, the result is little-endian
+pub(crate) fn xor_into_bytes<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    table: &TypedTableId<Xor8Table>,
+    a: &UInt32<F>,
+    b: &UInt32<F>,
+) -> [UInt8<F>; 4] {
+    let a = a.to_le_bytes(cs);
+    let b = b.to_le_bytes(cs);
+
+    std::array::from_fn(|i| {
+        let [result] =
+            table.perform_lookup::<F, CS, 2, 1>(cs, &[a[i].get_variable(), b[i].get_variable()]);
+        unsafe { UInt8::from_variable_unchecked(result) }
+    })
+}
+
+/// Rotates the little-endian word right by `SPLIT_AT` bits, splitting every byte into the low
+/// `SPLIT_AT` bits and the rest by the table
+pub(crate) fn rotate_bits_right<F: SmallField, CS: ConstraintSystem<F>, const SPLIT_AT: usize>(
+    cs: &mut CS,
+    table: &TypedTableId<ByteSplitTable<SPLIT_AT>>,
+    bytes: [UInt8<F>; 4],
+) -> [UInt8<F>; 4] {
+    let splits = bytes.map(|el| table.perform_lookup::<F, CS, 1, 2>(cs, &[el.get_variable()]));
+
+    std::array::from_fn(|i| {
+        let [_, high] = splits[i];
+        let [low_of_next, _] = splits[(i + 1) % 4];
+        let byte = Num::linear_combination(
+            cs,
+            &[(high, F::ONE), (low_of_next, F::from_u64_unchecked(1u64 << (8 - SPLIT_AT)))],
+        );
+
+        unsafe { UInt8::from_variable_unchecked(byte.get_variable()) }
+    })
+}
+
+pub(crate) fn rotate_bytes_right<F: SmallField>(bytes: [UInt8<F>; 4], by: usize) -> [UInt8<F>; 4] {
+    std::array::from_fn(|i| bytes[(i + by) % 4])
+}
+
+pub trait OverflowTrackerExt {
+    /// Panics unless the element is known to be less than the modulus, that is required before
+    /// comparing limbs or converting them into integers
+    fn assert_normalized(&self);
+}
+
+impl OverflowTrackerExt for OverflowTracker {
+    fn assert_normalized(&self) {
+        assert!(
+            self.max_moduluses == 1,
+            "non-native field element must be normalized, but it may hold up to {} moduluses",
+            self.max_moduluses
+        );
+    }
+}
+
+/// Constructors and helpers for non-native field elements that are not provided by `boojum`
+pub trait NonNativeFieldOverU16Ext<
+    F: SmallField,
+    P: boojum::pairing::ff::PrimeField,
+    const N: usize,
+>: Sized
+{
+    /// Assembles the element from little-endian bits, 16 bits per limb. Limbs are range checked by
+    /// construction, and the element is not reduced
+    fn from_bits<CS: ConstraintSystem<F>>(
+        cs: &mut CS,
+        bits: &[Boolean<F>],
+        params: &Arc<NonNativeFieldOverU16Params<P, N>>,
+    ) -> Self;
+
+    /// Normalizes the element and returns its limbs, so it can be a part of the variable length
+    /// encoding of FSM state. `CircuitVarLengthEncodable` can not be implemented for a foreign type
+    /// here, so structures that carry an element encode it with this function
+    fn circuit_encode<CS: ConstraintSystem<F>>(&mut self, cs: &mut CS) -> Vec<Variable>;
+
+    /// Inverse of `circuit_encode`. Limbs are not range checked again, so they must come from an
+    /// encoding that is committed to, e.g. the FSM input
+    fn circuit_decode<CS: ConstraintSystem<F>>(
+        cs: &mut CS,
+        vars: &[Variable],
+        params: &Arc<NonNativeFieldOverU16Params<P, N>>,
+    ) -> Self;
+}
+
+impl<F: SmallField, P: boojum::pairing::ff::PrimeField, const N: usize>
+    NonNativeFieldOverU16Ext<F, P, N> for NonNativeFieldOverU16<F, P, N>
+where
+    [(); N + 1]:,
+{
+    fn from_bits<CS: ConstraintSystem<F>>(
+        cs: &mut CS,
+        bits: &[Boolean<F>],
+        params: &Arc<NonNativeFieldOverU16Params<P, N>>,
+    ) -> Self {
+        assert!(bits.len() > 0);
+        assert!(bits.len() <= 16 * N);
+
+        let zero_var = cs.allocate_constant(F::ZERO);
+        let mut limbs = [zero_var; N];
+        for (dst, chunk) in limbs.iter_mut().zip(bits.chunks(16)) {
+            let lc: Vec<_> = chunk
+                .iter()
+                .enumerate()
+                .map(|(idx, bit)| (bit.get_variable(), F::from_u64_unchecked(1u64 << idx)))
+                .collect();
+            *dst = Num::linear_combination(cs, &lc).get_variable();
+        }
+
+        let mut max_value = U1024::from_word(1u64);
+        max_value = max_value.shl_vartime(bits.len());
+        max_value = max_value.saturating_sub(&U1024::from_word(1u64));
+
+        let (overflows, rem) = max_value.div_rem(&params.modulus_u1024);
+        assert!(overflows.lt(&U1024::from_word(1u64 << 32)));
+        let mut max_moduluses = overflows.as_words()[0] as u32;
+        if rem.is_zero().unwrap_u8() != 1 {
+            max_moduluses += 1;
+        }
+
+        NonNativeFieldOverU16 {
+            limbs,
+            non_zero_limbs: (bits.len() + 15) / 16,
+            tracker: OverflowTracker { max_moduluses },
+            form: RepresentationForm::Normalized,
+            params: params.clone(),
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    fn circuit_encode<CS: ConstraintSystem<F>>(&mut self, cs: &mut CS) -> Vec<Variable> {
+        self.normalize(cs);
+
+        self.limbs.to_vec()
+    }
+
+    fn circuit_decode<CS: ConstraintSystem<F>>(
+        _cs: &mut CS,
+        vars: &[Variable],
+        params: &Arc<NonNativeFieldOverU16Params<P, N>>,
+    ) -> Self {
+        assert_eq!(vars.len(), N);
+
+        let mut limbs = [Variable::placeholder(); N];
+        limbs.copy_from_slice(vars);
+
+        // encoded element is normalized, so only the limbs of the modulus can be non-zero
+        NonNativeFieldOverU16 {
+            limbs,
+            non_zero_limbs: (params.modulus_u1024.bits_vartime() + 15) / 16,
+            tracker: OverflowTracker { max_moduluses: 1 },
+            form: RepresentationForm::Normalized,
+            params: params.clone(),
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use boojum::{
+        field::goldilocks::GoldilocksField,
+        gadgets::traits::{allocatable::CSAllocatable, witnessable::WitnessHookable},
+        worker::Worker,
+    };
+
+    use super::*;
+    use crate::{
+        base_structures::log_query::{LogQuery, LOG_QUERY_PACKED_WIDTH},
+        ethereum_types::U256,
+        test_utils::create_test_cs,
+    };
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_queue_witness_incremental_construction() {
+        let element = |idx: u32| {
+            let mut witness = LogQuery::<F>::placeholder_witness();
+            witness.timestamp = idx;
+
+            (witness, [F::from_u64_unchecked(idx as u64); 4])
+        };
+
+        let witness =
+            CircuitQueueWitness::<F, LogQuery<F>, 4, LOG_QUERY_PACKED_WIDTH>::from_iterator(
+                (0..2).map(element),
+            );
+        let (item, previous_tail) = element(2);
+        witness.append(item, previous_tail);
+
+        let elements = witness.elements.read().unwrap();
+        assert_eq!(elements.len(), 3);
+        for (idx, (item, previous_tail)) in elements.iter().enumerate() {
+            assert_eq!(item.timestamp, idx as u32);
+            assert_eq!(previous_tail[0].as_u64_reduced(), idx as u64);
+        }
+    }
+
+    #[test]
+    fn test_uint256_evm_arithmetic() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let pairs = [
+            (U256::zero(), U256::zero()),
+            (U256::MAX, U256::one()),
+            (U256::zero(), U256::one()),
+            (U256::MAX, U256::MAX),
+            (U256::one() << 255, U256::from(2u64)),
+            (
+                U256::from_dec_str("123456789012345678901234567890").unwrap(),
+                U256::from_dec_str("987654321098765432109876543210").unwrap(),
+            ),
+        ];
+
+        for (a, b) in pairs {
+            let a_var = UInt256::allocate(cs, a);
+            let b_var = UInt256::allocate(cs, b);
+
+            let sum = UInt256::evm_add(cs, &a_var, &b_var);
+            let difference = UInt256::evm_sub(cs, &a_var, &b_var);
+            let product = UInt256::evm_mul(cs, &a_var, &b_var);
+
+            assert_eq!(sum.witness_hook(&*cs)().unwrap(), a.overflowing_add(b).0);
+            assert_eq!(difference.witness_hook(&*cs)().unwrap(), a.overflowing_sub(b).0);
+            assert_eq!(product.witness_hook(&*cs)().unwrap(), a.overflowing_mul(b).0);
+        }
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+
+    #[test]
+    fn test_uint256_reduce_mod_small_prime() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let values = [
+            U256::zero(),
+            U256::one(),
+            U256::MAX,
+            U256::one() << 255,
+            U256::from_dec_str("987654321098765432109876543210").unwrap(),
+        ];
+
+        for value in values {
+            let value_var = UInt256::allocate(cs, value);
+
+            let mod_2 = UInt256::reduce_mod_small_prime::<_, 2>(cs, &value_var);
+            let mod_3 = UInt256::reduce_mod_small_prime::<_, 3>(cs, &value_var);
+            let mod_5 = UInt256::reduce_mod_small_prime::<_, 5>(cs, &value_var);
+            let mod_7 = UInt256::reduce_mod_small_prime::<_, 7>(cs, &value_var);
+
+            assert_eq!(
+                mod_2.witness_hook(&*cs)().unwrap() as u64,
+                (value % U256::from(2u64)).as_u64()
+            );
+            assert_eq!(
+                mod_3.witness_hook(&*cs)().unwrap() as u64,
+                (value % U256::from(3u64)).as_u64()
+            );
+            assert_eq!(
+                mod_5.witness_hook(&*cs)().unwrap() as u64,
+                (value % U256::from(5u64)).as_u64()
+            );
+            assert_eq!(
+                mod_7.witness_hook(&*cs)().unwrap() as u64,
+                (value % U256::from(7u64)).as_u64()
+            );
+        }
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+
+    #[test]
+    fn test_non_native_field_from_bits() {
+        use boojum::pairing::{
+            bn256::Fq as Bn254Fq,
+            ff::{PrimeField, PrimeFieldRepr},
+        };
+
+        use crate::{
+            bn254::{bn254_base_field_params, Bn254NNField},
+            ecrecover::new_optimized::convert_uint256_to_field_element,
+        };
+
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let params = Arc::new(bn254_base_field_params());
+        let value = U256::from_str_radix(
+            "2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f1",
+            16,
+        )
+        .unwrap();
+        let mut modulus = U256::zero();
+        modulus.0.copy_from_slice(&Bn254Fq::char().as_ref()[..4]);
+
+        // from all 256 bits, and from the lowest 200 bits
+        for num_bits in [256, 200] {
+            let bits: Vec<_> = (0..num_bits)
+                .map(|idx| Boolean::allocate(cs, value.bit(idx)))
+                .collect();
+            let mut from_bits = Bn254NNField::from_bits(cs, &bits, &params);
+            from_bits.normalize(cs);
+
+            let truncated = value & ((U256::one() << num_bits) - U256::one());
+            assert!(truncated < modulus);
+            let truncated = UInt256::allocate(cs, truncated);
+            let mut expected = convert_uint256_to_field_element(cs, &truncated, &params);
+            expected.normalize(cs);
+
+            let is_equal = Bn254NNField::equals(cs, &mut from_bits, &mut expected);
+            assert!(is_equal.witness_hook(&*cs)().unwrap() == true);
+        }
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+
+    #[test]
+    fn test_overflow_tracker_assert_normalized() {
+        OverflowTracker { max_moduluses: 1 }.assert_normalized();
+    }
+
+    #[test]
+    #[should_panic(expected = "must be normalized")]
+    fn test_overflow_tracker_assert_normalized_panics() {
+        OverflowTracker { max_moduluses: 2 }.assert_normalized();
+    }
+
+    #[test]
+    fn test_keccak256_of_bytes() {
+        use zkevm_opcode_defs::sha3::*;
+
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        // empty input, inputs around the rate of 136 bytes and the longest one
+        for len in [0, 1, 135, 136, 137, KECCAK256_OF_BYTES_MAX_INPUT_LEN] {
+            let input: Vec<u8> = (0..len).map(|idx| (idx * 7 + 3) as u8).collect();
+            let bytes: Vec<_> = input
+                .iter()
+                .map(|el| UInt8::allocate_checked(cs, *el))
+                .collect();
+
+            let digest = keccak256_of_bytes(cs, &bytes);
+            let digest = digest.map(|el| el.witness_hook(&*cs)().unwrap());
+
+            let mut hasher = Keccak256::new();
+            hasher.update(&input);
+            assert_eq!(&digest[..], hasher.finalize().as_slice());
+        }
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+
+    #[test]
+    fn test_uint256_div_rem() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let pairs = [
+            (U256::zero(), U256::one()),
+            (U256::MAX, U256::one()),
+            (U256::MAX, U256::MAX),
+            (U256::one(), U256::MAX),
+            (U256::MAX, U256::from(3u64)),
+            (U256::one() << 255, U256::one() << 128),
+            (
+                U256::from_dec_str("987654321098765432109876543210").unwrap(),
+                U256::from_dec_str("123456789012345678901234567890").unwrap(),
+            ),
+        ];
+
+        for (a, b) in pairs {
+            let a_var = UInt256::allocate(cs, a);
+            let b_var = UInt256::allocate(cs, b);
+
+            let (quotient, remainder) = UInt256::div_rem(cs, &a_var, &b_var);
+            let (expected_quotient, expected_remainder) = a.div_mod(b);
+
+            assert_eq!(quotient.witness_hook(&*cs)().unwrap(), expected_quotient);
+            assert_eq!(remainder.witness_hook(&*cs)().unwrap(), expected_remainder);
+        }
+
+        // division by zero gives zero quotient and remainder
+        let a_var = UInt256::allocate(cs, U256::MAX);
+        let b_var = UInt256::allocate(cs, U256::zero());
+        let (quotient, remainder) = UInt256::div_rem(cs, &a_var, &b_var);
+        assert_eq!(quotient.witness_hook(&*cs)().unwrap(), U256::zero());
+        assert_eq!(remainder.witness_hook(&*cs)().unwrap(), U256::zero());
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+
+    // the constants allocator gate keeps a constant to variable mapping in the CS toolbox, so
+    // entry points may allocate the same constant many times without extra variables
+    #[test]
+    fn test_constant_allocations_are_shared() {
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+
+        let zero_u32 = UInt32::zero(cs);
+        let boolean_false = Boolean::allocated_constant(cs, false);
+        let zero_num = Num::zero(cs);
+        assert_eq!(zero_u32.get_variable(), UInt32::zero(cs).get_variable());
+        assert_eq!(zero_u32.get_variable(), boolean_false.get_variable());
+        assert_eq!(zero_u32.get_variable(), zero_num.get_variable());
+
+        let one_u32 = UInt32::allocated_constant(cs, 1);
+        let boolean_true = Boolean::allocated_constant(cs, true);
+        assert_eq!(one_u32.get_variable(), boolean_true.get_variable());
+        assert_ne!(one_u32.get_variable(), zero_u32.get_variable());
+    }
+
+    #[test]
+    fn test_non_native_field_circuit_encoding() {
+        use boojum::pairing::ff::{Field, PrimeField};
+
+        use crate::ecrecover::{secp256k1::fq::Fq as Secp256Fq, secp256k1_base_field_params};
+
+        let mut owned_cs = create_test_cs(1 << 20);
+        let cs = &mut owned_cs;
+        let params = Arc::new(secp256k1_base_field_params());
+
+        let value = Secp256Fq::from_str("1234567890").unwrap();
+        let mut element =
+            NonNativeFieldOverU16::<F, Secp256Fq, 17>::allocate_checked(cs, value, &params);
+        // not normalized, so encoding must take care of it
+        let mut doubled = element.double(cs);
+
+        for el in [&mut element, &mut doubled] {
+            let encoding = el.circuit_encode(cs);
+            assert_eq!(encoding.len(), 17);
+
+            let decoded = NonNativeFieldOverU16::circuit_decode(cs, &encoding, &params);
+            assert_eq!(
+                decoded.witness_hook(cs)().unwrap().get(),
+                el.witness_hook(cs)().unwrap().get()
+            );
+        }
+        let mut expected = value;
+        expected.double();
+        assert_eq!(doubled.witness_hook(cs)().unwrap().get(), expected);
+
+        cs.pad_and_shrink();
+        let worker = Worker::new();
+        let mut owned_cs = owned_cs.into_assembly::<std::alloc::Global>();
+        assert!(owned_cs.check_if_satisfied(&worker));
+    }
+}